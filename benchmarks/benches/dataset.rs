@@ -54,6 +54,18 @@ macro_rules! dataset {
                 });
             });
 
+            group.bench_function("decode_borrowed", move |b| {
+                let load_arena = Arena::new();
+                let dataset = load_dataset(dataset_bytes, &load_arena).unwrap();
+                b.iter(|| {
+                    for buf in dataset.payload {
+                        let arena = Arena::new();
+                        let message = <$ty>::from_buf_borrowed(*buf, &arena).unwrap();
+                        std::hint::black_box(&message);
+                    }
+                });
+            });
+
             group.bench_function("encode", move |b| {
                 // Create arena and decode all messages once
                 let arena = Arena::new();
@@ -75,6 +87,40 @@ macro_rules! dataset {
                 });
             });
 
+            group.bench_function("arena_encode", move |b| {
+                let arena = Arena::new();
+                let load_arena = Arena::new();
+                let dataset = load_dataset(dataset_bytes, &load_arena).unwrap();
+                let messages: Vec<_> = dataset
+                    .payload
+                    .iter()
+                    .map(|buf| <$ty>::from_buf(*buf, &arena).unwrap())
+                    .collect();
+                b.iter(|| {
+                    let encode_arena = Arena::new();
+                    for message in &messages {
+                        std::hint::black_box(message.arena_encode(&encode_arena));
+                    }
+                });
+            });
+
+            group.bench_function("arena_encode_reverse", move |b| {
+                let arena = Arena::new();
+                let load_arena = Arena::new();
+                let dataset = load_dataset(dataset_bytes, &load_arena).unwrap();
+                let messages: Vec<_> = dataset
+                    .payload
+                    .iter()
+                    .map(|buf| <$ty>::from_buf(*buf, &arena).unwrap())
+                    .collect();
+                b.iter(|| {
+                    let encode_arena = Arena::new();
+                    for message in &messages {
+                        std::hint::black_box(message.arena_encode_reverse(&encode_arena));
+                    }
+                });
+            });
+
             group.bench_function("encoded_len", move |b| {
                 // Create arena and decode all messages once
                 let arena = Arena::new();
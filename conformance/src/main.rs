@@ -1,10 +1,11 @@
 use std::io::{self, Read, Write};
 
 use bytes::{Buf, BufMut};
-use defiant::Encode;
+use defiant::json::JsonOptions;
+use defiant::{text_format, Encode};
 
 use protobuf::conformance::{
-    conformance_request, conformance_response, ConformanceRequest, WireFormat,
+    conformance_request, conformance_response, ConformanceRequest, TestCategory, WireFormat,
 };
 use protobuf::test_messages::proto2::TestAllTypesProto2;
 use protobuf::test_messages::proto3::TestAllTypesProto3;
@@ -67,40 +68,71 @@ fn handle_request<'arena>(
                 arena.alloc_str("output format unspecified"),
             );
         }
-        WireFormat::Json => {
-            return conformance_response::Result::Skipped(
-                arena.alloc_str("JSON output is not supported"),
-            );
-        }
         WireFormat::Jspb => {
             return conformance_response::Result::Skipped(
                 arena.alloc_str("JSPB output is not supported"),
             );
         }
-        WireFormat::TextFormat => {
-            return conformance_response::Result::Skipped(
-                arena.alloc_str("TEXT_FORMAT output is not supported"),
-            );
-        }
-        WireFormat::Protobuf => (),
+        WireFormat::Json | WireFormat::TextFormat | WireFormat::Protobuf => (),
     };
 
+    let json_options = JsonOptions::new()
+        .register::<TestAllTypesProto2>()
+        .register::<TestAllTypesProto3>()
+        .ignore_unknown_fields(
+            request.test_category == TestCategory::JsonIgnoreUnknownParsingTest as i32,
+        );
+
     let buf = match request.payload {
         None => return conformance_response::Result::ParseError(arena.alloc_str("no payload")),
-        Some(conformance_request::Payload::JsonPayload(_)) => {
-            return conformance_response::Result::Skipped(
-                arena.alloc_str("JSON input is not supported"),
-            );
+        Some(conformance_request::Payload::JsonPayload(json)) => {
+            let buf = match request.message_type {
+                "protobuf_test_messages.proto2.TestAllTypesProto2" => {
+                    json_options.to_wire::<TestAllTypesProto2>(json, arena)
+                }
+                "protobuf_test_messages.proto3.TestAllTypesProto3" => {
+                    json_options.to_wire::<TestAllTypesProto3>(json, arena)
+                }
+                _ => {
+                    let error =
+                        arena.alloc_str(&format!("unknown message type: {}", request.message_type));
+                    return conformance_response::Result::ParseError(error);
+                }
+            };
+            match buf {
+                Ok(buf) => buf,
+                Err(error) => {
+                    let error_str = arena.alloc_str(&error.to_string());
+                    return conformance_response::Result::ParseError(error_str);
+                }
+            }
         }
         Some(conformance_request::Payload::JspbPayload(_)) => {
             return conformance_response::Result::Skipped(
                 arena.alloc_str("JSPB input is not supported"),
             );
         }
-        Some(conformance_request::Payload::TextPayload(_)) => {
-            return conformance_response::Result::Skipped(
-                arena.alloc_str("TEXT input is not supported"),
-            );
+        Some(conformance_request::Payload::TextPayload(text)) => {
+            let buf = match request.message_type {
+                "protobuf_test_messages.proto2.TestAllTypesProto2" => {
+                    text_format::to_wire::<TestAllTypesProto2>(text, arena)
+                }
+                "protobuf_test_messages.proto3.TestAllTypesProto3" => {
+                    text_format::to_wire::<TestAllTypesProto3>(text, arena)
+                }
+                _ => {
+                    let error =
+                        arena.alloc_str(&format!("unknown message type: {}", request.message_type));
+                    return conformance_response::Result::ParseError(error);
+                }
+            };
+            match buf {
+                Ok(buf) => buf,
+                Err(error) => {
+                    let error_str = arena.alloc_str(&error.to_string());
+                    return conformance_response::Result::ParseError(error_str);
+                }
+            }
         }
         Some(conformance_request::Payload::ProtobufPayload(buf)) => buf,
     };
@@ -119,6 +151,44 @@ fn handle_request<'arena>(
     };
 
     match roundtrip {
+        RoundtripResult::Ok(buf) if format == WireFormat::TextFormat => {
+            let text = match request.message_type {
+                "protobuf_test_messages.proto2.TestAllTypesProto2" => {
+                    TestAllTypesProto2::from_buf(buf.as_slice(), arena).map(|m| m.to_text_format())
+                }
+                _ => {
+                    TestAllTypesProto3::from_buf(buf.as_slice(), arena).map(|m| m.to_text_format())
+                }
+            };
+            match text {
+                Ok(text) => conformance_response::Result::TextPayload(arena.alloc_str(&text)),
+                Err(error) => {
+                    let error_str = arena.alloc_str(&error.to_string());
+                    conformance_response::Result::SerializeError(error_str)
+                }
+            }
+        }
+        RoundtripResult::Ok(buf) if format == WireFormat::Json => {
+            let json = match request.message_type {
+                "protobuf_test_messages.proto2.TestAllTypesProto2" => {
+                    TestAllTypesProto2::from_buf(buf.as_slice(), arena)
+                        .map(|m| json_options.to_string(&m))
+                }
+                _ => TestAllTypesProto3::from_buf(buf.as_slice(), arena)
+                    .map(|m| json_options.to_string(&m)),
+            };
+            match json {
+                Ok(Ok(json)) => conformance_response::Result::JsonPayload(arena.alloc_str(&json)),
+                Ok(Err(error)) => {
+                    let error_str = arena.alloc_str(&error.to_string());
+                    conformance_response::Result::SerializeError(error_str)
+                }
+                Err(error) => {
+                    let error_str = arena.alloc_str(&error.to_string());
+                    conformance_response::Result::SerializeError(error_str)
+                }
+            }
+        }
         RoundtripResult::Ok(buf) => {
             // Copy Vec<u8> into arena-allocated slice
             let mut arena_vec = arena.new_vec_with_capacity(buf.len());
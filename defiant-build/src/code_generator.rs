@@ -3,12 +3,12 @@ use std::borrow::Cow;
 use std::collections::{HashMap, HashSet};
 use std::iter;
 
+use defiant_types::feature_set::{FieldPresence, RepeatedFieldEncoding};
 use defiant_types::field_descriptor_proto::{Label, Type};
 use defiant_types::source_code_info::Location;
 use defiant_types::{
-    DescriptorProto, EnumDescriptorProto, EnumValueDescriptorProto,
-    FieldDescriptorProto, FileDescriptorProto, OneofDescriptorProto,
-    ServiceDescriptorProto, SourceCodeInfo,
+    DescriptorProto, EnumDescriptorProto, EnumValueDescriptorProto, FieldDescriptorProto,
+    FileDescriptorProto, OneofDescriptorProto, ServiceDescriptorProto, SourceCodeInfo,
 };
 use itertools::{Either, Itertools};
 use log::debug;
@@ -16,16 +16,16 @@ use multimap::MultiMap;
 
 use crate::ast::{Comments, Method, Service};
 use crate::context::Context;
-use crate::ident::{strip_enum_prefix, to_snake, to_upper_camel};
+use crate::ident::{strip_enum_prefix, to_json_name, to_snake, to_upper_camel};
 use crate::Config;
 
 mod c_escaping;
 use c_escaping::unescape_c_escape_string;
 
-mod syntax;
-use syntax::Syntax;
+mod features;
+use features::Features;
 
-use crate::descriptor_ext::*;
+mod syntax;
 
 /// State object for the code generation process on a single input file.
 pub struct CodeGenerator<'buf, 'ctx, 'arena> {
@@ -33,7 +33,8 @@ pub struct CodeGenerator<'buf, 'ctx, 'arena> {
     package: String,
     type_path: Vec<String>,
     source_info: Option<SourceCodeInfo<'arena>>,
-    syntax: Syntax,
+    /// Features of the scope being generated
+    features: Features,
     depth: u8,
     path: Vec<i32>,
     buf: &'buf mut String,
@@ -50,19 +51,68 @@ fn push_indent(buf: &mut String, depth: u8) {
 struct Field<'arena> {
     descriptor: FieldDescriptorProto<'arena>,
     path_index: i32,
+    features: Features,
 }
 
 impl<'arena> Field<'arena> {
-    fn new(descriptor: FieldDescriptorProto<'arena>, path_index: i32) -> Self {
+    fn new(
+        mut descriptor: FieldDescriptorProto<'arena>,
+        path_index: i32,
+        features: Features,
+    ) -> Self {
+        features.lower(&mut descriptor);
         Self {
             descriptor,
             path_index,
+            features,
         }
     }
 
     fn rust_name(&self) -> String {
         to_snake(self.descriptor.name())
     }
+
+    /// Returns the `name` and `json_name` attributes for the field, where they differ from what
+    /// the derive infers from `inferred_name`, the name of the struct field or oneof variant.
+    fn name_attributes(&self, inferred_name: &str) -> String {
+        let name = self.descriptor.name();
+        let mut attributes = String::new();
+        if name != inferred_name {
+            attributes.push_str(&format!(", name = \"{name}\""));
+        }
+        if let Some(json_name) = self.descriptor.json_name {
+            if json_name != to_json_name(name) {
+                attributes.push_str(&format!(", json_name = \"{json_name}\""));
+            }
+        }
+        attributes
+    }
+
+    /// Returns the name the derive infers for a struct field.
+    fn inferred_field_name(&self) -> String {
+        let rust_name = self.rust_name();
+        rust_name
+            .strip_prefix("r#")
+            .unwrap_or(&rust_name)
+            .to_string()
+    }
+
+    /// Returns the name the derive infers for a oneof variant, by splitting its `UpperCamel`
+    /// identifier into `snake_case` words.
+    fn inferred_variant_name(&self) -> String {
+        let mut name = String::new();
+        for (i, c) in to_upper_camel(self.descriptor.name()).chars().enumerate() {
+            if c.is_uppercase() {
+                if i > 0 {
+                    name.push('_');
+                }
+                name.extend(c.to_lowercase());
+            } else {
+                name.push(c);
+            }
+        }
+        name
+    }
 }
 
 struct OneofField<'arena> {
@@ -106,6 +156,28 @@ impl<'arena> OneofField<'arena> {
     }
 }
 
+/// A field of an owned message, with the expressions converting a view's field to it and back.
+struct OwnedField {
+    name: String,
+    ty: String,
+    to_owned: String,
+    from_owned: String,
+    deprecated: bool,
+}
+
+impl OwnedField {
+    /// A field converted by `ToOwnedValue` and `ArenaFrom` alone.
+    fn converted(name: String, ty: String, prost_path: &str) -> Self {
+        Self {
+            to_owned: format!("{prost_path}::ToOwnedValue::to_owned_value(&self.{name})"),
+            from_owned: format!("{prost_path}::ArenaFrom::arena_from(&value.{name}, arena)"),
+            name,
+            ty,
+            deprecated: false,
+        }
+    }
+}
+
 impl<'buf, 'ctx, 'arena> CodeGenerator<'buf, 'ctx, 'arena> {
     fn config(&self) -> &Config<'_> {
         self.context.config()
@@ -115,7 +187,7 @@ impl<'buf, 'ctx, 'arena> CodeGenerator<'buf, 'ctx, 'arena> {
         context: &mut Context<'ctx, 'arena>,
         file: FileDescriptorProto<'arena>,
         buf: &mut String,
-    ) {
+    ) -> Result<(), String> {
         // Use source info as-is from arena (can't filter/sort immutable slice)
         // TODO: Consider pre-sorting at proto parse time or using linear search
         let source_info = file.source_code_info;
@@ -125,7 +197,7 @@ impl<'buf, 'ctx, 'arena> CodeGenerator<'buf, 'ctx, 'arena> {
             package: file.package.unwrap_or("").to_string(),
             type_path: Vec::new(),
             source_info: source_info.cloned(),
-            syntax: file.syntax.as_deref().into(),
+            features: Features::file(&file)?,
             depth: 0,
             path: Vec::new(),
             buf,
@@ -154,6 +226,14 @@ impl<'buf, 'ctx, 'arena> CodeGenerator<'buf, 'ctx, 'arena> {
         }
         code_gen.path.pop();
 
+        code_gen.path.push(7);
+        for (idx, extension) in file.extension.iter().enumerate() {
+            code_gen.path.push(idx as i32);
+            code_gen.append_extension(extension);
+            code_gen.path.pop();
+        }
+        code_gen.path.pop();
+
         if code_gen.context.service_generator_mut().is_some() {
             code_gen.path.push(6);
             for (idx, service) in file.service.into_iter().enumerate() {
@@ -168,6 +248,7 @@ impl<'buf, 'ctx, 'arena> CodeGenerator<'buf, 'ctx, 'arena> {
 
             code_gen.path.pop();
         }
+        Ok(())
     }
 
     fn append_message(&mut self, message: &DescriptorProto<'arena>) {
@@ -214,6 +295,16 @@ impl<'buf, 'ctx, 'arena> CodeGenerator<'buf, 'ctx, 'arena> {
                 }
             });
 
+        // Features declared on the message apply to everything nested in it.
+        let parent_features = self.features;
+        self.features = parent_features.merge(message.options.and_then(|options| options.features));
+        let field = |proto: &FieldDescriptorProto<'arena>, idx, features: Features| {
+            let map = proto
+                .type_name
+                .is_some_and(|type_name| map_types.contains_key(type_name));
+            Field::new(proto.clone(), idx, features.field(proto, map))
+        };
+
         // Split the fields into a vector of the normal fields, and oneof fields.
         // Path indexes are preserved so that comments can be retrieved.
         type OneofFieldsByIndex<'arena> = MultiMap<i32, Field<'arena>>;
@@ -224,11 +315,18 @@ impl<'buf, 'ctx, 'arena> CodeGenerator<'buf, 'ctx, 'arena> {
             .partition_map(|(idx, proto)| {
                 let idx = idx as i32;
                 if proto.proto3_optional.unwrap_or(false) {
-                    Either::Left(Field::new((*proto).clone(), idx))
+                    Either::Left(field(proto, idx, self.features))
                 } else if let Some(oneof_index) = proto.oneof_index {
-                    Either::Right((oneof_index, Field::new((*proto).clone(), idx)))
+                    let features = self.features.merge(
+                        message
+                            .oneof_decl
+                            .get(oneof_index as usize)
+                            .and_then(|oneof| oneof.options)
+                            .and_then(|options| options.features),
+                    );
+                    Either::Right((oneof_index, field(proto, idx, features)))
                 } else {
-                    Either::Left(Field::new((*proto).clone(), idx))
+                    Either::Left(field(proto, idx, self.features))
                 }
             });
         // Optional fields create a synthetic oneof that we want to skip
@@ -264,9 +362,13 @@ impl<'buf, 'ctx, 'arena> CodeGenerator<'buf, 'ctx, 'arena> {
         ));
         self.append_prost_path_attribute();
         self.append_skip_debug(&fq_message_name);
+        self.append_full_name(&fq_message_name);
 
         // Check if this message needs a lifetime parameter
-        let needs_lifetime = self.message_needs_lifetime(message);
+        let preserve_unknown_fields = self
+            .context
+            .should_preserve_unknown_fields(&fq_message_name);
+        let needs_lifetime = preserve_unknown_fields || self.message_needs_lifetime(message);
         if needs_lifetime {
             self.messages_with_lifetime.insert(fq_message_name.clone());
         }
@@ -305,11 +407,30 @@ impl<'buf, 'ctx, 'arena> CodeGenerator<'buf, 'ctx, 'arena> {
         }
         self.path.pop();
 
+        if preserve_unknown_fields {
+            self.append_unknown_fields();
+        }
+
         self.depth -= 1;
         self.push_indent();
         self.buf.push_str("}\n");
 
-        if !message.enum_type.is_empty() || !nested_types.is_empty() || !oneof_fields.is_empty() {
+        if needs_lifetime && self.config().owned_types {
+            self.append_owned_message(
+                &message_name,
+                &fq_message_name,
+                &fields,
+                &map_types,
+                &oneof_fields,
+                preserve_unknown_fields,
+            );
+        }
+
+        if !message.enum_type.is_empty()
+            || !nested_types.is_empty()
+            || !oneof_fields.is_empty()
+            || !message.extension.is_empty()
+        {
             self.push_mod(&message_name);
             self.path.push(3);
             for (nested_type, idx) in nested_types {
@@ -331,12 +452,21 @@ impl<'buf, 'ctx, 'arena> CodeGenerator<'buf, 'ctx, 'arena> {
                 self.append_oneof(&fq_message_name, oneof);
             }
 
+            self.path.push(6);
+            for (idx, extension) in message.extension.iter().enumerate() {
+                self.path.push(idx as i32);
+                self.append_extension(extension);
+                self.path.pop();
+            }
+            self.path.pop();
+
             self.pop_mod();
         }
 
         if self.config().enable_type_names {
             self.append_type_name(&message_name, &fq_message_name);
         }
+        self.features = parent_features;
     }
 
     fn append_type_name(&mut self, message_name: &str, fq_message_name: &str) {
@@ -403,6 +533,14 @@ impl<'buf, 'ctx, 'arena> CodeGenerator<'buf, 'ctx, 'arena> {
         }
     }
 
+    fn append_full_name(&mut self, fq_name: &str) {
+        push_indent(self.buf, self.depth);
+        self.buf.push_str(&format!(
+            "#[defiant(full_name = \"{}\")]\n",
+            fq_name.trim_start_matches('.')
+        ));
+    }
+
     fn append_skip_debug(&mut self, fq_message_name: &str) {
         if self.context.should_skip_debug(fq_message_name) {
             push_indent(self.buf, self.depth);
@@ -433,12 +571,17 @@ impl<'buf, 'ctx, 'arena> CodeGenerator<'buf, 'ctx, 'arena> {
         let type_ = field.descriptor.r#type();
         let repeated = field.descriptor.label() == Label::Repeated;
         let deprecated = self.deprecated(&field.descriptor);
-        let optional = self.optional(&field.descriptor);
+        let optional = self.optional(field);
         // For arena types, we don't need Box because &'arena T already breaks cycles
         // let boxed = self
         //     .context
         //     .should_box_message_field(fq_message_name, &field.descriptor);
         let ty = self.resolve_type(&field.descriptor, fq_message_name);
+        // Scalar-only messages are stored by value, there is nothing to gain from deferring them
+        let lazy = self
+            .context
+            .should_lazy_field(fq_message_name, &field.descriptor)
+            && ty.contains("<'arena>");
 
         debug!("    field: {:?}, type: {:?}", field.descriptor.name(), ty,);
 
@@ -472,21 +615,23 @@ impl<'buf, 'ctx, 'arena> CodeGenerator<'buf, 'ctx, 'arena> {
             Label::Repeated => {
                 self.buf.push_str(", repeated");
                 if can_pack(&field.descriptor)
-                    && !field
-                        .descriptor
-                        .options
-                        .as_ref()
-                        .map_or(self.syntax == Syntax::Proto3, |options| options.packed())
+                    && field.features.repeated_field_encoding == RepeatedFieldEncoding::Expanded
                 {
                     self.buf.push_str(", packed = \"false\"");
                 }
             }
         }
 
+        if lazy {
+            self.buf.push_str(", lazy");
+        }
+
         // For arena types, we don't need the boxed attribute
         // if boxed {
         //     self.buf.push_str(", boxed");
         // }
+        self.buf
+            .push_str(&field.name_attributes(&field.inferred_field_name()));
         self.buf.push_str(", tag = \"");
         self.buf.push_str(&field.descriptor.number().to_string());
 
@@ -543,10 +688,16 @@ impl<'buf, 'ctx, 'arena> CodeGenerator<'buf, 'ctx, 'arena> {
         // For message and group fields that have arena lifetimes, use &'arena references
         // Scalar-only messages (no lifetime) are stored by value
         // For repeated messages, the slice contains references: &'arena [&'arena T]
-        if (type_ == Type::Message || type_ == Type::Group) && ty.contains("<'arena>") {
-            self.buf.push_str("&'arena ");
+        if lazy {
+            let prost_path = self.context.prost_path();
+            self.buf
+                .push_str(&format!("{prost_path}::LazyMessage<'arena, {ty}>"));
+        } else {
+            if (type_ == Type::Message || type_ == Type::Group) && ty.contains("<'arena>") {
+                self.buf.push_str("&'arena ");
+            }
+            self.buf.push_str(&ty);
         }
-        self.buf.push_str(&ty);
         // if boxed {
         //     self.buf.push('>');
         // }
@@ -585,10 +736,11 @@ impl<'buf, 'ctx, 'arena> CodeGenerator<'buf, 'ctx, 'arena> {
         let value_tag = self.map_value_type_tag(value);
 
         self.buf.push_str(&format!(
-            "#[defiant({} = \"{}, {}\", tag = \"{}\")]\n",
+            "#[defiant({} = \"{}, {}\"{}, tag = \"{}\")]\n",
             map_type.annotation(),
             key_tag,
             value_tag,
+            field.name_attributes(&field.inferred_field_name()),
             field.descriptor.number()
         ));
         self.append_field_attributes(fq_message_name, field.descriptor.name());
@@ -612,14 +764,7 @@ impl<'buf, 'ctx, 'arena> CodeGenerator<'buf, 'ctx, 'arena> {
         let type_name = format!("{}::{}", to_snake(message_name), oneof.type_name());
 
         // Check if this oneof needs a lifetime parameter
-        let needs_lifetime = oneof
-            .fields
-            .iter()
-            .any(|field| match field.descriptor.r#type() {
-                Type::String | Type::Bytes => true,
-                Type::Message => self.message_type_needs_lifetime(field.descriptor.type_name()),
-                _ => false,
-            });
+        let needs_lifetime = self.oneof_needs_lifetime(oneof);
 
         let full_type_name = if needs_lifetime {
             format!("{}<'arena>", type_name)
@@ -647,6 +792,16 @@ impl<'buf, 'ctx, 'arena> CodeGenerator<'buf, 'ctx, 'arena> {
         ));
     }
 
+    fn append_unknown_fields(&mut self) {
+        self.push_indent();
+        self.buf.push_str("#[defiant(unknown_fields)]\n");
+        self.push_indent();
+        self.buf.push_str(&format!(
+            "pub unknown_fields: {}::UnknownFields<'arena>,\n",
+            self.context.prost_path()
+        ));
+    }
+
     fn append_oneof(&mut self, fq_message_name: &str, oneof: &OneofField) {
         self.path.push(8);
         self.path.push(oneof.path_index);
@@ -681,14 +836,7 @@ impl<'buf, 'ctx, 'arena> CodeGenerator<'buf, 'ctx, 'arena> {
         self.append_skip_debug(fq_message_name);
 
         // Check if any oneof field needs arena allocation
-        let needs_lifetime = oneof
-            .fields
-            .iter()
-            .any(|field| match field.descriptor.r#type() {
-                Type::String | Type::Bytes => true,
-                Type::Message => self.message_type_needs_lifetime(field.descriptor.type_name()),
-                _ => false,
-            });
+        let needs_lifetime = self.oneof_needs_lifetime(oneof);
 
         self.push_indent();
         self.buf.push_str("pub enum ");
@@ -713,8 +861,9 @@ impl<'buf, 'ctx, 'arena> CodeGenerator<'buf, 'ctx, 'arena> {
             self.push_indent();
             let ty_tag = self.field_type_tag(&field.descriptor);
             self.buf.push_str(&format!(
-                "#[defiant({}, tag = \"{}\")]\n",
+                "#[defiant({}{}, tag = \"{}\")]\n",
                 ty_tag,
+                field.name_attributes(&field.inferred_variant_name()),
                 field.descriptor.number()
             ));
             self.append_field_attributes(&oneof_name, field.descriptor.name());
@@ -755,6 +904,300 @@ impl<'buf, 'ctx, 'arena> CodeGenerator<'buf, 'ctx, 'arena> {
 
         self.push_indent();
         self.buf.push_str("}\n");
+
+        if needs_lifetime && self.config().owned_types {
+            self.append_owned_oneof(fq_message_name, oneof);
+        }
+    }
+
+    /// Appends the owned counterpart of a message, and the conversions between it and the view.
+    fn append_owned_message(
+        &mut self,
+        message_name: &str,
+        fq_message_name: &str,
+        fields: &[Field],
+        map_types: &HashMap<String, (FieldDescriptorProto, FieldDescriptorProto)>,
+        oneof_fields: &[OneofField],
+        preserve_unknown_fields: bool,
+    ) {
+        let prost_path = self.context.prost_path().to_string();
+        let view_name = to_upper_camel(message_name);
+        let owned_name = format!("{view_name}Owned");
+
+        let mut owned_fields = Vec::new();
+        for field in fields {
+            let descriptor = &field.descriptor;
+            let owned_field = match descriptor
+                .type_name
+                .as_ref()
+                .and_then(|type_name| map_types.get(*type_name))
+            {
+                Some((key, value)) => OwnedField::converted(
+                    field.rust_name(),
+                    format!(
+                        "{prost_path}::alloc::collections::BTreeMap<{}, {}>",
+                        self.owned_type(key, fq_message_name),
+                        self.owned_type(value, fq_message_name)
+                    ),
+                    &prost_path,
+                ),
+                None => self.owned_field(fq_message_name, field),
+            };
+            owned_fields.push(OwnedField {
+                deprecated: self.deprecated(descriptor),
+                ..owned_field
+            });
+        }
+        for oneof in oneof_fields {
+            let mut ty = format!("{}::{}", to_snake(message_name), oneof.type_name());
+            if self.oneof_needs_lifetime(oneof) {
+                ty.push_str("Owned");
+            }
+            owned_fields.push(OwnedField::converted(
+                oneof.rust_name(),
+                format!("::core::option::Option<{ty}>"),
+                &prost_path,
+            ));
+        }
+        if preserve_unknown_fields {
+            owned_fields.push(OwnedField::converted(
+                "unknown_fields".to_string(),
+                format!("{prost_path}::alloc::vec::Vec<u8>"),
+                &prost_path,
+            ));
+        }
+        let allow_deprecated = if owned_fields.iter().any(|field| field.deprecated) {
+            "#[allow(deprecated)]\n"
+        } else {
+            ""
+        };
+
+        self.push_indent();
+        self.buf.push_str(&format!(
+            "/// Owned counterpart of [`{view_name}`], which borrows from no arena and can be sent to other threads.\n"
+        ));
+        self.push_owned_derive(fq_message_name);
+        self.push_indent();
+        self.buf.push_str(&format!("pub struct {owned_name} {{\n"));
+        self.depth += 1;
+        for field in &owned_fields {
+            if field.deprecated {
+                self.push_indent();
+                self.buf.push_str("#[deprecated]\n");
+            }
+            self.push_indent();
+            self.buf
+                .push_str(&format!("pub {}: {},\n", field.name, field.ty));
+        }
+        self.depth -= 1;
+        self.push_indent();
+        self.buf.push_str("}\n");
+
+        // Views of messages without repeated fields are `Copy`
+        let allow_by_ref = if self.context.can_message_derive_copy(fq_message_name) {
+            "#[allow(clippy::wrong_self_convention)]\n"
+        } else {
+            ""
+        };
+        self.buf.push_str(&format!(
+            "impl {view_name}<'_> {{\n\
+             /// Copies the message out of its arena.\n\
+             {allow_by_ref}pub fn to_owned(&self) -> {owned_name} {{\n\
+             {prost_path}::ToOwnedValue::to_owned_value(self)\n\
+             }}\n\
+             }}\n"
+        ));
+        self.buf.push_str(&format!(
+            "{allow_deprecated}impl {prost_path}::ToOwnedValue for {view_name}<'_> {{\n\
+             type Owned = {owned_name};\n\
+             fn to_owned_value(&self) -> {owned_name} {{\n\
+             {owned_name} {{\n"
+        ));
+        for field in &owned_fields {
+            self.buf
+                .push_str(&format!("{}: {},\n", field.name, field.to_owned));
+        }
+        self.buf.push_str("}\n}\n}\n");
+        self.buf.push_str(&format!(
+            "impl {owned_name} {{\n\
+             /// Copies the message into `arena`.\n\
+             pub fn view_in<'arena>(&self, arena: &'arena {prost_path}::Arena) -> {view_name}<'arena> {{\n\
+             {prost_path}::ArenaInto::arena_into(self, arena)\n\
+             }}\n\
+             }}\n"
+        ));
+        self.buf.push_str(&format!(
+            "{allow_deprecated}impl<'arena> {prost_path}::ArenaFrom<'arena, &{owned_name}> for {view_name}<'arena> {{\n\
+             fn arena_from(value: &{owned_name}, arena: &'arena {prost_path}::Arena) -> Self {{\n\
+             {view_name} {{\n"
+        ));
+        for field in &owned_fields {
+            self.buf
+                .push_str(&format!("{}: {},\n", field.name, field.from_owned));
+        }
+        self.buf.push_str("}\n}\n}\n");
+    }
+
+    /// Appends the owned counterpart of a oneof, and the conversions between it and the view.
+    fn append_owned_oneof(&mut self, fq_message_name: &str, oneof: &OneofField) {
+        let prost_path = self.context.prost_path().to_string();
+        let view_name = oneof.type_name();
+        let owned_name = format!("{view_name}Owned");
+
+        self.push_indent();
+        self.buf.push_str(&format!(
+            "/// Owned counterpart of [`{view_name}`], which borrows from no arena.\n"
+        ));
+        self.push_owned_derive(fq_message_name);
+        self.push_indent();
+        self.buf.push_str(&format!("pub enum {owned_name} {{\n"));
+        let mut to_owned_arms = String::new();
+        let mut from_owned_arms = String::new();
+        self.depth += 1;
+        for field in &oneof.fields {
+            let variant = to_upper_camel(field.descriptor.name());
+            let boxed = self
+                .context
+                .should_box_owned_field(fq_message_name, &field.descriptor);
+            let mut ty = self.owned_type(&field.descriptor, fq_message_name);
+            let allow_deprecated = if self.deprecated(&field.descriptor) {
+                self.push_indent();
+                self.buf.push_str("#[deprecated]\n");
+                "#[allow(deprecated)]\n"
+            } else {
+                ""
+            };
+            let (to_owned, from_owned) = if boxed {
+                ty = format!("{prost_path}::alloc::boxed::Box<{ty}>");
+                (
+                    format!("{prost_path}::alloc::boxed::Box::new({prost_path}::ToOwnedValue::to_owned_value(value))"),
+                    format!("{prost_path}::ArenaFrom::arena_from(&**value, arena)"),
+                )
+            } else {
+                (
+                    format!("{prost_path}::ToOwnedValue::to_owned_value(value)"),
+                    format!("{prost_path}::ArenaFrom::arena_from(value, arena)"),
+                )
+            };
+            self.push_indent();
+            self.buf.push_str(&format!("{variant}({ty}),\n"));
+            to_owned_arms.push_str(&format!(
+                "{allow_deprecated}{view_name}::{variant}(value) => {owned_name}::{variant}({to_owned}),\n"
+            ));
+            from_owned_arms.push_str(&format!(
+                "{allow_deprecated}{owned_name}::{variant}(value) => {view_name}::{variant}({from_owned}),\n"
+            ));
+        }
+        self.depth -= 1;
+        self.push_indent();
+        self.buf.push_str("}\n");
+
+        self.buf.push_str(&format!(
+            "impl {prost_path}::ToOwnedValue for {view_name}<'_> {{\n\
+             type Owned = {owned_name};\n\
+             fn to_owned_value(&self) -> {owned_name} {{\n\
+             match self {{\n\
+             {to_owned_arms}\
+             }}\n}}\n}}\n"
+        ));
+        self.buf.push_str(&format!(
+            "impl<'arena> {prost_path}::ArenaFrom<'arena, &{owned_name}> for {view_name}<'arena> {{\n\
+             fn arena_from(value: &{owned_name}, arena: &'arena {prost_path}::Arena) -> Self {{\n\
+             match value {{\n\
+             {from_owned_arms}\
+             }}\n}}\n}}\n"
+        ));
+    }
+
+    fn push_owned_derive(&mut self, fq_message_name: &str) {
+        self.push_indent();
+        self.buf
+            .push_str(if self.context.should_skip_debug(fq_message_name) {
+                "#[derive(Clone, PartialEq)]\n"
+            } else {
+                "#[derive(Clone, PartialEq, Debug)]\n"
+            });
+    }
+
+    /// Returns the owned counterpart of a message field, with the conversions to and from it.
+    fn owned_field(&self, fq_message_name: &str, field: &Field) -> OwnedField {
+        let prost_path = self.context.prost_path();
+        let descriptor = &field.descriptor;
+        let name = field.rust_name();
+        let ty = self.owned_type(descriptor, fq_message_name);
+        let lazy = self.context.should_lazy_field(fq_message_name, descriptor)
+            && self
+                .resolve_type(descriptor, fq_message_name)
+                .contains("<'arena>");
+
+        if descriptor.label() == Label::Repeated {
+            OwnedField::converted(
+                name,
+                format!("{prost_path}::alloc::vec::Vec<{ty}>"),
+                prost_path,
+            )
+        } else if lazy {
+            OwnedField::converted(
+                name,
+                format!("::core::option::Option<{prost_path}::alloc::vec::Vec<u8>>"),
+                prost_path,
+            )
+        } else if self
+            .context
+            .should_box_owned_field(fq_message_name, descriptor)
+        {
+            let boxed = format!("{prost_path}::alloc::boxed::Box<{ty}>");
+            if self.optional(field) {
+                OwnedField {
+                    ty: format!("::core::option::Option<{boxed}>"),
+                    to_owned: format!(
+                        "self.{name}.as_ref().map(|value| {prost_path}::alloc::boxed::Box::new({prost_path}::ToOwnedValue::to_owned_value(value)))"
+                    ),
+                    from_owned: format!(
+                        "value.{name}.as_deref().map(|value| {prost_path}::ArenaFrom::arena_from(value, arena))"
+                    ),
+                    name,
+                    deprecated: false,
+                }
+            } else {
+                OwnedField {
+                    ty: boxed,
+                    to_owned: format!(
+                        "{prost_path}::alloc::boxed::Box::new({prost_path}::ToOwnedValue::to_owned_value(&self.{name}))"
+                    ),
+                    from_owned: format!("{prost_path}::ArenaFrom::arena_from(&*value.{name}, arena)"),
+                    name,
+                    deprecated: false,
+                }
+            }
+        } else if self.optional(field) {
+            OwnedField::converted(name, format!("::core::option::Option<{ty}>"), prost_path)
+        } else {
+            OwnedField::converted(name, ty, prost_path)
+        }
+    }
+
+    /// Returns the owned type of a single value of the field.
+    fn owned_type(&self, field: &FieldDescriptorProto, fq_message_name: &str) -> String {
+        let prost_path = self.context.prost_path();
+        match field.r#type() {
+            Type::String => format!("{prost_path}::alloc::string::String"),
+            Type::Bytes => format!("{prost_path}::alloc::vec::Vec<u8>"),
+            Type::Message | Type::Group if self.message_type_needs_lifetime(field.type_name()) => {
+                let ident = self.resolve_ident(field.type_name());
+                // Extern messages name their owned counterpart through `ToOwnedValue`
+                if self
+                    .context
+                    .resolve_extern_ident(field.type_name())
+                    .is_some()
+                {
+                    format!("<{ident}<'static> as {prost_path}::ToOwnedValue>::Owned")
+                } else {
+                    format!("{ident}Owned")
+                }
+            }
+            _ => self.resolve_type(field, fq_message_name),
+        }
     }
 
     fn location(&self) -> Option<&Location<'_>> {
@@ -811,6 +1254,7 @@ impl<'buf, 'ctx, 'arena> CodeGenerator<'buf, 'ctx, 'arena> {
             self.context.prost_path(),
         ));
         self.append_prost_path_attribute();
+        self.append_full_name(&fq_proto_enum_name);
         self.push_indent();
         self.buf.push_str("#[repr(i32)]\n");
         self.push_indent();
@@ -833,6 +1277,10 @@ impl<'buf, 'ctx, 'arena> CodeGenerator<'buf, 'ctx, 'arena> {
                 self.buf.push_str("#[deprecated]\n");
             }
             self.push_indent();
+            self.buf.push_str("#[defiant(name = \"");
+            self.buf.push_str(variant.proto_name);
+            self.buf.push_str("\")]\n");
+            self.push_indent();
             self.buf.push_str(&variant.generated_variant_name);
             self.buf.push_str(" = ");
             self.buf.push_str(&variant.proto_number.to_string());
@@ -940,6 +1388,85 @@ impl<'buf, 'ctx, 'arena> CodeGenerator<'buf, 'ctx, 'arena> {
         self.buf.push_str("}\n"); // End of impl
     }
 
+    /// Appends the `Extension` constant of a field declared in an `extend` block.
+    fn append_extension(&mut self, extension: &FieldDescriptorProto<'arena>) {
+        debug!("  extension: {:?}", extension.name());
+
+        let mut extension = extension.clone();
+        self.features.field(&extension, false).lower(&mut extension);
+        let extension = &extension;
+
+        let fq_name = self.fq_name(extension.name());
+        let extendee = extension.extendee();
+        let prost_path = self.context.prost_path().to_owned();
+
+        let mut extendee_type = self.resolve_ident(extendee);
+        if self.message_type_needs_lifetime(extendee) {
+            extendee_type.push_str("<'static>");
+        }
+
+        let message_type = || {
+            let ident = self.resolve_ident(extension.type_name());
+            if self.message_type_needs_lifetime(extension.type_name()) {
+                format!("{ident}<'static>")
+            } else {
+                ident
+            }
+        };
+        let (value_type, field_type) = match extension.r#type() {
+            Type::Enum => (
+                String::from("i32"),
+                format!(
+                    "{prost_path}::FieldType::Enum({prost_path}::EnumType::of::<{}>())",
+                    self.resolve_ident(extension.type_name())
+                ),
+            ),
+            Type::Message => (
+                message_type(),
+                format!(
+                    "{prost_path}::FieldType::Message({prost_path}::MessageType::of::<{}>())",
+                    message_type()
+                ),
+            ),
+            Type::Group => (
+                message_type(),
+                format!(
+                    "{prost_path}::FieldType::Group({prost_path}::MessageType::of::<{}>(), {:?})",
+                    message_type(),
+                    to_upper_camel(extension.type_name().rsplit('.').next().unwrap()),
+                ),
+            ),
+            ty => (
+                match ty {
+                    Type::String => String::from("&'static str"),
+                    Type::Bytes => String::from("&'static [u8]"),
+                    _ => self.resolve_type(extension, extendee),
+                },
+                format!("{prost_path}::FieldType::Scalar({prost_path}::ScalarType::{ty:?})"),
+            ),
+        };
+        let (value_type, repeated) = if extension.label() == Label::Repeated {
+            (format!("&'static [{value_type}]"), ".repeated()")
+        } else {
+            (value_type, "")
+        };
+
+        self.append_doc(&fq_name, None);
+        if self.deprecated(extension) {
+            self.push_indent();
+            self.buf.push_str("#[deprecated]\n");
+        }
+        self.push_indent();
+        self.buf.push_str(&format!(
+            "pub const {}: {prost_path}::Extension<{extendee_type}, {value_type}> = \
+             {prost_path}::Extension::new({:?}, {prost_path}::FieldInfo::new({:?}, {}, {field_type}){repeated});\n",
+            to_snake(extension.name()).to_uppercase(),
+            &extendee[1..],
+            &fq_name[1..],
+            extension.number(),
+        ));
+    }
+
     fn push_service(&mut self, service: &ServiceDescriptorProto<'arena>) {
         let name = service.name().to_owned();
         debug!("  service: {:?}", name);
@@ -992,7 +1519,9 @@ impl<'buf, 'ctx, 'arena> CodeGenerator<'buf, 'ctx, 'arena> {
                         .unwrap_or(defiant_types::MethodOptions {
                             deprecated: None,
                             idempotency_level: None,
+                            features: None,
                             uninterpreted_option: &[],
+                            unknown_fields: Default::default(),
                         }),
                     client_streaming,
                     server_streaming,
@@ -1012,7 +1541,9 @@ impl<'buf, 'ctx, 'arena> CodeGenerator<'buf, 'ctx, 'arena> {
                 .cloned()
                 .unwrap_or(defiant_types::ServiceOptions {
                     deprecated: None,
+                    features: None,
                     uninterpreted_option: &[],
+                    unknown_fields: Default::default(),
                 }),
         };
 
@@ -1050,6 +1581,18 @@ impl<'buf, 'ctx, 'arena> CodeGenerator<'buf, 'ctx, 'arena> {
         self.buf.push_str("}\n");
     }
 
+    /// Checks if a oneof needs a lifetime parameter based on its fields
+    fn oneof_needs_lifetime(&self, oneof: &OneofField) -> bool {
+        oneof
+            .fields
+            .iter()
+            .any(|field| match field.descriptor.r#type() {
+                Type::String | Type::Bytes => true,
+                Type::Message => self.message_type_needs_lifetime(field.descriptor.type_name()),
+                _ => false,
+            })
+    }
+
     /// Checks if a message needs a lifetime parameter based on its fields
     fn message_needs_lifetime(&self, message: &DescriptorProto<'arena>) -> bool {
         // Check all fields to see if any require arena allocation
@@ -1093,6 +1636,13 @@ impl<'buf, 'ctx, 'arena> CodeGenerator<'buf, 'ctx, 'arena> {
             return true;
         }
 
+        // Messages preserving unknown fields always borrow them from the arena
+        if self.context.resolve_extern_ident(type_name).is_none()
+            && self.context.should_preserve_unknown_fields(type_name)
+        {
+            return true;
+        }
+
         // Try to look it up in the message graph
         if let Some(message_desc) = self.context.message_graph().get_message(type_name) {
             // Recursively analyze the message to determine if it needs a lifetime
@@ -1225,19 +1775,9 @@ impl<'buf, 'ctx, 'arena> CodeGenerator<'buf, 'ctx, 'arena> {
         }
     }
 
-    fn optional(&self, field: &FieldDescriptorProto) -> bool {
-        if field.proto3_optional.unwrap_or(false) {
-            return true;
-        }
-
-        if field.label() != Label::Optional {
-            return false;
-        }
-
-        match field.r#type() {
-            Type::Message => true,
-            _ => self.syntax == Syntax::Proto2,
-        }
+    fn optional(&self, field: &Field) -> bool {
+        field.descriptor.label() == Label::Optional
+            && field.features.field_presence == FieldPresence::Explicit
     }
 
     /// Returns `true` if the field options includes the `deprecated` option.
@@ -0,0 +1,369 @@
+use defiant_types::feature_set::{
+    EnumType, FieldPresence, MessageEncoding, RepeatedFieldEncoding, Utf8Validation,
+};
+use defiant_types::field_descriptor_proto::{Label, Type};
+use defiant_types::{
+    DescriptorProto, Edition, EnumDescriptorProto, FeatureSet, FieldDescriptorProto,
+    FileDescriptorProto,
+};
+
+use super::syntax::Syntax;
+
+/// Resolved [features][1] of a file, message, enum or field.
+///
+/// Resolution starts from the defaults of the file's edition, where proto2 and proto3 files
+/// behave as editions of their own, and merges the `features` option of every enclosing scope
+/// down to the element itself.
+///
+/// Enums are always open and strings are always checked as UTF-8, so the `enum_type` and
+/// `utf8_validation` features aren't resolved, and editions files which set them otherwise fail
+/// to generate. Proto2 files generate the same way, as they did with prost.
+///
+/// [1]: https://protobuf.dev/editions/features/
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(super) struct Features {
+    pub(super) field_presence: FieldPresence,
+    pub(super) repeated_field_encoding: RepeatedFieldEncoding,
+    pub(super) message_encoding: MessageEncoding,
+}
+
+impl Features {
+    /// Returns the defaults of `edition`, or an error if it isn't a supported edition.
+    pub(super) fn defaults(edition: Edition) -> Result<Self, String> {
+        Ok(match edition {
+            Edition::Legacy | Edition::Proto2 => Self {
+                field_presence: FieldPresence::Explicit,
+                repeated_field_encoding: RepeatedFieldEncoding::Expanded,
+                message_encoding: MessageEncoding::LengthPrefixed,
+            },
+            Edition::Proto3 => Self {
+                field_presence: FieldPresence::Implicit,
+                repeated_field_encoding: RepeatedFieldEncoding::Packed,
+                message_encoding: MessageEncoding::LengthPrefixed,
+            },
+            Edition::Edition2023 | Edition::Edition2024 => Self {
+                field_presence: FieldPresence::Explicit,
+                ..Self::defaults(Edition::Proto3)?
+            },
+            edition => return Err(format!("unsupported edition: {}", edition.as_str_name())),
+        })
+    }
+
+    /// Returns the features of `file`, from its syntax or edition and its options, or an error if
+    /// it sets features which aren't supported.
+    pub(super) fn file(file: &FileDescriptorProto) -> Result<Self, String> {
+        let edition = match Syntax::from(file.syntax) {
+            Syntax::Proto2 => Edition::Proto2,
+            Syntax::Proto3 => Edition::Proto3,
+            Syntax::Editions => {
+                check_file(file).map_err(|error| format!("{}: {error}", file.name()))?;
+                file.edition()
+            }
+        };
+        let defaults =
+            Self::defaults(edition).map_err(|error| format!("{}: {error}", file.name()))?;
+        Ok(defaults.merge(file.options.and_then(|options| options.features)))
+    }
+
+    /// Overrides the features which are set in `features`.
+    pub(super) fn merge(mut self, features: Option<FeatureSet>) -> Self {
+        let Some(features) = features else {
+            return self;
+        };
+        if features.field_presence() != FieldPresence::Unknown {
+            self.field_presence = features.field_presence();
+        }
+        if features.repeated_field_encoding() != RepeatedFieldEncoding::Unknown {
+            self.repeated_field_encoding = features.repeated_field_encoding();
+        }
+        if features.message_encoding() != MessageEncoding::Unknown {
+            self.message_encoding = features.message_encoding();
+        }
+        self
+    }
+
+    /// Returns the features of `field`, declared in a scope with these features.
+    ///
+    /// Besides the field's own `features` option, this applies what proto2 and proto3 express
+    /// through the descriptor: `required` labels, groups, oneofs and the `packed` option. Message
+    /// fields always track presence, and map fields are always length-prefixed.
+    pub(super) fn field(self, field: &FieldDescriptorProto, map: bool) -> Self {
+        let mut features = self.merge(field.options.and_then(|options| options.features));
+        if field.label() == Label::Required {
+            features.field_presence = FieldPresence::LegacyRequired;
+        } else if features.field_presence != FieldPresence::LegacyRequired
+            && (field.oneof_index.is_some()
+                || matches!(field.r#type(), Type::Message | Type::Group))
+        {
+            features.field_presence = FieldPresence::Explicit;
+        }
+        if field.r#type() == Type::Group {
+            features.message_encoding = MessageEncoding::Delimited;
+        } else if map {
+            features.message_encoding = MessageEncoding::LengthPrefixed;
+        }
+        if let Some(packed) = field.options.and_then(|options| options.packed) {
+            features.repeated_field_encoding = if packed {
+                RepeatedFieldEncoding::Packed
+            } else {
+                RepeatedFieldEncoding::Expanded
+            };
+        }
+        features
+    }
+
+    /// Rewrites `field` into the proto2 shape these features correspond to, so that
+    /// `LEGACY_REQUIRED` fields generate as `required` and `DELIMITED` message fields as groups.
+    pub(super) fn lower(self, field: &mut FieldDescriptorProto) {
+        if self.field_presence == FieldPresence::LegacyRequired {
+            field.label = Some(Label::Required as i32);
+        }
+        if self.message_encoding == MessageEncoding::Delimited && field.r#type() == Type::Message {
+            field.r#type = Some(Type::Group as i32);
+        }
+    }
+}
+
+/// Fails if the options of `file`, or of an element declared in it, set a feature which isn't
+/// supported.
+fn check_file(file: &FileDescriptorProto) -> Result<(), String> {
+    check(file.options.and_then(|options| options.features))?;
+    let scope = file
+        .package
+        .map(|package| format!(".{package}"))
+        .unwrap_or_default();
+    for message in file.message_type {
+        check_message(message, &scope)?;
+    }
+    for enumeration in file.enum_type {
+        check_enum(enumeration, &scope)?;
+    }
+    for field in file.extension {
+        check_field(field, &scope)?;
+    }
+    Ok(())
+}
+
+fn check_message(message: &DescriptorProto, scope: &str) -> Result<(), String> {
+    let scope = format!("{scope}.{}", message.name());
+    check(message.options.and_then(|options| options.features))
+        .map_err(|error| format!("{scope}: {error}"))?;
+    for field in message.field.iter().chain(message.extension) {
+        check_field(field, &scope)?;
+    }
+    for oneof in message.oneof_decl {
+        check(oneof.options.and_then(|options| options.features))
+            .map_err(|error| format!("{scope}.{}: {error}", oneof.name()))?;
+    }
+    for nested in message.nested_type {
+        check_message(nested, &scope)?;
+    }
+    for enumeration in message.enum_type {
+        check_enum(enumeration, &scope)?;
+    }
+    Ok(())
+}
+
+fn check_enum(enumeration: &EnumDescriptorProto, scope: &str) -> Result<(), String> {
+    check(enumeration.options.and_then(|options| options.features))
+        .map_err(|error| format!("{scope}.{}: {error}", enumeration.name()))
+}
+
+fn check_field(field: &FieldDescriptorProto, scope: &str) -> Result<(), String> {
+    check(field.options.and_then(|options| options.features))
+        .map_err(|error| format!("{scope}.{}: {error}", field.name()))
+}
+
+/// Fails if `features` make enums closed or skip UTF-8 validation of strings.
+fn check(features: Option<FeatureSet>) -> Result<(), String> {
+    let Some(features) = features else {
+        return Ok(());
+    };
+    if features.enum_type() == EnumType::Closed {
+        Err(String::from("unsupported feature: enum_type = CLOSED"))
+    } else if features.utf8_validation() == Utf8Validation::None {
+        Err(String::from("unsupported feature: utf8_validation = NONE"))
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use defiant::Arena;
+    use defiant_types::{FieldDescriptorProtoBuilder, FileDescriptorProtoBuilder};
+
+    fn field<'arena>(text: &str, arena: &'arena Arena) -> FieldDescriptorProto<'arena> {
+        FieldDescriptorProtoBuilder::parse_text(text, arena)
+            .unwrap()
+            .freeze()
+    }
+
+    #[test]
+    fn test_legacy_syntax_features() {
+        let arena = Arena::new();
+        let proto2 = Features::defaults(Edition::Proto2).unwrap();
+        let proto3 = Features::defaults(Edition::Proto3).unwrap();
+
+        let required = field(r#"label: LABEL_REQUIRED type: TYPE_INT32"#, &arena);
+        assert_eq!(
+            proto2.field(&required, false).field_presence,
+            FieldPresence::LegacyRequired
+        );
+
+        let optional = field(
+            r#"label: LABEL_OPTIONAL type: TYPE_INT32 oneof_index: 0 proto3_optional: true"#,
+            &arena,
+        );
+        assert_eq!(
+            proto3.field(&optional, false).field_presence,
+            FieldPresence::Explicit
+        );
+
+        let group = field(r#"label: LABEL_OPTIONAL type: TYPE_GROUP"#, &arena);
+        assert_eq!(
+            proto2.field(&group, false).message_encoding,
+            MessageEncoding::Delimited
+        );
+
+        let packed = field(
+            r#"label: LABEL_REPEATED type: TYPE_INT32 options { packed: true }"#,
+            &arena,
+        );
+        assert_eq!(
+            proto2.field(&packed, false).repeated_field_encoding,
+            RepeatedFieldEncoding::Packed
+        );
+    }
+
+    #[test]
+    fn test_edition_features() {
+        let arena = Arena::new();
+        let features = Features::defaults(Edition::Edition2023).unwrap();
+        assert_eq!(features.field_presence, FieldPresence::Explicit);
+        assert_eq!(
+            features.repeated_field_encoding,
+            RepeatedFieldEncoding::Packed
+        );
+
+        let features = features.merge(Some(FeatureSet {
+            field_presence: Some(FieldPresence::Implicit as i32),
+            enum_type: None,
+            repeated_field_encoding: None,
+            utf8_validation: None,
+            message_encoding: Some(MessageEncoding::Delimited as i32),
+            json_format: None,
+        }));
+        assert_eq!(features.field_presence, FieldPresence::Implicit);
+        assert_eq!(features.message_encoding, MessageEncoding::Delimited);
+
+        // Message fields track presence, and map fields stay length-prefixed
+        let mut message = field(
+            r#"label: LABEL_OPTIONAL type: TYPE_MESSAGE type_name: ".test.Item""#,
+            &arena,
+        );
+        let message_features = features.field(&message, false);
+        assert_eq!(message_features.field_presence, FieldPresence::Explicit);
+        message_features.lower(&mut message);
+        assert_eq!(message.r#type(), Type::Group);
+
+        let mut map = field(
+            r#"label: LABEL_REPEATED type: TYPE_MESSAGE type_name: ".test.Order.CountsEntry""#,
+            &arena,
+        );
+        features.field(&map, true).lower(&mut map);
+        assert_eq!(map.r#type(), Type::Message);
+
+        let mut required = field(
+            r#"label: LABEL_OPTIONAL type: TYPE_STRING options { features { field_presence: LEGACY_REQUIRED } }"#,
+            &arena,
+        );
+        features.field(&required, false).lower(&mut required);
+        assert_eq!(required.label(), Label::Required);
+    }
+
+    #[test]
+    fn test_unsupported_editions() {
+        let arena = Arena::new();
+        for edition in [
+            Edition::Unknown,
+            Edition::Max,
+            Edition::Edition99999TestOnly,
+        ] {
+            assert_eq!(
+                Features::defaults(edition),
+                Err(format!("unsupported edition: {}", edition.as_str_name()))
+            );
+        }
+
+        let file = FileDescriptorProtoBuilder::parse_text(
+            r#"name: "future.proto" syntax: "editions" edition: EDITION_MAX"#,
+            &arena,
+        )
+        .unwrap()
+        .freeze();
+        assert_eq!(
+            Features::file(&file),
+            Err(String::from(
+                "future.proto: unsupported edition: EDITION_MAX"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_unsupported_features() {
+        let arena = Arena::new();
+        let check = |text: &str| {
+            let file = FileDescriptorProtoBuilder::parse_text(text, &arena)
+                .unwrap()
+                .freeze();
+            Features::file(&file).map(|_| ())
+        };
+
+        assert_eq!(
+            check(
+                r#"name: "closed.proto" package: "test" syntax: "editions" edition: EDITION_2023
+                enum_type { name: "Color" options { features { enum_type: CLOSED } } }"#
+            ),
+            Err(String::from(
+                "closed.proto: .test.Color: unsupported feature: enum_type = CLOSED"
+            ))
+        );
+        assert_eq!(
+            check(
+                r#"name: "bytes.proto" syntax: "editions" edition: EDITION_2023
+                message_type {
+                  name: "Text"
+                  field { name: "body" number: 1 type: TYPE_STRING options { features { utf8_validation: NONE } } }
+                }"#
+            ),
+            Err(String::from(
+                "bytes.proto: .Text.body: unsupported feature: utf8_validation = NONE"
+            ))
+        );
+        assert_eq!(
+            check(
+                r#"name: "file.proto" syntax: "editions" edition: EDITION_2023
+                options { features { enum_type: CLOSED } }"#
+            ),
+            Err(String::from(
+                "file.proto: unsupported feature: enum_type = CLOSED"
+            ))
+        );
+
+        // Proto2 enums are closed and its strings unchecked, but generate as open and checked
+        assert_eq!(
+            check(r#"name: "legacy.proto" syntax: "proto2" enum_type { name: "Color" }"#),
+            Ok(())
+        );
+        assert_eq!(
+            check(
+                r#"name: "open.proto" syntax: "editions" edition: EDITION_2023
+                options { features { enum_type: OPEN utf8_validation: VERIFY } }"#
+            ),
+            Ok(())
+        );
+    }
+}
@@ -14,7 +14,6 @@ use defiant_types::{FileDescriptorProto, FileDescriptorSet};
 
 use crate::code_generator::CodeGenerator;
 use crate::context::Context;
-use crate::descriptor_ext::*;
 use crate::extern_paths::ExternPaths;
 use crate::message_graph::MessageGraph;
 use crate::path::PathMap;
@@ -47,6 +46,10 @@ pub struct Config<'arena> {
     pub(crate) protoc_executable: PathBuf,
     pub(crate) disable_comments: PathMap<()>,
     pub(crate) skip_debug: PathMap<()>,
+    pub(crate) preserve_unknown_fields: PathMap<()>,
+    pub(crate) skip_unknown_fields: PathMap<()>,
+    pub(crate) lazy: PathMap<()>,
+    pub(crate) owned_types: bool,
     pub(crate) skip_protoc_run: bool,
     pub(crate) skip_source_info: bool,
     pub(crate) include_file: Option<PathBuf>,
@@ -85,6 +88,10 @@ impl<'arena> Config<'arena> {
             protoc_executable: protoc_from_env(),
             disable_comments: PathMap::default(),
             skip_debug: PathMap::default(),
+            preserve_unknown_fields: PathMap::default(),
+            skip_unknown_fields: PathMap::default(),
+            lazy: PathMap::default(),
+            owned_types: false,
             skip_protoc_run: false,
             skip_source_info: false,
             include_file: None,
@@ -478,6 +485,122 @@ impl<'arena> Config<'arena> {
         self
     }
 
+    /// Preserve unknown fields through decode and re-encode.
+    ///
+    /// Matching messages get an `unknown_fields: UnknownFields<'arena>` field marked
+    /// `#[defiant(unknown_fields)]`. Fields that are not part of the schema are collected there
+    /// while decoding and written back verbatim when the message is encoded, which lets a proxy
+    /// built against an older schema forward newer messages without losing data. Messages that
+    /// would otherwise be scalar-only gain an `'arena` lifetime.
+    ///
+    /// Messages declaring extension ranges preserve unknown fields whether or not they match,
+    /// since that is where their extensions are kept.
+    ///
+    /// # Arguments
+    ///
+    /// **`paths`** - paths to specific messages or packages which should preserve unknown fields.
+    /// Paths follow the same matching rules as [`btree_map`](#method.btree_map).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # let arena = defiant::Arena::new();
+    /// # let mut config = defiant_build::Config::new(&arena);
+    /// // Preserve unknown fields everywhere, except for one latency-critical message.
+    /// config.preserve_unknown_fields(&["."]);
+    /// config.skip_unknown_fields(&[".my_service.HotPath"]);
+    /// ```
+    pub fn preserve_unknown_fields<I, S>(&mut self, paths: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.preserve_unknown_fields.clear();
+        for matcher in paths {
+            self.preserve_unknown_fields
+                .insert(matcher.as_ref().to_string(), ());
+        }
+        self
+    }
+
+    /// Opts messages out of [`preserve_unknown_fields`](#method.preserve_unknown_fields).
+    ///
+    /// Unknown fields of matching messages are skipped while decoding, as they are by default.
+    pub fn skip_unknown_fields<I, S>(&mut self, paths: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.skip_unknown_fields.clear();
+        for matcher in paths {
+            self.skip_unknown_fields
+                .insert(matcher.as_ref().to_string(), ());
+        }
+        self
+    }
+
+    /// Decode matching message fields on demand.
+    ///
+    /// Matching fields are generated as `Option<LazyMessage<'arena, T>>` and marked
+    /// `#[defiant(message, lazy)]`. Decoding the parent only records where the sub-message's
+    /// bytes are; they are decoded by `LazyMessage::get`, and written back untouched when the
+    /// parent is encoded. This suits envelope-style messages whose payload is often forwarded
+    /// without being inspected.
+    ///
+    /// Only singular message fields outside of a `oneof` can be lazy, and only when the message
+    /// type has an `'arena` lifetime; other matching fields are generated as usual.
+    ///
+    /// # Arguments
+    ///
+    /// **`paths`** - paths to specific fields, messages, or packages whose message fields should
+    /// be lazy. Paths follow the same matching rules as [`btree_map`](#method.btree_map).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # let arena = defiant::Arena::new();
+    /// # let mut config = defiant_build::Config::new(&arena);
+    /// config.lazy(&[".my_service.Envelope.payload"]);
+    /// ```
+    pub fn lazy<I, S>(&mut self, paths: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.lazy.clear();
+        for matcher in paths {
+            self.lazy.insert(matcher.as_ref().to_string(), ());
+        }
+        self
+    }
+
+    /// Generate an owned counterpart of each message, which can cross thread boundaries.
+    ///
+    /// Views borrow from their arena, which ties them to the thread that owns it. When enabled,
+    /// every message `Foo<'arena>` gets a twin `FooOwned` holding `String`, `Vec` and `BTreeMap`
+    /// in place of arena slices and maps, and `Box` where a message field is recursive. Owned
+    /// messages are `Send + 'static`, and convert with `view.to_owned()` and
+    /// `owned.view_in(&arena)`, the latter through `ArenaFrom`/`ArenaInto`.
+    ///
+    /// Messages and oneofs without an `'arena` lifetime are their own owned counterpart. Unknown
+    /// fields and lazy sub-messages are kept as their encoded bytes. Messages from
+    /// [`extern_path`](#method.extern_path) crates must provide owned counterparts themselves,
+    /// by implementing `ToOwnedValue` and `ArenaFrom`.
+    ///
+    /// By default, this is disabled.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # let arena = defiant::Arena::new();
+    /// # let mut config = defiant_build::Config::new(&arena);
+    /// config.owned_types(true);
+    /// ```
+    pub fn owned_types(&mut self, enabled: bool) -> &mut Self {
+        self.owned_types = enabled;
+        self
+    }
+
     /// Declare an externally provided Protobuf package or type.
     ///
     /// `extern_path` allows `prost` types in external crates to be referenced in generated code.
@@ -1189,7 +1312,8 @@ impl<'arena> Config<'arena> {
             let buf = modules
                 .entry(request_module.clone())
                 .or_insert_with(String::new);
-            CodeGenerator::generate(&mut context, request_fd.clone(), buf);
+            CodeGenerator::generate(&mut context, request_fd.clone(), buf)
+                .map_err(|error| Error::new(ErrorKind::InvalidInput, error))?;
             if buf.is_empty() {
                 // Did not generate any code, remove from list to avoid inclusion in include file or output file list
                 modules.remove(&request_module);
@@ -1266,6 +1390,10 @@ impl<'arena> fmt::Debug for Config<'arena> {
             .field("protoc_args", &self.protoc_args)
             .field("disable_comments", &self.disable_comments)
             .field("skip_debug", &self.skip_debug)
+            .field("preserve_unknown_fields", &self.preserve_unknown_fields)
+            .field("skip_unknown_fields", &self.skip_unknown_fields)
+            .field("lazy", &self.lazy)
+            .field("owned_types", &self.owned_types)
             .field("prost_path", &self.prost_path)
             .finish()
     }
@@ -3,7 +3,6 @@ use defiant_types::{
     FieldDescriptorProto,
 };
 
-use crate::descriptor_ext::*;
 use crate::extern_paths::ExternPaths;
 use crate::message_graph::MessageGraph;
 use crate::{BytesType, Config, MapType, ServiceGenerator};
@@ -250,6 +249,62 @@ impl<'a, 'arena> Context<'a, 'arena> {
         self.config.skip_debug.get(fq_message_name).next().is_some()
     }
 
+    /// Returns whether the named message should carry an `unknown_fields` field.
+    ///
+    /// Extensible messages always do, as that is where their extensions are kept.
+    pub fn should_preserve_unknown_fields(&self, fq_message_name: &str) -> bool {
+        assert_eq!(b'.', fq_message_name.as_bytes()[0]);
+        (self
+            .config
+            .preserve_unknown_fields
+            .get(fq_message_name)
+            .next()
+            .is_some()
+            || self
+                .message_graph
+                .get_message(fq_message_name)
+                .is_some_and(|message| !message.extension_range.is_empty()))
+            && self
+                .config
+                .skip_unknown_fields
+                .get(fq_message_name)
+                .next()
+                .is_none()
+    }
+
+    /// Returns whether the field matches [`Config::lazy`] and can hold a lazily decoded message.
+    pub fn should_lazy_field(&self, fq_message_name: &str, field: &FieldDescriptorProto) -> bool {
+        assert_eq!(b'.', fq_message_name.as_bytes()[0]);
+        field.label() == Label::Optional
+            && field.r#type() == Type::Message
+            && (field.oneof_index.is_none() || field.proto3_optional.unwrap_or(false))
+            && self
+                .config
+                .lazy
+                .get_first_field(fq_message_name, field.name())
+                .is_some()
+    }
+
+    /// Returns whether the message field should be boxed in the owned counterpart of its message,
+    /// because the message type contains the parent or the field matches [`Config::boxed`].
+    pub fn should_box_owned_field(
+        &self,
+        fq_message_name: &str,
+        field: &FieldDescriptorProto,
+    ) -> bool {
+        assert_eq!(b'.', fq_message_name.as_bytes()[0]);
+        field.label() != Label::Repeated
+            && (field.r#type() == Type::Message || field.r#type() == Type::Group)
+            && (self
+                .message_graph
+                .is_nested(field.type_name(), fq_message_name)
+                || self
+                    .config
+                    .boxed
+                    .get_first_field(fq_message_name, field.name())
+                    .is_some())
+    }
+
     /// Returns the type name domain URL for the named message,
     /// or an empty string if such is not configured.
     pub fn type_name_domain(&self, fq_message_name: &str) -> &str {
@@ -1,5 +1,6 @@
 // This file is @generated by prost-build.
 #[derive(Clone, PartialEq, Eq, Hash, ::defiant::View)]
+#[defiant(full_name = "all_deprecated.Test")]
 pub struct Test<'arena> {
     #[defiant(string, tag = "1")]
     pub not_outdated: &'arena str,
@@ -41,10 +42,13 @@ pub mod test {
     }
 }
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::defiant::Enumeration)]
+#[defiant(full_name = "all_deprecated.Test2")]
 #[repr(i32)]
 pub enum Test2 {
+    #[defiant(name = "not_outdated")]
     NotOutdated = 0,
     #[deprecated]
+    #[defiant(name = "outdated")]
     Outdated = 1,
 }
 impl Test2 {
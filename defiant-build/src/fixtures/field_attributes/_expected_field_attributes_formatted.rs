@@ -1,5 +1,6 @@
 // This file is @generated by prost-build.
 #[derive(Clone, PartialEq, Eq, Hash, ::defiant::View)]
+#[defiant(full_name = "field_attributes.Container")]
 pub struct Container<'arena> {
     #[defiant(oneof = "container::Data", tags = "1, 2")]
     pub data: ::core::option::Option<container::Data<'arena>>,
@@ -15,15 +16,18 @@ pub mod container {
     }
 }
 #[derive(Clone, PartialEq, Eq, Hash, ::defiant::View)]
+#[defiant(full_name = "field_attributes.Foo")]
 pub struct Foo<'arena> {
     #[defiant(string, tag = "1")]
     pub foo: &'arena str,
 }
 #[derive(Clone, PartialEq, Eq, Hash, ::defiant::View)]
+#[defiant(full_name = "field_attributes.Bar")]
 pub struct Bar {
     #[defiant(message, optional, tag = "1")]
     pub qux: ::core::option::Option<Qux>,
 }
 #[derive(Clone, Copy, PartialEq, Eq, Hash, ::defiant::View)]
+#[defiant(full_name = "field_attributes.Qux")]
 pub struct Qux {
 }
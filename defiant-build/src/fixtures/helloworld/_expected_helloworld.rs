@@ -2,6 +2,7 @@
 #[derive(derive_builder::Builder)]
 #[derive(custom_proto::Input)]
 #[derive(Clone, PartialEq, Eq, Hash, ::defiant::View)]
+#[defiant(full_name = "helloworld.Message")]
 pub struct Message<'arena> {
     #[defiant(string, tag = "1")]
     pub say: &'arena str,
@@ -9,16 +10,21 @@ pub struct Message<'arena> {
 #[derive(derive_builder::Builder)]
 #[derive(custom_proto::Output)]
 #[derive(Clone, PartialEq, Eq, Hash, ::defiant::View)]
+#[defiant(full_name = "helloworld.Response")]
 pub struct Response<'arena> {
     #[defiant(string, tag = "1")]
     pub say: &'arena str,
 }
 #[some_enum_attr(u8)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::defiant::Enumeration)]
+#[defiant(full_name = "helloworld.ServingStatus")]
 #[repr(i32)]
 pub enum ServingStatus {
+    #[defiant(name = "UNKNOWN")]
     Unknown = 0,
+    #[defiant(name = "SERVING")]
     Serving = 1,
+    #[defiant(name = "NOT_SERVING")]
     NotServing = 2,
 }
 impl ServingStatus {
@@ -40,6 +40,24 @@ pub fn to_upper_camel(s: impl AsRef<str>) -> String {
     sanitize_identifier(s.as_ref().to_upper_camel_case())
 }
 
+/// Converts a `.proto` field name to the JSON name protoc assigns by default: underscores are
+/// dropped and the letter following each one is capitalized.
+pub fn to_json_name(s: impl AsRef<str>) -> String {
+    let mut json_name = String::new();
+    let mut capitalize = false;
+    for c in s.as_ref().chars() {
+        if c == '_' {
+            capitalize = true;
+        } else if capitalize {
+            json_name.extend(c.to_uppercase());
+            capitalize = false;
+        } else {
+            json_name.push(c);
+        }
+    }
+    json_name
+}
+
 /// Strip an enum's type name from the prefix of an enum value.
 ///
 /// This function assumes that both have been formatted to Rust's
@@ -72,6 +90,14 @@ pub fn strip_enum_prefix(prefix: &str, name: &str) -> String {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_to_json_name() {
+        assert_eq!("fooBar", &to_json_name("foo_bar"));
+        assert_eq!("FieldName3", &to_json_name("FieldName3"));
+        assert_eq!("FieldName14", &to_json_name("__Field_name14"));
+        assert_eq!("fieldName17", &to_json_name("field_name17__"));
+    }
+
     #[test]
     fn test_sanitize_identifier() {
         assert_eq!(sanitize_identifier("as"), "r#as");
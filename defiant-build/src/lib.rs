@@ -147,7 +147,6 @@ pub(crate) use collections::{BytesType, MapType};
 
 mod code_generator;
 mod context;
-mod descriptor_ext;
 mod extern_paths;
 mod ident;
 mod message_graph;
@@ -611,4 +610,194 @@ mod tests {
             tempdir.path().join("all_deprecated.rs")
         );
     }
+
+    #[test]
+    fn test_generate_owned_types() {
+        let _ = env_logger::try_init();
+        let arena = defiant::Arena::new();
+        // A recursive message with a oneof, and a message without an arena lifetime
+        let file = defiant_types::FileDescriptorProtoBuilder::parse_text(
+            r#"
+            name: "tree.proto" package: "tree" syntax: "proto3"
+            message_type {
+              name: "Node"
+              field { name: "label" number: 1 label: LABEL_OPTIONAL type: TYPE_STRING }
+              field { name: "children" number: 2 label: LABEL_REPEATED type: TYPE_MESSAGE type_name: ".tree.Node" }
+              field { name: "parent" number: 3 label: LABEL_OPTIONAL type: TYPE_MESSAGE type_name: ".tree.Node" }
+              field { name: "point" number: 4 label: LABEL_OPTIONAL type: TYPE_MESSAGE type_name: ".tree.Point" }
+              field { name: "name" number: 5 label: LABEL_OPTIONAL type: TYPE_STRING oneof_index: 0 }
+              field { name: "index" number: 6 label: LABEL_OPTIONAL type: TYPE_UINT32 oneof_index: 0 }
+              oneof_decl { name: "key" }
+            }
+            message_type {
+              name: "Point"
+              field { name: "x" number: 1 label: LABEL_OPTIONAL type: TYPE_INT32 }
+            }
+            "#,
+            &arena,
+        )
+        .unwrap()
+        .freeze();
+        let file = &*arena.alloc(file);
+
+        let mut config = Config::new(&arena);
+        config.owned_types(true);
+        let modules = config
+            .generate(vec![(Module::from_protobuf_package_name("tree"), file)])
+            .unwrap();
+        let code = &modules[&Module::from_protobuf_package_name("tree")];
+
+        for expected in [
+            "pub struct NodeOwned {",
+            "pub label: ::defiant::alloc::string::String,",
+            "pub children: ::defiant::alloc::vec::Vec<NodeOwned>,",
+            "pub parent: ::core::option::Option<::defiant::alloc::boxed::Box<NodeOwned>>,",
+            "pub point: ::core::option::Option<Point>,",
+            "pub key: ::core::option::Option<node::KeyOwned>,",
+            "pub fn to_owned(&self) -> NodeOwned {",
+            "pub fn view_in<'arena>(&self, arena: &'arena ::defiant::Arena) -> Node<'arena> {",
+            "impl<'arena> ::defiant::ArenaFrom<'arena, &NodeOwned> for Node<'arena> {",
+            "Name(::defiant::alloc::string::String),",
+            "Index(u32),",
+        ] {
+            assert!(code.contains(expected), "missing {expected:?} in:\n{code}");
+        }
+        assert!(!code.contains("PointOwned"));
+
+        let mut config = Config::new(&arena);
+        let modules = config
+            .generate(vec![(Module::from_protobuf_package_name("tree"), file)])
+            .unwrap();
+        assert!(!modules[&Module::from_protobuf_package_name("tree")].contains("Owned"));
+    }
+
+    #[test]
+    fn test_generate_extensions() {
+        let _ = env_logger::try_init();
+        let arena = defiant::Arena::new();
+        let file = defiant_types::FileDescriptorProtoBuilder::parse_text(
+            r#"
+            name: "ext.proto" package: "ext" syntax: "proto2"
+            message_type {
+              name: "Envelope"
+              field { name: "route" number: 1 label: LABEL_OPTIONAL type: TYPE_STRING }
+              extension_range { start: 100 end: 200 }
+            }
+            message_type {
+              name: "Point"
+              field { name: "x" number: 1 label: LABEL_OPTIONAL type: TYPE_INT32 }
+            }
+            message_type {
+              name: "Holder"
+              extension { name: "point" number: 101 label: LABEL_OPTIONAL type: TYPE_MESSAGE type_name: ".ext.Point" extendee: ".ext.Envelope" }
+            }
+            enum_type {
+              name: "Color"
+              value { name: "RED" number: 0 }
+            }
+            extension { name: "priority" number: 100 label: LABEL_OPTIONAL type: TYPE_UINT32 extendee: ".ext.Envelope" }
+            extension { name: "color" number: 102 label: LABEL_OPTIONAL type: TYPE_ENUM type_name: ".ext.Color" extendee: ".ext.Envelope" }
+            extension { name: "tags" number: 103 label: LABEL_REPEATED type: TYPE_STRING extendee: ".ext.Envelope" }
+            message_type {
+              name: "Audit"
+              field { name: "by" number: 1 label: LABEL_OPTIONAL type: TYPE_STRING }
+            }
+            extension { name: "audit" number: 104 label: LABEL_OPTIONAL type: TYPE_GROUP type_name: ".ext.Audit" extendee: ".ext.Envelope" }
+            "#,
+            &arena,
+        )
+        .unwrap()
+        .freeze();
+        let file = &*arena.alloc(file);
+
+        let mut config = Config::new(&arena);
+        let modules = config
+            .generate(vec![(Module::from_protobuf_package_name("ext"), file)])
+            .unwrap();
+        let code = &modules[&Module::from_protobuf_package_name("ext")];
+        for expected in [
+            // Extensible messages keep their extensions with their unknown fields
+            "pub unknown_fields: ::defiant::UnknownFields<'arena>,",
+            "pub const PRIORITY: ::defiant::Extension<Envelope<'static>, u32> = \
+             ::defiant::Extension::new(\"ext.Envelope\", ::defiant::FieldInfo::new(\"ext.priority\", 100, \
+             ::defiant::FieldType::Scalar(::defiant::ScalarType::Uint32)));",
+            // Extensions declared in a message are named after it and live in its module
+            "pub mod holder {",
+            "pub const POINT: ::defiant::Extension<super::Envelope<'static>, super::Point> = \
+             ::defiant::Extension::new(\"ext.Envelope\", ::defiant::FieldInfo::new(\"ext.Holder.point\", 101, \
+             ::defiant::FieldType::Message(::defiant::MessageType::of::<super::Point>())));",
+            "pub const COLOR: ::defiant::Extension<Envelope<'static>, i32> = \
+             ::defiant::Extension::new(\"ext.Envelope\", ::defiant::FieldInfo::new(\"ext.color\", 102, \
+             ::defiant::FieldType::Enum(::defiant::EnumType::of::<Color>())));",
+            "pub const TAGS: ::defiant::Extension<Envelope<'static>, &'static [&'static str]> = \
+             ::defiant::Extension::new(\"ext.Envelope\", ::defiant::FieldInfo::new(\"ext.tags\", 103, \
+             ::defiant::FieldType::Scalar(::defiant::ScalarType::String)).repeated());",
+            // Groups are delimited by tags named after their type
+            "pub const AUDIT: ::defiant::Extension<Envelope<'static>, Audit<'static>> = \
+             ::defiant::Extension::new(\"ext.Envelope\", ::defiant::FieldInfo::new(\"ext.audit\", 104, \
+             ::defiant::FieldType::Group(::defiant::MessageType::of::<Audit<'static>>(), \"Audit\")));",
+        ] {
+            assert!(code.contains(expected), "missing {expected:?} in:\n{code}");
+        }
+        // Messages without extension ranges don't
+        assert_eq!(code.matches("unknown_fields").count(), 2);
+    }
+
+    #[test]
+    fn test_generate_editions() {
+        let _ = env_logger::try_init();
+        let arena = defiant::Arena::new();
+        // File, message and field features, over the edition 2023 defaults
+        let file = defiant_types::FileDescriptorProtoBuilder::parse_text(
+            r#"
+            name: "order.proto" package: "order" syntax: "editions" edition: EDITION_2023
+            options { features { repeated_field_encoding: EXPANDED } }
+            message_type {
+              name: "Order"
+              options { features { message_encoding: DELIMITED } }
+              field { name: "id" number: 1 label: LABEL_OPTIONAL type: TYPE_INT32 }
+              field { name: "note" number: 2 label: LABEL_OPTIONAL type: TYPE_STRING options { features { field_presence: IMPLICIT } } }
+              field { name: "key" number: 3 label: LABEL_OPTIONAL type: TYPE_STRING options { features { field_presence: LEGACY_REQUIRED } } }
+              field { name: "ids" number: 4 label: LABEL_REPEATED type: TYPE_INT32 }
+              field { name: "codes" number: 5 label: LABEL_REPEATED type: TYPE_INT32 options { features { repeated_field_encoding: PACKED } } }
+              field { name: "item" number: 6 label: LABEL_OPTIONAL type: TYPE_MESSAGE type_name: ".order.Item" }
+              field { name: "counts" number: 7 label: LABEL_REPEATED type: TYPE_MESSAGE type_name: ".order.Order.CountsEntry" }
+              nested_type {
+                name: "CountsEntry"
+                options { map_entry: true }
+                field { name: "key" number: 1 label: LABEL_OPTIONAL type: TYPE_STRING }
+                field { name: "value" number: 2 label: LABEL_OPTIONAL type: TYPE_INT32 }
+              }
+            }
+            message_type {
+              name: "Item"
+              field { name: "sku" number: 1 label: LABEL_OPTIONAL type: TYPE_STRING }
+              field { name: "qty" number: 2 label: LABEL_OPTIONAL type: TYPE_UINT32 options { features { field_presence: IMPLICIT } } }
+            }
+            "#,
+            &arena,
+        )
+        .unwrap()
+        .freeze();
+        let file = &*arena.alloc(file);
+
+        let modules = Config::new(&arena)
+            .generate(vec![(Module::from_protobuf_package_name("order"), file)])
+            .unwrap();
+        let code = &modules[&Module::from_protobuf_package_name("order")];
+
+        for expected in [
+            "#[defiant(int32, optional, tag = \"1\")]",
+            "#[defiant(string, tag = \"2\")]",
+            "#[defiant(string, required, tag = \"3\")]",
+            "#[defiant(int32, repeated, packed = \"false\", tag = \"4\")]",
+            "#[defiant(int32, repeated, tag = \"5\")]",
+            "#[defiant(group, optional, tag = \"6\")]",
+            "#[defiant(arena_map = \"string, int32\", tag = \"7\")]",
+            "#[defiant(string, optional, tag = \"1\")]",
+            "#[defiant(uint32, tag = \"2\")]",
+        ] {
+            assert!(code.contains(expected), "missing {expected:?} in:\n{code}");
+        }
+    }
 }
@@ -9,8 +9,6 @@ use defiant_types::{
     DescriptorProto, FileDescriptorProto,
 };
 
-use crate::descriptor_ext::*;
-
 /// `MessageGraph` builds a graph of messages whose edges correspond to nesting.
 /// The goal is to recognize when message types are recursively nested, so
 /// that fields can be boxed when necessary.
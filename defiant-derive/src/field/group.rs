@@ -124,6 +124,65 @@ impl Field {
         }
     }
 
+    /// Like `encode`, but takes nested message lengths from `sizes`.
+    pub fn encode_cached(&self, prost_path: &Path, ident: TokenStream) -> TokenStream {
+        let tag = self.tag;
+        match self.label {
+            Label::Optional => quote! {
+                if let Some(msg) = #ident.as_ref() {
+                    #prost_path::encoding::group::encode_cached(#tag, msg, buf, sizes);
+                }
+            },
+            Label::Required => quote! {
+                #prost_path::encoding::group::encode_cached(#tag, &#ident, buf, sizes);
+            },
+            Label::Repeated => quote! {
+                for msg in #ident.iter() {
+                    #prost_path::encoding::group::encode_cached(#tag, msg, buf, sizes);
+                }
+            },
+        }
+    }
+
+    /// Like `encoded_len`, but records nested message lengths in `sizes`.
+    pub fn encoded_len_cached(&self, prost_path: &Path, ident: TokenStream) -> TokenStream {
+        let tag = self.tag;
+        match self.label {
+            Label::Optional => quote! {
+                #ident.as_ref().map_or(0, |msg| #prost_path::encoding::group::encoded_len_cached(#tag, msg, sizes))
+            },
+            Label::Required => quote! {
+                #prost_path::encoding::group::encoded_len_cached(#tag, &#ident, sizes)
+            },
+            Label::Repeated => quote! {
+                #ident
+                    .iter()
+                    .map(|msg| #prost_path::encoding::group::encoded_len_cached(#tag, msg, sizes))
+                    .sum::<usize>()
+            },
+        }
+    }
+
+    /// Like `encode`, but writes in front of a `ReverseBuf`, last element first.
+    pub fn encode_reverse(&self, prost_path: &Path, ident: TokenStream) -> TokenStream {
+        let tag = self.tag;
+        match self.label {
+            Label::Optional => quote! {
+                if let Some(msg) = #ident.as_ref() {
+                    #prost_path::encoding::group::encode_reverse(#tag, msg, buf);
+                }
+            },
+            Label::Required => quote! {
+                #prost_path::encoding::group::encode_reverse(#tag, &#ident, buf);
+            },
+            Label::Repeated => quote! {
+                for msg in #ident.iter().rev() {
+                    #prost_path::encoding::group::encode_reverse(#tag, msg, buf);
+                }
+            },
+        }
+    }
+
     pub fn clear(&self, ident: TokenStream) -> TokenStream {
         match self.label {
             Label::Optional => quote!(#ident = ::core::option::Option::None),
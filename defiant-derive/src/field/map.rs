@@ -413,6 +413,87 @@ impl Field {
         }
     }
 
+    /// Like `encode`, but takes the lengths of message values from `sizes`.
+    pub fn encode_cached(&self, prost_path: &Path, ident: TokenStream) -> TokenStream {
+        if !matches!(self.value_ty, ValueTy::Message) {
+            return self.encode(prost_path, ident);
+        }
+        let tag = self.tag;
+        let key_mod = self.key_ty.module();
+        let ke = if matches!(self.key_ty, scalar::Ty::String) {
+            quote!(|tag, key: &&str, buf| #prost_path::encoding::#key_mod::encode(tag, *key, buf))
+        } else {
+            quote!(#prost_path::encoding::#key_mod::encode)
+        };
+        let key_default = self.key_default();
+        let module = self.map_ty.module();
+        quote! {
+            #prost_path::encoding::#module::encode_message_cached(
+                #ke,
+                &#key_default,
+                #tag,
+                #ident.as_slice(),
+                buf,
+                sizes,
+            );
+        }
+    }
+
+    /// Like `encoded_len`, but records the lengths of message values in `sizes`.
+    pub fn encoded_len_cached(&self, prost_path: &Path, ident: TokenStream) -> TokenStream {
+        if !matches!(self.value_ty, ValueTy::Message) {
+            return self.encoded_len(prost_path, ident);
+        }
+        let tag = self.tag;
+        let key_mod = self.key_ty.module();
+        let kl = if matches!(self.key_ty, scalar::Ty::String) {
+            quote!(|tag, key: &&str| #prost_path::encoding::#key_mod::encoded_len(tag, *key))
+        } else {
+            quote!(#prost_path::encoding::#key_mod::encoded_len)
+        };
+        let key_default = self.key_default();
+        let module = self.map_ty.module();
+        quote! {
+            #prost_path::encoding::#module::encoded_len_message_cached(
+                #kl,
+                &#key_default,
+                #tag,
+                #ident.as_slice(),
+                sizes,
+            )
+        }
+    }
+
+    /// Like `encode`, but writes message values in front of a `ReverseBuf`, last
+    /// entry first.
+    pub fn encode_reverse(&self, prost_path: &Path, ident: TokenStream) -> TokenStream {
+        let tag = self.tag;
+        let key_mod = self.key_ty.module();
+        let (ke, kl) = if matches!(self.key_ty, scalar::Ty::String) {
+            (
+                quote!(|tag, key: &&str, buf| #prost_path::encoding::#key_mod::encode(tag, *key, buf)),
+                quote!(|tag, key: &&str| #prost_path::encoding::#key_mod::encoded_len(tag, *key)),
+            )
+        } else {
+            (
+                quote!(#prost_path::encoding::#key_mod::encode),
+                quote!(#prost_path::encoding::#key_mod::encoded_len),
+            )
+        };
+        let key_default = self.key_default();
+        let module = self.map_ty.module();
+        quote! {
+            #prost_path::encoding::#module::encode_message_reverse(
+                #ke,
+                #kl,
+                &#key_default,
+                #tag,
+                #ident.as_slice(),
+                buf,
+            );
+        }
+    }
+
     pub fn clear(&self, ident: TokenStream) -> TokenStream {
         quote!(#ident.clear())
     }
@@ -9,6 +9,7 @@ use crate::field::{set_bool, set_option, tag_attr, word_attr, Label};
 pub struct Field {
     pub label: Label,
     pub tag: u32,
+    pub lazy: bool,
 }
 
 impl Field {
@@ -17,6 +18,7 @@ impl Field {
         let mut label = None;
         let mut tag = None;
         let mut boxed = false;
+        let mut lazy = false;
 
         let mut unknown_attrs = Vec::new();
 
@@ -25,6 +27,8 @@ impl Field {
                 set_bool(&mut message, "duplicate message attribute")?;
             } else if word_attr("boxed", attr) {
                 set_bool(&mut boxed, "duplicate boxed attribute")?;
+            } else if word_attr("lazy", attr) {
+                set_bool(&mut lazy, "duplicate lazy attribute")?;
             } else if let Some(t) = tag_attr(attr)? {
                 set_option(&mut tag, t, "duplicate tag attributes")?;
             } else if let Some(l) = Label::from_attr(attr) {
@@ -50,10 +54,12 @@ impl Field {
             None => bail!("message field is missing a tag attribute"),
         };
 
-        Ok(Some(Field {
-            label: label.unwrap_or(Label::Optional),
-            tag,
-        }))
+        let label = label.unwrap_or(Label::Optional);
+        if lazy && label != Label::Optional {
+            bail!("lazy message fields must be optional");
+        }
+
+        Ok(Some(Field { label, tag, lazy }))
     }
 
     pub fn new_oneof(attrs: &[Meta]) -> Result<Option<Field>, Error> {
@@ -64,6 +70,9 @@ impl Field {
                     attr.path().into_token_stream()
                 );
             }
+            if field.lazy {
+                bail!("invalid attribute for oneof field: lazy");
+            }
             field.label = Label::Required;
             Ok(Some(field))
         } else {
@@ -156,6 +165,65 @@ impl Field {
         }
     }
 
+    /// Like `encode`, but takes nested message lengths from `sizes`.
+    pub fn encode_cached(&self, prost_path: &Path, ident: TokenStream) -> TokenStream {
+        let tag = self.tag;
+        match self.label {
+            Label::Optional => quote! {
+                if let Some(msg) = #ident.as_ref() {
+                    #prost_path::encoding::message::encode_cached(#tag, msg, buf, sizes);
+                }
+            },
+            Label::Required => quote! {
+                #prost_path::encoding::message::encode_cached(#tag, &(#ident), buf, sizes);
+            },
+            Label::Repeated => quote! {
+                for msg in #ident.iter() {
+                    #prost_path::encoding::message::encode_cached(#tag, msg, buf, sizes);
+                }
+            },
+        }
+    }
+
+    /// Like `encoded_len`, but records nested message lengths in `sizes`.
+    pub fn encoded_len_cached(&self, prost_path: &Path, ident: TokenStream) -> TokenStream {
+        let tag = self.tag;
+        match self.label {
+            Label::Optional => quote! {
+                #ident.as_ref().map_or(0, |msg| #prost_path::encoding::message::encoded_len_cached(#tag, msg, sizes))
+            },
+            Label::Required => quote! {
+                #prost_path::encoding::message::encoded_len_cached(#tag, &(#ident), sizes)
+            },
+            Label::Repeated => quote! {
+                #ident
+                    .iter()
+                    .map(|msg| #prost_path::encoding::message::encoded_len_cached(#tag, msg, sizes))
+                    .sum::<usize>()
+            },
+        }
+    }
+
+    /// Like `encode`, but writes in front of a `ReverseBuf`, last element first.
+    pub fn encode_reverse(&self, prost_path: &Path, ident: TokenStream) -> TokenStream {
+        let tag = self.tag;
+        match self.label {
+            Label::Optional => quote! {
+                if let Some(msg) = #ident.as_ref() {
+                    #prost_path::encoding::message::encode_reverse(#tag, msg, buf);
+                }
+            },
+            Label::Required => quote! {
+                #prost_path::encoding::message::encode_reverse(#tag, &(#ident), buf);
+            },
+            Label::Repeated => quote! {
+                for msg in #ident.iter().rev() {
+                    #prost_path::encoding::message::encode_reverse(#tag, msg, buf);
+                }
+            },
+        }
+    }
+
     pub fn clear(&self, ident: TokenStream) -> TokenStream {
         match self.label {
             Label::Optional => quote!(#ident = ::core::option::Option::None),
@@ -38,7 +38,7 @@ impl Field {
     /// If the meta items are invalid, an error will be returned.
     /// If the field should be ignored, `None` is returned.
     pub fn new(attrs: Vec<Attribute>, inferred_tag: Option<u32>) -> Result<Option<Field>, Error> {
-        let attrs = prost_attrs(attrs)?;
+        let attrs = type_attrs(attrs)?;
 
         // Check for skip attribute.
         if attrs.iter().any(|attr| word_attr("skip", attr)) {
@@ -67,7 +67,7 @@ impl Field {
     /// If the meta items are invalid, an error will be returned.
     /// If the field should be ignored, `None` is returned.
     pub fn new_oneof(attrs: Vec<Attribute>) -> Result<Option<Field>, Error> {
-        let attrs = prost_attrs(attrs)?;
+        let attrs = type_attrs(attrs)?;
 
         // Check for skip attribute.
         if attrs.iter().any(|attr| word_attr("skip", attr)) {
@@ -133,6 +133,63 @@ impl Field {
         }
     }
 
+    /// Returns a statement which encodes the field, taking the lengths of nested
+    /// messages from the `sizes` cache instead of recomputing them.
+    pub fn encode_cached(&self, prost_path: &Path, ident: TokenStream) -> TokenStream {
+        match *self {
+            Field::Scalar(ref scalar) => scalar.encode(prost_path, ident),
+            Field::Message(ref message) => message.encode_cached(prost_path, ident),
+            Field::Map(ref map) => map.encode_cached(prost_path, ident),
+            Field::Oneof(ref oneof) => oneof.encode_cached(ident),
+            Field::Group(ref group) => group.encode_cached(prost_path, ident),
+        }
+    }
+
+    /// Returns an expression which evaluates to the encoded length of the field,
+    /// recording the lengths of nested messages in the `sizes` cache.
+    pub fn encoded_len_cached(&self, prost_path: &Path, ident: TokenStream) -> TokenStream {
+        match *self {
+            Field::Scalar(ref scalar) => scalar.encoded_len(prost_path, ident),
+            Field::Map(ref map) => map.encoded_len_cached(prost_path, ident),
+            Field::Message(ref msg) => msg.encoded_len_cached(prost_path, ident),
+            Field::Oneof(ref oneof) => oneof.encoded_len_cached(ident),
+            Field::Group(ref group) => group.encoded_len_cached(prost_path, ident),
+        }
+    }
+
+    /// Returns a statement which writes the field in front of the contents of the
+    /// `buf` reverse buffer.
+    pub fn encode_reverse(&self, prost_path: &Path, ident: TokenStream) -> TokenStream {
+        match *self {
+            Field::Message(ref message) => message.encode_reverse(prost_path, ident),
+            Field::Map(ref map) if matches!(map.value_ty, ValueTy::Message) => {
+                map.encode_reverse(prost_path, ident)
+            }
+            Field::Oneof(ref oneof) => oneof.encode_reverse(ident),
+            Field::Group(ref group) => group.encode_reverse(prost_path, ident),
+            Field::Scalar(_) | Field::Map(_) => prepend_encoded(
+                self.encoded_len(prost_path, ident.clone()),
+                self.encode(prost_path, ident),
+            ),
+        }
+    }
+
+    /// Returns true if encoding the field writes the length prefix of a nested message.
+    pub fn has_nested_messages(&self) -> bool {
+        match *self {
+            Field::Scalar(_) => false,
+            Field::Map(ref map) => matches!(map.value_ty, ValueTy::Message),
+            // Lazy fields hold their encoded body, so their length is known up front.
+            Field::Message(ref message) => !message.lazy,
+            Field::Oneof(_) | Field::Group(_) => true,
+        }
+    }
+
+    /// Returns true if the field is a message field which is decoded on demand.
+    pub fn is_lazy(&self) -> bool {
+        matches!(*self, Field::Message(ref message) if message.lazy)
+    }
+
     /// Returns a statement which clears the field.
     pub fn clear(&self, ident: TokenStream) -> TokenStream {
         match *self {
@@ -189,6 +246,16 @@ impl Field {
         }
     }
 
+    /// Returns true if this field is a proto2 required field.
+    pub fn is_required(&self) -> bool {
+        match *self {
+            Field::Scalar(ref scalar) => matches!(scalar.kind, scalar::Kind::Required(_)),
+            Field::Message(ref message) => message.label == Label::Required,
+            Field::Group(ref group) => group.label == Label::Required,
+            _ => false,
+        }
+    }
+
     /// Returns true if this field is a repeated field.
     pub fn is_repeated(&self) -> bool {
         match *self {
@@ -251,6 +318,60 @@ impl fmt::Display for Label {
     }
 }
 
+/// The `.proto` and JSON names of a field, set with `#[defiant(name = "...")]` and
+/// `#[defiant(json_name = "...")]` where they differ from the names inferred from the field.
+#[derive(Clone, Default)]
+pub struct Names {
+    pub name: Option<String>,
+    pub json_name: Option<String>,
+}
+
+impl Names {
+    pub fn new(attrs: &[Attribute]) -> Result<Names, Error> {
+        let mut names = Names::default();
+        for attr in prost_attrs(attrs.to_vec())? {
+            if let Some(name) = str_attr("name", &attr)? {
+                set_option(&mut names.name, name, "duplicate name attributes")?;
+            } else if let Some(json_name) = str_attr("json_name", &attr)? {
+                set_option(
+                    &mut names.json_name,
+                    json_name,
+                    "duplicate json_name attributes",
+                )?;
+            }
+        }
+        Ok(names)
+    }
+}
+
+/// Returns `true` if the field is marked as the message's unknown field storage, i.e.
+/// `#[defiant(unknown_fields)]`.
+pub fn is_unknown_fields(attrs: &[Attribute]) -> Result<bool, Error> {
+    let attrs = prost_attrs(attrs.to_vec())?;
+    Ok(attrs.iter().any(|attr| word_attr("unknown_fields", attr)))
+}
+
+/// Returns a block which makes room for `encoded_len` bytes in front of the `buf`
+/// reverse buffer, and fills them in by running the forward `encode` statement.
+pub fn prepend_encoded(encoded_len: TokenStream, encode: TokenStream) -> TokenStream {
+    quote! {
+        {
+            let len = #encoded_len;
+            let mut dst = buf.prepend(len);
+            let buf = &mut dst;
+            #encode
+        }
+    }
+}
+
+/// Get the items of the 'defiant' attribute which describe the field's type, leaving out the
+/// names read by [`Names`].
+fn type_attrs(attrs: Vec<Attribute>) -> Result<Vec<Meta>, Error> {
+    let mut attrs = prost_attrs(attrs)?;
+    attrs.retain(|attr| !attr.path().is_ident("name") && !attr.path().is_ident("json_name"));
+    Ok(attrs)
+}
+
 /// Get the items belonging to the 'defiant' list attribute, e.g. `#[defiant(foo, bar="baz")]`.
 fn prost_attrs(attrs: Vec<Attribute>) -> Result<Vec<Meta>, Error> {
     let mut result = Vec::new();
@@ -321,6 +442,25 @@ fn bool_attr(key: &str, attr: &Meta) -> Result<Option<bool>, Error> {
     }
 }
 
+/// Unpacks a `key = "value"` attribute, returning the value.
+/// If the key doesn't match the attribute, `None` is returned.
+fn str_attr(key: &str, attr: &Meta) -> Result<Option<String>, Error> {
+    if !attr.path().is_ident(key) {
+        return Ok(None);
+    }
+    match *attr {
+        Meta::NameValue(MetaNameValue {
+            value:
+                Expr::Lit(ExprLit {
+                    lit: Lit::Str(ref lit),
+                    ..
+                }),
+            ..
+        }) => Ok(Some(lit.value())),
+        _ => bail!("invalid {} attribute", key),
+    }
+}
+
 /// Checks if an attribute matches a word.
 fn word_attr(key: &str, attr: &Meta) -> bool {
     if let Meta::Path(ref path) = *attr {
@@ -84,6 +84,32 @@ impl Field {
         }
     }
 
+    /// Like `encode`, but takes nested message lengths from `sizes`.
+    pub fn encode_cached(&self, ident: TokenStream) -> TokenStream {
+        quote! {
+            if let Some(ref oneof) = #ident {
+                oneof.encode_cached(buf, sizes)
+            }
+        }
+    }
+
+    /// Like `encoded_len`, but records nested message lengths in `sizes`.
+    pub fn encoded_len_cached(&self, ident: TokenStream) -> TokenStream {
+        let ty = &self.ty;
+        quote! {
+            #ident.as_ref().map_or(0, |v| #ty::encoded_len_cached(v, sizes))
+        }
+    }
+
+    /// Like `encode`, but writes in front of a `ReverseBuf`.
+    pub fn encode_reverse(&self, ident: TokenStream) -> TokenStream {
+        quote! {
+            if let Some(ref oneof) = #ident {
+                oneof.encode_reverse(buf)
+            }
+        }
+    }
+
     pub fn clear(&self, ident: TokenStream) -> TokenStream {
         quote!(#ident = ::core::option::Option::None)
     }
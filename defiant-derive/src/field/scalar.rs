@@ -123,39 +123,7 @@ impl Field {
 
         match self.kind {
             Kind::Plain(ref default) => {
-                // Check if value differs from default
-                // For strings/bytes with empty defaults, use .is_empty() for efficiency
-                // For strings/bytes with custom defaults, must compare against the default value
-                let condition = match self.ty {
-                    Ty::String => {
-                        if let DefaultValue::String(ref s) = default {
-                            if s.is_empty() {
-                                quote!(!#ident.is_empty())
-                            } else {
-                                let default_val = default.typed();
-                                quote!(#ident != #default_val)
-                            }
-                        } else {
-                            quote!(!#ident.is_empty())
-                        }
-                    }
-                    Ty::Bytes(_) => {
-                        if let DefaultValue::Bytes(ref b) = default {
-                            if b.is_empty() {
-                                quote!(!#ident.is_empty())
-                            } else {
-                                let default_val = default.typed();
-                                quote!(#ident != #default_val)
-                            }
-                        } else {
-                            quote!(!#ident.is_empty())
-                        }
-                    }
-                    _ => {
-                        let default = default.typed();
-                        quote!(#ident != #default)
-                    }
-                };
+                let condition = self.differs_from_default(default, &ident);
                 // For string/bytes types (&str, &[u8]), pass value directly (already a reference)
                 // For other types, pass by reference
                 let param = if matches!(self.ty, Ty::String | Ty::Bytes(_)) {
@@ -269,39 +237,7 @@ impl Field {
 
         match self.kind {
             Kind::Plain(ref default) => {
-                // Check if value differs from default
-                // For strings/bytes with empty defaults, use .is_empty() for efficiency
-                // For strings/bytes with custom defaults, must compare against the default value
-                let condition = match self.ty {
-                    Ty::String => {
-                        if let DefaultValue::String(ref s) = default {
-                            if s.is_empty() {
-                                quote!(!#ident.is_empty())
-                            } else {
-                                let default_val = default.typed();
-                                quote!(#ident != #default_val)
-                            }
-                        } else {
-                            quote!(!#ident.is_empty())
-                        }
-                    }
-                    Ty::Bytes(_) => {
-                        if let DefaultValue::Bytes(ref b) = default {
-                            if b.is_empty() {
-                                quote!(!#ident.is_empty())
-                            } else {
-                                let default_val = default.typed();
-                                quote!(#ident != #default_val)
-                            }
-                        } else {
-                            quote!(!#ident.is_empty())
-                        }
-                    }
-                    _ => {
-                        let default = default.typed();
-                        quote!(#ident != #default)
-                    }
-                };
+                let condition = self.differs_from_default(default, &ident);
                 // For string/bytes types (&str, &[u8]), pass value directly (already a reference)
                 // For other types, pass by reference
                 let param = if matches!(self.ty, Ty::String | Ty::Bytes(_)) {
@@ -338,6 +274,51 @@ impl Field {
         }
     }
 
+    /// Returns an expression which is true if a plain field differs from its default, and so
+    /// is encoded.
+    fn differs_from_default(&self, default: &DefaultValue, ident: &TokenStream) -> TokenStream {
+        // For strings/bytes with empty defaults, use .is_empty() for efficiency
+        // For strings/bytes with custom defaults, must compare against the default value
+        match (&self.ty, default) {
+            (Ty::String, DefaultValue::String(s)) if !s.is_empty() => {
+                let default_val = default.typed();
+                quote!(#ident != #default_val)
+            }
+            (Ty::Bytes(_), DefaultValue::Bytes(b)) if !b.is_empty() => {
+                let default_val = default.typed();
+                quote!(#ident != #default_val)
+            }
+            (Ty::String | Ty::Bytes(_), _) => quote!(!#ident.is_empty()),
+            _ => {
+                let default = default.typed();
+                quote!(#ident != #default)
+            }
+        }
+    }
+
+    /// Returns a statement which merges the same field of another message into the field, the
+    /// way merging its encoding would: values which are not encoded leave the field unchanged,
+    /// and repeated values are appended.
+    pub fn merge_from(&self, ident: TokenStream, other: TokenStream) -> TokenStream {
+        match self.kind {
+            Kind::Plain(ref default) => {
+                let condition = self.differs_from_default(default, &other);
+                quote! {
+                    if #condition {
+                        #ident = #other;
+                    }
+                }
+            }
+            Kind::Optional(..) => quote! {
+                if #other.is_some() {
+                    #ident = #other;
+                }
+            },
+            Kind::Required(..) => quote!(#ident = #other;),
+            Kind::Repeated | Kind::Packed => quote!(#ident.extend_from_slice(#other);),
+        }
+    }
+
     pub fn clear(&self, ident: TokenStream) -> TokenStream {
         match self.kind {
             Kind::Plain(ref default) | Kind::Required(ref default) => {
@@ -373,6 +354,19 @@ impl Field {
         }
     }
 
+    /// Returns the default value in a constant expression. `Default::default()` is not `const`,
+    /// so enumerations without a declared default use the `DEFAULT` constant generated by the
+    /// `Enumeration` derive instead.
+    pub fn const_default(&self, prost_path: &Path) -> TokenStream {
+        match self.kind {
+            Kind::Plain(DefaultValue::EnumerationDefault(ref path))
+            | Kind::Required(DefaultValue::EnumerationDefault(ref path)) => {
+                quote!(#path::DEFAULT as i32)
+            }
+            _ => self.default(prost_path),
+        }
+    }
+
     /// An inner debug wrapper, around the base type.
     fn debug_inner(&self, wrap_name: TokenStream) -> TokenStream {
         if let Ty::Enumeration(ref ty) = self.ty {
@@ -445,6 +439,45 @@ impl Field {
             Err(_) => quote!(#ident),
         };
 
+        let has = match self.kind {
+            Kind::Plain(ref default) => {
+                let has = Ident::new(&format!("has_{ident_str}"), Span::call_site());
+                let doc = format!(
+                    "Returns `true` if `{ident_str}` differs from its default value. Fields without \
+                     explicit presence can't distinguish an unset value from the default."
+                );
+                let condition = self.differs_from_default(default, &quote!(self.#ident));
+                quote! {
+                    #[doc=#doc]
+                    pub fn #has(&self) -> bool {
+                        #condition
+                    }
+                }
+            }
+            Kind::Optional(_) => {
+                let has = Ident::new(&format!("has_{ident_str}"), Span::call_site());
+                let doc = format!("Returns `true` if `{ident_str}` is set.");
+                quote! {
+                    #[doc=#doc]
+                    pub fn #has(&self) -> bool {
+                        self.#ident.is_some()
+                    }
+                }
+            }
+            Kind::Required(_) => {
+                let has = Ident::new(&format!("has_{ident_str}"), Span::call_site());
+                let doc =
+                    format!("Returns `true`, since required field `{ident_str}` is always set.");
+                quote! {
+                    #[doc=#doc]
+                    pub fn #has(&self) -> bool {
+                        true
+                    }
+                }
+            }
+            Kind::Repeated | Kind::Packed => quote!(),
+        };
+
         if let Ty::Enumeration(ref ty) = self.ty {
             let set = Ident::new(&format!("set_{ident_str}"), Span::call_site());
             let set_doc = format!("Sets `{ident_str}` to the provided enum value.");
@@ -455,6 +488,8 @@ impl Field {
                          or the default if the field is set to an invalid enum value."
                     );
                     quote! {
+                        #has
+
                         #[doc=#get_doc]
                         pub fn #get(&self) -> #ty {
                             ::core::convert::TryFrom::try_from(self.#ident).unwrap_or(#default)
@@ -472,6 +507,8 @@ impl Field {
                          or the default if the field is unset or set to an invalid enum value."
                     );
                     quote! {
+                        #has
+
                         #[doc=#get_doc]
                         pub fn #get(&self) -> #ty {
                             self.#ident.and_then(|x| {
@@ -524,6 +561,8 @@ impl Field {
             );
 
             Some(quote! {
+                #has
+
                 #[doc=#get_doc]
                 pub fn #get(&self) -> #ty {
                     match self.#ident {
@@ -532,8 +571,25 @@ impl Field {
                     }
                 }
             })
-        } else {
+        } else if self.is_repeated() {
             None
+        } else {
+            let ty = self.ty.rust_ref_type();
+            let value = if self.ty.is_numeric() {
+                quote!(self.#ident)
+            } else {
+                quote!(&self.#ident[..])
+            };
+            let get_doc = format!("Returns the value of `{ident_str}`.");
+
+            Some(quote! {
+                #has
+
+                #[doc=#get_doc]
+                pub fn #get(&self) -> #ty {
+                    #value
+                }
+            })
         }
     }
 }
@@ -769,6 +825,8 @@ pub enum DefaultValue {
     String(String),
     Bytes(Vec<u8>),
     Enumeration(TokenStream),
+    /// The `Default` value of an enumeration without a declared default.
+    EnumerationDefault(Path),
     Path(Path),
 }
 
@@ -932,7 +990,7 @@ impl DefaultValue {
             Ty::Bool => DefaultValue::Bool(false),
             Ty::String => DefaultValue::String(String::new()),
             Ty::Bytes(..) => DefaultValue::Bytes(Vec::new()),
-            Ty::Enumeration(ref path) => DefaultValue::Enumeration(quote!(#path::default())),
+            Ty::Enumeration(ref path) => DefaultValue::EnumerationDefault(path.clone()),
         }
     }
 
@@ -955,7 +1013,7 @@ impl DefaultValue {
     }
 
     pub fn typed(&self) -> TokenStream {
-        if let DefaultValue::Enumeration(_) = *self {
+        if let DefaultValue::Enumeration(_) | DefaultValue::EnumerationDefault(_) = *self {
             quote!(#self as i32)
         } else {
             quote!(#self)
@@ -979,6 +1037,9 @@ impl ToTokens for DefaultValue {
                 tokens.append_all(quote!(#byte_str as &[u8]));
             }
             DefaultValue::Enumeration(ref value) => value.to_tokens(tokens),
+            DefaultValue::EnumerationDefault(ref path) => {
+                tokens.append_all(quote!(#path::default()))
+            }
             DefaultValue::Path(ref value) => value.to_tokens(tokens),
         }
     }
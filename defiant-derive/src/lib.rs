@@ -178,6 +178,219 @@ fn extract_type_path(field_type: &syn::Type) -> syn::Path {
     }
 }
 
+/// Returns true if a singular message field holds its message by reference, looking through
+/// `Option`, rather than by value.
+fn is_held_by_reference(field_type: &syn::Type) -> bool {
+    match field_type {
+        syn::Type::Reference(_) => true,
+        syn::Type::Path(type_path) => {
+            let last_seg = type_path.path.segments.last().unwrap();
+            match &last_seg.arguments {
+                syn::PathArguments::AngleBracketed(args) if last_seg.ident == "Option" => {
+                    matches!(
+                        args.args.first(),
+                        Some(syn::GenericArgument::Type(syn::Type::Reference(_)))
+                    )
+                }
+                _ => false,
+            }
+        }
+        _ => false,
+    }
+}
+
+/// Returns the path of the builder of a message field's type, without lifetimes:
+/// `Option<&'arena foo::Bar<'arena>>` → `foo::BarBuilder`.
+fn builder_path(field_type: &syn::Type) -> syn::Path {
+    let mut path = extract_type_path(field_type);
+    let last_seg = path.segments.last_mut().unwrap();
+    last_seg.ident = Ident::new(&format!("{}Builder", last_seg.ident), Span::call_site());
+    path
+}
+
+/// Builds the `FieldInfo` which `FieldSet` returns for a field named `name`. The JSON name
+/// defaults to the lowerCamelCase form of `name`.
+fn field_info(
+    prost_path: &Path,
+    name: &str,
+    json_name: Option<&str>,
+    field: &crate::field::Field,
+    field_type: &syn::Type,
+) -> TokenStream {
+    use crate::field::{Field, Label, ValueTy};
+
+    let tag = field.tags()[0];
+    let (ty, repeated, lazy) = match field {
+        Field::Scalar(scalar) => (
+            scalar_field_type(prost_path, &scalar.ty),
+            matches!(scalar.kind, Kind::Repeated | Kind::Packed),
+            false,
+        ),
+        Field::Message(message) => {
+            let message_path = if message.lazy {
+                extract_type_path(&type_argument(field_type, 0))
+            } else {
+                extract_type_path(field_type)
+            };
+            (
+                quote!(#prost_path::FieldType::Message(#prost_path::MessageType::of::<#message_path>())),
+                message.label == Label::Repeated,
+                message.lazy,
+            )
+        }
+        Field::Group(group) => {
+            let message_path = extract_type_path(field_type);
+            let type_name = message_path.segments.last().unwrap().ident.to_string();
+            (
+                quote!(#prost_path::FieldType::Group(#prost_path::MessageType::of::<#message_path>(), #type_name)),
+                group.label == Label::Repeated,
+                false,
+            )
+        }
+        Field::Map(map) => {
+            let key = scalar_type(prost_path, &map.key_ty);
+            let value = match &map.value_ty {
+                ValueTy::Scalar(Ty::Enumeration(path)) => {
+                    quote!(#prost_path::MapValueType::Enum(#prost_path::EnumType::of::<#path>()))
+                }
+                ValueTy::Scalar(ty) => {
+                    let ty = scalar_type(prost_path, ty);
+                    quote!(#prost_path::MapValueType::Scalar(#ty))
+                }
+                ValueTy::Message => {
+                    let message_path = extract_type_path(&type_argument(field_type, 1));
+                    quote!(#prost_path::MapValueType::Message(#prost_path::MessageType::of::<#message_path>()))
+                }
+            };
+            (
+                quote!(#prost_path::FieldType::Map(#key, #value)),
+                false,
+                false,
+            )
+        }
+        Field::Oneof(_) => unreachable!("oneofs are resolved through their own FieldSet"),
+    };
+    let json_name = json_name.map_or_else(|| to_json_name(name), str::to_string);
+    let json_name = (json_name != name).then(|| quote!(.with_json_name(#json_name)));
+    let repeated = repeated.then(|| quote!(.repeated()));
+    let lazy = lazy.then(|| quote!(.lazy()));
+    let implicit_presence =
+        matches!(field, Field::Scalar(scalar) if matches!(scalar.kind, Kind::Plain(_)))
+            .then(|| quote!(.implicit_presence()));
+    let required = field.is_required().then(|| quote!(.required()));
+    quote!(#prost_path::FieldInfo::new(#name, #tag, #ty)#json_name #repeated #lazy #implicit_presence #required)
+}
+
+/// Converts a `.proto` field name to its default JSON name, as protoc does: underscores are
+/// dropped and the letter following each one is capitalized.
+fn to_json_name(name: &str) -> String {
+    let mut json_name = String::with_capacity(name.len());
+    let mut capitalize = false;
+    for c in name.chars() {
+        if c == '_' {
+            capitalize = true;
+        } else if capitalize {
+            json_name.extend(c.to_uppercase());
+            capitalize = false;
+        } else {
+            json_name.push(c);
+        }
+    }
+    json_name
+}
+
+/// Returns the `FieldType` of a scalar or enum field.
+fn scalar_field_type(prost_path: &Path, ty: &Ty) -> TokenStream {
+    match ty {
+        Ty::Enumeration(path) => {
+            quote!(#prost_path::FieldType::Enum(#prost_path::EnumType::of::<#path>()))
+        }
+        ty => {
+            let ty = scalar_type(prost_path, ty);
+            quote!(#prost_path::FieldType::Scalar(#ty))
+        }
+    }
+}
+
+/// Returns the `ScalarType` of a scalar type other than an enum.
+fn scalar_type(prost_path: &Path, ty: &Ty) -> TokenStream {
+    let variant = match ty {
+        Ty::Double => quote!(Double),
+        Ty::Float => quote!(Float),
+        Ty::Int32 => quote!(Int32),
+        Ty::Int64 => quote!(Int64),
+        Ty::Uint32 => quote!(Uint32),
+        Ty::Uint64 => quote!(Uint64),
+        Ty::Sint32 => quote!(Sint32),
+        Ty::Sint64 => quote!(Sint64),
+        Ty::Fixed32 => quote!(Fixed32),
+        Ty::Fixed64 => quote!(Fixed64),
+        Ty::Sfixed32 => quote!(Sfixed32),
+        Ty::Sfixed64 => quote!(Sfixed64),
+        Ty::Bool => quote!(Bool),
+        Ty::String => quote!(String),
+        Ty::Bytes(_) => quote!(Bytes),
+        Ty::Enumeration(_) => unreachable!("enums are not scalar types"),
+    };
+    quote!(#prost_path::ScalarType::#variant)
+}
+
+/// Returns the `index`th type argument of a field type, looking through `Option`, references
+/// and slices: the value type `V` of `ArenaMap<'arena, K, V>` is argument 1, and the message type
+/// of `Option<LazyMessage<'arena, M>>` is argument 0.
+fn type_argument(field_type: &syn::Type, index: usize) -> syn::Type {
+    match field_type {
+        syn::Type::Reference(type_ref) => match &*type_ref.elem {
+            syn::Type::Slice(type_slice) => type_argument(&type_slice.elem, index),
+            elem => type_argument(elem, index),
+        },
+        syn::Type::Path(type_path) => {
+            let segment = type_path.path.segments.last().unwrap();
+            if let syn::PathArguments::AngleBracketed(args) = &segment.arguments {
+                let mut types = args.args.iter().filter_map(|arg| match arg {
+                    syn::GenericArgument::Type(ty) => Some(ty),
+                    _ => None,
+                });
+                if segment.ident == "Option" {
+                    if let Some(inner_type) = types.next() {
+                        return type_argument(inner_type, index);
+                    }
+                } else if let Some(ty) = types.nth(index) {
+                    return ty.clone();
+                }
+            }
+            panic!("Failed to extract a type argument of a field type")
+        }
+        _ => panic!("Unsupported field type"),
+    }
+}
+
+/// The type of a message or oneof copied into another arena: the same type, borrowing from the
+/// `'__clone` lifetime of `ArenaClone::clone_in` if it borrows at all.
+fn cloned_type(ident: &Ident, generics: &syn::Generics) -> TokenStream {
+    if generics.lifetimes().next().is_some() {
+        quote!(#ident<'__clone>)
+    } else {
+        quote!(#ident)
+    }
+}
+
+/// Converts an `UpperCamelCase` oneof variant name back to its `snake_case` field name.
+fn to_snake(name: &str) -> String {
+    let mut snake = String::with_capacity(name.len() + 4);
+    for (i, c) in name.chars().enumerate() {
+        if c.is_uppercase() {
+            if i > 0 {
+                snake.push('_');
+            }
+            snake.extend(c.to_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+    snake
+}
+
 /// Checks if a nested message type in a field type has a lifetime parameter
 /// Examples:
 /// - `&'arena [Address<'arena>]` → true (Address has <'arena>)
@@ -301,6 +514,7 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
     let Attributes {
         skip_debug,
         prost_path,
+        full_name,
     } = Attributes::new(input.attrs)?;
 
     let variant_data = match input.data {
@@ -339,6 +553,10 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
 
     let mut next_tag: u32 = 1;
     let mut fields_with_types: Vec<(TokenStream, syn::Type, field::Field)> = Vec::new();
+    // The `.proto` names of `fields_with_types`, in the same order
+    let mut field_names: Vec<field::Names> = Vec::new();
+    // The field marked `#[defiant(unknown_fields)]`, if any, which collects unrecognized fields
+    let mut unknown_fields: Option<TokenStream> = None;
 
     for (i, syn_field) in fields.into_iter().enumerate() {
         let field_ident = syn_field
@@ -354,15 +572,30 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
             });
         let field_type = syn_field.ty.clone();
 
+        if field::is_unknown_fields(&syn_field.attrs)? {
+            if !is_struct {
+                bail!("unknown_fields is not supported on tuple struct {ident}");
+            }
+            set_option(
+                &mut unknown_fields,
+                field_ident,
+                &format!("message {ident} has multiple unknown_fields fields"),
+            )?;
+            continue;
+        }
+
         // Validate that the field doesn't use disallowed types (Box, Vec, String, HashMap, BTreeMap)
         if let Err(err) = validate_arena_field_type(&field_type, &field_ident.to_string()) {
             bail!(err.context(format!("invalid field type for {ident}.{field_ident}")));
         }
 
+        let names = field::Names::new(&syn_field.attrs)
+            .with_context(|| format!("invalid message field {ident}.{field_ident}"))?;
         match Field::new(syn_field.attrs, Some(next_tag)) {
             Ok(Some(field)) => {
                 next_tag = field.tags().iter().max().map(|t| t + 1).unwrap_or(next_tag);
                 fields_with_types.push((field_ident, field_type, field));
+                field_names.push(names);
             }
             Ok(None) => {}
             Err(err) => {
@@ -411,7 +644,7 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
         .collect();
 
     // Generate View-specific encode/encoded_len that uses _ref variants for repeated strings/bytes
-    let view_encoded_len_stmts: Vec<_> = fields
+    let mut view_encoded_len_stmts: Vec<_> = fields
         .iter()
         .map(|(field_ident, field)| {
             use crate::field::Field;
@@ -472,7 +705,7 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
         })
         .collect();
 
-    let view_encode_stmts: Vec<_> = fields
+    let mut view_encode_stmts: Vec<_> = fields
         .iter()
         .map(|(field_ident, field)| {
             use crate::field::Field;
@@ -531,6 +764,70 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
         })
         .collect();
 
+    // Unknown fields are written back verbatim after all known fields
+    if let Some(unknown_fields) = &unknown_fields {
+        view_encoded_len_stmts.push(quote!(self.#unknown_fields.encoded_len()));
+        view_encode_stmts.push(quote!(self.#unknown_fields.encode_raw(buf);));
+    }
+
+    // Messages with nested messages measure each one once up front, so that writing
+    // the length prefixes doesn't re-measure every subtree at every level
+    let has_nested_messages = fields.iter().any(|(_, field)| field.has_nested_messages());
+    let view_encoded_len_cached_stmts: Vec<_> = fields
+        .iter()
+        .zip(&view_encoded_len_stmts)
+        .map(|((field_ident, field), stmt)| {
+            if field.has_nested_messages() {
+                field.encoded_len_cached(&prost_path, quote!(self.#field_ident))
+            } else {
+                stmt.clone()
+            }
+        })
+        .chain(view_encoded_len_stmts.get(fields.len()).cloned())
+        .collect();
+    let view_encode_cached_stmts: Vec<_> = fields
+        .iter()
+        .zip(&view_encode_stmts)
+        .map(|((field_ident, field), stmt)| {
+            if field.has_nested_messages() {
+                field.encode_cached(&prost_path, quote!(self.#field_ident))
+            } else {
+                stmt.clone()
+            }
+        })
+        .chain(view_encode_stmts.get(fields.len()).cloned())
+        .collect();
+
+    // The reverse encoder writes the fields last to first, so unknown fields come first
+    let view_encode_reverse_stmts: Vec<_> = fields
+        .iter()
+        .zip(view_encoded_len_stmts.iter().zip(&view_encode_stmts))
+        .map(|((field_ident, field), (len_stmt, stmt))| {
+            if field.has_nested_messages() {
+                field.encode_reverse(&prost_path, quote!(self.#field_ident))
+            } else {
+                field::prepend_encoded(len_stmt.clone(), stmt.clone())
+            }
+        })
+        .chain(
+            view_encoded_len_stmts
+                .get(fields.len())
+                .zip(view_encode_stmts.get(fields.len()))
+                .map(|(len_stmt, stmt)| field::prepend_encoded(len_stmt.clone(), stmt.clone())),
+        )
+        .rev()
+        .collect();
+
+    // Skip fields outside of the projection passed to `Decode::decode_projected`
+    let project_field = quote! {
+        let ctx = match ctx.project(tag) {
+            ::core::option::Option::Some(ctx) => ctx,
+            ::core::option::Option::None => {
+                return #prost_path::encoding::skip_field(wire_type, tag, buf, ctx);
+            }
+        };
+    };
+
     let merge = fields_with_types.iter().map(|(field_ident, field_type, field)| {
         use crate::field::Field;
         use crate::field::Label;
@@ -553,7 +850,18 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
             _ => false,
         };
 
-        if is_map_with_message_values {
+        if field.is_lazy() {
+            // Lazy messages: keep the encoded body, it is decoded by LazyMessage::get
+            quote! {
+                #(#tags)* => {
+                    #prost_path::encoding::lazy_message::merge(wire_type, &mut self.#field_ident, buf, arena, ctx)
+                        .map_err(|mut error| {
+                            error.push(STRUCT_NAME, stringify!(#field_ident));
+                            error
+                        })
+                },
+            }
+        } else if is_map_with_message_values {
             // Maps with message values: decode Builder, freeze to View, allocate, store reference
             // Extract value type from ArenaMap<'arena, K, V> -> V is the message type
             // Builder field is ArenaVec<'arena, (K, &'arena V)>
@@ -674,12 +982,12 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
             // Generate code to push the value into the map, with allocation if needed
             let push_code = if value_is_reference {
                 quote! {
-                    let value_ref = &*arena.alloc(value_view);
-                    self.#field_ident.push((key, value_ref));
+                    let value_ref = &*arena.try_alloc(value_view)?;
+                    self.#field_ident.try_push((key, value_ref))?;
                 }
             } else {
                 quote! {
-                    self.#field_ident.push((key, value_view));
+                    self.#field_ident.try_push((key, value_view))?;
                 }
             };
 
@@ -689,6 +997,7 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
                 #(#tags)* => {
                     // Custom map merge for message values
                     ctx.limit_reached()
+                        .and_then(|()| ctx.check_repeated_len(self.#field_ident.len()))
                         .map_err(|mut error| {
                             error.push(STRUCT_NAME, stringify!(#field_ident));
                             error
@@ -806,7 +1115,7 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
             let merge_code = match label {
                 Label::Optional => {
                     let assign_code = if message_needs_arena {
-                        quote!(self.#field_ident = Some(&*arena.alloc(view));)
+                        quote!(self.#field_ident = Some(&*arena.try_alloc(view)?);)
                     } else {
                         quote!(self.#field_ident = Some(view);)
                     };
@@ -822,10 +1131,10 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
                                 error.push(STRUCT_NAME, stringify!(#field_ident));
                                 error
                             })?;
-                        // If field already has a value, copy to builder to merge
+                        // If field already has a value, turn it back into a builder to merge
                         // Otherwise create a new builder
                         let mut builder = if let Some(existing_view) = self.#field_ident.take() {
-                            existing_view.copy_to_builder(arena)
+                            existing_view.to_builder(arena)
                         } else {
                             #builder_type_name::new_in(arena)
                         };
@@ -840,7 +1149,7 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
                 },
                 Label::Required => {
                     let assign_code = if message_needs_arena {
-                        quote!(self.#field_ident = &*arena.alloc(view);)
+                        quote!(self.#field_ident = &*arena.try_alloc(view)?;)
                     } else {
                         quote!(self.#field_ident = view;)
                     };
@@ -856,8 +1165,8 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
                                 error.push(STRUCT_NAME, stringify!(#field_ident));
                                 error
                             })?;
-                        // Copy existing view to builder to enable merging
-                        let mut builder = self.#field_ident.copy_to_builder(arena);
+                        // Turn the existing view back into a builder to enable merging
+                        let mut builder = self.#field_ident.to_builder(arena);
                         #merge_fn.map_err(|mut error| {
                             error.push(STRUCT_NAME, stringify!(#field_ident));
                             error
@@ -948,10 +1257,10 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
             };
             let push_code = if elem_is_reference {
                 // Slice element is &'arena T - allocate View and push reference
-                quote!(self.#field_ident.push(&*arena.alloc(builder.freeze()));)
+                quote!(self.#field_ident.try_push(&*arena.try_alloc(builder.freeze())?)?;)
             } else {
                 // Slice element is T - push View directly (ArenaVec will store it in arena)
-                quote!(self.#field_ident.push(builder.freeze());)
+                quote!(self.#field_ident.try_push(builder.freeze())?;)
             };
 
             quote! {
@@ -962,6 +1271,7 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
                             error
                         })?;
                     ctx.limit_reached()
+                        .and_then(|()| ctx.check_repeated_len(self.#field_ident.len()))
                         .map_err(|mut error| {
                             error.push(STRUCT_NAME, stringify!(#field_ident));
                             error
@@ -1003,6 +1313,17 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
         }
     });
 
+    // Messages with a full name can be extended, and check registered extensions as they're read
+    let merge_unknown = match (&unknown_fields, &full_name) {
+        (Some(unknown_fields), Some(full_name)) => quote! {
+            #prost_path::encoding::merge_extension_field(#full_name, wire_type, tag, buf, &mut self.#unknown_fields, ctx)
+        },
+        (Some(unknown_fields), None) => quote! {
+            #prost_path::encoding::merge_unknown_field(wire_type, tag, buf, &mut self.#unknown_fields, ctx)
+        },
+        (None, _) => quote!(#prost_path::encoding::skip_field(wire_type, tag, buf, ctx)),
+    };
+
     let struct_name = if fields.is_empty() {
         quote!()
     } else {
@@ -1033,24 +1354,160 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
         )}
     };
 
-    // Collect field-specific helper methods (e.g., for repeated enums)
-    let field_methods = fields
+    // Collect field-specific helper methods (presence and getters for singular fields). The
+    // mutating helpers of repeated and map fields need owned collections, so arena views skip them.
+    let mut field_methods = fields
         .iter()
+        .filter(|(_, field)| !(needs_arena && field.is_repeated()))
         .flat_map(|(field_ident, field)| field.methods(&prost_path, field_ident))
         .collect::<Vec<_>>();
 
-    // For arena-allocated messages, these methods will be added to the Builder impl later
-    // For scalar-only messages, create a separate impl block on the View
-    let methods = if needs_arena || field_methods.is_empty() {
-        // Skip creating an impl block here - methods will be added to Builder impl for arena types
-        quote!()
-    } else {
-        // For scalar-only messages, add methods to the message type itself
-        quote! {
-            #[allow(dead_code)]
-            impl #impl_generics #ident #ty_generics #where_clause {
-                #(#field_methods)*
+    // Presence and getters for message, group and oneof fields, which return the empty
+    // `DEFAULT` instance of the message type when unset
+    if is_struct {
+        field_methods.extend(fields_with_types.iter().filter_map(|(field_ident, field_type, field)| {
+            use crate::field::{Field, Label};
+
+            let ident_string = field_ident.to_string();
+            let name = ident_string.strip_prefix("r#").unwrap_or(&ident_string);
+            let has = Ident::new(&format!("has_{name}"), Span::call_site());
+            let has_doc = format!("Returns `true` if `{name}` is set.");
+            let get_doc = format!("Returns the value of `{name}`, or the default instance if `{name}` is unset.");
+
+            let label = match field {
+                Field::Message(message) => message.label,
+                Field::Group(group) => group.label,
+                Field::Oneof(_) => {
+                    return Some(quote! {
+                        #[doc=#has_doc]
+                        pub fn #has(&self) -> bool {
+                            self.#field_ident.is_some()
+                        }
+                    });
+                }
+                _ => return None,
+            };
+            let path = extract_type_path(field_type);
+            let lazy = matches!(field, Field::Message(message) if message.lazy);
+            match label {
+                Label::Repeated => None,
+                Label::Required => {
+                    let has_doc = format!("Returns `true`, since required field `{name}` is always set.");
+                    let getter = if is_held_by_reference(field_type) {
+                        quote!(pub fn #field_ident(&self) -> #field_type { self.#field_ident })
+                    } else {
+                        quote!(pub fn #field_ident(&self) -> &#field_type { &self.#field_ident })
+                    };
+                    Some(quote! {
+                        #[doc=#has_doc]
+                        pub fn #has(&self) -> bool {
+                            true
+                        }
+
+                        #[doc=#get_doc]
+                        #getter
+                    })
+                }
+                Label::Optional => {
+                    let getter = if lazy {
+                        let message_type = type_argument(field_type, 0);
+                        let get_doc = format!("Returns the value of `{name}`, which is empty if `{name}` is unset.");
+                        quote! {
+                            #[doc=#get_doc]
+                            pub fn #field_ident(&self) -> #prost_path::LazyMessage<'arena, #message_type> {
+                                self.#field_ident.unwrap_or(#prost_path::LazyMessage::new(&[]))
+                            }
+                        }
+                    } else if is_held_by_reference(field_type) {
+                        quote! {
+                            #[doc=#get_doc]
+                            pub fn #field_ident(&self) -> &'arena #path<'arena> {
+                                match self.#field_ident {
+                                    ::core::option::Option::Some(value) => value,
+                                    ::core::option::Option::None => &#path::DEFAULT,
+                                }
+                            }
+                        }
+                    } else {
+                        let ty = if type_uses_arena(field_type) {
+                            quote!(#path<'arena>)
+                        } else {
+                            quote!(#path)
+                        };
+                        quote! {
+                            #[doc=#get_doc]
+                            pub fn #field_ident(&self) -> &#ty {
+                                match &self.#field_ident {
+                                    ::core::option::Option::Some(value) => value,
+                                    ::core::option::Option::None => &#path::DEFAULT,
+                                }
+                            }
+                        }
+                    };
+                    Some(quote! {
+                        #[doc=#has_doc]
+                        pub fn #has(&self) -> bool {
+                            self.#field_ident.is_some()
+                        }
+
+                        #getter
+                    })
+                }
             }
+        }));
+    }
+
+    // The instance with every field unset, usable in constant expressions
+    let default_inits = fields_with_types
+        .iter()
+        .map(|(field_ident, field_type, field)| {
+            use crate::field::{Field, Label};
+
+            let value = match field {
+                Field::Map(_) => quote!(#prost_path::ArenaMap::new(&[])),
+                Field::Message(message) if message.label == Label::Required => {
+                    let path = extract_type_path(field_type);
+                    if is_held_by_reference(field_type) {
+                        quote!(&#path::DEFAULT)
+                    } else {
+                        quote!(#path::DEFAULT)
+                    }
+                }
+                Field::Group(group) if group.label == Label::Required => {
+                    let path = extract_type_path(field_type);
+                    if is_held_by_reference(field_type) {
+                        quote!(&#path::DEFAULT)
+                    } else {
+                        quote!(#path::DEFAULT)
+                    }
+                }
+                _ if field.is_repeated() => quote!(&[]),
+                Field::Scalar(scalar) => scalar.const_default(&prost_path),
+                _ => quote!(::core::option::Option::None),
+            };
+            quote!(#field_ident: #value,)
+        });
+    let unknown_fields_default = unknown_fields
+        .as_ref()
+        .map(|unknown_fields| quote!(#unknown_fields: #prost_path::UnknownFields::new(&[]),));
+    let static_ty = if needs_arena {
+        quote!(#ident<'static>)
+    } else {
+        quote!(#ident)
+    };
+    let methods = quote! {
+        impl #static_ty {
+            /// The message with every field unset, which getters of unset message fields
+            /// return.
+            pub const DEFAULT: Self = #ident {
+                #(#default_inits)*
+                #unknown_fields_default
+            };
+        }
+
+        #[allow(dead_code)]
+        impl #impl_generics #ident #ty_generics #where_clause {
+            #(#field_methods)*
         }
     };
 
@@ -1106,10 +1563,15 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
             quote!(pub(crate) #field_ident: #message_field_type)
         });
 
+        let unknown_fields_def = unknown_fields.as_ref().map(|unknown_fields| {
+            quote!(pub(crate) #unknown_fields: #prost_path::arena::ArenaVec<'arena, u8>,)
+        });
+
         if needs_arena {
             quote! {
                 pub(crate) arena: &'arena #prost_path::Arena,
                 #(#field_defs,)*
+                #unknown_fields_def
             }
         } else {
             quote! {
@@ -1229,6 +1691,47 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
             }
         });
 
+        // Generate clear_* methods, which reset a field to its unset state
+        let clear_methods = fields_with_types
+            .iter()
+            .map(|(field_ident, field_type, field)| {
+                use crate::field::{Field, Label};
+
+                let ident_string = field_ident.to_string();
+                let name = ident_string.strip_prefix("r#").unwrap_or(&ident_string);
+                let clear = Ident::new(&format!("clear_{name}"), Span::call_site());
+                let doc = format!("Clears `{name}`, as if it had never been set.");
+
+                let statement = match field {
+                    _ if field.is_repeated() => quote!(self.#field_ident.clear()),
+                    Field::Scalar(scalar) => scalar.clear(quote!(self.#field_ident)),
+                    Field::Message(message) if message.label == Label::Required => {
+                        let path = extract_type_path(field_type);
+                        if is_held_by_reference(field_type) {
+                            quote!(self.#field_ident = &#path::DEFAULT)
+                        } else {
+                            quote!(self.#field_ident = #path::DEFAULT)
+                        }
+                    }
+                    Field::Group(group) if group.label == Label::Required => {
+                        let path = extract_type_path(field_type);
+                        if is_held_by_reference(field_type) {
+                            quote!(self.#field_ident = &#path::DEFAULT)
+                        } else {
+                            quote!(self.#field_ident = #path::DEFAULT)
+                        }
+                    }
+                    _ => quote!(self.#field_ident = ::core::option::Option::None),
+                };
+                quote! {
+                    #[doc=#doc]
+                    pub fn #clear(&mut self) {
+                        #statement;
+                    }
+                }
+            })
+            .collect::<Vec<_>>();
+
         // Generate setter methods (set_* for singular, push_* for repeated)
         let setter_methods = fields_with_types.iter().map(|(field_ident, field_type, field)| {
             use crate::field::{Field, Ty};
@@ -1510,14 +2013,8 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
                 use crate::field::Field;
 
                 if matches!(field, Field::Map(_)) {
-                    // For map fields, sort by key and wrap in ArenaMap
-                    quote! {
-                        #field_ident: {
-                            let mut entries = self.#field_ident;
-                            entries.sort_by(|(k1, _), (k2, _)| k1.cmp(k2));
-                            #prost_path::ArenaMap::new(entries.freeze())
-                        }
-                    }
+                    // For map fields, sort by key (last duplicate wins) and wrap in ArenaMap
+                    quote!(#field_ident: #prost_path::ArenaMap::from_vec(self.#field_ident))
                 } else if field.is_repeated() {
                     // For all repeated fields (messages, groups, scalars), just freeze the ArenaVec
                     // Builders store view references, not builders, so no transformation needed
@@ -1529,6 +2026,138 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
             })
             .collect();
 
+        // merge_from() merges another view the way merging its encoding would; message fields
+        // are merged through their own builders
+        let merge_from_calls: Vec<_> = fields_with_types
+            .iter()
+            .map(|(field_ident, field_type, field)| {
+                use crate::field::{Field, Label, ValueTy};
+
+                match field {
+                    Field::Scalar(scalar_field) => {
+                        scalar_field.merge_from(quote!(self.#field_ident), quote!(other.#field_ident))
+                    }
+                    // Map entries are appended, and the last entry of a key wins on freeze()
+                    Field::Map(map_field)
+                        if matches!(map_field.value_ty, ValueTy::Message)
+                            && !matches!(type_argument(field_type, 1), syn::Type::Reference(_)) =>
+                    {
+                        quote! {
+                            for (k, v) in other.#field_ident.iter() {
+                                self.#field_ident.push((*k, v.to_builder(self.arena).freeze()));
+                            }
+                        }
+                    }
+                    Field::Map(_) => quote! {
+                        self.#field_ident.extend_from_slice(other.#field_ident.as_slice());
+                    },
+                    _ if field.is_repeated() => quote! {
+                        self.#field_ident.extend_from_slice(other.#field_ident);
+                    },
+                    Field::Oneof(oneof_field) => {
+                        let oneof_ty = &oneof_field.ty;
+                        let arena = type_uses_arena(field_type).then(|| quote!(, self.arena));
+                        quote! {
+                            if let ::core::option::Option::Some(value) = &other.#field_ident {
+                                #oneof_ty::merge_from(&mut self.#field_ident, value #arena);
+                            }
+                        }
+                    }
+                    // Concatenated encodings of a message merge into one
+                    Field::Message(msg_field) if msg_field.lazy => quote! {
+                        if let ::core::option::Option::Some(value) = other.#field_ident {
+                            self.#field_ident = ::core::option::Option::Some(match self.#field_ident {
+                                ::core::option::Option::Some(existing) => {
+                                    let existing = existing.as_bytes();
+                                    let mut bytes = self
+                                        .arena
+                                        .new_vec_with_capacity(existing.len() + value.as_bytes().len());
+                                    bytes.extend_from_slice(existing);
+                                    bytes.extend_from_slice(value.as_bytes());
+                                    #prost_path::LazyMessage::new(bytes.freeze())
+                                }
+                                ::core::option::Option::None => value,
+                            });
+                        }
+                    },
+                    Field::Message(_) | Field::Group(_) => {
+                        let label = match field {
+                            Field::Message(mf) => mf.label,
+                            Field::Group(gf) => gf.label,
+                            _ => unreachable!(),
+                        };
+                        let merged = if !needs_arena {
+                            // Builders of scalar-only messages have no arena, nor need one
+                            let builder_path = builder_path(field_type);
+                            quote! {{
+                                let mut builder = #builder_path::new();
+                                builder.merge_from(&existing);
+                                builder.merge_from(&value);
+                                builder.freeze()
+                            }}
+                        } else if is_held_by_reference(field_type) {
+                            quote! {{
+                                let mut builder = existing.to_builder(self.arena);
+                                builder.merge_from(value);
+                                &*self.arena.alloc(builder.freeze())
+                            }}
+                        } else {
+                            quote! {{
+                                let mut builder = existing.to_builder(self.arena);
+                                builder.merge_from(&value);
+                                builder.freeze()
+                            }}
+                        };
+                        match label {
+                            Label::Optional => quote! {
+                                if let ::core::option::Option::Some(value) = other.#field_ident {
+                                    self.#field_ident = ::core::option::Option::Some(match self.#field_ident {
+                                        ::core::option::Option::Some(existing) => #merged,
+                                        ::core::option::Option::None => value,
+                                    });
+                                }
+                            },
+                            Label::Required => quote! {
+                                {
+                                    let existing = self.#field_ident;
+                                    let value = other.#field_ident;
+                                    self.#field_ident = #merged;
+                                }
+                            },
+                            Label::Repeated => unreachable!("Repeated already handled"),
+                        }
+                    }
+                }
+            })
+            .collect();
+
+        let unknown_fields_init = unknown_fields
+            .as_ref()
+            .map(|unknown_fields| quote!(#unknown_fields: arena.new_vec(),));
+        let unknown_fields_merge_from = unknown_fields.as_ref().map(|unknown_fields| {
+            quote!(self.#unknown_fields.extend_from_slice(other.#unknown_fields.as_bytes());)
+        });
+        let unknown_fields_freeze = unknown_fields.as_ref().map(|unknown_fields| {
+            quote!(#unknown_fields: #prost_path::UnknownFields::new(self.#unknown_fields.freeze()),)
+        });
+        let extension_methods = unknown_fields.as_ref().map(|unknown_fields| {
+            quote! {
+                /// Sets the value of the extension, replacing any previous value.
+                pub fn set_extension<T: #prost_path::ExtensionValue>(
+                    &mut self,
+                    extension: &#prost_path::Extension<#ident<'static>, T>,
+                    value: T::Cloned<'arena>,
+                ) {
+                    extension.set(&mut self.#unknown_fields, &value);
+                }
+
+                /// Removes the extension.
+                pub fn clear_extension<T>(&mut self, extension: &#prost_path::Extension<#ident<'static>, T>) {
+                    extension.clear(&mut self.#unknown_fields);
+                }
+            }
+        });
+
         if needs_arena {
             quote! {
                 #[allow(missing_docs)]
@@ -1538,19 +2167,33 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
                         Self {
                             arena,
                             #(#field_inits,)*
+                            #unknown_fields_init
                         }
                     }
 
                     #(#setter_methods)*
 
+                    #(#clear_methods)*
+
+                    #extension_methods
+
                     #(#getter_methods)*
 
                     pub fn freeze(self) -> #ident #ty_generics {
                         #ident {
                             #(#freeze_field_inits,)*
+                            #unknown_fields_freeze
                         }
                     }
 
+                    /// Merges the fields of `other` into this builder, as merging its encoding
+                    /// would: set singular fields overwrite, repeated fields are appended, maps
+                    /// are merged by key and message fields are merged recursively.
+                    pub fn merge_from(&mut self, other: &#ident #ty_generics) {
+                        #(#merge_from_calls)*
+                        #unknown_fields_merge_from
+                    }
+
                     pub fn decode(buf: impl #prost_path::bytes::Buf, arena: &'arena #prost_path::Arena)
                         -> ::core::result::Result<Self, #prost_path::DecodeError>
                     {
@@ -1558,6 +2201,22 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
                         message.merge(buf, arena)?;
                         Ok(message)
                     }
+
+                    /// Parses a message from text format.
+                    pub fn parse_text(text: &str, arena: &'arena #prost_path::Arena)
+                        -> ::core::result::Result<Self, #prost_path::text_format::ParseError>
+                    {
+                        let buf = #prost_path::text_format::to_wire::<#ident #ty_generics>(text, arena)?;
+                        Ok(Self::decode(buf, arena)?)
+                    }
+
+                    /// Parses a message from its canonical JSON form.
+                    pub fn parse_json(json: &str, arena: &'arena #prost_path::Arena)
+                        -> ::core::result::Result<Self, #prost_path::json::ParseError>
+                    {
+                        let buf = #prost_path::json::to_wire::<#ident #ty_generics>(json, arena)?;
+                        Ok(Self::decode(buf, arena)?)
+                    }
                 }
             }
         } else {
@@ -1579,6 +2238,8 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
 
                     #(#setter_methods)*
 
+                    #(#clear_methods)*
+
                     #(#getter_methods)*
 
                     pub fn freeze(self) -> #ident #ty_generics {
@@ -1587,6 +2248,12 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
                         }
                     }
 
+                    /// Merges the fields of `other` into this builder, as merging its encoding
+                    /// would.
+                    pub fn merge_from(&mut self, other: &#ident #ty_generics) {
+                        #(#merge_from_calls)*
+                    }
+
                     pub fn decode(buf: impl #prost_path::bytes::Buf, arena: &#prost_path::Arena)
                         -> ::core::result::Result<Self, #prost_path::DecodeError>
                     {
@@ -1594,6 +2261,22 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
                         message.merge(buf, arena)?;
                         Ok(message)
                     }
+
+                    /// Parses a message from text format.
+                    pub fn parse_text(text: &str, arena: &#prost_path::Arena)
+                        -> ::core::result::Result<Self, #prost_path::text_format::ParseError>
+                    {
+                        let buf = #prost_path::text_format::to_wire::<#ident #ty_generics>(text, arena)?;
+                        Ok(Self::decode(buf, arena)?)
+                    }
+
+                    /// Parses a message from its canonical JSON form.
+                    pub fn parse_json(json: &str, arena: &#prost_path::Arena)
+                        -> ::core::result::Result<Self, #prost_path::json::ParseError>
+                    {
+                        let buf = #prost_path::json::to_wire::<#ident #ty_generics>(json, arena)?;
+                        Ok(Self::decode(buf, arena)?)
+                    }
                 }
             }
         }
@@ -1668,6 +2351,22 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
                         message.merge(buf, arena)?;
                         Ok(message)
                     }
+
+                    /// Parses a message from text format.
+                    pub fn parse_text(text: &str, arena: &'arena #prost_path::Arena)
+                        -> ::core::result::Result<Self, #prost_path::text_format::ParseError>
+                    {
+                        let buf = #prost_path::text_format::to_wire::<#ident #ty_generics>(text, arena)?;
+                        Ok(Self::decode(buf, arena)?)
+                    }
+
+                    /// Parses a message from its canonical JSON form.
+                    pub fn parse_json(json: &str, arena: &'arena #prost_path::Arena)
+                        -> ::core::result::Result<Self, #prost_path::json::ParseError>
+                    {
+                        let buf = #prost_path::json::to_wire::<#ident #ty_generics>(json, arena)?;
+                        Ok(Self::decode(buf, arena)?)
+                    }
                 }
             }
         } else {
@@ -1694,9 +2393,32 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
                         message.merge(buf, arena)?;
                         Ok(message)
                     }
-                }
-            }
-        }
+
+                    /// Parses a message from text format.
+                    pub fn parse_text(text: &str, arena: &#prost_path::Arena)
+                        -> ::core::result::Result<Self, #prost_path::text_format::ParseError>
+                    {
+                        let buf = #prost_path::text_format::to_wire::<#ident #ty_generics>(text, arena)?;
+                        Ok(Self::decode(buf, arena)?)
+                    }
+
+                    /// Parses a message from its canonical JSON form.
+                    pub fn parse_json(json: &str, arena: &#prost_path::Arena)
+                        -> ::core::result::Result<Self, #prost_path::json::ParseError>
+                    {
+                        let buf = #prost_path::json::to_wire::<#ident #ty_generics>(json, arena)?;
+                        Ok(Self::decode(buf, arena)?)
+                    }
+                }
+            }
+        }
+    };
+
+    // Borrowed string and bytes fields must live as long as the builder's arena
+    let decode_context = if needs_arena {
+        quote!(#prost_path::encoding::DecodeContext<'_, 'arena>)
+    } else {
+        quote!(#prost_path::encoding::DecodeContext)
     };
 
     // Generate internal methods for Builder (decode/encode infrastructure)
@@ -1720,14 +2442,15 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
                     wire_type: #prost_path::encoding::wire_type::WireType,
                     buf: &mut impl #prost_path::bytes::Buf,
                     arena: &#prost_path::Arena,
-                    ctx: #prost_path::encoding::DecodeContext,
+                    ctx: #decode_context,
                 ) -> ::core::result::Result<(), #prost_path::DecodeError>
                 {
                     #arena_binding
                     #struct_name
+                    #project_field
                     match tag {
                         #(#merge)*
-                        _ => #prost_path::encoding::skip_field(wire_type, tag, buf, ctx),
+                        _ => #merge_unknown,
                     }
                 }
 
@@ -1759,11 +2482,12 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
                     wire_type: #prost_path::encoding::wire_type::WireType,
                     buf: &mut impl #prost_path::bytes::Buf,
                     arena: &#prost_path::Arena,
-                    ctx: #prost_path::encoding::DecodeContext,
+                    ctx: #decode_context,
                 ) -> ::core::result::Result<(), #prost_path::DecodeError>
                 {
                     #arena_binding
                     #struct_name
+                    #project_field
                     match tag {
                         #(#merge)*
                         _ => #prost_path::encoding::skip_field(wire_type, tag, buf, ctx),
@@ -1782,34 +2506,183 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
         }
     };
 
+    let view_encode_methods = if has_nested_messages {
+        quote! {
+            // Nested lengths are recomputed here, which keeps `encode_raw` free of
+            // allocations; the `encode*` entry points measure them once instead
+            #[allow(unused_variables)]
+            fn encode_raw(&self, buf: &mut impl #prost_path::bytes::BufMut) {
+                use #prost_path::Encode as _;
+                #(#view_encode_stmts)*
+            }
+
+            fn encoded_len(&self) -> usize {
+                use #prost_path::Encode as _;
+                0 #(+ #view_encoded_len_stmts)*
+            }
+
+            #[allow(unused_variables)]
+            fn encoded_len_cached(&self, sizes: &mut #prost_path::encoding::SizeCache) -> usize {
+                use #prost_path::Encode as _;
+                0 #(+ #view_encoded_len_cached_stmts)*
+            }
+
+            #[allow(unused_variables)]
+            fn encode_raw_cached(&self, buf: &mut impl #prost_path::bytes::BufMut, sizes: &mut #prost_path::encoding::SizeCache) {
+                use #prost_path::Encode as _;
+                #(#view_encode_cached_stmts)*
+            }
+
+            #[allow(unused_variables)]
+            fn encode_reverse(&self, buf: &mut #prost_path::arena::ReverseBuf<'_>) {
+                use #prost_path::Encode as _;
+                #(#view_encode_reverse_stmts)*
+            }
+        }
+    } else {
+        quote! {
+            #[allow(unused_variables)]
+            fn encode_raw(&self, buf: &mut impl #prost_path::bytes::BufMut) {
+                use #prost_path::Encode as _;
+                #(#view_encode_stmts)*
+            }
+
+            fn encoded_len(&self) -> usize {
+                use #prost_path::Encode as _;
+                0 #(+ #view_encoded_len_stmts)*
+            }
+
+            #[allow(unused_variables)]
+            fn encode_reverse(&self, buf: &mut #prost_path::arena::ReverseBuf<'_>) {
+                use #prost_path::Encode as _;
+                #(#view_encode_reverse_stmts)*
+            }
+        }
+    };
+
+    // Extensions are kept with the unknown fields
+    let view_extension_methods = unknown_fields.as_ref().map(|unknown_fields| {
+        quote! {
+            /// Returns `true` if the extension is set.
+            pub fn has_extension<T>(&self, extension: &#prost_path::Extension<#ident<'static>, T>) -> bool {
+                extension.has(self.#unknown_fields)
+            }
+
+            /// Decodes the value of the extension, or returns `None` if it isn't set.
+            pub fn get_extension<T: #prost_path::ExtensionValue>(
+                &self,
+                extension: &#prost_path::Extension<#ident<'static>, T>,
+                arena: &'arena #prost_path::Arena,
+            ) -> ::core::result::Result<::core::option::Option<T::Cloned<'arena>>, #prost_path::DecodeError> {
+                extension.get(self.#unknown_fields, arena)
+            }
+        }
+    });
+
+    // Walk nested views, oneofs and lazily decoded bodies for missing required fields; a view
+    // holds a value for each of its own required fields
+    let missing_required_calls = fields_with_types
+        .iter()
+        .zip(&field_names)
+        .filter_map(|((field_ident, _, field), names)| {
+            use crate::field::{Field, Label, ValueTy};
+
+            let ident_string = field_ident.to_string();
+            let name = match &names.name {
+                Some(name) => name.clone(),
+                None => ident_string
+                    .strip_prefix("r#")
+                    .unwrap_or(&ident_string)
+                    .to_string(),
+            };
+            let check = quote!(value.collect_missing_required(missing));
+            let label = match field {
+                Field::Message(message_field) => message_field.label,
+                Field::Group(group_field) => group_field.label,
+                Field::Map(map_field) if matches!(map_field.value_ty, ValueTy::Message) => {
+                    return Some(quote! {
+                        missing.field(#name, |missing| {
+                            for (key, value) in self.#field_ident.iter() {
+                                missing.element(key, |missing| #check);
+                            }
+                        });
+                    });
+                }
+                Field::Oneof(_) => {
+                    return Some(quote! {
+                        if let ::core::option::Option::Some(value) = &self.#field_ident {
+                            #check;
+                        }
+                    });
+                }
+                _ => return None,
+            };
+            Some(match label {
+                Label::Optional => quote! {
+                    if let ::core::option::Option::Some(value) = &self.#field_ident {
+                        missing.field(#name, |missing| #check);
+                    }
+                },
+                Label::Required => quote! {
+                    let value = &self.#field_ident;
+                    missing.field(#name, |missing| #check);
+                },
+                Label::Repeated => quote! {
+                    missing.field(#name, |missing| {
+                        for (index, value) in self.#field_ident.iter().enumerate() {
+                            missing.element(index, |missing| #check);
+                        }
+                    });
+                },
+            })
+        })
+        .collect::<Vec<_>>();
+    let view_required_methods = quote! {
+        /// Returns `true` if no required field is missing from the message or the messages
+        /// nested in it.
+        pub fn is_initialized(&self) -> bool {
+            self.missing_required_fields().is_empty()
+        }
+
+        /// Returns the paths of the required fields missing from the message or the messages
+        /// nested in it, such as `items[2].owner.name`.
+        ///
+        /// A view holds a value for each of its required fields, so only messages nested as
+        /// [`LazyMessage`](#prost_path::LazyMessage) bodies can be missing any. Those are checked
+        /// without decoding them.
+        pub fn missing_required_fields(&self) -> #prost_path::alloc::vec::Vec<#prost_path::alloc::string::String> {
+            let mut missing = #prost_path::MissingRequired::default();
+            self.collect_missing_required(&mut missing);
+            missing.into_paths()
+        }
+
+        /// Encodes the message like `Encode::encode`, failing without writing anything if a
+        /// required field is missing, see [`missing_required_fields`](Self::missing_required_fields).
+        pub fn encode_checked(&self, buf: &mut impl #prost_path::bytes::BufMut) -> ::core::result::Result<(), #prost_path::EncodeError> {
+            if !self.is_initialized() {
+                return ::core::result::Result::Err(#prost_path::EncodeError::missing_required_fields());
+            }
+            #prost_path::Encode::encode(self, buf)
+        }
+
+        /// Collects the required fields missing from the messages nested in this one.
+        #[doc(hidden)]
+        #[allow(unused_variables)]
+        pub fn collect_missing_required(&self, missing: &mut #prost_path::MissingRequired) {
+            #(#missing_required_calls)*
+        }
+    };
+
     // Generate Encode impl for View types (arena-allocated messages)
     let view_encode_impl = if needs_arena {
         quote! {
             impl #impl_generics #prost_path::Encode for #ident #ty_generics #where_clause {
-                #[allow(unused_variables)]
-                fn encode_raw(&self, buf: &mut impl #prost_path::bytes::BufMut) {
-                    use #prost_path::Encode as _;
-                    #(#view_encode_stmts)*
-                }
-
-                fn encoded_len(&self) -> usize {
-                    use #prost_path::Encode as _;
-                    0 #(+ #view_encoded_len_stmts)*
-                }
+                #view_encode_methods
             }
 
             // Also implement Encode for &T so arena-allocated messages can be used in collections
             impl #impl_generics #prost_path::Encode for &#ident #ty_generics #where_clause {
-                #[allow(unused_variables)]
-                fn encode_raw(&self, buf: &mut impl #prost_path::bytes::BufMut) {
-                    use #prost_path::Encode as _;
-                    #(#view_encode_stmts)*
-                }
-
-                fn encoded_len(&self) -> usize {
-                    use #prost_path::Encode as _;
-                    0 #(+ #view_encoded_len_stmts)*
-                }
+                #view_encode_methods
             }
 
             impl #impl_generics #ident #ty_generics #where_clause {
@@ -1833,6 +2706,56 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
                     builder.merge_length_delimited(buf, arena)?;
                     Ok(builder.freeze())
                 }
+
+                /// Constructs a View from encoded bytes, enforcing the limits in `options`
+                pub fn from_buf_with_options(buf: impl #prost_path::bytes::Buf, arena: &'arena #prost_path::Arena, options: #prost_path::DecodeOptions) -> ::core::result::Result<Self, #prost_path::DecodeError> {
+                    use #prost_path::Decode as _;
+                    let builder = #message_ident::decode_with_options(buf, arena, options)?;
+                    Ok(builder.freeze())
+                }
+
+                /// Constructs a View from encoded bytes, decoding only the fields selected by `projection`
+                pub fn from_buf_projected(buf: impl #prost_path::bytes::Buf, arena: &'arena #prost_path::Arena, projection: &#prost_path::Projection) -> ::core::result::Result<Self, #prost_path::DecodeError> {
+                    use #prost_path::Decode as _;
+                    let builder = #message_ident::decode_projected(buf, arena, projection)?;
+                    Ok(builder.freeze())
+                }
+
+                /// Constructs a View from encoded bytes, checking the extensions in `registry`
+                pub fn from_buf_with_extensions(buf: impl #prost_path::bytes::Buf, arena: &'arena #prost_path::Arena, registry: &#prost_path::ExtensionRegistry) -> ::core::result::Result<Self, #prost_path::DecodeError> {
+                    use #prost_path::Decode as _;
+                    let builder = #message_ident::decode_with_extensions(buf, arena, registry)?;
+                    Ok(builder.freeze())
+                }
+
+                /// Constructs a View from encoded bytes, borrowing string and bytes fields from `buf`
+                pub fn from_buf_borrowed(buf: &'arena [u8], arena: &'arena #prost_path::Arena) -> ::core::result::Result<Self, #prost_path::DecodeError> {
+                    use #prost_path::Decode as _;
+                    let builder = #message_ident::decode_borrowed(buf, arena)?;
+                    Ok(builder.freeze())
+                }
+
+                #view_extension_methods
+
+                /// Formats the message as text format.
+                pub fn to_text_format(&self) -> #prost_path::alloc::string::String {
+                    #prost_path::text_format::to_string(self)
+                }
+
+                /// Formats the message in its canonical JSON form.
+                pub fn to_json(&self) -> ::core::result::Result<
+                    #prost_path::alloc::string::String,
+                    #prost_path::json::SerializeError,
+                > {
+                    #prost_path::json::to_string(self)
+                }
+
+                /// Returns the fields which differ between this message and `other`.
+                pub fn diff(&self, other: &Self) -> #prost_path::alloc::vec::Vec<#prost_path::diff::FieldDiff> {
+                    #prost_path::diff::diff(self, other)
+                }
+
+                #view_required_methods
             }
         }
     } else {
@@ -1973,26 +2896,12 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
         // Also implement Encode for &T so it can be used in Option<&T> fields
         quote! {
             impl #prost_path::Encode for #ident #ty_generics #where_clause {
-                #[allow(unused_variables)]
-                fn encode_raw(&self, buf: &mut impl #prost_path::bytes::BufMut) {
-                    #(#view_encode_stmts)*
-                }
-
-                fn encoded_len(&self) -> usize {
-                    0 #(+ #view_encoded_len_stmts)*
-                }
+                #view_encode_methods
             }
 
             // Implement Encode for &T so scalar-only messages can be used in Option<&T>
             impl #prost_path::Encode for &#ident #ty_generics #where_clause {
-                #[allow(unused_variables)]
-                fn encode_raw(&self, buf: &mut impl #prost_path::bytes::BufMut) {
-                    #(#view_encode_stmts)*
-                }
-
-                fn encoded_len(&self) -> usize {
-                    0 #(+ #view_encoded_len_stmts)*
-                }
+                #view_encode_methods
             }
 
             impl<'arena> #prost_path::Decode<'arena> for #ident #ty_generics #where_clause {
@@ -2002,14 +2911,19 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
                     }
                 }
 
+                fn message_type() -> ::core::option::Option<#prost_path::MessageType> {
+                    ::core::option::Option::Some(#prost_path::MessageType::of::<#ident #ty_generics>())
+                }
+
                 fn merge_field(
                     &mut self,
                     tag: u32,
                     wire_type: #prost_path::encoding::wire_type::WireType,
                     buf: &mut impl #prost_path::bytes::Buf,
                     arena: &'arena #prost_path::Arena,
-                    ctx: #prost_path::encoding::DecodeContext,
+                    ctx: #prost_path::encoding::DecodeContext<'_, 'arena>,
                 ) -> ::core::result::Result<(), #prost_path::DecodeError> {
+                    #project_field
                     match tag {
                         #(#scalar_merge_stmts,)*
                         _ => #prost_path::encoding::skip_field(wire_type, tag, buf, ctx),
@@ -2023,6 +2937,38 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
                     let builder = #message_ident::decode(buf, arena)?;
                     Ok(builder.freeze())
                 }
+
+                /// Constructs a View from encoded bytes, enforcing the limits in `options`
+                pub fn from_buf_with_options(buf: impl #prost_path::bytes::Buf, arena: &#prost_path::Arena, options: #prost_path::DecodeOptions) -> ::core::result::Result<Self, #prost_path::DecodeError> {
+                    let builder = <#message_ident as #prost_path::Decode>::decode_with_options(buf, arena, options)?;
+                    Ok(builder.freeze())
+                }
+
+                /// Constructs a View from encoded bytes, decoding only the fields selected by `projection`
+                pub fn from_buf_projected(buf: impl #prost_path::bytes::Buf, arena: &#prost_path::Arena, projection: &#prost_path::Projection) -> ::core::result::Result<Self, #prost_path::DecodeError> {
+                    let builder = <#message_ident as #prost_path::Decode>::decode_projected(buf, arena, projection)?;
+                    Ok(builder.freeze())
+                }
+
+                /// Formats the message as text format.
+                pub fn to_text_format(&self) -> #prost_path::alloc::string::String {
+                    #prost_path::text_format::to_string(self)
+                }
+
+                /// Formats the message in its canonical JSON form.
+                pub fn to_json(&self) -> ::core::result::Result<
+                    #prost_path::alloc::string::String,
+                    #prost_path::json::SerializeError,
+                > {
+                    #prost_path::json::to_string(self)
+                }
+
+                /// Returns the fields which differ between this message and `other`.
+                pub fn diff(&self, other: &Self) -> #prost_path::alloc::vec::Vec<#prost_path::diff::FieldDiff> {
+                    #prost_path::diff::diff(self, other)
+                }
+
+                #view_required_methods
             }
         }
     };
@@ -2070,22 +3016,31 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
             }
         }).collect();
 
+        let unknown_fields_init = unknown_fields
+            .as_ref()
+            .map(|unknown_fields| quote!(#unknown_fields: arena.new_vec(),));
+
         quote! {
             impl #impl_generics #prost_path::Decode<'arena> for #message_ident #ty_generics #where_clause {
                 fn new_in(arena: &'arena #prost_path::Arena) -> Self {
                     Self {
                         arena,
                         #(#default_field_inits,)*
+                        #unknown_fields_init
                     }
                 }
 
+                fn message_type() -> ::core::option::Option<#prost_path::MessageType> {
+                    ::core::option::Option::Some(#prost_path::MessageType::of::<#ident #ty_generics>())
+                }
+
                 fn merge_field(
                     &mut self,
                     tag: u32,
                     wire_type: #prost_path::encoding::wire_type::WireType,
                     buf: &mut impl #prost_path::bytes::Buf,
                     arena: &'arena #prost_path::Arena,
-                    ctx: #prost_path::encoding::DecodeContext,
+                    ctx: #prost_path::encoding::DecodeContext<'_, 'arena>,
                 ) -> ::core::result::Result<(), #prost_path::DecodeError> {
                     self.merge_field(tag, wire_type, buf, arena, ctx)
                 }
@@ -2099,6 +3054,10 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
                     Self::new()
                 }
 
+                fn message_type() -> ::core::option::Option<#prost_path::MessageType> {
+                    ::core::option::Option::Some(#prost_path::MessageType::of::<#ident>())
+                }
+
                 fn merge_field(
                     &mut self,
                     tag: u32,
@@ -2123,336 +3082,345 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
                     let builder = #message_ident::decode(buf, arena)?;
                     Ok(builder.freeze())
                 }
+
+                fn from_buf_with_options(buf: impl #prost_path::bytes::Buf, arena: &'arena #prost_path::Arena, options: #prost_path::DecodeOptions) -> ::core::result::Result<Self, #prost_path::DecodeError> {
+                    use #prost_path::Decode as _;
+                    let builder = #message_ident::decode_with_options(buf, arena, options)?;
+                    Ok(builder.freeze())
+                }
             }
         }
     } else {
         quote!()
     };
 
-    // Generate copy_to_builder() method for Views (enables merging and request/response reuse)
-    // Generate for all structs, not just arena types, since scalar-only messages can be nested
-    let copy_to_builder_impl = if is_struct {
-        let copy_calls: Vec<_> = fields_with_types.iter().map(|(field_ident, field_type, field)| {
-            use crate::field::{Field, Label};
+    let copy_unknown_fields = unknown_fields.as_ref().map(|unknown_fields| {
+        quote!(builder.#unknown_fields.extend_from_slice(self.#unknown_fields.as_bytes());)
+    });
 
-            match field {
-                // Map fields - iterate and push each entry
-                // For message values that need arena, recursively copy; otherwise copy directly
-                Field::Map(map_field) => {
-                    use crate::field::ValueTy;
-                    match &map_field.value_ty {
-                        ValueTy::Message => {
-                            // Check if the map value type in the View is a reference
-                            // Extract value type from ArenaMap<'arena, K, V>
-                            let value_is_ref = if let syn::Type::Path(type_path) = field_type {
-                                if let Some(last_seg) = type_path.path.segments.last() {
-                                    if last_seg.ident == "ArenaMap" {
-                                        if let syn::PathArguments::AngleBracketed(args) = &last_seg.arguments {
-                                            let type_args: Vec<_> = args.args.iter().skip(1).collect();
-                                            if type_args.len() == 2 {
-                                                if let syn::GenericArgument::Type(val_ty) = type_args[1] {
-                                                    matches!(val_ty, syn::Type::Reference(_))
-                                                } else {
-                                                    false
-                                                }
-                                            } else {
-                                                false
-                                            }
-                                        } else {
-                                            false
-                                        }
-                                    } else {
-                                        false
-                                    }
-                                } else {
-                                    false
-                                }
-                            } else {
-                                false
-                            };
+    // to_builder() shares everything the view borrows from the arena: only repeated fields,
+    // map entries and unknown fields are copied into the builder's vectors, while strings,
+    // bytes and nested messages stay where they are until a setter replaces them
+    let share_calls = fields_with_types
+        .iter()
+        .map(|(field_ident, field_type, field)| {
+            use crate::field::{Field, ValueTy};
 
-                            // Message values - recursively copy and push
-                            if value_is_ref {
-                                quote! {
-                                    for (k, v) in self.#field_ident.iter() {
-                                        let v_builder = v.copy_to_builder(arena);
-                                        let v_frozen = v_builder.freeze();
-                                        let v_ref = &*arena.alloc(v_frozen);
-                                        builder.#field_ident.push((*k, v_ref));
-                                    }
-                                }
-                            } else {
-                                quote! {
-                                    for (k, v) in self.#field_ident.iter() {
-                                        let v_builder = v.copy_to_builder(arena);
-                                        let v_frozen = v_builder.freeze();
-                                        builder.#field_ident.push((*k, v_frozen));
-                                    }
-                                }
-                            }
-                        }
-                        ValueTy::Scalar(_) => {
-                            // Scalar values can be copied directly
-                            quote! {
-                                for (k, v) in self.#field_ident.iter() {
-                                    builder.#field_ident.push((*k, *v));
-                                }
-                            }
-                        }
-                    }
-                },
-                // Repeated fields - extend from slice
-                _ if field.is_repeated() => {
+            match field {
+                Field::Map(map_field)
+                    if matches!(map_field.value_ty, ValueTy::Message)
+                        && !matches!(type_argument(field_type, 1), syn::Type::Reference(_)) =>
+                {
                     quote! {
-                        builder.#field_ident.extend_from_slice(self.#field_ident);
-                    }
-                },
-                // Singular message fields - recursively copy if arena type, else shallow copy
-                Field::Message(msg_field) => {
-                    let message_needs_arena = type_uses_arena(field_type);
-                    if message_needs_arena {
-                        match msg_field.label {
-                            Label::Optional => {
-                                quote! {
-                                    builder.#field_ident = self.#field_ident.map(|nested_view| {
-                                        let nested_builder = nested_view.copy_to_builder(arena);
-                                        let nested_frozen = nested_builder.freeze();
-                                        &*arena.alloc(nested_frozen)
-                                    });
-                                }
-                            },
-                            Label::Required => {
-                                quote! {
-                                    {
-                                        let nested_builder = self.#field_ident.copy_to_builder(arena);
-                                        let nested_frozen = nested_builder.freeze();
-                                        builder.#field_ident = &*arena.alloc(nested_frozen);
-                                    }
-                                }
-                            },
-                            Label::Repeated => unreachable!("Repeated already handled"),
-                        }
-                    } else {
-                        // Scalar-only message - shallow copy
-                        quote! {
-                            builder.#field_ident = self.#field_ident;
+                        for (k, v) in self.#field_ident.iter() {
+                            builder.#field_ident.push((*k, v.to_builder(arena).freeze()));
                         }
                     }
+                }
+                Field::Map(_) => quote! {
+                    builder.#field_ident.extend_from_slice(self.#field_ident.as_slice());
                 },
-                // Group fields - similar to message
-                Field::Group(group_field) => {
-                    let message_needs_arena = type_uses_arena(field_type);
-                    if message_needs_arena {
-                        match group_field.label {
-                            Label::Optional => {
-                                quote! {
-                                    builder.#field_ident = self.#field_ident.map(|nested_view| {
-                                        let nested_builder = nested_view.copy_to_builder(arena);
-                                        let nested_frozen = nested_builder.freeze();
-                                        &*arena.alloc(nested_frozen)
-                                    });
-                                }
-                            },
-                            Label::Required => {
-                                quote! {
-                                    {
-                                        let nested_builder = self.#field_ident.copy_to_builder(arena);
-                                        let nested_frozen = nested_builder.freeze();
-                                        builder.#field_ident = &*arena.alloc(nested_frozen);
-                                    }
-                                }
-                            },
-                            Label::Repeated => unreachable!("Repeated already handled"),
-                        }
-                    } else {
-                        // Scalar-only group - shallow copy
-                        quote! {
-                            builder.#field_ident = self.#field_ident;
-                        }
-                    }
+                _ if field.is_repeated() => quote! {
+                    builder.#field_ident.extend_from_slice(self.#field_ident);
                 },
-                // Oneof fields - clone
-                Field::Oneof(_) => {
-                    quote! {
-                        builder.#field_ident = self.#field_ident.clone();
-                    }
+                Field::Oneof(_) => quote! {
+                    builder.#field_ident = self.#field_ident.clone();
+                },
+                _ => quote! {
+                    builder.#field_ident = self.#field_ident;
                 },
-                // Scalar fields - simple copy
-                _ => {
-                    quote! {
-                        builder.#field_ident = self.#field_ident;
-                    }
-                }
             }
-        }).collect();
+        })
+        .collect::<Vec<_>>();
 
+    // Generate to_builder() and copy_to_builder() for Views (enables merging and request/response
+    // reuse). Generate for all structs, not just arena types, since scalar-only messages can be nested
+    let to_builder_impl = if is_struct {
         if needs_arena {
             quote! {
                 impl #impl_generics #ident #ty_generics #where_clause {
-                    /// Creates a new builder by copying data from this view.
-                    /// This is expensive as it recursively copies all nested messages.
-                    /// Useful for merging or creating modified copies.
-                    pub fn copy_to_builder(&self, arena: &'arena #prost_path::Arena) -> #message_ident #ty_generics {
+                    /// Creates a builder holding the same field values as this view, to modify a
+                    /// decoded message and encode it again.
+                    ///
+                    /// Repeated fields, map entries and unknown fields are copied into vectors in
+                    /// `arena`; strings, bytes and nested messages are shared with the view rather
+                    /// than copied, and only replaced once a setter is called.
+                    pub fn to_builder(&self, arena: &'arena #prost_path::Arena) -> #message_ident #ty_generics {
                         let mut builder = #message_ident::new_in(arena);
-                        #(#copy_calls)*
+                        #(#share_calls)*
+                        #copy_unknown_fields
                         builder
                     }
+
+                    /// Same as [`to_builder`](Self::to_builder), which shares nested messages with
+                    /// the view instead of copying them.
+                    pub fn copy_to_builder(&self, arena: &'arena #prost_path::Arena) -> #message_ident #ty_generics {
+                        self.to_builder(arena)
+                    }
+
+                    /// Returns this view with `other` merged into it, as if the encoding of
+                    /// `other` was decoded on top of it.
+                    pub fn merged(&self, other: &Self, arena: &'arena #prost_path::Arena) -> Self {
+                        let mut builder = self.to_builder(arena);
+                        builder.merge_from(other);
+                        builder.freeze()
+                    }
                 }
             }
         } else {
             // Scalar-only messages still need arena param since they might be nested in arena messages
             quote! {
                 impl #ident {
-                    /// Creates a new builder by copying data from this view.
-                    pub fn copy_to_builder<'arena>(&self, arena: &'arena #prost_path::Arena) -> #message_ident {
+                    /// Creates a builder holding the same field values as this view, to modify a
+                    /// decoded message and encode it again.
+                    pub fn to_builder<'arena>(&self, arena: &'arena #prost_path::Arena) -> #message_ident {
                         let mut builder = #message_ident::new_in(arena);
-                        #(#copy_calls)*
+                        #(#share_calls)*
                         builder
                     }
+
+                    /// Same as [`to_builder`](Self::to_builder).
+                    pub fn copy_to_builder<'arena>(&self, arena: &'arena #prost_path::Arena) -> #message_ident {
+                        self.to_builder(arena)
+                    }
+
+                    /// Returns this view with `other` merged into it, as if the encoding of
+                    /// `other` was decoded on top of it.
+                    pub fn merged(&self, other: &Self, arena: &#prost_path::Arena) -> Self {
+                        let mut builder = self.to_builder(arena);
+                        builder.merge_from(other);
+                        builder.freeze()
+                    }
+                }
+            }
+        }
+    } else if needs_arena {
+        // Tuple structs - fields are accessed by index
+        quote! {
+            impl #ident {
+                /// Creates a builder holding the same field values as this view, to modify a
+                /// decoded message and encode it again.
+                pub fn to_builder<'arena>(&self, arena: &'arena #prost_path::Arena) -> #message_ident<'arena> {
+                    let mut builder = #message_ident::new_in(arena);
+                    #(#share_calls)*
+                    #copy_unknown_fields
+                    builder
+                }
+
+                /// Same as [`to_builder`](Self::to_builder).
+                pub fn copy_to_builder<'arena>(&self, arena: &'arena #prost_path::Arena) -> #message_ident<'arena> {
+                    self.to_builder(arena)
                 }
             }
         }
     } else {
-        // Tuple structs - copy implementation is similar but accesses fields by index
-        let copy_calls: Vec<_> = fields_with_types.iter().map(|(field_ident, field_type, field)| {
-            use crate::field::{Field, Label};
+        quote! {
+            impl #ident {
+                /// Creates a builder holding the same field values as this view, to modify a
+                /// decoded message and encode it again.
+                pub fn to_builder<'arena>(&self, arena: &'arena #prost_path::Arena) -> #message_ident {
+                    let mut builder = #message_ident::new_in(arena);
+                    #(#share_calls)*
+                    builder
+                }
 
-            match field {
-                Field::Map(map_field) => {
-                    use crate::field::ValueTy;
-                    match &map_field.value_ty {
-                        ValueTy::Message => {
-                            let value_is_ref = if let syn::Type::Path(type_path) = field_type {
-                                if let Some(last_seg) = type_path.path.segments.last() {
-                                    if last_seg.ident == "ArenaMap" {
-                                        if let syn::PathArguments::AngleBracketed(args) = &last_seg.arguments {
-                                            let type_args: Vec<_> = args.args.iter().skip(1).collect();
-                                            if type_args.len() == 2 {
-                                                if let syn::GenericArgument::Type(val_ty) = type_args[1] {
-                                                    matches!(val_ty, syn::Type::Reference(_))
-                                                } else {
-                                                    false
-                                                }
-                                            } else {
-                                                false
-                                            }
-                                        } else {
-                                            false
-                                        }
-                                    } else {
-                                        false
-                                    }
-                                } else {
-                                    false
-                                }
-                            } else {
-                                false
-                            };
+                /// Same as [`to_builder`](Self::to_builder).
+                pub fn copy_to_builder<'arena>(&self, arena: &'arena #prost_path::Arena) -> #message_ident {
+                    self.to_builder(arena)
+                }
+            }
+        }
+    };
 
-                            if value_is_ref {
-                                quote! {
-                                    for (k, v) in self.#field_ident.iter() {
-                                        let v_builder = v.copy_to_builder(arena);
-                                        let v_frozen = v_builder.freeze();
-                                        let v_ref = &*arena.alloc(v_frozen);
-                                        builder.#field_ident.push((*k, v_ref));
-                                    }
-                                }
-                            } else {
-                                quote! {
-                                    for (k, v) in self.#field_ident.iter() {
-                                        let v_builder = v.copy_to_builder(arena);
-                                        let v_frozen = v_builder.freeze();
-                                        builder.#field_ident.push((*k, v_frozen));
-                                    }
-                                }
-                            }
-                        }
+    // Resolve fields by name and tag for projections, text format and JSON; oneof members are
+    // looked up in the oneof
+    let field_set_impl = {
+        let mut named_fields = Vec::new();
+        let mut tagged_fields = Vec::new();
+        let mut oneofs = Vec::new();
+        let mut oneof_names = Vec::new();
+        let mut required_tags = Vec::new();
+        if is_struct {
+            for ((field_ident, field_type, field), names) in
+                fields_with_types.iter().zip(&field_names)
+            {
+                let ident_string = field_ident.to_string();
+                let name = match &names.name {
+                    Some(name) => name.as_str(),
+                    None => ident_string.strip_prefix("r#").unwrap_or(&ident_string),
+                };
+                if let Field::Oneof(oneof) = field {
+                    oneofs.push(oneof.ty.clone());
+                    oneof_names.push(name.to_string());
+                    continue;
+                }
+                let tag = field.tags()[0];
+                if field.is_required() {
+                    required_tags.push(tag);
+                }
+                let info = field_info(
+                    &prost_path,
+                    name,
+                    names.json_name.as_deref(),
+                    field,
+                    field_type,
+                );
+                let json_name = names
+                    .json_name
+                    .clone()
+                    .unwrap_or_else(|| to_json_name(name));
+                if json_name != name {
+                    named_fields.push(quote!(#json_name => ::core::option::Option::Some(#info),));
+                }
+                named_fields.push(quote!(#name => ::core::option::Option::Some(#info),));
+                tagged_fields.push(quote!(#tag => ::core::option::Option::Some(#info),));
+            }
+        }
+        let full_name_fn = full_name.as_ref().map(|full_name| {
+            quote! {
+                fn full_name() -> ::core::option::Option<&'static str> {
+                    ::core::option::Option::Some(#full_name)
+                }
+            }
+        });
+        required_tags.sort_unstable();
+        let required_tags_fn = (!required_tags.is_empty()).then(|| {
+            quote! {
+                fn required_tags() -> &'static [u32] {
+                    &[#(#required_tags),*]
+                }
+            }
+        });
+        quote! {
+            impl #impl_generics #prost_path::FieldSet for #ident #ty_generics #where_clause {
+                #[allow(unreachable_patterns)]
+                fn field(name: &str) -> ::core::option::Option<#prost_path::FieldInfo> {
+                    match name {
+                        #(#named_fields)*
                         _ => {
-                            quote! {
-                                for (k, v) in self.#field_ident.iter() {
-                                    builder.#field_ident.push((*k, *v));
+                            #(
+                                if let ::core::option::Option::Some(field) = <#oneofs as #prost_path::FieldSet>::field(name) {
+                                    return ::core::option::Option::Some(field.in_oneof(#oneof_names));
                                 }
-                            }
+                            )*
+                            ::core::option::Option::None
                         }
                     }
                 }
-                Field::Message(_) | Field::Group(_) => {
-                    let label = match field {
-                        Field::Message(mf) => mf.label,
-                        Field::Group(gf) => gf.label,
-                        _ => unreachable!(),
-                    };
-                    match label {
-                        Label::Optional => {
-                            quote! {
-                                builder.#field_ident = self.#field_ident.map(|msg| {
-                                    let msg_builder = msg.copy_to_builder(arena);
-                                    let msg_frozen = msg_builder.freeze();
-                                    &*arena.alloc(msg_frozen)
-                                });
-                            }
-                        }
-                        Label::Required => {
-                            quote! {
-                                builder.#field_ident = {
-                                    let msg_builder = self.#field_ident.copy_to_builder(arena);
-                                    let msg_frozen = msg_builder.freeze();
-                                    &*arena.alloc(msg_frozen)
-                                };
-                            }
-                        }
-                        Label::Repeated => {
-                            quote! {
-                                for msg in self.#field_ident {
-                                    let msg_builder = msg.copy_to_builder(arena);
-                                    let msg_frozen = msg_builder.freeze();
-                                    let msg_ref = &*arena.alloc(msg_frozen);
-                                    builder.#field_ident.push(msg_ref);
+
+                fn field_by_tag(tag: u32) -> ::core::option::Option<#prost_path::FieldInfo> {
+                    match tag {
+                        #(#tagged_fields)*
+                        _ => {
+                            #(
+                                if let ::core::option::Option::Some(field) = <#oneofs as #prost_path::FieldSet>::field_by_tag(tag) {
+                                    return ::core::option::Option::Some(field.in_oneof(#oneof_names));
                                 }
-                            }
-                        }
-                    }
-                }
-                _ => {
-                    if field.is_repeated() {
-                        quote! {
-                            for item in self.#field_ident {
-                                builder.#field_ident.push(*item);
-                            }
-                        }
-                    } else {
-                        quote! {
-                            builder.#field_ident = self.#field_ident;
+                            )*
+                            ::core::option::Option::None
                         }
                     }
                 }
+
+                #full_name_fn
+
+                #required_tags_fn
             }
-        }).collect();
+        }
+    };
 
-        if needs_arena {
-            quote! {
-                impl #ident {
-                    /// Creates a new builder by copying data from this view.
-                    pub fn copy_to_builder<'arena>(&self, arena: &'arena #prost_path::Arena) -> #message_ident<'arena> {
-                        let mut builder = #message_ident::new_in(arena);
-                        #(#copy_calls)*
-                        builder
-                    }
+    // Builds a copy of the message with each field converted by `convert`
+    let all_fields: Vec<&TokenStream> = fields_with_types
+        .iter()
+        .map(|(field_ident, ..)| field_ident)
+        .chain(&unknown_fields)
+        .collect();
+    let construct = |convert: &dyn Fn(&TokenStream) -> TokenStream| {
+        let values = all_fields.iter().map(|field_ident| convert(field_ident));
+        if is_struct {
+            let field_idents = &all_fields;
+            quote!(#ident { #(#field_idents: #values,)* })
+        } else if all_fields.is_empty() {
+            quote!(#ident)
+        } else {
+            quote!(#ident(#(#values,)*))
+        }
+    };
+
+    // Deep-copy a view into another arena, field by field
+    let arena_clone_impl = {
+        let cloned = cloned_type(&ident, generics);
+        let body = construct(
+            &|field_ident| quote!(#prost_path::ArenaClone::clone_in(&self.#field_ident, arena)),
+        );
+        quote! {
+            impl #impl_generics #prost_path::ArenaClone for #ident #ty_generics #where_clause {
+                type Cloned<'__clone> = #cloned;
+
+                fn clone_in<'__clone>(&self, arena: &'__clone #prost_path::Arena) -> #cloned {
+                    #body
                 }
             }
+        }
+    };
+
+    // Messages can be the values of extensions, decoded from the merged bodies of every value
+    let extension_value_impl = {
+        let cloned = cloned_type(&ident, generics);
+        let static_ty = if generics.lifetimes().next().is_some() {
+            quote!(#ident<'static>)
         } else {
-            quote! {
-                impl #ident {
-                    /// Creates a new builder by copying data from this view.
-                    pub fn copy_to_builder<'arena>(&self, arena: &'arena #prost_path::Arena) -> #message_ident {
-                        let mut builder = #message_ident::new_in(arena);
-                        #(#copy_calls)*
-                        builder
-                    }
+            quote!(#ident)
+        };
+        quote! {
+            impl #prost_path::ExtensionValue for #static_ty {
+                fn decode<'__clone>(
+                    ty: #prost_path::FieldType,
+                    values: &[(#prost_path::encoding::WireType, &'__clone [u8])],
+                    arena: &'__clone #prost_path::Arena,
+                ) -> ::core::result::Result<#cloned, #prost_path::DecodeError> {
+                    <#cloned>::from_buf(#prost_path::extension::message_body(ty, values, arena)?, arena)
+                }
+
+                fn encode<'__clone>(
+                    value: &#cloned,
+                    ty: #prost_path::FieldType,
+                    tag: u32,
+                    buf: &mut impl #prost_path::bytes::BufMut,
+                ) {
+                    #prost_path::extension::encode_message(value, ty, tag, buf);
+                }
+            }
+        }
+    };
+
+    // Messages which borrow nothing are their own owned counterpart; generated owned messages
+    // hold them as they are
+    let owned_impl = if generics.params.is_empty() {
+        let to_owned = construct(
+            &|field_ident| quote!(#prost_path::ToOwnedValue::to_owned_value(&self.#field_ident)),
+        );
+        let from_owned = construct(
+            &|field_ident| quote!(#prost_path::ArenaFrom::arena_from(&value.#field_ident, arena)),
+        );
+        quote! {
+            impl #prost_path::ToOwnedValue for #ident {
+                type Owned = Self;
+
+                fn to_owned_value(&self) -> Self {
+                    #to_owned
+                }
+            }
+
+            impl<'__arena> #prost_path::ArenaFrom<'__arena, &#ident> for #ident {
+                #[allow(unused_variables)]
+                fn arena_from(value: &#ident, arena: &'__arena #prost_path::Arena) -> Self {
+                    #from_owned
                 }
             }
         }
+    } else {
+        quote!()
     };
 
     let expanded = quote! {
@@ -2462,7 +3430,11 @@ fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
         #builder_decode_impl
         #view_encode_impl
         #message_view_impl
-        #copy_to_builder_impl
+        #to_builder_impl
+        #field_set_impl
+        #arena_clone_impl
+        #extension_value_impl
+        #owned_impl
     };
     let expanded = if skip_debug {
         expanded
@@ -2579,7 +3551,11 @@ fn try_enumeration(input: TokenStream) -> Result<TokenStream, Error> {
     let input: DeriveInput = syn::parse2(input)?;
     let ident = input.ident;
 
-    let Attributes { prost_path, .. } = Attributes::new(input.attrs)?;
+    let Attributes {
+        prost_path,
+        full_name,
+        ..
+    } = Attributes::new(input.attrs)?;
 
     let generics = &input.generics;
     let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
@@ -2592,6 +3568,7 @@ fn try_enumeration(input: TokenStream) -> Result<TokenStream, Error> {
 
     // Map the variants into 'fields'.
     let mut variants: Vec<(Ident, Expr, Option<TokenStream>)> = Vec::new();
+    let mut names: Vec<String> = Vec::new();
     for Variant {
         attrs,
         ident,
@@ -2606,6 +3583,10 @@ fn try_enumeration(input: TokenStream) -> Result<TokenStream, Error> {
                 bail!("Enumeration variants may not have fields")
             }
         }
+        names.push(match variant_name(&attrs)? {
+            Some(name) => name,
+            None => to_snake(&ident.to_string()).to_uppercase(),
+        });
         match discriminant {
             Some((_, expr)) => {
                 let deprecated_attr = if attrs.iter().any(|v| v.path().is_ident("deprecated")) {
@@ -2634,12 +3615,32 @@ fn try_enumeration(input: TokenStream) -> Result<TokenStream, Error> {
         .iter()
         .map(|(variant, value, deprecated)| quote!(#value => ::core::result::Result::Ok(#deprecated #ident::#variant)));
 
+    let name_of = variants
+        .iter()
+        .zip(&names)
+        .map(|((_, value, _), name)| quote!(#value => ::core::option::Option::Some(#name)));
+    let value_of = variants
+        .iter()
+        .zip(&names)
+        .map(|((_, value, _), name)| quote!(#name => ::core::option::Option::Some(#value)));
+
+    let full_name_fn = full_name.map(|full_name| {
+        quote! {
+            fn full_name() -> ::core::option::Option<&'static str> {
+                ::core::option::Option::Some(#full_name)
+            }
+        }
+    });
+
     let is_valid_doc = format!("Returns `true` if `value` is a variant of `{ident}`.");
     let from_i32_doc =
         format!("Converts an `i32` to a `{ident}`, or `None` if `value` is not a valid variant.");
 
     let expanded = quote! {
         impl #impl_generics #ident #ty_generics #where_clause {
+            /// The default variant, usable in constant expressions.
+            pub const DEFAULT: #ident = #default_deprecated #ident::#default;
+
             #[doc=#is_valid_doc]
             pub fn is_valid(value: i32) -> bool {
                 match value {
@@ -2660,7 +3661,27 @@ fn try_enumeration(input: TokenStream) -> Result<TokenStream, Error> {
 
         impl #impl_generics ::core::default::Default for #ident #ty_generics #where_clause {
             fn default() -> #ident {
-                #default_deprecated #ident::#default
+                #ident::DEFAULT
+            }
+        }
+
+        impl #impl_generics #prost_path::Enumeration for #ident #ty_generics #where_clause {
+            #full_name_fn
+
+            #[allow(unreachable_patterns)]
+            fn name_of(value: i32) -> ::core::option::Option<&'static str> {
+                match value {
+                    #(#name_of,)*
+                    _ => ::core::option::Option::None,
+                }
+            }
+
+            #[allow(unreachable_patterns)]
+            fn value_of(name: &str) -> ::core::option::Option<i32> {
+                match name {
+                    #(#value_of,)*
+                    _ => ::core::option::Option::None,
+                }
             }
         }
 
@@ -2685,6 +3706,11 @@ fn try_enumeration(input: TokenStream) -> Result<TokenStream, Error> {
     Ok(expanded)
 }
 
+/// Returns the `.proto` name of an enum variant set with `#[defiant(name = "...")]`, if any.
+fn variant_name(attrs: &[Attribute]) -> Result<Option<String>, Error> {
+    get_str_attr(&prost_attrs(attrs.to_vec())?, "name")
+}
+
 #[proc_macro_derive(Enumeration, attributes(prost, defiant))]
 pub fn enumeration(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     try_enumeration(input.into()).unwrap().into()
@@ -2698,6 +3724,7 @@ fn try_oneof(input: TokenStream) -> Result<TokenStream, Error> {
     let Attributes {
         skip_debug,
         prost_path,
+        ..
     } = Attributes::new(input.attrs)?;
 
     let variants = match input.data {
@@ -2711,6 +3738,8 @@ fn try_oneof(input: TokenStream) -> Result<TokenStream, Error> {
 
     // Map the variants into 'fields'.
     let mut fields: Vec<(Ident, Field, Option<TokenStream>, syn::Type)> = Vec::new();
+    // The `.proto` names of `fields`, in the same order
+    let mut field_names: Vec<field::Names> = Vec::new();
     for Variant {
         attrs,
         ident: variant_ident,
@@ -2734,6 +3763,7 @@ fn try_oneof(input: TokenStream) -> Result<TokenStream, Error> {
             None
         };
         let variant_ty = variant_fields.first().unwrap().ty.clone();
+        field_names.push(field::Names::new(&attrs)?);
         match Field::new_oneof(attrs)? {
             Some(field) => fields.push((variant_ident, field, deprecated_attr, variant_ty)),
             None => bail!("invalid oneof variant: oneof variants may not be ignored"),
@@ -2783,6 +3813,18 @@ fn try_oneof(input: TokenStream) -> Result<TokenStream, Error> {
             quote!(#deprecated #ident::#variant_ident(value) => { #encode })
         });
 
+    let encode_cached = fields
+        .iter()
+        .map(|(variant_ident, field, deprecated, variant_ty)| {
+            let value_expr = if matches!(variant_ty, syn::Type::Reference(_)) {
+                quote!(*value)
+            } else {
+                quote!(value)
+            };
+            let encode = field.encode_cached(&prost_path, value_expr);
+            quote!(#deprecated #ident::#variant_ident(value) => { #encode })
+        });
+
     let merge = fields.iter().map(|(variant_ident, field, deprecated, variant_ty)| {
         let tag = field.tags()[0];
 
@@ -2819,7 +3861,7 @@ fn try_oneof(input: TokenStream) -> Result<TokenStream, Error> {
                         #prost_path::encoding::check_wire_type(#prost_path::encoding::WireType::LengthDelimited, wire_type)?;
                         ctx.limit_reached()?;
                         let mut builder = if let ::core::option::Option::Some(#deprecated #ident::#variant_ident(existing)) = field {
-                            existing.copy_to_builder(arena)
+                            existing.to_builder(arena)
                         } else {
                             <#builder_ty>::new_in(arena)
                         };
@@ -2832,7 +3874,7 @@ fn try_oneof(input: TokenStream) -> Result<TokenStream, Error> {
                                 builder.merge_field(tag, wire_type, buf, arena, ctx)
                             }
                         )?;
-                        let view = &*arena.alloc(builder.freeze());
+                        let view = &*arena.try_alloc(builder.freeze())?;
                         *field = ::core::option::Option::Some(#deprecated #ident::#variant_ident(view));
                         Ok(())
                     }
@@ -2855,7 +3897,7 @@ fn try_oneof(input: TokenStream) -> Result<TokenStream, Error> {
                         #prost_path::encoding::check_wire_type(#prost_path::encoding::WireType::LengthDelimited, wire_type)?;
                         ctx.limit_reached()?;
                         let mut builder = if let ::core::option::Option::Some(#deprecated #ident::#variant_ident(existing)) = field {
-                            existing.copy_to_builder(arena)
+                            existing.to_builder(arena)
                         } else {
                             <#builder_ty>::new_in(arena)
                         };
@@ -2924,6 +3966,30 @@ fn try_oneof(input: TokenStream) -> Result<TokenStream, Error> {
             quote!(#deprecated #ident::#variant_ident(value) => #encoded_len)
         });
 
+    let encode_reverse = fields
+        .iter()
+        .map(|(variant_ident, field, deprecated, variant_ty)| {
+            let value_expr = if matches!(variant_ty, syn::Type::Reference(_)) {
+                quote!(*value)
+            } else {
+                quote!(value)
+            };
+            let encode = field.encode_reverse(&prost_path, value_expr);
+            quote!(#deprecated #ident::#variant_ident(value) => { #encode })
+        });
+
+    let encoded_len_cached = fields
+        .iter()
+        .map(|(variant_ident, field, deprecated, variant_ty)| {
+            let value_expr = if matches!(variant_ty, syn::Type::Reference(_)) {
+                quote!(*value)
+            } else {
+                quote!(value)
+            };
+            let encoded_len = field.encoded_len_cached(&prost_path, value_expr);
+            quote!(#deprecated #ident::#variant_ident(value) => #encoded_len)
+        });
+
     // Generate merge function signature - always include arena parameter for consistency
     // with Builder merge_field signature, even if scalar-only oneofs don't use it
     let merge_signature = if needs_arena {
@@ -2935,7 +4001,7 @@ fn try_oneof(input: TokenStream) -> Result<TokenStream, Error> {
                 wire_type: #prost_path::encoding::wire_type::WireType,
                 buf: &mut impl #prost_path::bytes::Buf,
                 arena: &'arena #prost_path::Arena,
-                ctx: #prost_path::encoding::DecodeContext,
+                ctx: #prost_path::encoding::DecodeContext<'_, 'arena>,
             ) -> ::core::result::Result<(), #prost_path::DecodeError>
         }
     } else {
@@ -2953,8 +4019,111 @@ fn try_oneof(input: TokenStream) -> Result<TokenStream, Error> {
         }
     };
 
+    // merge_from() replaces the member unless both hold the same message member, which is merged
+    let merge_from = fields.iter().map(|(variant_ident, field, deprecated, variant_ty)| {
+        use crate::field::Field;
+
+        if !matches!(field, Field::Message(_) | Field::Group(_)) {
+            return quote! {
+                #deprecated
+                (_, #ident::#variant_ident(value)) => {
+                    *field = ::core::option::Option::Some(#ident::#variant_ident(*value));
+                }
+            };
+        }
+        if matches!(variant_ty, syn::Type::Reference(_)) {
+            quote! {
+                #deprecated
+                (::core::option::Option::Some(#ident::#variant_ident(existing)), #ident::#variant_ident(value)) => {
+                    let mut builder = existing.to_builder(arena);
+                    builder.merge_from(value);
+                    *existing = &*arena.alloc(builder.freeze());
+                }
+                #deprecated
+                (_, #ident::#variant_ident(value)) => {
+                    *field = ::core::option::Option::Some(#ident::#variant_ident(*value));
+                }
+            }
+        } else {
+            quote! {
+                #deprecated
+                (::core::option::Option::Some(#ident::#variant_ident(existing)), #ident::#variant_ident(value)) => {
+                    let mut builder = existing.to_builder(arena);
+                    builder.merge_from(value);
+                    *existing = builder.freeze();
+                }
+                #deprecated
+                (_, #ident::#variant_ident(value)) => {
+                    *field = ::core::option::Option::Some(#ident::#variant_ident(value.to_builder(arena).freeze()));
+                }
+            }
+        }
+    });
+    let merge_from_signature = if needs_arena {
+        quote! {
+            pub fn merge_from(
+                field: &mut ::core::option::Option<#ident #ty_generics>,
+                other: &#ident #ty_generics,
+                arena: &'arena #prost_path::Arena,
+            )
+        }
+    } else {
+        quote! {
+            pub fn merge_from(
+                field: &mut ::core::option::Option<#ident #ty_generics>,
+                other: &#ident #ty_generics,
+            )
+        }
+    };
+
+    // Oneof members are named after their variants in the `.proto` definition
+    let mut field_by_name_arms = Vec::new();
+    let mut field_by_tag_arms = Vec::new();
+    let mut missing_required_arms = Vec::new();
+    for ((variant_ident, field, deprecated, variant_ty), names) in fields.iter().zip(&field_names) {
+        let name = names
+            .name
+            .clone()
+            .unwrap_or_else(|| to_snake(&variant_ident.to_string()));
+        if matches!(field, Field::Message(_) | Field::Group(_)) {
+            missing_required_arms.push(quote! {
+                #deprecated #ident::#variant_ident(value) => {
+                    missing.field(#name, |missing| value.collect_missing_required(missing));
+                }
+            });
+        }
+        let tag = field.tags()[0];
+        let info = field_info(
+            &prost_path,
+            &name,
+            names.json_name.as_deref(),
+            field,
+            variant_ty,
+        );
+        let json_name = names
+            .json_name
+            .clone()
+            .unwrap_or_else(|| to_json_name(&name));
+        if json_name != name {
+            field_by_name_arms.push(quote!(#json_name => ::core::option::Option::Some(#info),));
+        }
+        field_by_name_arms.push(quote!(#name => ::core::option::Option::Some(#info),));
+        field_by_tag_arms.push(quote!(#tag => ::core::option::Option::Some(#info),));
+    }
+
     let expanded = quote! {
         impl #impl_generics #ident #ty_generics #where_clause {
+            /// Collects the required fields missing from the message held by the oneof.
+            #[doc(hidden)]
+            #[allow(unused_variables)]
+            pub fn collect_missing_required(&self, missing: &mut #prost_path::MissingRequired) {
+                #[allow(unreachable_patterns)]
+                match self {
+                    #(#missing_required_arms)*
+                    _ => {}
+                }
+            }
+
             /// Encodes the message to a buffer.
             pub fn encode(&self, buf: &mut impl #prost_path::bytes::BufMut) {
                 match self {
@@ -2971,6 +4140,13 @@ fn try_oneof(input: TokenStream) -> Result<TokenStream, Error> {
                 }
             }
 
+            /// Merges `other` into the member set in `field`, as merging its encoding would.
+            #merge_from_signature {
+                match (field.as_mut(), other) {
+                    #(#merge_from)*
+                }
+            }
+
             /// Returns the encoded length of the message without a length delimiter.
             #[inline]
             pub fn encoded_len(&self) -> usize {
@@ -2978,8 +4154,102 @@ fn try_oneof(input: TokenStream) -> Result<TokenStream, Error> {
                     #(#encoded_len,)*
                 }
             }
+
+            /// Encodes the message, taking nested message lengths from `sizes`.
+            #[doc(hidden)]
+            #[allow(unused_variables)]
+            pub fn encode_cached(&self, buf: &mut impl #prost_path::bytes::BufMut, sizes: &mut #prost_path::encoding::SizeCache) {
+                match self {
+                    #(#encode_cached,)*
+                }
+            }
+
+            /// Writes the message in front of the contents of `buf`.
+            #[doc(hidden)]
+            #[allow(unused_variables)]
+            pub fn encode_reverse(&self, buf: &mut #prost_path::arena::ReverseBuf<'_>) {
+                match self {
+                    #(#encode_reverse,)*
+                }
+            }
+
+            /// Returns the encoded length of the message, recording nested message lengths in `sizes`.
+            #[doc(hidden)]
+            #[allow(unused_variables)]
+            pub fn encoded_len_cached(&self, sizes: &mut #prost_path::encoding::SizeCache) -> usize {
+                match self {
+                    #(#encoded_len_cached,)*
+                }
+            }
+        }
+
+        impl #impl_generics #prost_path::FieldSet for #ident #ty_generics #where_clause {
+            #[allow(unreachable_patterns)]
+            fn field(name: &str) -> ::core::option::Option<#prost_path::FieldInfo> {
+                match name {
+                    #(#field_by_name_arms)*
+                    _ => ::core::option::Option::None,
+                }
+            }
+
+            fn field_by_tag(tag: u32) -> ::core::option::Option<#prost_path::FieldInfo> {
+                match tag {
+                    #(#field_by_tag_arms)*
+                    _ => ::core::option::Option::None,
+                }
+            }
+        }
+    };
+    let cloned = cloned_type(&ident, generics);
+    let clone_arms = fields.iter().map(|(variant_ident, _, deprecated, _)| {
+        quote!(#deprecated #ident::#variant_ident(value) => #ident::#variant_ident(#prost_path::ArenaClone::clone_in(value, arena)))
+    });
+    let expanded = quote! {
+        #expanded
+
+        impl #impl_generics #prost_path::ArenaClone for #ident #ty_generics #where_clause {
+            type Cloned<'__clone> = #cloned;
+
+            fn clone_in<'__clone>(&self, arena: &'__clone #prost_path::Arena) -> #cloned {
+                match self {
+                    #(#clone_arms,)*
+                }
+            }
         }
+    };
+    // Oneofs which borrow nothing are their own owned counterpart, like messages
+    let expanded = if generics.params.is_empty() {
+        let deprecated = fields
+            .iter()
+            .map(|(_, _, deprecated, _)| deprecated)
+            .collect::<Vec<_>>();
+        let variants = fields
+            .iter()
+            .map(|(variant_ident, ..)| quote!(#ident::#variant_ident))
+            .collect::<Vec<_>>();
+        quote! {
+            #expanded
+
+            impl #prost_path::ToOwnedValue for #ident {
+                type Owned = Self;
+
+                fn to_owned_value(&self) -> Self {
+                    match self {
+                        #(#deprecated #variants(value) => #variants(#prost_path::ToOwnedValue::to_owned_value(value)),)*
+                    }
+                }
+            }
 
+            impl<'__arena> #prost_path::ArenaFrom<'__arena, &#ident> for #ident {
+                fn arena_from(value: &#ident, arena: &'__arena #prost_path::Arena) -> Self {
+                    match value {
+                        #(#deprecated #variants(value) => #variants(#prost_path::ArenaFrom::arena_from(value, arena)),)*
+                    }
+                }
+            }
+        }
+    } else {
+        expanded
     };
     let expanded = if skip_debug {
         expanded
@@ -3068,9 +4338,36 @@ fn get_prost_path(attrs: &[Meta]) -> Result<Path, Error> {
     Ok(prost_path)
 }
 
+/// Returns the value of a `key = "..."` attribute, if present.
+fn get_str_attr(attrs: &[Meta], key: &str) -> Result<Option<String>, Error> {
+    let mut value = None;
+    for attr in attrs {
+        if let Meta::NameValue(MetaNameValue {
+            path,
+            value: Expr::Lit(ExprLit {
+                lit: Lit::Str(lit), ..
+            }),
+            ..
+        }) = attr
+        {
+            if path.is_ident(key) {
+                set_option(
+                    &mut value,
+                    lit.value(),
+                    &format!("duplicate {key} attributes"),
+                )?;
+            }
+        }
+    }
+    Ok(value)
+}
+
 struct Attributes {
     skip_debug: bool,
     prost_path: Path,
+    /// The fully qualified `.proto` name of the message or enum, from
+    /// `#[defiant(full_name = "...")]`.
+    full_name: Option<String>,
 }
 
 impl Attributes {
@@ -3080,10 +4377,12 @@ impl Attributes {
 
         let attrs = prost_attrs(attrs)?;
         let prost_path = get_prost_path(&attrs)?;
+        let full_name = get_str_attr(&attrs, "full_name")?;
 
         Ok(Self {
             skip_debug,
             prost_path,
+            full_name,
         })
     }
 }
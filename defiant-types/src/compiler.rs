@@ -2,6 +2,7 @@
 /// The version number of protocol compiler.
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Clone, PartialEq, Eq, Hash, ::defiant::View)]
+#[defiant(full_name = "google.protobuf.compiler.Version")]
 pub struct Version<'arena> {
     #[defiant(int32, optional, tag = "1")]
     pub major: ::core::option::Option<i32>,
@@ -17,6 +18,7 @@ pub struct Version<'arena> {
 /// An encoded CodeGeneratorRequest is written to the plugin's stdin.
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Clone, PartialEq, ::defiant::View)]
+#[defiant(full_name = "google.protobuf.compiler.CodeGeneratorRequest")]
 pub struct CodeGeneratorRequest<'arena> {
     /// The .proto files that were explicitly listed on the command-line.  The
     /// code generator should generate code only for these files.  Each file's
@@ -49,6 +51,7 @@ pub struct CodeGeneratorRequest<'arena> {
 /// The plugin writes an encoded CodeGeneratorResponse to stdout.
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Clone, PartialEq, ::defiant::View)]
+#[defiant(full_name = "google.protobuf.compiler.CodeGeneratorResponse")]
 pub struct CodeGeneratorResponse<'arena> {
     /// Error message.  If non-empty, code generation failed.  The plugin process
     /// should exit with status code zero even if it reports an error in this way.
@@ -72,6 +75,7 @@ pub mod code_generator_response {
     /// Represents a single generated file.
     #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
     #[derive(Clone, PartialEq, ::defiant::View)]
+    #[defiant(full_name = "google.protobuf.compiler.CodeGeneratorResponse.File")]
     pub struct File<'arena> {
         /// The file name, relative to the output directory.  The name must not
         /// contain "." or ".." components and must be relative, not be absolute (so,
@@ -137,9 +141,12 @@ pub mod code_generator_response {
     /// Sync with code_generator.h.
     #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
     #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::defiant::Enumeration)]
+    #[defiant(full_name = "google.protobuf.compiler.CodeGeneratorResponse.Feature")]
     #[repr(i32)]
     pub enum Feature {
+        #[defiant(name = "FEATURE_NONE")]
         None = 0,
+        #[defiant(name = "FEATURE_PROTO3_OPTIONAL")]
         Proto3Optional = 1,
     }
     impl Feature {
@@ -0,0 +1,859 @@
+use core::fmt;
+
+use defiant::alloc::collections::{btree_map, BTreeMap, BTreeSet};
+use defiant::alloc::format;
+use defiant::alloc::string::String;
+use defiant::alloc::vec::Vec;
+
+use crate::dynamic::MessageDescriptor;
+use crate::field_descriptor_proto::Type;
+use crate::{
+    DescriptorProto, EnumDescriptorProto, EnumValueDescriptorProto, FieldDescriptorProto,
+    FileDescriptorProto, FileDescriptorSet, ServiceDescriptorProto,
+};
+
+/// An index over the definitions in a [`FileDescriptorSet`].
+///
+/// Every package, message, enum, enum value, service and extension is indexed by its
+/// fully-qualified name, such as `"google.protobuf.Duration"`. Building the pool validates the
+/// set as a whole:
+///
+/// - every file's dependencies are part of the set,
+/// - no name is defined twice,
+/// - every `type_name`, `extendee` and method input or output type resolves to a definition in
+///   the file itself or in a file it imports,
+/// - no field number is used twice within a message or by two extensions of the same message,
+/// - no field or enum value uses a reserved number or name.
+///
+/// Type references may be fully-qualified (with a leading `.`, as written by `protoc`) or relative
+/// to the scope of the referring definition, following the `.proto` language's scoping rules.
+#[derive(Debug)]
+pub struct DescriptorPool<'arena> {
+    files: &'arena FileDescriptorSet<'arena>,
+    /// Definitions by fully-qualified name, without a leading `.`.
+    names: BTreeMap<String, Entry<'arena>>,
+    /// Extensions by the fully-qualified name of the message they extend.
+    extensions: BTreeMap<String, Vec<&'arena FieldDescriptorProto<'arena>>>,
+    /// For each file, the indices of the files whose definitions it can refer to.
+    visible: Vec<BTreeSet<usize>>,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Entry<'arena> {
+    /// Index of the defining file; for packages, the first file declaring the package.
+    file: usize,
+    definition: Definition<'arena>,
+}
+
+/// A definition found in a [`DescriptorPool`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Definition<'arena> {
+    /// A package, or a prefix of a package name.
+    Package,
+    Message(&'arena DescriptorProto<'arena>),
+    Enum(&'arena EnumDescriptorProto<'arena>),
+    /// An enum value, which is scoped to the enum's parent like in C++.
+    EnumValue(&'arena EnumValueDescriptorProto<'arena>),
+    Service(&'arena ServiceDescriptorProto<'arena>),
+    Extension(&'arena FieldDescriptorProto<'arena>),
+}
+
+impl<'arena> DescriptorPool<'arena> {
+    /// Indexes and validates `files`.
+    pub fn new(files: &'arena FileDescriptorSet<'arena>) -> Result<Self, DescriptorError> {
+        let mut pool = DescriptorPool {
+            files,
+            names: BTreeMap::new(),
+            extensions: BTreeMap::new(),
+            visible: Vec::new(),
+        };
+
+        let mut file_names = BTreeMap::new();
+        for (index, file) in files.file.iter().enumerate() {
+            let name = file.name.unwrap_or("");
+            if file_names.insert(name, index).is_some() {
+                return Err(DescriptorError::DuplicateFile(String::from(name)));
+            }
+        }
+        for file in files.file {
+            for dependency in file.dependency {
+                if !file_names.contains_key(dependency) {
+                    return Err(DescriptorError::MissingDependency {
+                        file: String::from(file.name.unwrap_or("")),
+                        dependency: String::from(*dependency),
+                    });
+                }
+            }
+        }
+        pool.visible = files
+            .file
+            .iter()
+            .enumerate()
+            .map(|(index, file)| visible_files(files, &file_names, index, file))
+            .collect();
+
+        for (index, file) in files.file.iter().enumerate() {
+            pool.add_file(index, file)?;
+        }
+        for (index, file) in files.file.iter().enumerate() {
+            pool.check_file(index, file)?;
+        }
+        Ok(pool)
+    }
+
+    /// Returns the files in the pool.
+    pub fn files(&self) -> &'arena [&'arena FileDescriptorProto<'arena>] {
+        self.files.file
+    }
+
+    /// Looks up a definition by its fully-qualified name. A leading `.` is accepted.
+    pub fn get(&self, name: &str) -> Option<Definition<'arena>> {
+        self.entry(name).map(|entry| entry.definition)
+    }
+
+    /// Looks up a message by its fully-qualified name.
+    pub fn get_message(&self, name: &str) -> Option<&'arena DescriptorProto<'arena>> {
+        match self.get(name)? {
+            Definition::Message(message) => Some(message),
+            _ => None,
+        }
+    }
+
+    /// Looks up an enum by its fully-qualified name.
+    pub fn get_enum(&self, name: &str) -> Option<&'arena EnumDescriptorProto<'arena>> {
+        match self.get(name)? {
+            Definition::Enum(enumeration) => Some(enumeration),
+            _ => None,
+        }
+    }
+
+    /// Looks up a service by its fully-qualified name.
+    pub fn get_service(&self, name: &str) -> Option<&'arena ServiceDescriptorProto<'arena>> {
+        match self.get(name)? {
+            Definition::Service(service) => Some(service),
+            _ => None,
+        }
+    }
+
+    /// Looks up an extension by its fully-qualified name.
+    pub fn get_extension(&self, name: &str) -> Option<&'arena FieldDescriptorProto<'arena>> {
+        match self.get(name)? {
+            Definition::Extension(extension) => Some(extension),
+            _ => None,
+        }
+    }
+
+    /// Returns the extensions of the message with the given fully-qualified name.
+    pub fn extensions_of(&self, extendee: &str) -> &[&'arena FieldDescriptorProto<'arena>] {
+        let extendee = extendee.strip_prefix('.').unwrap_or(extendee);
+        self.extensions.get(extendee).map_or(&[], Vec::as_slice)
+    }
+
+    /// Returns the file which defines the named definition.
+    ///
+    /// For a package, this is the first file in the set which declares it.
+    pub fn file_of(&self, name: &str) -> Option<&'arena FileDescriptorProto<'arena>> {
+        self.entry(name).map(|entry| self.files.file[entry.file])
+    }
+
+    /// Returns a descriptor for building [`DynamicMessage`](crate::dynamic::DynamicMessage)s
+    /// of the named message type.
+    ///
+    /// The descriptor borrows the pool, and resolves the types of message fields through it
+    /// like [`resolve`](Self::resolve).
+    pub fn message_descriptor(&'arena self, name: &str) -> Option<MessageDescriptor<'arena>> {
+        let (name, entry) = self
+            .names
+            .get_key_value(name.strip_prefix('.').unwrap_or(name))?;
+        let Definition::Message(message) = entry.definition else {
+            return None;
+        };
+        Some(MessageDescriptor::in_pool(
+            self,
+            name,
+            self.files.file[entry.file],
+            message,
+        ))
+    }
+
+    /// Returns the fully-qualified names and definitions in the pool, in lexicographic order.
+    pub fn definitions(&self) -> impl Iterator<Item = (&str, Definition<'arena>)> + '_ {
+        self.names
+            .iter()
+            .map(|(name, entry)| (name.as_str(), entry.definition))
+    }
+
+    /// Resolves a type reference as written in the scope with the given fully-qualified name.
+    ///
+    /// Relative names are looked up in `scope` first, then in each enclosing scope, as `protoc`
+    /// does. Returns the fully-qualified name of the definition, without a leading `.`.
+    pub fn resolve(&self, scope: &str, name: &str) -> Option<(String, Definition<'arena>)> {
+        self.resolve_from(None, scope, name)
+    }
+
+    /// Returns the set the pool was built from.
+    pub(crate) fn file_set(&self) -> &'arena FileDescriptorSet<'arena> {
+        self.files
+    }
+
+    fn entry(&self, name: &str) -> Option<&Entry<'arena>> {
+        self.names.get(name.strip_prefix('.').unwrap_or(name))
+    }
+
+    /// Looks up `name`, only considering definitions visible from `file` if it is given.
+    fn lookup(&self, file: Option<usize>, name: &str) -> Option<Definition<'arena>> {
+        let entry = self.names.get(name)?;
+        let visible = match (file, entry.definition) {
+            (None, _) | (_, Definition::Package) => true,
+            (Some(file), _) => self.visible[file].contains(&entry.file),
+        };
+        visible.then_some(entry.definition)
+    }
+
+    fn resolve_from(
+        &self,
+        file: Option<usize>,
+        scope: &str,
+        name: &str,
+    ) -> Option<(String, Definition<'arena>)> {
+        if let Some(name) = name.strip_prefix('.') {
+            return self
+                .lookup(file, name)
+                .map(|definition| (String::from(name), definition));
+        }
+
+        let (first, nested) = match name.split_once('.') {
+            Some((first, _)) => (first, true),
+            None => (name, false),
+        };
+        let mut scope = scope;
+        loop {
+            // The first component of a nested reference must name something that can contain
+            // definitions; otherwise the search continues in the enclosing scope.
+            let found = self.lookup(file, &join(scope, first));
+            if matches!(
+                (found, nested),
+                (Some(Definition::Package | Definition::Message(_)), _) | (Some(_), false)
+            ) {
+                let name = join(scope, name);
+                return self
+                    .lookup(file, &name)
+                    .map(|definition| (name, definition));
+            }
+            if scope.is_empty() {
+                return None;
+            }
+            scope = scope.rfind('.').map_or("", |index| &scope[..index]);
+        }
+    }
+
+    fn add(
+        &mut self,
+        file: usize,
+        name: String,
+        definition: Definition<'arena>,
+    ) -> Result<(), DescriptorError> {
+        match self.names.entry(name) {
+            btree_map::Entry::Vacant(entry) => {
+                entry.insert(Entry { file, definition });
+                Ok(())
+            }
+            btree_map::Entry::Occupied(entry) => match (entry.get().definition, definition) {
+                (Definition::Package, Definition::Package) => Ok(()),
+                _ => Err(DescriptorError::DuplicateName(entry.key().clone())),
+            },
+        }
+    }
+
+    fn add_file(
+        &mut self,
+        file: usize,
+        descriptor: &'arena FileDescriptorProto<'arena>,
+    ) -> Result<(), DescriptorError> {
+        let package = descriptor.package.unwrap_or("");
+        if !package.is_empty() {
+            for (index, _) in package.match_indices('.') {
+                self.add(file, String::from(&package[..index]), Definition::Package)?;
+            }
+            self.add(file, String::from(package), Definition::Package)?;
+        }
+
+        for message in descriptor.message_type {
+            self.add_message(file, package, message)?;
+        }
+        for enumeration in descriptor.enum_type {
+            self.add_enum(file, package, enumeration)?;
+        }
+        for service in descriptor.service {
+            let name = join(package, service.name.unwrap_or(""));
+            self.add(file, name, Definition::Service(service))?;
+        }
+        for extension in descriptor.extension {
+            let name = join(package, extension.name.unwrap_or(""));
+            self.add(file, name, Definition::Extension(extension))?;
+        }
+        Ok(())
+    }
+
+    fn add_message(
+        &mut self,
+        file: usize,
+        scope: &str,
+        message: &'arena DescriptorProto<'arena>,
+    ) -> Result<(), DescriptorError> {
+        let name = join(scope, message.name.unwrap_or(""));
+        self.add(file, name.clone(), Definition::Message(message))?;
+        for nested in message.nested_type {
+            self.add_message(file, &name, nested)?;
+        }
+        for enumeration in message.enum_type {
+            self.add_enum(file, &name, enumeration)?;
+        }
+        for extension in message.extension {
+            let extension_name = join(&name, extension.name.unwrap_or(""));
+            self.add(file, extension_name, Definition::Extension(extension))?;
+        }
+        Ok(())
+    }
+
+    fn add_enum(
+        &mut self,
+        file: usize,
+        scope: &str,
+        enumeration: &'arena EnumDescriptorProto<'arena>,
+    ) -> Result<(), DescriptorError> {
+        let name = join(scope, enumeration.name.unwrap_or(""));
+        self.add(file, name, Definition::Enum(enumeration))?;
+        for value in enumeration.value {
+            let value_name = join(scope, value.name.unwrap_or(""));
+            self.add(file, value_name, Definition::EnumValue(value))?;
+        }
+        Ok(())
+    }
+
+    fn check_file(
+        &mut self,
+        file: usize,
+        descriptor: &'arena FileDescriptorProto<'arena>,
+    ) -> Result<(), DescriptorError> {
+        let package = descriptor.package.unwrap_or("");
+        for message in descriptor.message_type {
+            self.check_message(file, package, message)?;
+        }
+        for enumeration in descriptor.enum_type {
+            check_enum(package, enumeration)?;
+        }
+        for service in descriptor.service {
+            let name = join(package, service.name.unwrap_or(""));
+            for method in service.method {
+                let method_name = join(&name, method.name.unwrap_or(""));
+                for type_name in [method.input_type, method.output_type] {
+                    self.check_type(file, package, &method_name, type_name, Some(Type::Message))?;
+                }
+            }
+        }
+        for extension in descriptor.extension {
+            self.check_extension(file, package, extension)?;
+        }
+        Ok(())
+    }
+
+    fn check_message(
+        &mut self,
+        file: usize,
+        scope: &str,
+        message: &'arena DescriptorProto<'arena>,
+    ) -> Result<(), DescriptorError> {
+        let name = join(scope, message.name.unwrap_or(""));
+        let mut numbers = BTreeSet::new();
+        for field in message.field {
+            let number = field.number.unwrap_or(0);
+            if !numbers.insert(number) {
+                return Err(DescriptorError::TagCollision {
+                    message: name,
+                    number,
+                });
+            }
+            let reserved = message
+                .reserved_range
+                .iter()
+                .any(|range| (range.start.unwrap_or(0)..range.end.unwrap_or(0)).contains(&number));
+            if reserved {
+                return Err(DescriptorError::ReservedNumber {
+                    definition: name,
+                    number,
+                });
+            }
+            let field_name = field.name.unwrap_or("");
+            if message.reserved_name.contains(&field_name) {
+                return Err(DescriptorError::ReservedName {
+                    definition: name,
+                    name: String::from(field_name),
+                });
+            }
+            let referrer = join(&name, field_name);
+            self.check_type(file, &name, &referrer, field.type_name, field_type(field))?;
+        }
+
+        for nested in message.nested_type {
+            self.check_message(file, &name, nested)?;
+        }
+        for enumeration in message.enum_type {
+            check_enum(&name, enumeration)?;
+        }
+        for extension in message.extension {
+            self.check_extension(file, &name, extension)?;
+        }
+        Ok(())
+    }
+
+    /// Checks an extension declared in `scope`, and indexes it by the message it extends.
+    fn check_extension(
+        &mut self,
+        file: usize,
+        scope: &str,
+        extension: &'arena FieldDescriptorProto<'arena>,
+    ) -> Result<(), DescriptorError> {
+        let referrer = join(scope, extension.name.unwrap_or(""));
+        self.check_type(
+            file,
+            scope,
+            &referrer,
+            extension.type_name,
+            field_type(extension),
+        )?;
+
+        let extendee = extension.extendee.unwrap_or("");
+        let (extendee, message) = match self.resolve_from(Some(file), scope, extendee) {
+            Some((name, Definition::Message(message))) => (name, message),
+            _ => {
+                return Err(DescriptorError::UnresolvedType {
+                    referrer,
+                    type_name: String::from(extendee),
+                })
+            }
+        };
+        let number = extension.number.unwrap_or(0);
+        let extensions = self.extensions.entry(extendee.clone()).or_default();
+        let collides = message
+            .field
+            .iter()
+            .any(|field| field.number == Some(number))
+            || extensions.iter().any(|other| other.number == Some(number));
+        if collides {
+            return Err(DescriptorError::TagCollision {
+                message: extendee,
+                number,
+            });
+        }
+        extensions.push(extension);
+        Ok(())
+    }
+
+    /// Checks that `type_name`, referenced from `referrer` in `scope`, resolves to a definition
+    /// of the expected kind.
+    fn check_type(
+        &self,
+        file: usize,
+        scope: &str,
+        referrer: &str,
+        type_name: Option<&str>,
+        expected: Option<Type>,
+    ) -> Result<(), DescriptorError> {
+        let type_name = match (type_name, expected) {
+            (Some(type_name), _) => type_name,
+            (None, Some(Type::Message | Type::Group | Type::Enum)) => "",
+            (None, _) => return Ok(()),
+        };
+        let valid = matches!(
+            (self.resolve_from(Some(file), scope, type_name), expected),
+            (
+                Some((_, Definition::Message(_))),
+                Some(Type::Message | Type::Group) | None
+            ) | (Some((_, Definition::Enum(_))), Some(Type::Enum) | None)
+        );
+        if !valid {
+            return Err(DescriptorError::UnresolvedType {
+                referrer: String::from(referrer),
+                type_name: String::from(type_name),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Returns the indices of the files visible from `file`: the file itself, its dependencies, and
+/// the public dependencies of those, transitively.
+fn visible_files(
+    files: &FileDescriptorSet<'_>,
+    file_names: &BTreeMap<&str, usize>,
+    index: usize,
+    file: &FileDescriptorProto<'_>,
+) -> BTreeSet<usize> {
+    let mut visible = BTreeSet::new();
+    visible.insert(index);
+    let mut pending: Vec<usize> = file
+        .dependency
+        .iter()
+        .filter_map(|dependency| file_names.get(dependency).copied())
+        .collect();
+    while let Some(dependency) = pending.pop() {
+        if !visible.insert(dependency) {
+            continue;
+        }
+        let dependency = files.file[dependency];
+        for &public in dependency.public_dependency {
+            let name = usize::try_from(public)
+                .ok()
+                .and_then(|public| dependency.dependency.get(public));
+            if let Some(index) = name.and_then(|name| file_names.get(name)) {
+                pending.push(*index);
+            }
+        }
+    }
+    visible
+}
+
+fn check_enum(scope: &str, enumeration: &EnumDescriptorProto<'_>) -> Result<(), DescriptorError> {
+    let name = join(scope, enumeration.name.unwrap_or(""));
+    for value in enumeration.value {
+        let number = value.number.unwrap_or(0);
+        // Unlike message reserved ranges, the end of an enum reserved range is inclusive
+        let reserved = enumeration
+            .reserved_range
+            .iter()
+            .any(|range| (range.start.unwrap_or(0)..=range.end.unwrap_or(0)).contains(&number));
+        if reserved {
+            return Err(DescriptorError::ReservedNumber {
+                definition: name,
+                number,
+            });
+        }
+        let value_name = value.name.unwrap_or("");
+        if enumeration.reserved_name.contains(&value_name) {
+            return Err(DescriptorError::ReservedName {
+                definition: name,
+                name: String::from(value_name),
+            });
+        }
+    }
+    Ok(())
+}
+
+fn field_type(field: &FieldDescriptorProto<'_>) -> Option<Type> {
+    field.r#type.and_then(|ty| Type::try_from(ty).ok())
+}
+
+fn join(scope: &str, name: &str) -> String {
+    if scope.is_empty() {
+        String::from(name)
+    } else {
+        format!("{scope}.{name}")
+    }
+}
+
+/// An inconsistency found while building a [`DescriptorPool`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DescriptorError {
+    /// Two files in the set have the same name.
+    DuplicateFile(String),
+    /// A file imports a file which is not part of the set.
+    MissingDependency { file: String, dependency: String },
+    /// A fully-qualified name is defined more than once.
+    DuplicateName(String),
+    /// A type reference does not resolve to a visible definition of the right kind.
+    UnresolvedType { referrer: String, type_name: String },
+    /// A field number is used more than once in a message, including by its extensions.
+    TagCollision { message: String, number: i32 },
+    /// A field or enum value uses a reserved number.
+    ReservedNumber { definition: String, number: i32 },
+    /// A field or enum value uses a reserved name.
+    ReservedName { definition: String, name: String },
+}
+
+impl fmt::Display for DescriptorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DescriptorError::DuplicateFile(file) => write!(f, "duplicate file: {file}"),
+            DescriptorError::MissingDependency { file, dependency } => {
+                write!(f, "{file} imports {dependency}, which is not in the set")
+            }
+            DescriptorError::DuplicateName(name) => write!(f, "{name} is already defined"),
+            DescriptorError::UnresolvedType {
+                referrer,
+                type_name,
+            } => write!(f, "{referrer}: \"{type_name}\" is not defined"),
+            DescriptorError::TagCollision { message, number } => {
+                write!(
+                    f,
+                    "field number {number} is used more than once in {message}"
+                )
+            }
+            DescriptorError::ReservedNumber { definition, number } => {
+                write!(f, "{definition} uses reserved number {number}")
+            }
+            DescriptorError::ReservedName { definition, name } => {
+                write!(f, "{definition} uses reserved name \"{name}\"")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DescriptorError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::descriptor_proto::ReservedRange;
+    use crate::field_descriptor_proto::Label;
+    use crate::{DescriptorProtoBuilder, FieldDescriptorProtoBuilder, FileDescriptorProtoBuilder};
+    use defiant::Arena;
+
+    fn field<'arena>(
+        arena: &'arena Arena,
+        name: &'arena str,
+        number: i32,
+        type_name: Option<&'arena str>,
+    ) -> &'arena FieldDescriptorProto<'arena> {
+        let ty = if type_name.is_some() {
+            Type::Message
+        } else {
+            Type::Int32
+        };
+        arena.alloc(FieldDescriptorProto {
+            name: Some(name),
+            number: Some(number),
+            label: Some(Label::Optional as i32),
+            r#type: Some(ty as i32),
+            type_name,
+            ..FieldDescriptorProtoBuilder::new_in(arena).freeze()
+        })
+    }
+
+    fn message<'arena>(
+        arena: &'arena Arena,
+        name: &'arena str,
+        field: &'arena [&'arena FieldDescriptorProto<'arena>],
+    ) -> DescriptorProto<'arena> {
+        DescriptorProto {
+            name: Some(name),
+            field,
+            ..DescriptorProtoBuilder::new_in(arena).freeze()
+        }
+    }
+
+    fn file<'arena>(
+        arena: &'arena Arena,
+        name: &'arena str,
+        dependency: &'arena [&'arena str],
+        message_type: &'arena [&'arena DescriptorProto<'arena>],
+    ) -> &'arena FileDescriptorProto<'arena> {
+        arena.alloc(FileDescriptorProto {
+            name: Some(name),
+            package: Some(name.trim_end_matches(".proto")),
+            dependency,
+            message_type,
+            ..FileDescriptorProtoBuilder::new_in(arena).freeze()
+        })
+    }
+
+    fn set<'arena>(
+        arena: &'arena Arena,
+        files: &[&'arena FileDescriptorProto<'arena>],
+    ) -> &'arena FileDescriptorSet<'arena> {
+        let mut file = arena.new_vec();
+        file.extend_from_slice(files);
+        arena.alloc(FileDescriptorSet {
+            file: file.freeze(),
+        })
+    }
+
+    #[test]
+    fn check_pool_resolves_across_imports() {
+        let arena = Arena::new();
+        let shared = arena.alloc(message(&arena, "Shared", &[]));
+        let a = file(&arena, "a.proto", &[], arena.alloc([&*shared]));
+
+        let inner = arena.alloc(message(&arena, "Inner", &[]));
+        let outer = arena.alloc(DescriptorProto {
+            nested_type: arena.alloc([&*inner]),
+            ..message(
+                &arena,
+                "Outer",
+                arena.alloc([
+                    field(&arena, "shared", 1, Some("a.Shared")),
+                    field(&arena, "inner", 2, Some("Inner")),
+                    field(&arena, "absolute", 3, Some(".a.b.Outer.Inner")),
+                ]),
+            )
+        });
+        let b = arena.alloc(FileDescriptorProto {
+            package: Some("a.b"),
+            ..*file(&arena, "b.proto", &["a.proto"], arena.alloc([&*outer]))
+        });
+
+        let pool = DescriptorPool::new(set(&arena, &[a, b])).unwrap();
+        assert_eq!(pool.get("a.b"), Some(Definition::Package));
+        assert_eq!(pool.get_message(".a.b.Outer.Inner"), Some(&*inner));
+        assert_eq!(pool.file_of("a.Shared").unwrap().name, Some("a.proto"));
+        assert_eq!(
+            pool.resolve("a.b.Outer", "Shared"),
+            Some((String::from("a.Shared"), Definition::Message(shared)))
+        );
+        assert_eq!(
+            pool.resolve("a.b.Outer", "Inner").map(|(name, _)| name),
+            Some(String::from("a.b.Outer.Inner"))
+        );
+        assert!(pool.get_enum("a.Shared").is_none());
+        assert_eq!(
+            pool.message_descriptor("a.Shared").unwrap().name(),
+            "Shared"
+        );
+    }
+
+    #[test]
+    fn check_pool_visibility() {
+        let arena = Arena::new();
+        let shared = arena.alloc(message(&arena, "Shared", &[]));
+        let a = file(&arena, "a.proto", &[], arena.alloc([&*shared]));
+        let user = arena.alloc(message(
+            &arena,
+            "User",
+            arena.alloc([field(&arena, "shared", 1, Some(".a.Shared"))]),
+        ));
+
+        // Not imported
+        let c = file(&arena, "c.proto", &[], arena.alloc([&*user]));
+        assert_eq!(
+            DescriptorPool::new(set(&arena, &[a, c])).unwrap_err(),
+            DescriptorError::UnresolvedType {
+                referrer: String::from("c.User.shared"),
+                type_name: String::from(".a.Shared"),
+            }
+        );
+
+        // Imported through a public import of a direct dependency
+        let b = arena.alloc(FileDescriptorProto {
+            public_dependency: &[0],
+            ..*file(&arena, "b.proto", &["a.proto"], &[])
+        });
+        let c = file(&arena, "c.proto", &["b.proto"], arena.alloc([&*user]));
+        assert!(DescriptorPool::new(set(&arena, &[a, b, c])).is_ok());
+
+        // Imported through a non-public import of a direct dependency
+        let b = file(&arena, "b.proto", &["a.proto"], &[]);
+        assert!(DescriptorPool::new(set(&arena, &[a, b, c])).is_err());
+
+        assert_eq!(
+            DescriptorPool::new(set(&arena, &[c])).unwrap_err(),
+            DescriptorError::MissingDependency {
+                file: String::from("c.proto"),
+                dependency: String::from("b.proto"),
+            }
+        );
+    }
+
+    #[test]
+    fn check_pool_validation() {
+        let arena = Arena::new();
+        let duplicate = arena.alloc(message(&arena, "Dup", &[]));
+        let a = file(
+            &arena,
+            "a.proto",
+            &[],
+            arena.alloc([&*duplicate, &*duplicate]),
+        );
+        assert_eq!(
+            DescriptorPool::new(set(&arena, &[a])).unwrap_err(),
+            DescriptorError::DuplicateName(String::from("a.Dup"))
+        );
+        assert_eq!(
+            DescriptorPool::new(set(&arena, &[a, a])).unwrap_err(),
+            DescriptorError::DuplicateFile(String::from("a.proto"))
+        );
+
+        let check = |message: &'_ DescriptorProto<'_>| {
+            let a = file(&arena, "a.proto", &[], arena.alloc([message]));
+            DescriptorPool::new(set(&arena, &[a])).unwrap_err()
+        };
+        let fields = arena.alloc([field(&arena, "x", 1, None), field(&arena, "y", 1, None)]);
+        assert_eq!(
+            check(arena.alloc(message(&arena, "M", fields))),
+            DescriptorError::TagCollision {
+                message: String::from("a.M"),
+                number: 1,
+            }
+        );
+
+        let fields = arena.alloc([field(&arena, "x", 5, None)]);
+        let reserved = arena.alloc(DescriptorProto {
+            reserved_range: arena.alloc([ReservedRange {
+                start: Some(4),
+                end: Some(6),
+            }]),
+            ..message(&arena, "M", fields)
+        });
+        assert_eq!(
+            check(reserved),
+            DescriptorError::ReservedNumber {
+                definition: String::from("a.M"),
+                number: 5,
+            }
+        );
+
+        let reserved = arena.alloc(DescriptorProto {
+            reserved_name: &["x"],
+            ..message(&arena, "M", fields)
+        });
+        assert_eq!(
+            check(reserved),
+            DescriptorError::ReservedName {
+                definition: String::from("a.M"),
+                name: String::from("x"),
+            }
+        );
+    }
+
+    #[test]
+    fn check_pool_extensions() {
+        let arena = Arena::new();
+        let target = arena.alloc(message(
+            &arena,
+            "Target",
+            arena.alloc([field(&arena, "x", 1, None)]),
+        ));
+        let extension = |name, number| {
+            &*arena.alloc(FieldDescriptorProto {
+                extendee: Some("Target"),
+                ..*field(&arena, name, number, None)
+            })
+        };
+
+        let a = arena.alloc(FileDescriptorProto {
+            extension: arena.alloc([extension("e", 100), extension("f", 101)]),
+            ..*file(&arena, "a.proto", &[], arena.alloc([&*target]))
+        });
+        let pool = DescriptorPool::new(set(&arena, &[a])).unwrap();
+        assert_eq!(pool.extensions_of(".a.Target").len(), 2);
+        assert_eq!(pool.get_extension("a.f").unwrap().number, Some(101));
+
+        let a = arena.alloc(FileDescriptorProto {
+            extension: arena.alloc([extension("e", 100), extension("f", 100)]),
+            ..*a
+        });
+        assert_eq!(
+            DescriptorPool::new(set(&arena, &[a])).unwrap_err(),
+            DescriptorError::TagCollision {
+                message: String::from("a.Target"),
+                number: 100,
+            }
+        );
+
+        let a = arena.alloc(FileDescriptorProto {
+            extension: arena.alloc([extension("e", 1)]),
+            ..*a
+        });
+        assert!(DescriptorPool::new(set(&arena, &[a])).is_err());
+    }
+}
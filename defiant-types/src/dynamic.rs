@@ -0,0 +1,1655 @@
+//! Messages whose types are only known at runtime, described by a [`FileDescriptorSet`].
+//!
+//! A [`DynamicMessage`] decodes, inspects, modifies and encodes any message described by a
+//! descriptor, without Rust types being generated for it:
+//!
+//! ```rust,ignore
+//! let files = FileDescriptorSet::from_buf(descriptor_bytes, &arena)?;
+//! let descriptor = MessageDescriptor::new(&files, "my.package.Request").unwrap();
+//!
+//! let mut request = DynamicMessage::decode(descriptor, wire_bytes, &arena)?;
+//! assert_eq!(request.get("method"), Some(Value::String("GET")));
+//! request.set("method", Value::String("POST"))?;
+//! let bytes = request.encode_to_vec();
+//! ```
+
+use core::cmp::Ordering;
+use core::fmt;
+
+use defiant::alloc::format;
+use defiant::alloc::string::String;
+use defiant::alloc::vec::Vec;
+use defiant::bytes::{Buf, BufMut};
+use defiant::encoding::{
+    self, decode_key, decode_varint, encode_key, encode_varint, encoded_len_varint, key_len,
+    DecodeContext, SizeCache, WireType,
+};
+use defiant::{Arena, ArenaVec, DecodeError, DecodeOptions, Encode};
+
+use crate::field_descriptor_proto::{Label, Type};
+use crate::{
+    DescriptorPool, DescriptorProto, FieldDescriptorProto, FileDescriptorProto, FileDescriptorSet,
+};
+
+/// A message type within a [`FileDescriptorSet`].
+///
+/// Nested message types are resolved by their fully-qualified `type_name` within the same set,
+/// so the set must include the files that define them. Descriptors returned by
+/// [`DescriptorPool::message_descriptor`] resolve them through the pool instead, which also
+/// accepts type names relative to the message's scope.
+#[derive(Clone, Copy)]
+pub struct MessageDescriptor<'arena> {
+    files: &'arena FileDescriptorSet<'arena>,
+    file: &'arena FileDescriptorProto<'arena>,
+    message: &'arena DescriptorProto<'arena>,
+    /// The pool the message was looked up in, and its fully-qualified name there.
+    pool: Option<(&'arena DescriptorPool<'arena>, &'arena str)>,
+}
+
+impl<'arena> MessageDescriptor<'arena> {
+    /// Looks up a message by its fully-qualified name, such as `"google.protobuf.Duration"`.
+    ///
+    /// A leading `.`, as used by `type_name` in field descriptors, is accepted.
+    pub fn new(files: &'arena FileDescriptorSet<'arena>, full_name: &str) -> Option<Self> {
+        let full_name = full_name.strip_prefix('.').unwrap_or(full_name);
+        files.file.iter().find_map(|file| {
+            let path = match file.package.unwrap_or("") {
+                "" => full_name,
+                package => full_name
+                    .strip_prefix(package)
+                    .and_then(|name| name.strip_prefix('.'))?,
+            };
+            let mut names = path.split('.');
+            let first = names.next()?;
+            let mut message = *file
+                .message_type
+                .iter()
+                .find(|message| message.name == Some(first))?;
+            for name in names {
+                message = *message
+                    .nested_type
+                    .iter()
+                    .find(|message| message.name == Some(name))?;
+            }
+            Some(MessageDescriptor {
+                files,
+                file,
+                message,
+                pool: None,
+            })
+        })
+    }
+
+    /// Creates a descriptor which resolves field types through `pool`.
+    pub(crate) fn in_pool(
+        pool: &'arena DescriptorPool<'arena>,
+        full_name: &'arena str,
+        file: &'arena FileDescriptorProto<'arena>,
+        message: &'arena DescriptorProto<'arena>,
+    ) -> Self {
+        MessageDescriptor {
+            files: pool.file_set(),
+            file,
+            message,
+            pool: Some((pool, full_name)),
+        }
+    }
+
+    /// Returns the message's unqualified name.
+    pub fn name(&self) -> &'arena str {
+        self.message.name.unwrap_or("")
+    }
+
+    /// Returns the underlying descriptor.
+    pub fn descriptor(&self) -> &'arena DescriptorProto<'arena> {
+        self.message
+    }
+
+    /// Returns the descriptors of the message's fields, in declaration order.
+    pub fn fields(&self) -> impl Iterator<Item = &'arena FieldDescriptorProto<'arena>> {
+        self.message.field.iter().copied()
+    }
+
+    /// Looks up a field by name.
+    pub fn field_by_name(&self, name: &str) -> Option<&'arena FieldDescriptorProto<'arena>> {
+        self.fields().find(|field| field.name == Some(name))
+    }
+
+    /// Looks up a field by number.
+    pub fn field_by_number(&self, number: u32) -> Option<&'arena FieldDescriptorProto<'arena>> {
+        self.fields()
+            .find(|field| field.number.map(|n| n as u32) == Some(number))
+    }
+
+    /// Returns the type of a message, group or map field.
+    pub fn message_type(
+        &self,
+        field: &FieldDescriptorProto<'_>,
+    ) -> Option<MessageDescriptor<'arena>> {
+        match self.pool {
+            Some((pool, scope)) => {
+                let (name, _) = pool.resolve(scope, field.type_name?)?;
+                pool.message_descriptor(&name)
+            }
+            None => MessageDescriptor::new(self.files, field.type_name?),
+        }
+    }
+
+    /// Returns `true` if the message is the synthesized entry type of a map field.
+    pub fn is_map_entry(&self) -> bool {
+        self.message
+            .options
+            .is_some_and(|options| options.map_entry == Some(true))
+    }
+
+    fn is_proto3(&self) -> bool {
+        self.file.syntax == Some("proto3")
+    }
+
+    /// Returns the key and value fields if `field` is a map field.
+    fn map_entry(
+        &self,
+        field: &FieldDescriptorProto<'_>,
+    ) -> Option<(
+        &'arena FieldDescriptorProto<'arena>,
+        &'arena FieldDescriptorProto<'arena>,
+        MessageDescriptor<'arena>,
+    )> {
+        if label(field) != Label::Repeated || ty(field) != Type::Message {
+            return None;
+        }
+        let entry = self.message_type(field)?;
+        if !entry.is_map_entry() {
+            return None;
+        }
+        Some((entry.field_by_number(1)?, entry.field_by_number(2)?, entry))
+    }
+
+    /// Returns `true` if `value` can be stored in a singular `field`.
+    fn matches(&self, field: &FieldDescriptorProto<'_>, value: &Value<'_>) -> bool {
+        match (ty(field), value) {
+            (Type::Bool, Value::Bool(_))
+            | (Type::Int32 | Type::Sint32 | Type::Sfixed32, Value::I32(_))
+            | (Type::Int64 | Type::Sint64 | Type::Sfixed64, Value::I64(_))
+            | (Type::Uint32 | Type::Fixed32, Value::U32(_))
+            | (Type::Uint64 | Type::Fixed64, Value::U64(_))
+            | (Type::Float, Value::F32(_))
+            | (Type::Double, Value::F64(_))
+            | (Type::String, Value::String(_))
+            | (Type::Bytes, Value::Bytes(_))
+            | (Type::Enum, Value::Enum(_)) => true,
+            (Type::Message | Type::Group, Value::Message(message)) => {
+                self.message_type(field) == Some(message.descriptor)
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns `true` if repeated values of the field are written packed.
+    fn is_packed(&self, field: &FieldDescriptorProto<'_>) -> bool {
+        is_packable(ty(field))
+            && field
+                .options
+                .and_then(|options| options.packed)
+                .unwrap_or(self.is_proto3())
+    }
+
+    /// Returns `true` if the field is only written when it differs from its default.
+    fn has_implicit_presence(&self, field: &FieldDescriptorProto<'_>) -> bool {
+        self.is_proto3()
+            && label(field) == Label::Optional
+            && !matches!(ty(field), Type::Message | Type::Group)
+            && field.oneof_index.is_none()
+            && field.proto3_optional != Some(true)
+    }
+
+    /// Returns `true` if the field numbered `other` is another member of the oneof of `field`,
+    /// which setting `field` clears.
+    fn same_oneof(&self, field: &FieldDescriptorProto<'_>, other: u32) -> bool {
+        let (Some(oneof), Label::Optional) = (field.oneof_index, label(field)) else {
+            return false;
+        };
+        other != field.number.unwrap_or(0) as u32
+            && self
+                .field_by_number(other)
+                .is_some_and(|other| other.oneof_index == Some(oneof))
+    }
+}
+
+impl PartialEq for MessageDescriptor<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        core::ptr::eq(self.message, other.message)
+    }
+}
+
+impl fmt::Debug for MessageDescriptor<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("MessageDescriptor")
+            .field(&self.name())
+            .finish()
+    }
+}
+
+/// The value of a field in a [`DynamicMessage`].
+///
+/// Variants are named after the Rust representation rather than the Protobuf type, so `sint32`,
+/// `sfixed32` and `int32` fields all hold [`I32`](Value::I32) values.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Value<'arena> {
+    Bool(bool),
+    I32(i32),
+    I64(i64),
+    U32(u32),
+    U64(u64),
+    F32(f32),
+    F64(f64),
+    String(&'arena str),
+    Bytes(&'arena [u8]),
+    /// The number of an enum value, which may not be declared by the enum.
+    Enum(i32),
+    /// A message or group.
+    Message(&'arena DynamicMessage<'arena>),
+    /// The values of a repeated field.
+    List(&'arena [Value<'arena>]),
+    /// The entries of a map field, sorted by key.
+    Map(&'arena [(Value<'arena>, Value<'arena>)]),
+}
+
+impl Value<'_> {
+    /// Returns `true` for the zero value of a scalar type.
+    fn is_default(&self) -> bool {
+        match *self {
+            Value::Bool(value) => !value,
+            Value::I32(value) | Value::Enum(value) => value == 0,
+            Value::I64(value) => value == 0,
+            Value::U32(value) => value == 0,
+            Value::U64(value) => value == 0,
+            Value::F32(value) => value.to_bits() == 0,
+            Value::F64(value) => value.to_bits() == 0,
+            Value::String(value) => value.is_empty(),
+            Value::Bytes(value) => value.is_empty(),
+            Value::Message(_) | Value::List(_) | Value::Map(_) => false,
+        }
+    }
+
+    /// Orders map keys, which are integers, booleans or strings.
+    fn cmp_key(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
+            (Value::I32(a), Value::I32(b)) => a.cmp(b),
+            (Value::I64(a), Value::I64(b)) => a.cmp(b),
+            (Value::U32(a), Value::U32(b)) => a.cmp(b),
+            (Value::U64(a), Value::U64(b)) => a.cmp(b),
+            (Value::String(a), Value::String(b)) => a.cmp(b),
+            _ => Ordering::Equal,
+        }
+    }
+}
+
+/// An error from setting a field of a [`DynamicMessage`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DynamicError {
+    /// The message has no field with this name or number.
+    UnknownField(String),
+    /// The value does not match the type of the field.
+    TypeMismatch(String),
+}
+
+impl fmt::Display for DynamicError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DynamicError::UnknownField(field) => write!(f, "unknown field: {field}"),
+            DynamicError::TypeMismatch(field) => {
+                write!(f, "value does not match the type of field: {field}")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DynamicError {}
+
+/// A message of a type described at runtime by a [`MessageDescriptor`].
+///
+/// Field values are allocated in the arena. Fields which are not part of the descriptor are kept
+/// as unknown fields and written back when the message is encoded.
+pub struct DynamicMessage<'arena> {
+    descriptor: MessageDescriptor<'arena>,
+    arena: &'arena Arena,
+    /// The fields which are present, sorted by number.
+    fields: ArenaVec<'arena, (u32, Value<'arena>)>,
+    unknown_fields: ArenaVec<'arena, u8>,
+}
+
+/// Repeated and map values collected while decoding, which are appended to the message's own
+/// values once the whole message has been read.
+#[derive(Default)]
+struct Pending<'arena> {
+    lists: Vec<(u32, Vec<Value<'arena>>)>,
+    maps: Vec<(u32, Vec<(Value<'arena>, Value<'arena>)>)>,
+    /// Singular message fields, which repeated occurrences are merged into in place.
+    messages: Vec<(u32, DynamicMessage<'arena>)>,
+}
+
+impl<'arena> DynamicMessage<'arena> {
+    /// Creates an empty message.
+    pub fn new(descriptor: MessageDescriptor<'arena>, arena: &'arena Arena) -> Self {
+        DynamicMessage {
+            descriptor,
+            arena,
+            fields: arena.new_vec(),
+            unknown_fields: arena.new_vec(),
+        }
+    }
+
+    /// Decodes a message of the given type.
+    pub fn decode(
+        descriptor: MessageDescriptor<'arena>,
+        mut buf: impl Buf,
+        arena: &'arena Arena,
+    ) -> Result<Self, DecodeError> {
+        let mut message = DynamicMessage::new(descriptor, arena);
+        message.merge(&mut buf)?;
+        Ok(message)
+    }
+
+    /// Decodes a message of the given type, enforcing the limits in `options`.
+    ///
+    /// With [`check_required`](DecodeOptions::check_required), the decode fails if a required
+    /// field is missing, see [`missing_required_fields`](Self::missing_required_fields).
+    /// Projections and extension registries only apply to generated messages.
+    pub fn decode_with_options(
+        descriptor: MessageDescriptor<'arena>,
+        mut buf: impl Buf,
+        arena: &'arena Arena,
+        options: DecodeOptions,
+    ) -> Result<Self, DecodeError> {
+        options.check_input_len(buf.remaining())?;
+        let mut message = DynamicMessage::new(descriptor, arena);
+        message.merge_with_context(&mut buf, DecodeContext::with_options(&options))?;
+        if options.is_checking_required() {
+            let missing = message.missing_required_fields();
+            if !missing.is_empty() {
+                return Err(DecodeError::new(format!(
+                    "missing required fields: {}",
+                    missing.join(", ")
+                )));
+            }
+        }
+        Ok(message)
+    }
+
+    /// Decodes fields from `buf` and merges them into the message.
+    pub fn merge(&mut self, buf: impl Buf) -> Result<(), DecodeError> {
+        self.merge_with_context(buf, DecodeContext::default())
+    }
+
+    fn merge_with_context(
+        &mut self,
+        mut buf: impl Buf,
+        ctx: DecodeContext<'_, 'arena>,
+    ) -> Result<(), DecodeError> {
+        let mut pending = Pending::default();
+        while buf.has_remaining() {
+            let (tag, wire_type) = decode_key(&mut buf)?;
+            self.merge_field(&mut pending, tag, wire_type, &mut buf, ctx.clone())?;
+        }
+        self.flush(pending)
+    }
+
+    /// Returns the message's type.
+    pub fn descriptor(&self) -> MessageDescriptor<'arena> {
+        self.descriptor
+    }
+
+    /// Returns the value of the named field, or `None` if the field is not present.
+    pub fn get(&self, name: &str) -> Option<Value<'arena>> {
+        let field = self.descriptor.field_by_name(name)?;
+        self.get_by_number(field.number? as u32)
+    }
+
+    /// Returns the value of the field with the given number, or `None` if it is not present.
+    pub fn get_by_number(&self, number: u32) -> Option<Value<'arena>> {
+        self.find(number).ok().map(|index| self.fields[index].1)
+    }
+
+    /// Returns `true` if the named field is present.
+    pub fn has(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    /// Sets the named field.
+    ///
+    /// Repeated fields take a [`Value::List`] and map fields a [`Value::Map`]. Setting a member
+    /// of a `oneof` clears the other members.
+    pub fn set(&mut self, name: &str, value: Value<'arena>) -> Result<(), DynamicError> {
+        let field = self
+            .descriptor
+            .field_by_name(name)
+            .ok_or_else(|| DynamicError::UnknownField(String::from(name)))?;
+        self.set_field(field, value)
+    }
+
+    /// Sets the field with the given number, see [`set`](Self::set).
+    pub fn set_by_number(&mut self, number: u32, value: Value<'arena>) -> Result<(), DynamicError> {
+        let field = self
+            .descriptor
+            .field_by_number(number)
+            .ok_or_else(|| DynamicError::UnknownField(format!("{number}")))?;
+        self.set_field(field, value)
+    }
+
+    /// Removes the named field, returning its value if it was present.
+    pub fn clear(&mut self, name: &str) -> Option<Value<'arena>> {
+        let number = self.descriptor.field_by_name(name)?.number? as u32;
+        self.clear_by_number(number)
+    }
+
+    /// Removes the field with the given number, returning its value if it was present.
+    pub fn clear_by_number(&mut self, number: u32) -> Option<Value<'arena>> {
+        let index = self.find(number).ok()?;
+        Some(self.fields.remove(index).1)
+    }
+
+    /// Returns the present fields and their values, in field number order.
+    pub fn fields(
+        &self,
+    ) -> impl Iterator<Item = (&'arena FieldDescriptorProto<'arena>, Value<'arena>)> + '_ {
+        self.fields.iter().filter_map(move |&(number, value)| {
+            Some((self.descriptor.field_by_number(number)?, value))
+        })
+    }
+
+    /// Returns the encoded fields which are not part of the message's descriptor.
+    pub fn unknown_fields(&self) -> &[u8] {
+        &self.unknown_fields
+    }
+
+    /// Returns the paths of the required fields missing from the message or from a message
+    /// nested in it, such as `id` or `items[2].owner.name`. Map values are checked under their
+    /// key, such as `users["ann"].id`.
+    pub fn missing_required_fields(&self) -> Vec<String> {
+        let mut missing = Vec::new();
+        self.collect_missing(&mut String::new(), &mut missing);
+        missing
+    }
+
+    fn collect_missing(&self, path: &mut String, missing: &mut Vec<String>) {
+        let enter = |path: &mut String, name: &str| {
+            let len = path.len();
+            if len > 0 {
+                path.push('.');
+            }
+            path.push_str(name);
+            len
+        };
+        for field in self.descriptor.fields() {
+            let name = field.name.unwrap_or("");
+            let number = field.number.unwrap_or(0) as u32;
+            let value = match self.get_by_number(number) {
+                Some(value) => value,
+                None => {
+                    if label(field) == Label::Required {
+                        let len = enter(path, name);
+                        missing.push(path.clone());
+                        path.truncate(len);
+                    }
+                    continue;
+                }
+            };
+            let len = enter(path, name);
+            match value {
+                Value::Message(message) => message.collect_missing(path, missing),
+                Value::List(values) => {
+                    for (index, value) in values.iter().enumerate() {
+                        if let Value::Message(message) = value {
+                            let len = path.len();
+                            path.push_str(&format!("[{index}]"));
+                            message.collect_missing(path, missing);
+                            path.truncate(len);
+                        }
+                    }
+                }
+                Value::Map(entries) => {
+                    for (key, value) in entries {
+                        if let Value::Message(message) = value {
+                            let len = path.len();
+                            match key {
+                                Value::String(key) => path.push_str(&format!("[{key:?}]")),
+                                Value::Bool(key) => path.push_str(&format!("[{key}]")),
+                                Value::I32(key) => path.push_str(&format!("[{key}]")),
+                                Value::I64(key) => path.push_str(&format!("[{key}]")),
+                                Value::U32(key) => path.push_str(&format!("[{key}]")),
+                                Value::U64(key) => path.push_str(&format!("[{key}]")),
+                                _ => path.push_str("[]"),
+                            }
+                            message.collect_missing(path, missing);
+                            path.truncate(len);
+                        }
+                    }
+                }
+                _ => {}
+            }
+            path.truncate(len);
+        }
+    }
+
+    fn find(&self, number: u32) -> Result<usize, usize> {
+        self.fields
+            .binary_search_by_key(&number, |&(number, _)| number)
+    }
+
+    fn set_field(
+        &mut self,
+        field: &FieldDescriptorProto<'_>,
+        value: Value<'arena>,
+    ) -> Result<(), DynamicError> {
+        let mismatch = || DynamicError::TypeMismatch(String::from(field.name.unwrap_or("")));
+        let value = match value {
+            Value::List(values) if self.descriptor.map_entry(field).is_none() => {
+                if label(field) != Label::Repeated
+                    || !values
+                        .iter()
+                        .all(|value| self.descriptor.matches(field, value))
+                {
+                    return Err(mismatch());
+                }
+                value
+            }
+            Value::Map(entries) => {
+                let (key_field, value_field, entry) =
+                    self.descriptor.map_entry(field).ok_or_else(mismatch)?;
+                let entry_matches = |(key, value): &(Value<'_>, Value<'_>)| {
+                    entry.matches(key_field, key) && entry.matches(value_field, value)
+                };
+                if !entries.iter().all(entry_matches) {
+                    return Err(mismatch());
+                }
+                Value::Map(sort_entries(self.arena, entries.iter().copied()))
+            }
+            _ if label(field) != Label::Repeated && self.descriptor.matches(field, &value) => value,
+            _ => return Err(mismatch()),
+        };
+        self.insert(field, value);
+        Ok(())
+    }
+
+    /// Stores a value, clearing the other members of its oneof.
+    fn insert(&mut self, field: &FieldDescriptorProto<'_>, value: Value<'arena>) {
+        let number = field.number.unwrap_or(0) as u32;
+        let descriptor = self.descriptor;
+        self.fields
+            .retain(|&(other, _)| !descriptor.same_oneof(field, other));
+        match self.find(number) {
+            Ok(index) => self.fields[index].1 = value,
+            Err(index) => self.fields.insert(index, (number, value)),
+        }
+    }
+
+    fn merge_field<B: Buf>(
+        &mut self,
+        pending: &mut Pending<'arena>,
+        tag: u32,
+        wire_type: WireType,
+        buf: &mut B,
+        ctx: DecodeContext<'_, 'arena>,
+    ) -> Result<(), DecodeError> {
+        let descriptor = self.descriptor;
+        let field = match descriptor.field_by_number(tag) {
+            Some(field) => field,
+            None => {
+                return encoding::merge_unknown_field(
+                    wire_type,
+                    tag,
+                    buf,
+                    &mut self.unknown_fields,
+                    ctx,
+                )
+            }
+        };
+
+        if let Some((key_field, value_field, entry)) = descriptor.map_entry(field) {
+            let entries = match pending.maps.iter().position(|(number, _)| *number == tag) {
+                Some(index) => &mut pending.maps[index].1,
+                None => {
+                    pending.maps.push((tag, Vec::new()));
+                    &mut pending.maps.last_mut().unwrap().1
+                }
+            };
+            ctx.check_repeated_len(entries.len())?;
+            let entry = self.merge_map_entry(key_field, value_field, entry, wire_type, buf, ctx)?;
+            entries.push(entry);
+            return Ok(());
+        }
+
+        if label(field) == Label::Repeated {
+            let values = match pending.lists.iter().position(|(number, _)| *number == tag) {
+                Some(index) => &mut pending.lists[index].1,
+                None => {
+                    pending.lists.push((tag, Vec::new()));
+                    &mut pending.lists.last_mut().unwrap().1
+                }
+            };
+            if wire_type == WireType::LengthDelimited && is_packable(ty(field)) {
+                let arena = self.arena;
+                return encoding::merge_loop(values, buf, ctx, |values, buf, ctx| {
+                    ctx.check_repeated_len(values.len())?;
+                    values.push(decode_value(
+                        descriptor,
+                        field,
+                        scalar_wire_type(ty(field)),
+                        buf,
+                        arena,
+                        ctx,
+                    )?);
+                    Ok(())
+                });
+            }
+            ctx.check_repeated_len(values.len())?;
+            let value = self.decode_value(field, wire_type, buf, ctx)?;
+            values.push(value);
+            return Ok(());
+        }
+
+        if matches!(ty(field), Type::Message | Type::Group) {
+            let index = match pending
+                .messages
+                .iter()
+                .position(|(number, _)| *number == tag)
+            {
+                Some(index) => index,
+                None => {
+                    // A repeated occurrence of a singular message is merged into the first
+                    let message = match self.get_by_number(tag) {
+                        Some(Value::Message(existing)) => existing.clone_in(self.arena),
+                        _ => new_nested(descriptor, field, self.arena)?,
+                    };
+                    pending
+                        .messages
+                        .retain(|&(number, _)| !descriptor.same_oneof(field, number));
+                    pending.messages.push((tag, message));
+                    pending.messages.len() - 1
+                }
+            };
+            return pending.messages[index]
+                .1
+                .merge_nested(field, wire_type, buf, ctx);
+        }
+
+        let value = self.decode_value(field, wire_type, buf, ctx)?;
+        pending
+            .messages
+            .retain(|&(number, _)| !descriptor.same_oneof(field, number));
+        self.insert(field, value);
+        Ok(())
+    }
+
+    fn decode_value<B: Buf>(
+        &self,
+        field: &FieldDescriptorProto<'_>,
+        wire_type: WireType,
+        buf: &mut B,
+        ctx: DecodeContext<'_, 'arena>,
+    ) -> Result<Value<'arena>, DecodeError> {
+        decode_value(self.descriptor, field, wire_type, buf, self.arena, ctx)
+    }
+
+    /// Decodes the body of a message or group field into `self`.
+    fn merge_nested<B: Buf>(
+        &mut self,
+        field: &FieldDescriptorProto<'_>,
+        wire_type: WireType,
+        buf: &mut B,
+        ctx: DecodeContext<'_, 'arena>,
+    ) -> Result<(), DecodeError> {
+        let mut pending = Pending::default();
+        if ty(field) == Type::Group {
+            encoding::check_wire_type(WireType::StartGroup, wire_type)?;
+            ctx.limit_reached()?;
+            let ctx = ctx.enter_recursion();
+            let end = field.number.unwrap_or(0) as u32;
+            loop {
+                let (tag, wire_type) = decode_key(buf)?;
+                if wire_type == WireType::EndGroup {
+                    if tag != end {
+                        return Err(DecodeError::new("unexpected end group tag"));
+                    }
+                    break;
+                }
+                self.merge_field(&mut pending, tag, wire_type, buf, ctx.clone())?;
+            }
+        } else {
+            encoding::check_wire_type(WireType::LengthDelimited, wire_type)?;
+            ctx.limit_reached()?;
+            encoding::merge_loop(
+                &mut (&mut *self, &mut pending),
+                buf,
+                ctx.enter_recursion(),
+                |(message, pending), buf, ctx| {
+                    let (tag, wire_type) = decode_key(buf)?;
+                    message.merge_field(pending, tag, wire_type, buf, ctx)
+                },
+            )?;
+        }
+        self.flush(pending)
+    }
+
+    fn merge_map_entry<B: Buf>(
+        &self,
+        key_field: &FieldDescriptorProto<'_>,
+        value_field: &FieldDescriptorProto<'_>,
+        entry: MessageDescriptor<'arena>,
+        wire_type: WireType,
+        buf: &mut B,
+        ctx: DecodeContext<'_, 'arena>,
+    ) -> Result<(Value<'arena>, Value<'arena>), DecodeError> {
+        encoding::check_wire_type(WireType::LengthDelimited, wire_type)?;
+        ctx.limit_reached()?;
+        let arena = self.arena;
+        let mut key = None;
+        let mut value = None;
+        encoding::merge_loop(
+            &mut (&mut key, &mut value),
+            buf,
+            ctx.enter_recursion(),
+            |(key, value), buf, ctx| {
+                let (tag, wire_type) = decode_key(buf)?;
+                match tag {
+                    1 => **key = Some(decode_value(entry, key_field, wire_type, buf, arena, ctx)?),
+                    2 => {
+                        **value = Some(decode_value(
+                            entry,
+                            value_field,
+                            wire_type,
+                            buf,
+                            arena,
+                            ctx,
+                        )?)
+                    }
+                    _ => encoding::skip_field(wire_type, tag, buf, ctx)?,
+                }
+                Ok(())
+            },
+        )?;
+        let key = key.unwrap_or_else(|| default_value(entry, key_field, arena));
+        let value = value.unwrap_or_else(|| default_value(entry, value_field, arena));
+        Ok((key, value))
+    }
+
+    /// Appends the repeated and map values collected while decoding.
+    fn flush(&mut self, pending: Pending<'arena>) -> Result<(), DecodeError> {
+        let descriptor = self.descriptor;
+        for (number, message) in pending.messages {
+            let field = descriptor.field_by_number(number).unwrap();
+            self.insert(field, Value::Message(self.arena.try_alloc(message)?));
+        }
+        for (number, values) in pending.lists {
+            let mut list = self.arena.new_vec();
+            if let Some(Value::List(existing)) = self.get_by_number(number) {
+                list.try_reserve(existing.len() + values.len())?;
+                list.extend_from_slice(existing);
+            } else {
+                list.try_reserve(values.len())?;
+            }
+            list.extend_from_slice(&values);
+            let field = descriptor.field_by_number(number).unwrap();
+            self.insert(field, Value::List(list.freeze()));
+        }
+        for (number, entries) in pending.maps {
+            let existing = match self.get_by_number(number) {
+                Some(Value::Map(existing)) => existing,
+                _ => &[],
+            };
+            let entries = existing.iter().copied().chain(entries);
+            let field = descriptor.field_by_number(number).unwrap();
+            self.insert(field, Value::Map(sort_entries(self.arena, entries)));
+        }
+        Ok(())
+    }
+
+    /// Copies the message, so that it can be modified without affecting `self`.
+    fn clone_in(&self, arena: &'arena Arena) -> DynamicMessage<'arena> {
+        let mut message = DynamicMessage::new(self.descriptor, arena);
+        message.fields.extend_from_slice(&self.fields);
+        message
+            .unknown_fields
+            .extend_from_slice(&self.unknown_fields);
+        message
+    }
+
+    /// Returns `false` if the field would not be written.
+    fn is_written(&self, field: &FieldDescriptorProto<'_>, value: &Value<'_>) -> bool {
+        !(self.descriptor.has_implicit_presence(field) && value.is_default())
+    }
+}
+
+impl Encode for DynamicMessage<'_> {
+    fn encode_raw(&self, buf: &mut impl BufMut) {
+        let mut sizes = SizeCache::new();
+        self.encoded_len_cached(&mut sizes);
+        self.encode_raw_cached(buf, &mut sizes);
+    }
+
+    fn encoded_len(&self) -> usize {
+        self.encoded_len_cached(&mut SizeCache::new())
+    }
+
+    fn encoded_len_cached(&self, sizes: &mut SizeCache) -> usize {
+        let fields: usize = self
+            .fields()
+            .filter(|(field, value)| self.is_written(field, value))
+            .map(|(field, value)| {
+                let tag = field.number.unwrap_or(0) as u32;
+                match value {
+                    Value::List(values) if self.descriptor.is_packed(field) => {
+                        if values.is_empty() {
+                            return 0;
+                        }
+                        let len = packed_len(ty(field), values);
+                        key_len(tag) + encoded_len_varint(len as u64) + len
+                    }
+                    Value::List(values) => values
+                        .iter()
+                        .map(|value| field_len(ty(field), tag, value, sizes))
+                        .sum(),
+                    Value::Map(entries) => {
+                        let (key_field, value_field, _) = self.descriptor.map_entry(field).unwrap();
+                        let (key_ty, value_ty) = (ty(key_field), ty(value_field));
+                        entries
+                            .iter()
+                            .map(|(key, value)| {
+                                let slot = sizes.reserve_slot();
+                                let len = field_len(key_ty, 1, key, sizes)
+                                    + field_len(value_ty, 2, value, sizes);
+                                sizes.record(slot, len);
+                                key_len(tag) + encoded_len_varint(len as u64) + len
+                            })
+                            .sum()
+                    }
+                    value => field_len(ty(field), tag, &value, sizes),
+                }
+            })
+            .sum();
+        fields + self.unknown_fields.len()
+    }
+
+    fn encode_raw_cached(&self, buf: &mut impl BufMut, sizes: &mut SizeCache) {
+        for (field, value) in self.fields() {
+            if !self.is_written(field, &value) {
+                continue;
+            }
+            let tag = field.number.unwrap_or(0) as u32;
+            match value {
+                Value::List(values) if self.descriptor.is_packed(field) => {
+                    if values.is_empty() {
+                        continue;
+                    }
+                    let ty = ty(field);
+                    encode_key(tag, WireType::LengthDelimited, buf);
+                    encode_varint(packed_len(ty, values) as u64, buf);
+                    for value in values {
+                        encode_scalar(ty, value, buf, sizes);
+                    }
+                }
+                Value::List(values) => {
+                    for value in values {
+                        encode_field(ty(field), tag, value, buf, sizes);
+                    }
+                }
+                Value::Map(entries) => {
+                    let (key_field, value_field, _) = self.descriptor.map_entry(field).unwrap();
+                    let (key_ty, value_ty) = (ty(key_field), ty(value_field));
+                    for (key, value) in entries {
+                        encode_key(tag, WireType::LengthDelimited, buf);
+                        encode_varint(sizes.next_len() as u64, buf);
+                        encode_field(key_ty, 1, key, buf, sizes);
+                        encode_field(value_ty, 2, value, buf, sizes);
+                    }
+                }
+                value => encode_field(ty(field), tag, &value, buf, sizes),
+            }
+        }
+        buf.put_slice(&self.unknown_fields);
+    }
+}
+
+impl PartialEq for DynamicMessage<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.descriptor == other.descriptor
+            && *self.fields == *other.fields
+            && *self.unknown_fields == *other.unknown_fields
+    }
+}
+
+impl fmt::Debug for DynamicMessage<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut builder = f.debug_struct(self.descriptor.name());
+        for (field, value) in self.fields() {
+            builder.field(field.name.unwrap_or(""), &value);
+        }
+        if !self.unknown_fields.is_empty() {
+            builder.field("unknown_fields", &&*self.unknown_fields);
+        }
+        builder.finish()
+    }
+}
+
+fn label(field: &FieldDescriptorProto<'_>) -> Label {
+    field
+        .label
+        .and_then(|label| Label::try_from(label).ok())
+        .unwrap_or(Label::Optional)
+}
+
+fn ty(field: &FieldDescriptorProto<'_>) -> Type {
+    field
+        .r#type
+        .and_then(|ty| Type::try_from(ty).ok())
+        .unwrap_or(Type::Double)
+}
+
+fn is_packable(ty: Type) -> bool {
+    !matches!(ty, Type::String | Type::Bytes | Type::Message | Type::Group)
+}
+
+fn scalar_wire_type(ty: Type) -> WireType {
+    match ty {
+        Type::Fixed32 | Type::Sfixed32 | Type::Float => WireType::ThirtyTwoBit,
+        Type::Fixed64 | Type::Sfixed64 | Type::Double => WireType::SixtyFourBit,
+        Type::String | Type::Bytes | Type::Message => WireType::LengthDelimited,
+        Type::Group => WireType::StartGroup,
+        _ => WireType::Varint,
+    }
+}
+
+/// Sorts map entries by key; of several entries with the same key, the last one wins.
+fn sort_entries<'arena>(
+    arena: &'arena Arena,
+    entries: impl Iterator<Item = (Value<'arena>, Value<'arena>)>,
+) -> &'arena [(Value<'arena>, Value<'arena>)] {
+    let mut entries: Vec<_> = entries.collect();
+    // The sort is stable, so duplicates stay in the order they were added
+    entries.sort_by(|(a, _), (b, _)| a.cmp_key(b));
+    let mut sorted: ArenaVec<'_, (Value<'_>, Value<'_>)> =
+        arena.new_vec_with_capacity(entries.len());
+    for entry in entries {
+        match sorted.last_mut() {
+            Some(last) if last.0.cmp_key(&entry.0) == Ordering::Equal => *last = entry,
+            _ => sorted.push(entry),
+        }
+    }
+    sorted.freeze()
+}
+
+fn default_value<'arena>(
+    descriptor: MessageDescriptor<'arena>,
+    field: &FieldDescriptorProto<'_>,
+    arena: &'arena Arena,
+) -> Value<'arena> {
+    match ty(field) {
+        Type::Bool => Value::Bool(false),
+        Type::Int32 | Type::Sint32 | Type::Sfixed32 => Value::I32(0),
+        Type::Int64 | Type::Sint64 | Type::Sfixed64 => Value::I64(0),
+        Type::Uint32 | Type::Fixed32 => Value::U32(0),
+        Type::Uint64 | Type::Fixed64 => Value::U64(0),
+        Type::Float => Value::F32(0.0),
+        Type::Double => Value::F64(0.0),
+        Type::String => Value::String(""),
+        Type::Bytes => Value::Bytes(&[]),
+        Type::Enum => Value::Enum(0),
+        Type::Message | Type::Group => match descriptor.message_type(field) {
+            Some(message) => Value::Message(arena.alloc(DynamicMessage::new(message, arena))),
+            None => Value::Bytes(&[]),
+        },
+    }
+}
+
+/// Creates an empty message of the type of the message or group `field`.
+fn new_nested<'arena>(
+    descriptor: MessageDescriptor<'arena>,
+    field: &FieldDescriptorProto<'_>,
+    arena: &'arena Arena,
+) -> Result<DynamicMessage<'arena>, DecodeError> {
+    let nested = descriptor.message_type(field).ok_or_else(|| {
+        DecodeError::new(format!(
+            "unknown message type: {}",
+            field.type_name.unwrap_or("")
+        ))
+    })?;
+    Ok(DynamicMessage::new(nested, arena))
+}
+
+fn decode_value<'arena, B: Buf>(
+    descriptor: MessageDescriptor<'arena>,
+    field: &FieldDescriptorProto<'_>,
+    wire_type: WireType,
+    buf: &mut B,
+    arena: &'arena Arena,
+    ctx: DecodeContext<'_, 'arena>,
+) -> Result<Value<'arena>, DecodeError> {
+    let ty = ty(field);
+    if matches!(ty, Type::Message | Type::Group) {
+        let mut message = new_nested(descriptor, field, arena)?;
+        message.merge_nested(field, wire_type, buf, ctx)?;
+        return Ok(Value::Message(arena.try_alloc(message)?));
+    }
+
+    encoding::check_wire_type(scalar_wire_type(ty), wire_type)?;
+    let value = match ty {
+        Type::String => Value::String(encoding::string::merge_arena(wire_type, buf, arena, ctx)?),
+        Type::Bytes => Value::Bytes(encoding::bytes::merge_arena(wire_type, buf, arena, ctx)?),
+        Type::Float => Value::F32(fixed::<4, B>(buf)?.get_f32_le()),
+        Type::Double => Value::F64(fixed::<8, B>(buf)?.get_f64_le()),
+        Type::Fixed32 => Value::U32(fixed::<4, B>(buf)?.get_u32_le()),
+        Type::Fixed64 => Value::U64(fixed::<8, B>(buf)?.get_u64_le()),
+        Type::Sfixed32 => Value::I32(fixed::<4, B>(buf)?.get_i32_le()),
+        Type::Sfixed64 => Value::I64(fixed::<8, B>(buf)?.get_i64_le()),
+        _ => {
+            let varint = decode_varint(buf)?;
+            match ty {
+                Type::Bool => Value::Bool(varint != 0),
+                Type::Int32 => Value::I32(varint as i32),
+                Type::Int64 => Value::I64(varint as i64),
+                Type::Uint32 => Value::U32(varint as u32),
+                Type::Uint64 => Value::U64(varint),
+                Type::Sint32 => Value::I32(((varint as u32) >> 1) as i32 ^ -((varint & 1) as i32)),
+                Type::Sint64 => Value::I64((varint >> 1) as i64 ^ -((varint & 1) as i64)),
+                _ => Value::Enum(varint as i32),
+            }
+        }
+    };
+    Ok(value)
+}
+
+/// Checks that a fixed-width value of `N` bytes is available.
+fn fixed<const N: usize, B: Buf>(buf: &mut B) -> Result<&mut B, DecodeError> {
+    if buf.remaining() < N {
+        return Err(DecodeError::new("buffer underflow"));
+    }
+    Ok(buf)
+}
+
+/// Writes a scalar value without its key, taking the lengths of messages from `sizes`.
+fn encode_scalar(ty: Type, value: &Value<'_>, buf: &mut impl BufMut, sizes: &mut SizeCache) {
+    match (ty, *value) {
+        (Type::Sint32, Value::I32(value)) => {
+            encode_varint(((value << 1) ^ (value >> 31)) as u32 as u64, buf)
+        }
+        (Type::Sint64, Value::I64(value)) => {
+            encode_varint(((value << 1) ^ (value >> 63)) as u64, buf)
+        }
+        (Type::Sfixed32, Value::I32(value)) => buf.put_i32_le(value),
+        (Type::Sfixed64, Value::I64(value)) => buf.put_i64_le(value),
+        (Type::Fixed32, Value::U32(value)) => buf.put_u32_le(value),
+        (Type::Fixed64, Value::U64(value)) => buf.put_u64_le(value),
+        (_, Value::F32(value)) => buf.put_f32_le(value),
+        (_, Value::F64(value)) => buf.put_f64_le(value),
+        (_, Value::Bool(value)) => encode_varint(u64::from(value), buf),
+        (_, Value::I32(value) | Value::Enum(value)) => encode_varint(value as i64 as u64, buf),
+        (_, Value::I64(value)) => encode_varint(value as u64, buf),
+        (_, Value::U32(value)) => encode_varint(u64::from(value), buf),
+        (_, Value::U64(value)) => encode_varint(value, buf),
+        (_, Value::String(value)) => {
+            encode_varint(value.len() as u64, buf);
+            buf.put_slice(value.as_bytes());
+        }
+        (_, Value::Bytes(value)) => {
+            encode_varint(value.len() as u64, buf);
+            buf.put_slice(value);
+        }
+        (_, Value::Message(message)) => {
+            encode_varint(sizes.next_len() as u64, buf);
+            message.encode_raw_cached(buf, sizes);
+        }
+        (_, Value::List(_) | Value::Map(_)) => {}
+    }
+}
+
+/// Returns the number of bytes `encode_scalar` writes, recording the lengths of messages in
+/// `sizes`.
+fn scalar_len(ty: Type, value: &Value<'_>, sizes: &mut SizeCache) -> usize {
+    match (ty, *value) {
+        (Type::Sint32, Value::I32(value)) => {
+            encoded_len_varint(((value << 1) ^ (value >> 31)) as u32 as u64)
+        }
+        (Type::Sint64, Value::I64(value)) => {
+            encoded_len_varint(((value << 1) ^ (value >> 63)) as u64)
+        }
+        (Type::Sfixed32 | Type::Fixed32, _) | (_, Value::F32(_)) => 4,
+        (Type::Sfixed64 | Type::Fixed64, _) | (_, Value::F64(_)) => 8,
+        (_, Value::Bool(_)) => 1,
+        (_, Value::I32(value) | Value::Enum(value)) => encoded_len_varint(value as i64 as u64),
+        (_, Value::I64(value)) => encoded_len_varint(value as u64),
+        (_, Value::U32(value)) => encoded_len_varint(u64::from(value)),
+        (_, Value::U64(value)) => encoded_len_varint(value),
+        (_, Value::String(value)) => encoded_len_varint(value.len() as u64) + value.len(),
+        (_, Value::Bytes(value)) => encoded_len_varint(value.len() as u64) + value.len(),
+        (_, Value::Message(message)) => {
+            let slot = sizes.reserve_slot();
+            let len = message.encoded_len_cached(sizes);
+            sizes.record(slot, len);
+            encoded_len_varint(len as u64) + len
+        }
+        (_, Value::List(_) | Value::Map(_)) => 0,
+    }
+}
+
+/// Returns the length of a packed field's values, which are scalars.
+fn packed_len(ty: Type, values: &[Value<'_>]) -> usize {
+    let mut sizes = SizeCache::new();
+    values
+        .iter()
+        .map(|value| scalar_len(ty, value, &mut sizes))
+        .sum()
+}
+
+/// Writes a singular value with its key.
+fn encode_field(
+    ty: Type,
+    tag: u32,
+    value: &Value<'_>,
+    buf: &mut impl BufMut,
+    sizes: &mut SizeCache,
+) {
+    match (ty, value) {
+        (Type::Group, Value::Message(message)) => {
+            encode_key(tag, WireType::StartGroup, buf);
+            message.encode_raw_cached(buf, sizes);
+            encode_key(tag, WireType::EndGroup, buf);
+        }
+        _ => {
+            encode_key(tag, scalar_wire_type(ty), buf);
+            encode_scalar(ty, value, buf, sizes);
+        }
+    }
+}
+
+/// Returns the number of bytes `encode_field` writes.
+fn field_len(ty: Type, tag: u32, value: &Value<'_>, sizes: &mut SizeCache) -> usize {
+    match (ty, value) {
+        (Type::Group, Value::Message(message)) => {
+            2 * key_len(tag) + message.encoded_len_cached(sizes)
+        }
+        _ => key_len(tag) + scalar_len(ty, value, sizes),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use defiant::DecodeLimit;
+
+    use crate::{
+        DescriptorProtoBuilder, FileDescriptorProtoBuilder, MessageOptions, MessageOptionsBuilder,
+    };
+
+    fn field<'arena>(
+        name: &'arena str,
+        number: i32,
+        label: Label,
+        ty: Type,
+        type_name: Option<&'arena str>,
+    ) -> FieldDescriptorProto<'arena> {
+        FieldDescriptorProto {
+            name: Some(name),
+            number: Some(number),
+            label: Some(label as i32),
+            r#type: Some(ty as i32),
+            type_name,
+            extendee: None,
+            default_value: None,
+            oneof_index: None,
+            json_name: None,
+            options: None,
+            proto3_optional: None,
+        }
+    }
+
+    fn message<'arena>(
+        arena: &'arena Arena,
+        name: &'arena str,
+        fields: &'arena [&'arena FieldDescriptorProto<'arena>],
+        nested_type: &'arena [&'arena DescriptorProto<'arena>],
+        options: Option<&'arena MessageOptions<'arena>>,
+    ) -> &'arena DescriptorProto<'arena> {
+        arena.alloc(DescriptorProto {
+            name: Some(name),
+            field: fields,
+            nested_type,
+            options,
+            ..DescriptorProtoBuilder::new_in(arena).freeze()
+        })
+    }
+
+    /// Describes, in package `test`:
+    ///
+    /// ```proto
+    /// message Header { string trace_id = 1; sint64 deadline = 2; }
+    /// message Request {
+    ///   Header header = 1;
+    ///   repeated int32 codes = 2;
+    ///   map<string, Header> routes = 3;
+    ///   oneof body { string text = 4; bytes data = 5; }
+    ///   repeated string tags = 6;
+    ///   double weight = 7;
+    ///   message RoutesEntry { string key = 1; Header value = 2; }
+    /// }
+    /// ```
+    fn files(arena: &Arena) -> &FileDescriptorSet<'_> {
+        let header = message(
+            arena,
+            "Header",
+            arena.alloc([
+                &*arena.alloc(field("trace_id", 1, Label::Optional, Type::String, None)),
+                &*arena.alloc(field("deadline", 2, Label::Optional, Type::Sint64, None)),
+            ]),
+            &[],
+            None,
+        );
+        let entry = message(
+            arena,
+            "RoutesEntry",
+            arena.alloc([
+                &*arena.alloc(field("key", 1, Label::Optional, Type::String, None)),
+                &*arena.alloc(field(
+                    "value",
+                    2,
+                    Label::Optional,
+                    Type::Message,
+                    Some(".test.Header"),
+                )),
+            ]),
+            &[],
+            Some(arena.alloc(MessageOptions {
+                map_entry: Some(true),
+                ..MessageOptionsBuilder::new_in(arena).freeze()
+            })),
+        );
+        let mut text = field("text", 4, Label::Optional, Type::String, None);
+        text.oneof_index = Some(0);
+        let mut data = field("data", 5, Label::Optional, Type::Bytes, None);
+        data.oneof_index = Some(0);
+        let request = message(
+            arena,
+            "Request",
+            arena.alloc([
+                &*arena.alloc(field(
+                    "header",
+                    1,
+                    Label::Optional,
+                    Type::Message,
+                    Some(".test.Header"),
+                )),
+                &*arena.alloc(field("codes", 2, Label::Repeated, Type::Int32, None)),
+                &*arena.alloc(field(
+                    "routes",
+                    3,
+                    Label::Repeated,
+                    Type::Message,
+                    Some(".test.Request.RoutesEntry"),
+                )),
+                &*arena.alloc(text),
+                &*arena.alloc(data),
+                &*arena.alloc(field("tags", 6, Label::Repeated, Type::String, None)),
+                &*arena.alloc(field("weight", 7, Label::Optional, Type::Double, None)),
+            ]),
+            arena.alloc([entry]),
+            None,
+        );
+        let file = arena.alloc(FileDescriptorProto {
+            name: Some("test.proto"),
+            package: Some("test"),
+            message_type: arena.alloc([header, request]),
+            syntax: Some("proto3"),
+            ..FileDescriptorProtoBuilder::new_in(arena).freeze()
+        });
+        arena.alloc(FileDescriptorSet {
+            file: arena.alloc([&*file]),
+        })
+    }
+
+    /// Describes, in package `test` and proto2 syntax:
+    ///
+    /// ```proto
+    /// message Node { required int32 id = 1; optional Node child = 2; repeated Node items = 3; }
+    /// ```
+    fn nodes(arena: &Arena) -> &FileDescriptorSet<'_> {
+        let node = message(
+            arena,
+            "Node",
+            arena.alloc([
+                &*arena.alloc(field("id", 1, Label::Required, Type::Int32, None)),
+                &*arena.alloc(field(
+                    "child",
+                    2,
+                    Label::Optional,
+                    Type::Message,
+                    Some(".test.Node"),
+                )),
+                &*arena.alloc(field(
+                    "items",
+                    3,
+                    Label::Repeated,
+                    Type::Message,
+                    Some(".test.Node"),
+                )),
+            ]),
+            &[],
+            None,
+        );
+        let file = arena.alloc(FileDescriptorProto {
+            name: Some("node.proto"),
+            package: Some("test"),
+            message_type: arena.alloc([node]),
+            syntax: Some("proto2"),
+            ..FileDescriptorProtoBuilder::new_in(arena).freeze()
+        });
+        arena.alloc(FileDescriptorSet {
+            file: arena.alloc([&*file]),
+        })
+    }
+
+    #[test]
+    fn check_dynamic_pool_resolution() {
+        let arena = Arena::new();
+        let inner = message(
+            &arena,
+            "Inner",
+            arena.alloc([&*arena.alloc(field("x", 1, Label::Optional, Type::Int32, None))]),
+            &[],
+            None,
+        );
+        let outer = message(
+            &arena,
+            "Outer",
+            arena.alloc([&*arena.alloc(field(
+                "inner",
+                1,
+                Label::Optional,
+                Type::Message,
+                Some("Inner"),
+            ))]),
+            arena.alloc([inner]),
+            None,
+        );
+        let file = arena.alloc(FileDescriptorProto {
+            name: Some("outer.proto"),
+            package: Some("test"),
+            message_type: arena.alloc([outer]),
+            syntax: Some("proto2"),
+            ..FileDescriptorProtoBuilder::new_in(&arena).freeze()
+        });
+        let files = arena.alloc(FileDescriptorSet {
+            file: arena.alloc([&*file]),
+        });
+
+        // Relative type names are only resolved through a pool
+        let field = outer.field[0];
+        let unresolved = MessageDescriptor::new(files, "test.Outer").unwrap();
+        assert_eq!(unresolved.message_type(field), None);
+
+        let pool = DescriptorPool::new(files).unwrap();
+        let outer_type = pool.message_descriptor(".test.Outer").unwrap();
+        assert_eq!(outer_type, unresolved);
+        let inner_type = outer_type.message_type(field).unwrap();
+        assert_eq!(inner_type.descriptor(), inner);
+        assert!(pool.message_descriptor("test.Outer.Inner.x").is_none());
+
+        // inner: {x: 7}
+        let wire = [0x0a, 0x02, 0x08, 0x07];
+        let decoded = DynamicMessage::decode(outer_type, &wire[..], &arena).unwrap();
+        let Some(Value::Message(nested)) = decoded.get("inner") else {
+            panic!("inner is not set: {decoded:?}");
+        };
+        assert_eq!(nested.descriptor(), inner_type);
+        assert_eq!(nested.get("x"), Some(Value::I32(7)));
+        assert_eq!(decoded.encode_to_vec(), wire);
+    }
+
+    #[test]
+    fn check_dynamic_descriptor_lookup() {
+        let arena = Arena::new();
+        let files = files(&arena);
+        let request = MessageDescriptor::new(files, "test.Request").unwrap();
+        assert_eq!(request.name(), "Request");
+        assert_eq!(
+            MessageDescriptor::new(files, ".test.Request.RoutesEntry")
+                .unwrap()
+                .name(),
+            "RoutesEntry"
+        );
+        assert!(MessageDescriptor::new(files, "Request").is_none());
+        assert!(MessageDescriptor::new(files, "test.Missing").is_none());
+        assert_eq!(request.field_by_number(7).unwrap().name, Some("weight"));
+    }
+
+    #[test]
+    fn check_dynamic_roundtrip() {
+        let arena = Arena::new();
+        let files = files(&arena);
+        let request_type = MessageDescriptor::new(files, "test.Request").unwrap();
+        let header_type = MessageDescriptor::new(files, "test.Header").unwrap();
+
+        let mut header = DynamicMessage::new(header_type, &arena);
+        header.set("trace_id", Value::String("abc")).unwrap();
+        header.set("deadline", Value::I64(-30)).unwrap();
+        let header = &*arena.alloc(header);
+
+        let mut request = DynamicMessage::new(request_type, &arena);
+        request.set("header", Value::Message(header)).unwrap();
+        request
+            .set("codes", Value::List(&[Value::I32(1), Value::I32(-2)]))
+            .unwrap();
+        request
+            .set(
+                "routes",
+                Value::Map(arena.alloc([
+                    (Value::String("b"), Value::Message(header)),
+                    (Value::String("a"), Value::Message(header)),
+                ])),
+            )
+            .unwrap();
+        request.set("text", Value::String("hello")).unwrap();
+        request
+            .set_by_number(6, Value::List(&[Value::String("x")]))
+            .unwrap();
+        // Implicit presence: the default is not written
+        request.set("weight", Value::F64(0.0)).unwrap();
+
+        let bytes = request.encode_to_vec();
+        assert_eq!(bytes.len(), request.encoded_len());
+
+        let decoded = DynamicMessage::decode(request_type, bytes.as_slice(), &arena).unwrap();
+        assert!(!decoded.has("weight"));
+        assert_eq!(decoded.get("text"), Some(Value::String("hello")));
+        assert_eq!(
+            decoded.get("codes"),
+            Some(Value::List(&[Value::I32(1), Value::I32(-2)]))
+        );
+        match decoded.get("header") {
+            Some(Value::Message(header)) => {
+                assert_eq!(header.get("deadline"), Some(Value::I64(-30)));
+            }
+            other => panic!("unexpected header: {other:?}"),
+        }
+        match decoded.get("routes") {
+            Some(Value::Map(routes)) => {
+                assert_eq!(routes.len(), 2);
+                assert_eq!(routes[0].0, Value::String("a"));
+                assert_eq!(routes[1].1, Value::Message(header));
+            }
+            other => panic!("unexpected routes: {other:?}"),
+        }
+        assert_eq!(decoded.encode_to_vec(), bytes);
+    }
+
+    #[test]
+    fn check_dynamic_merge_semantics() {
+        let arena = Arena::new();
+        let files = files(&arena);
+        let request_type = MessageDescriptor::new(files, "test.Request").unwrap();
+
+        let wire = [
+            0x10, 0x01, // codes: 1, unpacked
+            0x12, 0x02, 0x02, 0x03, // codes: [2, 3], packed
+            0x22, 0x01, b'a', // text: "a"
+            0x2a, 0x01, 0xff, // data: [0xff], replaces text
+            0x0a, 0x03, 0x0a, 0x01, b't', // header.trace_id: "t"
+            0x0a, 0x02, 0x10, 0x03, // header.deadline: -2, merged into header
+            0x48, 0x07, // field 9 is unknown
+        ];
+        let message = DynamicMessage::decode(request_type, &wire[..], &arena).unwrap();
+        assert_eq!(
+            message.get("codes"),
+            Some(Value::List(&[Value::I32(1), Value::I32(2), Value::I32(3)]))
+        );
+        assert!(!message.has("text"));
+        assert_eq!(message.get("data"), Some(Value::Bytes(&[0xff])));
+        match message.get("header") {
+            Some(Value::Message(header)) => {
+                assert_eq!(header.get("trace_id"), Some(Value::String("t")));
+                assert_eq!(header.get("deadline"), Some(Value::I64(-2)));
+            }
+            other => panic!("unexpected header: {other:?}"),
+        }
+        assert_eq!(message.unknown_fields(), &[0x48, 0x07]);
+
+        // Merging again copies the header, leaving values read earlier untouched
+        let before = message.get("header");
+        let mut message = message;
+        message.merge(&[0x0a, 0x02, 0x10, 0x05][..]).unwrap();
+        match message.get("header") {
+            Some(Value::Message(header)) => {
+                assert_eq!(header.get("trace_id"), Some(Value::String("t")));
+                assert_eq!(header.get("deadline"), Some(Value::I64(-3)));
+            }
+            other => panic!("unexpected header: {other:?}"),
+        }
+        match before {
+            Some(Value::Message(header)) => {
+                assert_eq!(header.get("deadline"), Some(Value::I64(-2)))
+            }
+            other => panic!("unexpected header: {other:?}"),
+        }
+
+        assert_eq!(message.clear("data"), Some(Value::Bytes(&[0xff])));
+        assert!(message.encode_to_vec().ends_with(&[0x48, 0x07]));
+    }
+
+    #[test]
+    fn check_dynamic_set_errors() {
+        let arena = Arena::new();
+        let files = files(&arena);
+        let request_type = MessageDescriptor::new(files, "test.Request").unwrap();
+        let mut request = DynamicMessage::new(request_type, &arena);
+
+        assert_eq!(
+            request.set("missing", Value::I32(1)),
+            Err(DynamicError::UnknownField(String::from("missing")))
+        );
+        assert!(request.set("codes", Value::I32(1)).is_err());
+        assert!(request.set("codes", Value::List(&[Value::I64(1)])).is_err());
+        assert!(request.set("weight", Value::F32(1.0)).is_err());
+        let wrong = arena.alloc(DynamicMessage::new(request_type, &arena));
+        assert!(request.set("header", Value::Message(wrong)).is_err());
+        assert!(request
+            .set(
+                "routes",
+                Value::Map(arena.alloc([(Value::I32(1), Value::Bool(true))]))
+            )
+            .is_err());
+        assert!(request.fields().next().is_none());
+    }
+
+    #[test]
+    fn check_dynamic_decode_errors() {
+        let arena = Arena::new();
+        let files = files(&arena);
+        let request_type = MessageDescriptor::new(files, "test.Request").unwrap();
+
+        // text has the wrong wire type
+        assert!(DynamicMessage::decode(request_type, &[0x20, 0x01][..], &arena).is_err());
+        // header is truncated
+        assert!(DynamicMessage::decode(request_type, &[0x0a, 0x05, 0x08][..], &arena).is_err());
+        // invalid UTF-8
+        assert!(DynamicMessage::decode(request_type, &[0x22, 0x01, 0xff][..], &arena).is_err());
+    }
+
+    #[test]
+    fn check_dynamic_deep_nesting() {
+        let arena = Arena::new();
+        let node_type = MessageDescriptor::new(nodes(&arena), "test.Node").unwrap();
+
+        let mut node = DynamicMessage::new(node_type, &arena);
+        node.set("id", Value::I32(0)).unwrap();
+        for id in 1..=200 {
+            let child = &*arena.alloc(node);
+            node = DynamicMessage::new(node_type, &arena);
+            node.set("id", Value::I32(id)).unwrap();
+            node.set("child", Value::Message(child)).unwrap();
+        }
+
+        let bytes = node.encode_to_vec();
+        assert_eq!(bytes.len(), node.encoded_len());
+        let mut raw = Vec::new();
+        node.encode_raw(&mut raw);
+        assert_eq!(raw, bytes);
+
+        let options = DecodeOptions::new().recursion_limit(500);
+        let decoded =
+            DynamicMessage::decode_with_options(node_type, bytes.as_slice(), &arena, options)
+                .unwrap();
+        assert_eq!(decoded, node);
+        assert_eq!(decoded.encode_to_vec(), bytes);
+
+        // The default recursion limit is 100
+        assert!(DynamicMessage::decode(node_type, bytes.as_slice(), &arena).is_err());
+    }
+
+    #[test]
+    fn check_dynamic_decode_options() {
+        let arena = Arena::new();
+        let request_type = MessageDescriptor::new(files(&arena), "test.Request").unwrap();
+
+        // codes: [1, 2, 3], packed; tags: "abcd"
+        let wire = [
+            0x12, 0x03, 0x01, 0x02, 0x03, 0x32, 0x04, b'a', b'b', b'c', b'd',
+        ];
+        let decode =
+            |options| DynamicMessage::decode_with_options(request_type, &wire[..], &arena, options);
+        assert!(decode(DecodeOptions::new().max_repeated_len(3).max_string_len(4)).is_ok());
+        let limit = |options| decode(options).unwrap_err().limit_exceeded();
+        assert_eq!(
+            limit(DecodeOptions::new().max_repeated_len(2)),
+            Some(DecodeLimit::RepeatedLength)
+        );
+        assert_eq!(
+            limit(DecodeOptions::new().max_string_len(3)),
+            Some(DecodeLimit::StringLength)
+        );
+        assert_eq!(
+            limit(DecodeOptions::new().max_input_len(10)),
+            Some(DecodeLimit::InputLength)
+        );
+    }
+
+    #[test]
+    fn check_dynamic_required_fields() {
+        let arena = Arena::new();
+        let node_type = MessageDescriptor::new(nodes(&arena), "test.Node").unwrap();
+
+        // id: 1, child: {}, items: [{id: 2}, {}]
+        let wire = [0x08, 0x01, 0x12, 0x00, 0x1a, 0x02, 0x08, 0x02, 0x1a, 0x00];
+        let node = DynamicMessage::decode(node_type, &wire[..], &arena).unwrap();
+        assert_eq!(node.missing_required_fields(), ["child.id", "items[1].id"]);
+
+        let strict = DecodeOptions::new().check_required(true);
+        let error =
+            DynamicMessage::decode_with_options(node_type, &wire[..], &arena, strict).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "failed to decode Protobuf message: missing required fields: child.id, items[1].id"
+        );
+        assert!(DynamicMessage::decode_with_options(node_type, &wire[..2], &arena, strict).is_ok());
+    }
+}
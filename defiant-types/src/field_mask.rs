@@ -0,0 +1,66 @@
+use super::*;
+use defiant::{FieldSet, Projection, ProjectionError};
+
+impl FieldMask<'_> {
+    /// Builds a [`Projection`] which decodes only the fields named by this mask.
+    ///
+    /// Paths are resolved against `M`, so `"header.trace_id"` selects the `trace_id` field of
+    /// the message in `M`'s `header` field. Fails if any path does not name a field.
+    pub fn to_projection<M: FieldSet>(&self) -> Result<Projection, ProjectionError> {
+        Projection::from_paths::<M, _, _>(self.paths)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use defiant::Arena;
+
+    #[test]
+    fn check_field_mask_projection() {
+        let arena = Arena::new();
+        let mask = FieldMask {
+            paths: &["options.deprecated", "name"],
+        };
+        let projection = mask.to_projection::<EnumDescriptorProto>().unwrap();
+        assert_eq!(
+            projection,
+            Projection::new()
+                .field(1)
+                .nested(3, Projection::new().field(3))
+        );
+
+        let options = EnumOptions {
+            allow_alias: Some(true),
+            deprecated: Some(true),
+            ..EnumOptionsBuilder::new_in(&arena).freeze()
+        };
+        let value = EnumValueDescriptorProto {
+            name: Some("A"),
+            number: Some(1),
+            options: None,
+        };
+        let message = EnumDescriptorProto {
+            name: Some("Kind"),
+            value: &[&value],
+            options: Some(&options),
+            ..EnumDescriptorProtoBuilder::new_in(&arena).freeze()
+        };
+        let bytes = message.encode_to_vec();
+
+        let projected =
+            EnumDescriptorProtoBuilder::decode_projected(bytes.as_slice(), &arena, &projection)
+                .unwrap()
+                .freeze();
+        assert_eq!(projected.name, Some("Kind"));
+        assert!(projected.value.is_empty());
+        assert_eq!(projected.options.unwrap().deprecated, Some(true));
+        assert_eq!(projected.options.unwrap().allow_alias, None);
+
+        let mask = FieldMask {
+            paths: &["name.length"],
+        };
+        let error = mask.to_projection::<EnumDescriptorProto>().unwrap_err();
+        assert_eq!(error.path(), "name.length");
+    }
+}
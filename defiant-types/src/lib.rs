@@ -74,6 +74,13 @@ mod any;
 mod duration;
 pub use duration::DurationError;
 
+mod descriptor_pool;
+pub use descriptor_pool::{Definition, DescriptorError, DescriptorPool};
+
+mod field_mask;
+
+pub mod dynamic;
+
 mod timestamp;
 pub use timestamp::TimestampError;
 
@@ -3,6 +3,7 @@
 /// files it parses.
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Clone, PartialEq, ::defiant::View)]
+#[defiant(full_name = "google.protobuf.FileDescriptorSet")]
 pub struct FileDescriptorSet<'arena> {
     #[defiant(message, repeated, tag = "1")]
     pub file: &'arena [&'arena FileDescriptorProto<'arena>],
@@ -10,6 +11,7 @@ pub struct FileDescriptorSet<'arena> {
 /// Describes a complete .proto file.
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Clone, PartialEq, ::defiant::View)]
+#[defiant(full_name = "google.protobuf.FileDescriptorProto")]
 pub struct FileDescriptorProto<'arena> {
     /// file name, relative to root of source tree
     #[defiant(string, optional, tag = "1")]
@@ -45,13 +47,19 @@ pub struct FileDescriptorProto<'arena> {
     #[defiant(message, optional, tag = "9")]
     pub source_code_info: ::core::option::Option<&'arena SourceCodeInfo<'arena>>,
     /// The syntax of the proto file.
-    /// The supported values are "proto2" and "proto3".
+    /// The supported values are "proto2", "proto3", and "editions".
+    ///
+    /// If `edition` is present, this value must be "editions".
     #[defiant(string, optional, tag = "12")]
     pub syntax: ::core::option::Option<&'arena str>,
+    /// The edition of the proto file.
+    #[defiant(enumeration = "Edition", optional, tag = "14")]
+    pub edition: ::core::option::Option<i32>,
 }
 /// Describes a message type.
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Clone, PartialEq, ::defiant::View)]
+#[defiant(full_name = "google.protobuf.DescriptorProto")]
 pub struct DescriptorProto<'arena> {
     #[defiant(string, optional, tag = "1")]
     pub name: ::core::option::Option<&'arena str>,
@@ -80,6 +88,7 @@ pub struct DescriptorProto<'arena> {
 pub mod descriptor_proto {
     #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
     #[derive(Clone, PartialEq, ::defiant::View)]
+    #[defiant(full_name = "google.protobuf.DescriptorProto.ExtensionRange")]
     pub struct ExtensionRange<'arena> {
         /// Inclusive.
         #[defiant(int32, optional, tag = "1")]
@@ -95,6 +104,7 @@ pub mod descriptor_proto {
     /// not overlap.
     #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
     #[derive(Clone, Copy, PartialEq, Eq, Hash, ::defiant::View)]
+    #[defiant(full_name = "google.protobuf.DescriptorProto.ReservedRange")]
     pub struct ReservedRange {
         /// Inclusive.
         #[defiant(int32, optional, tag = "1")]
@@ -106,14 +116,21 @@ pub mod descriptor_proto {
 }
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Clone, PartialEq, ::defiant::View)]
+#[defiant(full_name = "google.protobuf.ExtensionRangeOptions")]
 pub struct ExtensionRangeOptions<'arena> {
+    /// Any features defined in the specific edition.
+    #[defiant(message, optional, tag = "50")]
+    pub features: ::core::option::Option<FeatureSet>,
     /// The parser stores options it doesn't recognize here. See above.
     #[defiant(message, repeated, tag = "999")]
     pub uninterpreted_option: &'arena [&'arena UninterpretedOption<'arena>],
+    #[defiant(unknown_fields)]
+    pub unknown_fields: ::defiant::UnknownFields<'arena>,
 }
 /// Describes a field within a message.
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Clone, PartialEq, ::defiant::View)]
+#[defiant(full_name = "google.protobuf.FieldDescriptorProto")]
 pub struct FieldDescriptorProto<'arena> {
     #[defiant(string, optional, tag = "1")]
     pub name: ::core::option::Option<&'arena str>,
@@ -183,39 +200,58 @@ pub struct FieldDescriptorProto<'arena> {
 pub mod field_descriptor_proto {
     #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
     #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::defiant::Enumeration)]
+    #[defiant(full_name = "google.protobuf.FieldDescriptorProto.Type")]
     #[repr(i32)]
     pub enum Type {
         /// 0 is reserved for errors.
         /// Order is weird for historical reasons.
+        #[defiant(name = "TYPE_DOUBLE")]
         Double = 1,
+        #[defiant(name = "TYPE_FLOAT")]
         Float = 2,
         /// Not ZigZag encoded.  Negative numbers take 10 bytes.  Use TYPE_SINT64 if
         /// negative values are likely.
+        #[defiant(name = "TYPE_INT64")]
         Int64 = 3,
+        #[defiant(name = "TYPE_UINT64")]
         Uint64 = 4,
         /// Not ZigZag encoded.  Negative numbers take 10 bytes.  Use TYPE_SINT32 if
         /// negative values are likely.
+        #[defiant(name = "TYPE_INT32")]
         Int32 = 5,
+        #[defiant(name = "TYPE_FIXED64")]
         Fixed64 = 6,
+        #[defiant(name = "TYPE_FIXED32")]
         Fixed32 = 7,
+        #[defiant(name = "TYPE_BOOL")]
         Bool = 8,
+        #[defiant(name = "TYPE_STRING")]
         String = 9,
         /// Tag-delimited aggregate.
         /// Group type is deprecated and not supported in proto3. However, Proto3
         /// implementations should still be able to parse the group wire format and
         /// treat group fields as unknown fields.
+        #[defiant(name = "TYPE_GROUP")]
         Group = 10,
         /// Length-delimited aggregate.
+        #[defiant(name = "TYPE_MESSAGE")]
         Message = 11,
         /// New in version 2.
+        #[defiant(name = "TYPE_BYTES")]
         Bytes = 12,
+        #[defiant(name = "TYPE_UINT32")]
         Uint32 = 13,
+        #[defiant(name = "TYPE_ENUM")]
         Enum = 14,
+        #[defiant(name = "TYPE_SFIXED32")]
         Sfixed32 = 15,
+        #[defiant(name = "TYPE_SFIXED64")]
         Sfixed64 = 16,
         /// Uses ZigZag encoding.
+        #[defiant(name = "TYPE_SINT32")]
         Sint32 = 17,
         /// Uses ZigZag encoding.
+        #[defiant(name = "TYPE_SINT64")]
         Sint64 = 18,
     }
     impl Type {
@@ -272,11 +308,15 @@ pub mod field_descriptor_proto {
     }
     #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
     #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::defiant::Enumeration)]
+    #[defiant(full_name = "google.protobuf.FieldDescriptorProto.Label")]
     #[repr(i32)]
     pub enum Label {
         /// 0 is reserved for errors
+        #[defiant(name = "LABEL_OPTIONAL")]
         Optional = 1,
+        #[defiant(name = "LABEL_REQUIRED")]
         Required = 2,
+        #[defiant(name = "LABEL_REPEATED")]
         Repeated = 3,
     }
     impl Label {
@@ -305,6 +345,7 @@ pub mod field_descriptor_proto {
 /// Describes a oneof.
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Clone, PartialEq, ::defiant::View)]
+#[defiant(full_name = "google.protobuf.OneofDescriptorProto")]
 pub struct OneofDescriptorProto<'arena> {
     #[defiant(string, optional, tag = "1")]
     pub name: ::core::option::Option<&'arena str>,
@@ -314,6 +355,7 @@ pub struct OneofDescriptorProto<'arena> {
 /// Describes an enum type.
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Clone, PartialEq, ::defiant::View)]
+#[defiant(full_name = "google.protobuf.EnumDescriptorProto")]
 pub struct EnumDescriptorProto<'arena> {
     #[defiant(string, optional, tag = "1")]
     pub name: ::core::option::Option<&'arena str>,
@@ -341,6 +383,7 @@ pub mod enum_descriptor_proto {
     /// domain.
     #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
     #[derive(Clone, Copy, PartialEq, Eq, Hash, ::defiant::View)]
+    #[defiant(full_name = "google.protobuf.EnumDescriptorProto.EnumReservedRange")]
     pub struct EnumReservedRange {
         /// Inclusive.
         #[defiant(int32, optional, tag = "1")]
@@ -353,6 +396,7 @@ pub mod enum_descriptor_proto {
 /// Describes a value within an enum.
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Clone, PartialEq, ::defiant::View)]
+#[defiant(full_name = "google.protobuf.EnumValueDescriptorProto")]
 pub struct EnumValueDescriptorProto<'arena> {
     #[defiant(string, optional, tag = "1")]
     pub name: ::core::option::Option<&'arena str>,
@@ -364,6 +408,7 @@ pub struct EnumValueDescriptorProto<'arena> {
 /// Describes a service.
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Clone, PartialEq, ::defiant::View)]
+#[defiant(full_name = "google.protobuf.ServiceDescriptorProto")]
 pub struct ServiceDescriptorProto<'arena> {
     #[defiant(string, optional, tag = "1")]
     pub name: ::core::option::Option<&'arena str>,
@@ -375,6 +420,7 @@ pub struct ServiceDescriptorProto<'arena> {
 /// Describes a method of a service.
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Clone, PartialEq, ::defiant::View)]
+#[defiant(full_name = "google.protobuf.MethodDescriptorProto")]
 pub struct MethodDescriptorProto<'arena> {
     #[defiant(string, optional, tag = "1")]
     pub name: ::core::option::Option<&'arena str>,
@@ -427,6 +473,7 @@ pub struct MethodDescriptorProto<'arena> {
 ///   to automatically assign option numbers.
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Clone, PartialEq, ::defiant::View)]
+#[defiant(full_name = "google.protobuf.FileOptions")]
 pub struct FileOptions<'arena> {
     /// Sets the Java package where classes generated from this .proto will be
     /// placed.  By default, the proto package is used, but this is often
@@ -531,25 +578,34 @@ pub struct FileOptions<'arena> {
     /// determining the ruby package.
     #[defiant(string, optional, tag = "45")]
     pub ruby_package: ::core::option::Option<&'arena str>,
+    /// Any features defined in the specific edition.
+    #[defiant(message, optional, tag = "50")]
+    pub features: ::core::option::Option<FeatureSet>,
     /// The parser stores options it doesn't recognize here.
     /// See the documentation for the "Options" section above.
     #[defiant(message, repeated, tag = "999")]
     pub uninterpreted_option: &'arena [&'arena UninterpretedOption<'arena>],
+    #[defiant(unknown_fields)]
+    pub unknown_fields: ::defiant::UnknownFields<'arena>,
 }
 /// Nested message and enum types in `FileOptions`.
 pub mod file_options {
     /// Generated classes can be optimized for speed or code size.
     #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
     #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::defiant::Enumeration)]
+    #[defiant(full_name = "google.protobuf.FileOptions.OptimizeMode")]
     #[repr(i32)]
     pub enum OptimizeMode {
         /// Generate complete code for parsing, serialization,
+        #[defiant(name = "SPEED")]
         Speed = 1,
         /// etc.
         ///
         /// Use ReflectionOps to implement these methods.
+        #[defiant(name = "CODE_SIZE")]
         CodeSize = 2,
         /// Generate code using MessageLite and the lite runtime.
+        #[defiant(name = "LITE_RUNTIME")]
         LiteRuntime = 3,
     }
     impl OptimizeMode {
@@ -577,6 +633,7 @@ pub mod file_options {
 }
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Clone, PartialEq, ::defiant::View)]
+#[defiant(full_name = "google.protobuf.MessageOptions")]
 pub struct MessageOptions<'arena> {
     /// Set true to use the old proto1 MessageSet wire format for extensions.
     /// This is provided for backwards-compatibility with the MessageSet wire
@@ -632,12 +689,18 @@ pub struct MessageOptions<'arena> {
     /// parser.
     #[defiant(bool, optional, tag = "7")]
     pub map_entry: ::core::option::Option<bool>,
+    /// Any features defined in the specific edition.
+    #[defiant(message, optional, tag = "12")]
+    pub features: ::core::option::Option<FeatureSet>,
     /// The parser stores options it doesn't recognize here. See above.
     #[defiant(message, repeated, tag = "999")]
     pub uninterpreted_option: &'arena [&'arena UninterpretedOption<'arena>],
+    #[defiant(unknown_fields)]
+    pub unknown_fields: ::defiant::UnknownFields<'arena>,
 }
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Clone, PartialEq, ::defiant::View)]
+#[defiant(full_name = "google.protobuf.FieldOptions")]
 pub struct FieldOptions<'arena> {
     /// The ctype option instructs the C++ code generator to use a different
     /// representation of the field than it normally would.  See the specific
@@ -703,19 +766,28 @@ pub struct FieldOptions<'arena> {
     /// For Google-internal migration only. Do not use.
     #[defiant(bool, optional, tag = "10", default = "false")]
     pub weak: ::core::option::Option<bool>,
+    /// Any features defined in the specific edition.
+    #[defiant(message, optional, tag = "21")]
+    pub features: ::core::option::Option<FeatureSet>,
     /// The parser stores options it doesn't recognize here. See above.
     #[defiant(message, repeated, tag = "999")]
     pub uninterpreted_option: &'arena [&'arena UninterpretedOption<'arena>],
+    #[defiant(unknown_fields)]
+    pub unknown_fields: ::defiant::UnknownFields<'arena>,
 }
 /// Nested message and enum types in `FieldOptions`.
 pub mod field_options {
     #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
     #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::defiant::Enumeration)]
+    #[defiant(full_name = "google.protobuf.FieldOptions.CType")]
     #[repr(i32)]
     pub enum CType {
         /// Default mode.
+        #[defiant(name = "STRING")]
         String = 0,
+        #[defiant(name = "CORD")]
         Cord = 1,
+        #[defiant(name = "STRING_PIECE")]
         StringPiece = 2,
     }
     impl CType {
@@ -742,13 +814,17 @@ pub mod field_options {
     }
     #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
     #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::defiant::Enumeration)]
+    #[defiant(full_name = "google.protobuf.FieldOptions.JsType")]
     #[repr(i32)]
     pub enum JsType {
         /// Use the default type.
+        #[defiant(name = "JS_NORMAL")]
         JsNormal = 0,
         /// Use JavaScript strings.
+        #[defiant(name = "JS_STRING")]
         JsString = 1,
         /// Use JavaScript numbers.
+        #[defiant(name = "JS_NUMBER")]
         JsNumber = 2,
     }
     impl JsType {
@@ -776,13 +852,20 @@ pub mod field_options {
 }
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Clone, PartialEq, ::defiant::View)]
+#[defiant(full_name = "google.protobuf.OneofOptions")]
 pub struct OneofOptions<'arena> {
+    /// Any features defined in the specific edition.
+    #[defiant(message, optional, tag = "1")]
+    pub features: ::core::option::Option<FeatureSet>,
     /// The parser stores options it doesn't recognize here. See above.
     #[defiant(message, repeated, tag = "999")]
     pub uninterpreted_option: &'arena [&'arena UninterpretedOption<'arena>],
+    #[defiant(unknown_fields)]
+    pub unknown_fields: ::defiant::UnknownFields<'arena>,
 }
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Clone, PartialEq, ::defiant::View)]
+#[defiant(full_name = "google.protobuf.EnumOptions")]
 pub struct EnumOptions<'arena> {
     /// Set this option to true to allow mapping different tag names to the same
     /// value.
@@ -794,12 +877,18 @@ pub struct EnumOptions<'arena> {
     /// is a formalization for deprecating enums.
     #[defiant(bool, optional, tag = "3", default = "false")]
     pub deprecated: ::core::option::Option<bool>,
+    /// Any features defined in the specific edition.
+    #[defiant(message, optional, tag = "7")]
+    pub features: ::core::option::Option<FeatureSet>,
     /// The parser stores options it doesn't recognize here. See above.
     #[defiant(message, repeated, tag = "999")]
     pub uninterpreted_option: &'arena [&'arena UninterpretedOption<'arena>],
+    #[defiant(unknown_fields)]
+    pub unknown_fields: ::defiant::UnknownFields<'arena>,
 }
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Clone, PartialEq, ::defiant::View)]
+#[defiant(full_name = "google.protobuf.EnumValueOptions")]
 pub struct EnumValueOptions<'arena> {
     /// Is this enum value deprecated?
     /// Depending on the target platform, this can emit Deprecated annotations
@@ -807,12 +896,18 @@ pub struct EnumValueOptions<'arena> {
     /// this is a formalization for deprecating enum values.
     #[defiant(bool, optional, tag = "1", default = "false")]
     pub deprecated: ::core::option::Option<bool>,
+    /// Any features defined in the specific edition.
+    #[defiant(message, optional, tag = "2")]
+    pub features: ::core::option::Option<FeatureSet>,
     /// The parser stores options it doesn't recognize here. See above.
     #[defiant(message, repeated, tag = "999")]
     pub uninterpreted_option: &'arena [&'arena UninterpretedOption<'arena>],
+    #[defiant(unknown_fields)]
+    pub unknown_fields: ::defiant::UnknownFields<'arena>,
 }
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Clone, PartialEq, ::defiant::View)]
+#[defiant(full_name = "google.protobuf.ServiceOptions")]
 pub struct ServiceOptions<'arena> {
     // Note:  Field numbers 1 through 32 are reserved for Google's internal RPC
     // framework.  We apologize for hoarding these numbers to ourselves, but
@@ -825,12 +920,18 @@ pub struct ServiceOptions<'arena> {
     /// this is a formalization for deprecating services.
     #[defiant(bool, optional, tag = "33", default = "false")]
     pub deprecated: ::core::option::Option<bool>,
+    /// Any features defined in the specific edition.
+    #[defiant(message, optional, tag = "34")]
+    pub features: ::core::option::Option<FeatureSet>,
     /// The parser stores options it doesn't recognize here. See above.
     #[defiant(message, repeated, tag = "999")]
     pub uninterpreted_option: &'arena [&'arena UninterpretedOption<'arena>],
+    #[defiant(unknown_fields)]
+    pub unknown_fields: ::defiant::UnknownFields<'arena>,
 }
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Clone, PartialEq, ::defiant::View)]
+#[defiant(full_name = "google.protobuf.MethodOptions")]
 pub struct MethodOptions<'arena> {
     // Note:  Field numbers 1 through 32 are reserved for Google's internal RPC
     // framework.  We apologize for hoarding these numbers to ourselves, but
@@ -845,9 +946,14 @@ pub struct MethodOptions<'arena> {
     pub deprecated: ::core::option::Option<bool>,
     #[defiant(enumeration = "method_options::IdempotencyLevel", optional, tag = "34", default = "IdempotencyUnknown")]
     pub idempotency_level: ::core::option::Option<i32>,
+    /// Any features defined in the specific edition.
+    #[defiant(message, optional, tag = "35")]
+    pub features: ::core::option::Option<FeatureSet>,
     /// The parser stores options it doesn't recognize here. See above.
     #[defiant(message, repeated, tag = "999")]
     pub uninterpreted_option: &'arena [&'arena UninterpretedOption<'arena>],
+    #[defiant(unknown_fields)]
+    pub unknown_fields: ::defiant::UnknownFields<'arena>,
 }
 /// Nested message and enum types in `MethodOptions`.
 pub mod method_options {
@@ -856,12 +962,16 @@ pub mod method_options {
     /// methods, and PUT verb for idempotent methods instead of the default POST.
     #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
     #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::defiant::Enumeration)]
+    #[defiant(full_name = "google.protobuf.MethodOptions.IdempotencyLevel")]
     #[repr(i32)]
     pub enum IdempotencyLevel {
+        #[defiant(name = "IDEMPOTENCY_UNKNOWN")]
         IdempotencyUnknown = 0,
         /// implies idempotent
+        #[defiant(name = "NO_SIDE_EFFECTS")]
         NoSideEffects = 1,
         /// idempotent, but may have side effects
+        #[defiant(name = "IDEMPOTENT")]
         Idempotent = 2,
     }
     impl IdempotencyLevel {
@@ -895,6 +1005,7 @@ pub mod method_options {
 /// in them.
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Clone, PartialEq, ::defiant::View)]
+#[defiant(full_name = "google.protobuf.UninterpretedOption")]
 pub struct UninterpretedOption<'arena> {
     #[defiant(message, repeated, tag = "2")]
     pub name: &'arena [&'arena uninterpreted_option::NamePart<'arena>],
@@ -922,6 +1033,7 @@ pub mod uninterpreted_option {
     /// "foo.(bar.baz).qux".
     #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
     #[derive(Clone, PartialEq, Eq, Hash, ::defiant::View)]
+    #[defiant(full_name = "google.protobuf.UninterpretedOption.NamePart")]
     pub struct NamePart<'arena> {
         #[defiant(string, required, tag = "1")]
         pub name_part: &'arena str,
@@ -929,6 +1041,279 @@ pub mod uninterpreted_option {
         pub is_extension: bool,
     }
 }
+/// TODO Enums in C++ gencode (and potentially other languages) are
+/// not well scoped.  This means that each of the feature enums below can clash
+/// with each other.  The short names we've chosen maximize call-site
+/// readability, but leave us very open to this scenario.  A future feature will
+/// be designed and implemented to handle this, hopefully before we ever hit a
+/// conflict here.
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, ::defiant::View)]
+#[defiant(full_name = "google.protobuf.FeatureSet")]
+pub struct FeatureSet {
+    #[defiant(enumeration = "feature_set::FieldPresence", optional, tag = "1")]
+    pub field_presence: ::core::option::Option<i32>,
+    #[defiant(enumeration = "feature_set::EnumType", optional, tag = "2")]
+    pub enum_type: ::core::option::Option<i32>,
+    #[defiant(enumeration = "feature_set::RepeatedFieldEncoding", optional, tag = "3")]
+    pub repeated_field_encoding: ::core::option::Option<i32>,
+    #[defiant(enumeration = "feature_set::Utf8Validation", optional, tag = "4")]
+    pub utf8_validation: ::core::option::Option<i32>,
+    #[defiant(enumeration = "feature_set::MessageEncoding", optional, tag = "5")]
+    pub message_encoding: ::core::option::Option<i32>,
+    #[defiant(enumeration = "feature_set::JsonFormat", optional, tag = "6")]
+    pub json_format: ::core::option::Option<i32>,
+}
+/// Nested message and enum types in `FeatureSet`.
+pub mod feature_set {
+    #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::defiant::Enumeration)]
+    #[defiant(full_name = "google.protobuf.FeatureSet.FieldPresence")]
+    #[repr(i32)]
+    pub enum FieldPresence {
+        #[defiant(name = "FIELD_PRESENCE_UNKNOWN")]
+        Unknown = 0,
+        #[defiant(name = "EXPLICIT")]
+        Explicit = 1,
+        #[defiant(name = "IMPLICIT")]
+        Implicit = 2,
+        #[defiant(name = "LEGACY_REQUIRED")]
+        LegacyRequired = 3,
+    }
+    impl FieldPresence {
+        /// String value of the enum field names used in the ProtoBuf definition.
+        ///
+        /// The values are not transformed in any way and thus are considered stable
+        /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+        pub fn as_str_name(&self) -> &'static str {
+            match self {
+                Self::Unknown => "FIELD_PRESENCE_UNKNOWN",
+                Self::Explicit => "EXPLICIT",
+                Self::Implicit => "IMPLICIT",
+                Self::LegacyRequired => "LEGACY_REQUIRED",
+            }
+        }
+        /// Creates an enum from field names used in the ProtoBuf definition.
+        pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+            match value {
+                "FIELD_PRESENCE_UNKNOWN" => Some(Self::Unknown),
+                "EXPLICIT" => Some(Self::Explicit),
+                "IMPLICIT" => Some(Self::Implicit),
+                "LEGACY_REQUIRED" => Some(Self::LegacyRequired),
+                _ => None,
+            }
+        }
+    }
+    #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::defiant::Enumeration)]
+    #[defiant(full_name = "google.protobuf.FeatureSet.EnumType")]
+    #[repr(i32)]
+    pub enum EnumType {
+        #[defiant(name = "ENUM_TYPE_UNKNOWN")]
+        Unknown = 0,
+        #[defiant(name = "OPEN")]
+        Open = 1,
+        #[defiant(name = "CLOSED")]
+        Closed = 2,
+    }
+    impl EnumType {
+        /// String value of the enum field names used in the ProtoBuf definition.
+        ///
+        /// The values are not transformed in any way and thus are considered stable
+        /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+        pub fn as_str_name(&self) -> &'static str {
+            match self {
+                Self::Unknown => "ENUM_TYPE_UNKNOWN",
+                Self::Open => "OPEN",
+                Self::Closed => "CLOSED",
+            }
+        }
+        /// Creates an enum from field names used in the ProtoBuf definition.
+        pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+            match value {
+                "ENUM_TYPE_UNKNOWN" => Some(Self::Unknown),
+                "OPEN" => Some(Self::Open),
+                "CLOSED" => Some(Self::Closed),
+                _ => None,
+            }
+        }
+    }
+    #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::defiant::Enumeration)]
+    #[defiant(full_name = "google.protobuf.FeatureSet.RepeatedFieldEncoding")]
+    #[repr(i32)]
+    pub enum RepeatedFieldEncoding {
+        #[defiant(name = "REPEATED_FIELD_ENCODING_UNKNOWN")]
+        Unknown = 0,
+        #[defiant(name = "PACKED")]
+        Packed = 1,
+        #[defiant(name = "EXPANDED")]
+        Expanded = 2,
+    }
+    impl RepeatedFieldEncoding {
+        /// String value of the enum field names used in the ProtoBuf definition.
+        ///
+        /// The values are not transformed in any way and thus are considered stable
+        /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+        pub fn as_str_name(&self) -> &'static str {
+            match self {
+                Self::Unknown => "REPEATED_FIELD_ENCODING_UNKNOWN",
+                Self::Packed => "PACKED",
+                Self::Expanded => "EXPANDED",
+            }
+        }
+        /// Creates an enum from field names used in the ProtoBuf definition.
+        pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+            match value {
+                "REPEATED_FIELD_ENCODING_UNKNOWN" => Some(Self::Unknown),
+                "PACKED" => Some(Self::Packed),
+                "EXPANDED" => Some(Self::Expanded),
+                _ => None,
+            }
+        }
+    }
+    #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::defiant::Enumeration)]
+    #[defiant(full_name = "google.protobuf.FeatureSet.Utf8Validation")]
+    #[repr(i32)]
+    pub enum Utf8Validation {
+        #[defiant(name = "UTF8_VALIDATION_UNKNOWN")]
+        Unknown = 0,
+        #[defiant(name = "VERIFY")]
+        Verify = 2,
+        #[defiant(name = "NONE")]
+        None = 3,
+    }
+    impl Utf8Validation {
+        /// String value of the enum field names used in the ProtoBuf definition.
+        ///
+        /// The values are not transformed in any way and thus are considered stable
+        /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+        pub fn as_str_name(&self) -> &'static str {
+            match self {
+                Self::Unknown => "UTF8_VALIDATION_UNKNOWN",
+                Self::Verify => "VERIFY",
+                Self::None => "NONE",
+            }
+        }
+        /// Creates an enum from field names used in the ProtoBuf definition.
+        pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+            match value {
+                "UTF8_VALIDATION_UNKNOWN" => Some(Self::Unknown),
+                "VERIFY" => Some(Self::Verify),
+                "NONE" => Some(Self::None),
+                _ => None,
+            }
+        }
+    }
+    #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::defiant::Enumeration)]
+    #[defiant(full_name = "google.protobuf.FeatureSet.MessageEncoding")]
+    #[repr(i32)]
+    pub enum MessageEncoding {
+        #[defiant(name = "MESSAGE_ENCODING_UNKNOWN")]
+        Unknown = 0,
+        #[defiant(name = "LENGTH_PREFIXED")]
+        LengthPrefixed = 1,
+        #[defiant(name = "DELIMITED")]
+        Delimited = 2,
+    }
+    impl MessageEncoding {
+        /// String value of the enum field names used in the ProtoBuf definition.
+        ///
+        /// The values are not transformed in any way and thus are considered stable
+        /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+        pub fn as_str_name(&self) -> &'static str {
+            match self {
+                Self::Unknown => "MESSAGE_ENCODING_UNKNOWN",
+                Self::LengthPrefixed => "LENGTH_PREFIXED",
+                Self::Delimited => "DELIMITED",
+            }
+        }
+        /// Creates an enum from field names used in the ProtoBuf definition.
+        pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+            match value {
+                "MESSAGE_ENCODING_UNKNOWN" => Some(Self::Unknown),
+                "LENGTH_PREFIXED" => Some(Self::LengthPrefixed),
+                "DELIMITED" => Some(Self::Delimited),
+                _ => None,
+            }
+        }
+    }
+    #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::defiant::Enumeration)]
+    #[defiant(full_name = "google.protobuf.FeatureSet.JsonFormat")]
+    #[repr(i32)]
+    pub enum JsonFormat {
+        #[defiant(name = "JSON_FORMAT_UNKNOWN")]
+        Unknown = 0,
+        #[defiant(name = "ALLOW")]
+        Allow = 1,
+        #[defiant(name = "LEGACY_BEST_EFFORT")]
+        LegacyBestEffort = 2,
+    }
+    impl JsonFormat {
+        /// String value of the enum field names used in the ProtoBuf definition.
+        ///
+        /// The values are not transformed in any way and thus are considered stable
+        /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+        pub fn as_str_name(&self) -> &'static str {
+            match self {
+                Self::Unknown => "JSON_FORMAT_UNKNOWN",
+                Self::Allow => "ALLOW",
+                Self::LegacyBestEffort => "LEGACY_BEST_EFFORT",
+            }
+        }
+        /// Creates an enum from field names used in the ProtoBuf definition.
+        pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+            match value {
+                "JSON_FORMAT_UNKNOWN" => Some(Self::Unknown),
+                "ALLOW" => Some(Self::Allow),
+                "LEGACY_BEST_EFFORT" => Some(Self::LegacyBestEffort),
+                _ => None,
+            }
+        }
+    }
+}
+/// A compiled specification for the defaults of a set of features.  These
+/// messages are generated from FeatureSet extensions and can be used to seed
+/// feature resolution. The resolution with this object becomes a simple search
+/// for the closest matching edition, followed by proto merges.
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[derive(Clone, PartialEq, ::defiant::View)]
+#[defiant(full_name = "google.protobuf.FeatureSetDefaults")]
+pub struct FeatureSetDefaults<'arena> {
+    #[defiant(message, repeated, tag = "1")]
+    pub defaults: &'arena [feature_set_defaults::FeatureSetEditionDefault],
+    /// The minimum supported edition (inclusive) when this was constructed.
+    /// Editions before this will not have defaults.
+    #[defiant(enumeration = "Edition", optional, tag = "4")]
+    pub minimum_edition: ::core::option::Option<i32>,
+    /// The maximum known edition (inclusive) when this was constructed. Editions
+    /// after this will not have reliable defaults.
+    #[defiant(enumeration = "Edition", optional, tag = "5")]
+    pub maximum_edition: ::core::option::Option<i32>,
+}
+/// Nested message and enum types in `FeatureSetDefaults`.
+pub mod feature_set_defaults {
+    /// A map from every known edition with a unique set of defaults to its
+    /// defaults. Not all editions may be contained here.  For a given edition,
+    /// the defaults at the closest matching edition ordered at or before it should
+    /// be used.  This field must be in strict ascending order by edition.
+    #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+    #[derive(Clone, Copy, PartialEq, Eq, Hash, ::defiant::View)]
+    #[defiant(full_name = "google.protobuf.FeatureSetDefaults.FeatureSetEditionDefault")]
+    pub struct FeatureSetEditionDefault {
+        #[defiant(enumeration = "super::Edition", optional, tag = "3")]
+        pub edition: ::core::option::Option<i32>,
+        /// Defaults of features that can be overridden in this edition.
+        #[defiant(message, optional, tag = "4")]
+        pub overridable_features: ::core::option::Option<super::FeatureSet>,
+        /// Defaults of features that can't be overridden in this edition.
+        #[defiant(message, optional, tag = "5")]
+        pub fixed_features: ::core::option::Option<super::FeatureSet>,
+    }
+}
 // ===================================================================
 // Optional source code info
 
@@ -936,6 +1321,7 @@ pub mod uninterpreted_option {
 /// FileDescriptorProto was generated.
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Clone, PartialEq, ::defiant::View)]
+#[defiant(full_name = "google.protobuf.SourceCodeInfo")]
 pub struct SourceCodeInfo<'arena> {
     /// A Location identifies a piece of source code in a .proto file which
     /// corresponds to a particular definition.  This information is intended
@@ -988,6 +1374,7 @@ pub struct SourceCodeInfo<'arena> {
 pub mod source_code_info {
     #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
     #[derive(Clone, PartialEq, Eq, Hash, ::defiant::View)]
+    #[defiant(full_name = "google.protobuf.SourceCodeInfo.Location")]
     pub struct Location<'arena> {
         /// Identifies which part of the FileDescriptorProto was defined at this
         /// location.
@@ -1081,6 +1468,7 @@ pub mod source_code_info {
 /// source file, but may contain references to different source .proto files.
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Clone, PartialEq, ::defiant::View)]
+#[defiant(full_name = "google.protobuf.GeneratedCodeInfo")]
 pub struct GeneratedCodeInfo<'arena> {
     /// An Annotation connects some span of text in generated code to an element
     /// of its generating .proto file.
@@ -1091,6 +1479,7 @@ pub struct GeneratedCodeInfo<'arena> {
 pub mod generated_code_info {
     #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
     #[derive(Clone, PartialEq, Eq, Hash, ::defiant::View)]
+    #[defiant(full_name = "google.protobuf.GeneratedCodeInfo.Annotation")]
     pub struct Annotation<'arena> {
         /// Identifies the element in the original source .proto file. This field
         /// is formatted the same as SourceCodeInfo.Location.path.
@@ -1110,6 +1499,92 @@ pub mod generated_code_info {
         pub end: ::core::option::Option<i32>,
     }
 }
+/// The full set of known editions.
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::defiant::Enumeration)]
+#[defiant(full_name = "google.protobuf.Edition")]
+#[repr(i32)]
+pub enum Edition {
+    /// A placeholder for an unknown edition value.
+    #[defiant(name = "EDITION_UNKNOWN")]
+    Unknown = 0,
+    /// A placeholder edition for specifying default behaviors *before* a feature
+    /// was first introduced.  This is effectively an "infinite past".
+    #[defiant(name = "EDITION_LEGACY")]
+    Legacy = 900,
+    /// Legacy syntax "editions".  These pre-date editions, but behave much like
+    /// distinct editions.  These can't be used to specify the edition of proto
+    /// files, but feature definitions must supply proto2/proto3 defaults for
+    /// backwards compatibility.
+    #[defiant(name = "EDITION_PROTO2")]
+    Proto2 = 998,
+    #[defiant(name = "EDITION_PROTO3")]
+    Proto3 = 999,
+    /// Editions that have been released.  The specific values are arbitrary and
+    /// should not be depended on, but they will always be time-ordered for easy
+    /// comparison.
+    #[defiant(name = "EDITION_2023")]
+    Edition2023 = 1000,
+    #[defiant(name = "EDITION_2024")]
+    Edition2024 = 1001,
+    /// Placeholder editions for testing feature resolution.  These should not be
+    /// used or relied on outside of tests.
+    #[defiant(name = "EDITION_1_TEST_ONLY")]
+    Edition1TestOnly = 1,
+    #[defiant(name = "EDITION_2_TEST_ONLY")]
+    Edition2TestOnly = 2,
+    #[defiant(name = "EDITION_99997_TEST_ONLY")]
+    Edition99997TestOnly = 99997,
+    #[defiant(name = "EDITION_99998_TEST_ONLY")]
+    Edition99998TestOnly = 99998,
+    #[defiant(name = "EDITION_99999_TEST_ONLY")]
+    Edition99999TestOnly = 99999,
+    /// Placeholder for specifying unbounded edition support.  This should only
+    /// ever be used by plugins that can expect to never require any changes to
+    /// support a new edition.
+    #[defiant(name = "EDITION_MAX")]
+    Max = 2147483647,
+}
+impl Edition {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            Self::Unknown => "EDITION_UNKNOWN",
+            Self::Legacy => "EDITION_LEGACY",
+            Self::Proto2 => "EDITION_PROTO2",
+            Self::Proto3 => "EDITION_PROTO3",
+            Self::Edition2023 => "EDITION_2023",
+            Self::Edition2024 => "EDITION_2024",
+            Self::Edition1TestOnly => "EDITION_1_TEST_ONLY",
+            Self::Edition2TestOnly => "EDITION_2_TEST_ONLY",
+            Self::Edition99997TestOnly => "EDITION_99997_TEST_ONLY",
+            Self::Edition99998TestOnly => "EDITION_99998_TEST_ONLY",
+            Self::Edition99999TestOnly => "EDITION_99999_TEST_ONLY",
+            Self::Max => "EDITION_MAX",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "EDITION_UNKNOWN" => Some(Self::Unknown),
+            "EDITION_LEGACY" => Some(Self::Legacy),
+            "EDITION_PROTO2" => Some(Self::Proto2),
+            "EDITION_PROTO3" => Some(Self::Proto3),
+            "EDITION_2023" => Some(Self::Edition2023),
+            "EDITION_2024" => Some(Self::Edition2024),
+            "EDITION_1_TEST_ONLY" => Some(Self::Edition1TestOnly),
+            "EDITION_2_TEST_ONLY" => Some(Self::Edition2TestOnly),
+            "EDITION_99997_TEST_ONLY" => Some(Self::Edition99997TestOnly),
+            "EDITION_99998_TEST_ONLY" => Some(Self::Edition99998TestOnly),
+            "EDITION_99999_TEST_ONLY" => Some(Self::Edition99999TestOnly),
+            "EDITION_MAX" => Some(Self::Max),
+            _ => None,
+        }
+    }
+}
 /// `Any` contains an arbitrary serialized protocol buffer message along with a
 /// URL that describes the type of the serialized message.
 ///
@@ -1205,6 +1680,7 @@ pub mod generated_code_info {
 /// ```
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Clone, PartialEq, Eq, Hash, ::defiant::View)]
+#[defiant(full_name = "google.protobuf.Any")]
 pub struct Any<'arena> {
     /// A URL/resource name that uniquely identifies the type of the serialized
     /// protocol buffer message. This string must contain at least
@@ -1243,6 +1719,7 @@ pub struct Any<'arena> {
 /// protobuf element, like the file in which it is defined.
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Clone, PartialEq, Eq, Hash, ::defiant::View)]
+#[defiant(full_name = "google.protobuf.SourceContext")]
 pub struct SourceContext<'arena> {
     /// The path-qualified name of the .proto file that contained the associated
     /// protobuf element.  For example: `"google/protobuf/source_context.proto"`.
@@ -1252,6 +1729,7 @@ pub struct SourceContext<'arena> {
 /// A protocol buffer message type.
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Clone, PartialEq, ::defiant::View)]
+#[defiant(full_name = "google.protobuf.Type")]
 pub struct Type<'arena> {
     /// The fully qualified message name.
     #[defiant(string, tag = "1")]
@@ -1271,10 +1749,14 @@ pub struct Type<'arena> {
     /// The source syntax.
     #[defiant(enumeration = "Syntax", tag = "6")]
     pub syntax: i32,
+    /// The source edition string, only valid when syntax is SYNTAX_EDITIONS.
+    #[defiant(string, tag = "7")]
+    pub edition: &'arena str,
 }
 /// A single field of a message type.
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Clone, PartialEq, ::defiant::View)]
+#[defiant(full_name = "google.protobuf.Field")]
 pub struct Field<'arena> {
     /// The field type.
     #[defiant(enumeration = "field::Kind", tag = "1")]
@@ -1314,45 +1796,65 @@ pub mod field {
     /// Basic field types.
     #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
     #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::defiant::Enumeration)]
+    #[defiant(full_name = "google.protobuf.Field.Kind")]
     #[repr(i32)]
     pub enum Kind {
         /// Field type unknown.
+        #[defiant(name = "TYPE_UNKNOWN")]
         TypeUnknown = 0,
         /// Field type double.
+        #[defiant(name = "TYPE_DOUBLE")]
         TypeDouble = 1,
         /// Field type float.
+        #[defiant(name = "TYPE_FLOAT")]
         TypeFloat = 2,
         /// Field type int64.
+        #[defiant(name = "TYPE_INT64")]
         TypeInt64 = 3,
         /// Field type uint64.
+        #[defiant(name = "TYPE_UINT64")]
         TypeUint64 = 4,
         /// Field type int32.
+        #[defiant(name = "TYPE_INT32")]
         TypeInt32 = 5,
         /// Field type fixed64.
+        #[defiant(name = "TYPE_FIXED64")]
         TypeFixed64 = 6,
         /// Field type fixed32.
+        #[defiant(name = "TYPE_FIXED32")]
         TypeFixed32 = 7,
         /// Field type bool.
+        #[defiant(name = "TYPE_BOOL")]
         TypeBool = 8,
         /// Field type string.
+        #[defiant(name = "TYPE_STRING")]
         TypeString = 9,
         /// Field type group. Proto2 syntax only, and deprecated.
+        #[defiant(name = "TYPE_GROUP")]
         TypeGroup = 10,
         /// Field type message.
+        #[defiant(name = "TYPE_MESSAGE")]
         TypeMessage = 11,
         /// Field type bytes.
+        #[defiant(name = "TYPE_BYTES")]
         TypeBytes = 12,
         /// Field type uint32.
+        #[defiant(name = "TYPE_UINT32")]
         TypeUint32 = 13,
         /// Field type enum.
+        #[defiant(name = "TYPE_ENUM")]
         TypeEnum = 14,
         /// Field type sfixed32.
+        #[defiant(name = "TYPE_SFIXED32")]
         TypeSfixed32 = 15,
         /// Field type sfixed64.
+        #[defiant(name = "TYPE_SFIXED64")]
         TypeSfixed64 = 16,
         /// Field type sint32.
+        #[defiant(name = "TYPE_SINT32")]
         TypeSint32 = 17,
         /// Field type sint64.
+        #[defiant(name = "TYPE_SINT64")]
         TypeSint64 = 18,
     }
     impl Kind {
@@ -1412,15 +1914,20 @@ pub mod field {
     /// Whether a field is optional, required, or repeated.
     #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
     #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::defiant::Enumeration)]
+    #[defiant(full_name = "google.protobuf.Field.Cardinality")]
     #[repr(i32)]
     pub enum Cardinality {
         /// For fields with unknown cardinality.
+        #[defiant(name = "CARDINALITY_UNKNOWN")]
         Unknown = 0,
         /// For optional fields.
+        #[defiant(name = "CARDINALITY_OPTIONAL")]
         Optional = 1,
         /// For required fields. Proto2 syntax only.
+        #[defiant(name = "CARDINALITY_REQUIRED")]
         Required = 2,
         /// For repeated fields.
+        #[defiant(name = "CARDINALITY_REPEATED")]
         Repeated = 3,
     }
     impl Cardinality {
@@ -1451,6 +1958,7 @@ pub mod field {
 /// Enum type definition.
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Clone, PartialEq, ::defiant::View)]
+#[defiant(full_name = "google.protobuf.Enum")]
 pub struct Enum<'arena> {
     /// Enum type name.
     #[defiant(string, tag = "1")]
@@ -1467,10 +1975,14 @@ pub struct Enum<'arena> {
     /// The source syntax.
     #[defiant(enumeration = "Syntax", tag = "5")]
     pub syntax: i32,
+    /// The source edition string, only valid when syntax is SYNTAX_EDITIONS.
+    #[defiant(string, tag = "6")]
+    pub edition: &'arena str,
 }
 /// Enum value definition.
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Clone, PartialEq, ::defiant::View)]
+#[defiant(full_name = "google.protobuf.EnumValue")]
 pub struct EnumValue<'arena> {
     /// Enum value name.
     #[defiant(string, tag = "1")]
@@ -1486,6 +1998,7 @@ pub struct EnumValue<'arena> {
 /// enumeration, etc.
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Clone, PartialEq, Eq, Hash, ::defiant::View)]
+#[defiant(full_name = "google.protobuf.Option")]
 pub struct Option<'arena> {
     /// The option's name. For protobuf built-in options (options defined in
     /// descriptor.proto), this is the short name. For example, `"map_entry"`.
@@ -1503,12 +2016,18 @@ pub struct Option<'arena> {
 /// The syntax in which a protocol buffer element is defined.
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::defiant::Enumeration)]
+#[defiant(full_name = "google.protobuf.Syntax")]
 #[repr(i32)]
 pub enum Syntax {
     /// Syntax `proto2`.
+    #[defiant(name = "SYNTAX_PROTO2")]
     Proto2 = 0,
     /// Syntax `proto3`.
+    #[defiant(name = "SYNTAX_PROTO3")]
     Proto3 = 1,
+    /// Syntax `editions`.
+    #[defiant(name = "SYNTAX_EDITIONS")]
+    Editions = 2,
 }
 impl Syntax {
     /// String value of the enum field names used in the ProtoBuf definition.
@@ -1519,6 +2038,7 @@ impl Syntax {
         match self {
             Self::Proto2 => "SYNTAX_PROTO2",
             Self::Proto3 => "SYNTAX_PROTO3",
+            Self::Editions => "SYNTAX_EDITIONS",
         }
     }
     /// Creates an enum from field names used in the ProtoBuf definition.
@@ -1526,6 +2046,7 @@ impl Syntax {
         match value {
             "SYNTAX_PROTO2" => Some(Self::Proto2),
             "SYNTAX_PROTO3" => Some(Self::Proto3),
+            "SYNTAX_EDITIONS" => Some(Self::Editions),
             _ => None,
         }
     }
@@ -1541,6 +2062,7 @@ impl Syntax {
 /// detailed terminology.
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Clone, PartialEq, ::defiant::View)]
+#[defiant(full_name = "google.protobuf.Api")]
 pub struct Api<'arena> {
     /// The fully qualified name of this interface, including package name
     /// followed by the interface's simple name.
@@ -1587,6 +2109,7 @@ pub struct Api<'arena> {
 /// Method represents a method of an API interface.
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Clone, PartialEq, ::defiant::View)]
+#[defiant(full_name = "google.protobuf.Method")]
 pub struct Method<'arena> {
     /// The simple name of this method.
     #[defiant(string, tag = "1")]
@@ -1700,6 +2223,7 @@ pub struct Method<'arena> {
 /// ```
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Clone, PartialEq, Eq, Hash, ::defiant::View)]
+#[defiant(full_name = "google.protobuf.Mixin")]
 pub struct Mixin<'arena> {
     /// The fully qualified name of the interface which is included.
     #[defiant(string, tag = "1")]
@@ -1775,6 +2299,7 @@ pub struct Mixin<'arena> {
 /// microsecond should be expressed in JSON format as "3.000001s".
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Clone, Copy, PartialEq, Eq, Hash, ::defiant::View)]
+#[defiant(full_name = "google.protobuf.Duration")]
 pub struct Duration {
     /// Signed seconds of the span of time. Must be from -315,576,000,000
     /// to +315,576,000,000 inclusive. Note: these bounds are computed from:
@@ -2014,6 +2539,7 @@ pub struct Duration {
 /// `INVALID_ARGUMENT` error if any path is unmappable.
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Clone, PartialEq, Eq, Hash, ::defiant::View)]
+#[defiant(full_name = "google.protobuf.FieldMask")]
 pub struct FieldMask<'arena> {
     /// The set of field mask paths.
     #[defiant(string, repeated, tag = "1")]
@@ -2029,6 +2555,7 @@ pub struct FieldMask<'arena> {
 /// The JSON representation for `Struct` is JSON object.
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Clone, PartialEq, ::defiant::View)]
+#[defiant(full_name = "google.protobuf.Struct")]
 pub struct Struct<'arena> {
     /// Unordered map of dynamically typed values.
     #[defiant(arena_map = "string, message", tag = "1")]
@@ -2042,6 +2569,7 @@ pub struct Struct<'arena> {
 /// The JSON representation for `Value` is JSON value.
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Clone, PartialEq, ::defiant::View)]
+#[defiant(full_name = "google.protobuf.Value")]
 pub struct Value<'arena> {
     /// The kind of value.
     #[defiant(oneof = "value::Kind", tags = "1, 2, 3, 4, 5, 6")]
@@ -2078,6 +2606,7 @@ pub mod value {
 /// The JSON representation for `ListValue` is JSON array.
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Clone, PartialEq, ::defiant::View)]
+#[defiant(full_name = "google.protobuf.ListValue")]
 pub struct ListValue<'arena> {
     /// Repeated field of dynamically typed values.
     #[defiant(message, repeated, tag = "1")]
@@ -2089,9 +2618,11 @@ pub struct ListValue<'arena> {
 /// The JSON representation for `NullValue` is JSON `null`.
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::defiant::Enumeration)]
+#[defiant(full_name = "google.protobuf.NullValue")]
 #[repr(i32)]
 pub enum NullValue {
     /// Null value.
+    #[defiant(name = "NULL_VALUE")]
     NullValue = 0,
 }
 impl NullValue {
@@ -2213,6 +2744,7 @@ impl NullValue {
 /// the Joda Time's [`ISODateTimeFormat.dateTime()`](<http://www.joda.org/joda-time/apidocs/org/joda/time/format/ISODateTimeFormat.html#dateTime%2D%2D>) to obtain a formatter capable of generating timestamps in this format.
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Clone, Copy, PartialEq, Eq, Hash, ::defiant::View)]
+#[defiant(full_name = "google.protobuf.Timestamp")]
 pub struct Timestamp {
     /// Represents seconds of UTC time since Unix epoch
     /// 1970-01-01T00:00:00Z. Must be from 0001-01-01T00:00:00Z to
@@ -416,6 +416,18 @@ mod tests {
         }
     }
 
+    #[test]
+    fn check_timestamp_json() {
+        let arena = defiant::Arena::new();
+        let timestamp = Timestamp::date_time_nanos(1972, 1, 1, 10, 0, 20, 21_000_000).unwrap();
+        let json = timestamp.to_json().unwrap();
+        assert_eq!(json, format!("\"{timestamp}\""));
+        let parsed = TimestampBuilder::parse_json(&json, &arena)
+            .unwrap()
+            .freeze();
+        assert_eq!(parsed, timestamp);
+    }
+
     #[cfg(feature = "arbitrary")]
     #[test]
     fn check_timestamp_implements_arbitrary() {
@@ -7,6 +7,8 @@
 use bumpalo::Bump;
 use core::ops::{Deref, DerefMut};
 
+use crate::{DecodeError, DecodeLimit};
+
 pub use bumpalo::collections::Vec as BumpVec;
 
 /// Arena-allocated vector that can be frozen to an immutable slice.
@@ -34,6 +36,53 @@ impl<'arena, T> ArenaVec<'arena, T> {
         self.0.reserve(additional);
     }
 
+    /// Reserves capacity for at least `additional` more elements, failing instead
+    /// of aborting when the arena's budget is exhausted.
+    #[inline]
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), DecodeError> {
+        self.0
+            .try_reserve(additional)
+            .map_err(|_| budget_exceeded())
+    }
+
+    /// Appends an element to the back of the vector, failing instead of aborting
+    /// when the arena's budget is exhausted.
+    #[inline]
+    pub fn try_push(&mut self, value: T) -> Result<(), DecodeError> {
+        if self.0.len() == self.0.capacity() {
+            self.try_reserve(1)?;
+        }
+        self.0.push(value);
+        Ok(())
+    }
+
+    /// Inserts an element at `index`, shifting all elements after it to the right.
+    #[inline]
+    pub fn insert(&mut self, index: usize, value: T) {
+        self.0.insert(index, value);
+    }
+
+    /// Removes and returns the element at `index`, shifting all elements after it to the left.
+    #[inline]
+    pub fn remove(&mut self, index: usize) -> T {
+        self.0.remove(index)
+    }
+
+    /// Removes all elements, keeping the allocated capacity.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+
+    /// Retains only the elements for which `keep` returns `true`.
+    #[inline]
+    pub fn retain<F>(&mut self, keep: F)
+    where
+        F: FnMut(&T) -> bool,
+    {
+        self.0.retain(keep);
+    }
+
     /// Returns the number of elements in the vector.
     #[inline]
     pub fn len(&self) -> usize {
@@ -136,6 +185,100 @@ impl<'arena, T> core::iter::Extend<T> for ArenaVec<'arena, T> {
     }
 }
 
+/// Arena buffer which is filled from the back, for encoding messages last field first.
+///
+/// Writing a message's fields before its key and length prefix means the length
+/// is known once the body has been written, so it never has to be computed up
+/// front. When the buffer runs out of room, a buffer twice the size is allocated
+/// from the arena and the bytes written so far are moved to its end.
+pub struct ReverseBuf<'arena> {
+    arena: &'arena Arena,
+    buf: &'arena mut [u8],
+    start: usize,
+}
+
+impl<'arena> ReverseBuf<'arena> {
+    /// Creates an empty buffer. Nothing is allocated until the first write.
+    #[inline]
+    pub fn new(arena: &'arena Arena) -> Self {
+        ReverseBuf {
+            arena,
+            buf: &mut [],
+            start: 0,
+        }
+    }
+
+    /// Creates an empty buffer with room for `capacity` bytes.
+    #[inline]
+    pub fn with_capacity(arena: &'arena Arena, capacity: usize) -> Self {
+        let buf = arena.bump.alloc_slice_fill_copy(capacity, 0u8);
+        ReverseBuf {
+            arena,
+            buf,
+            start: capacity,
+        }
+    }
+
+    /// Returns the number of bytes written so far.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.buf.len() - self.start
+    }
+
+    /// Returns true if nothing has been written yet.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.start == self.buf.len()
+    }
+
+    /// Makes room for `len` bytes in front of everything written so far, and
+    /// returns them to be filled in.
+    #[inline]
+    pub fn prepend(&mut self, len: usize) -> &mut [u8] {
+        if len > self.start {
+            self.grow(len);
+        }
+        self.start -= len;
+        &mut self.buf[self.start..self.start + len]
+    }
+
+    /// Writes `bytes` in front of everything written so far.
+    #[inline]
+    pub fn put_slice(&mut self, bytes: &[u8]) {
+        self.prepend(bytes.len()).copy_from_slice(bytes);
+    }
+
+    /// Writes a varint in front of everything written so far.
+    #[inline]
+    pub fn put_varint(&mut self, value: u64) {
+        let len = crate::encoding::encoded_len_varint(value);
+        crate::encoding::encode_varint(value, &mut self.prepend(len));
+    }
+
+    /// Writes a field key in front of everything written so far.
+    #[inline]
+    pub fn put_key(&mut self, tag: u32, wire_type: crate::encoding::WireType) {
+        self.put_varint(u64::from((tag << 3) | wire_type as u32));
+    }
+
+    /// Returns the bytes written, which stay in the arena.
+    #[inline]
+    pub fn finish(self) -> &'arena [u8] {
+        let ReverseBuf { buf, start, .. } = self;
+        &buf[start..]
+    }
+
+    #[cold]
+    fn grow(&mut self, additional: usize) {
+        let len = self.len();
+        let capacity = (len + additional).max(2 * self.buf.len()).max(64);
+        let buf = self.arena.bump.alloc_slice_fill_copy(capacity, 0u8);
+        buf[capacity - len..].copy_from_slice(&self.buf[self.start..]);
+        self.buf = buf;
+        self.start = capacity - len;
+    }
+}
+
 /// An arena allocator for protobuf messages.
 ///
 /// All messages decoded with this arena will have their data allocated from
@@ -179,6 +322,28 @@ impl Arena {
         }
     }
 
+    /// Creates a new arena which allocates at most `limit` bytes.
+    ///
+    /// The limit applies to the memory the arena requests from the system, so
+    /// it includes bookkeeping overhead and unused space at the end of each
+    /// chunk. Decoding into a limited arena fails with a `DecodeError`, for which
+    /// [`DecodeError::limit_exceeded`] returns [`DecodeLimit::ArenaBudget`],
+    /// rather than aborting when the budget runs out.
+    ///
+    /// ```
+    /// use defiant::Arena;
+    ///
+    /// let arena = Arena::with_limit(1024);
+    /// assert!(arena.try_alloc_str("small").is_ok());
+    /// assert!(arena.try_alloc_str(&"x".repeat(4096)).is_err());
+    /// ```
+    #[inline]
+    pub fn with_limit(limit: usize) -> Self {
+        let bump = Bump::new();
+        bump.set_allocation_limit(Some(limit));
+        Arena { bump }
+    }
+
     /// Allocates a string slice in the arena.
     ///
     /// The string data is copied into the arena and a reference with the
@@ -188,12 +353,29 @@ impl Arena {
         self.bump.alloc_str(s)
     }
 
+    /// Allocates a string slice in the arena, failing instead of aborting when
+    /// the arena's budget is exhausted.
+    #[inline]
+    pub fn try_alloc_str(&self, s: &str) -> Result<&str, DecodeError> {
+        self.bump
+            .try_alloc_str(s)
+            .map(|s| &*s)
+            .map_err(|_| budget_exceeded())
+    }
+
     /// Allocates a value in the arena.
     #[inline]
     pub fn alloc<T>(&self, value: T) -> &mut T {
         self.bump.alloc(value)
     }
 
+    /// Allocates a value in the arena, failing instead of aborting when the
+    /// arena's budget is exhausted.
+    #[inline]
+    pub fn try_alloc<T>(&self, value: T) -> Result<&mut T, DecodeError> {
+        self.bump.try_alloc(value).map_err(|_| budget_exceeded())
+    }
+
     /// Creates a new arena-allocated Vec for accumulating repeated field elements.
     ///
     /// During protobuf decoding, repeated fields accumulate elements into this Vec.
@@ -209,6 +391,19 @@ impl Arena {
         ArenaVec(BumpVec::with_capacity_in(capacity, &self.bump))
     }
 
+    /// Creates a new arena-allocated Vec with the specified capacity, failing
+    /// instead of aborting when the arena's budget is exhausted.
+    #[inline]
+    pub fn try_new_vec_with_capacity<T>(
+        &self,
+        capacity: usize,
+    ) -> Result<ArenaVec<'_, T>, DecodeError> {
+        let mut vec = BumpVec::new_in(&self.bump);
+        vec.try_reserve_exact(capacity)
+            .map_err(|_| budget_exceeded())?;
+        Ok(ArenaVec(vec))
+    }
+
     /// Resets the arena, reclaiming all allocated memory.
     ///
     /// After calling this, all previous allocations from this arena are
@@ -239,6 +434,11 @@ impl Default for Arena {
     }
 }
 
+#[cold]
+fn budget_exceeded() -> DecodeError {
+    DecodeError::limit(DecodeLimit::ArenaBudget)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -293,6 +493,46 @@ mod tests {
         let s = arena.alloc_str("test");
         assert_eq!(s, "test");
     }
+
+    #[test]
+    fn test_with_limit() {
+        let arena = Arena::with_limit(4096);
+        let mut vec = arena.new_vec::<u64>();
+        assert!(vec.try_reserve(16).is_ok());
+        let error = vec.try_reserve(1 << 20).unwrap_err();
+        assert_eq!(error.limit_exceeded(), Some(DecodeLimit::ArenaBudget));
+        assert!(arena.try_new_vec_with_capacity::<u8>(1 << 20).is_err());
+        assert!(arena.try_alloc([0u8; 8192]).is_err());
+        assert_eq!(arena.try_alloc_str("fits").unwrap(), "fits");
+    }
+
+    #[test]
+    fn test_map_from_unsorted_last_wins() {
+        let arena = Arena::new();
+        let map =
+            ArenaMap::from_unsorted(&arena, [(3, "c"), (1, "a"), (3, "z"), (2, "b"), (1, "y")]);
+        assert_eq!(map.as_slice(), &[(1, "y"), (2, "b"), (3, "z")]);
+        assert!(map.contains_key(&2));
+        assert!(!map.contains_key(&4));
+        assert_eq!(map.get(&3), Some(&"z"));
+    }
+
+    #[test]
+    fn test_reverse_buf() {
+        let arena = Arena::new();
+        let mut buf = ReverseBuf::new(&arena);
+        assert!(buf.is_empty());
+
+        let tail = [7u8; 100];
+        buf.put_slice(&tail);
+        buf.put_varint(300);
+        buf.put_key(1, crate::encoding::WireType::LengthDelimited);
+        assert_eq!(buf.len(), 103);
+
+        let bytes = buf.finish();
+        assert_eq!(&bytes[..3], &[0x0a, 0xac, 0x02]);
+        assert_eq!(&bytes[3..], &tail[..]);
+    }
 }
 
 /// A conversion trait that requires an arena for allocation.
@@ -323,6 +563,194 @@ where
     }
 }
 
+/// Deep-copies a value into another arena.
+///
+/// Views borrow from the arena they were decoded or built in, and [`Arena::reset`] invalidates
+/// all of them. Cloning a view into another arena keeps it alive independently, and cloning a
+/// message built through many edits into a fresh arena leaves the discarded allocations behind.
+///
+/// `#[derive(View)]` and `#[derive(Oneof)]` implement this for messages and oneofs.
+///
+/// ```
+/// use defiant::{Arena, ArenaClone, View};
+///
+/// #[derive(View)]
+/// struct Note<'arena> {
+///     #[defiant(string, tag = "1")]
+///     text: &'arena str,
+/// }
+///
+/// let kept = Arena::new();
+/// let mut batch = Arena::new();
+/// let note = NoteBuilder::parse_text("text: 'keep me'", &batch).unwrap().freeze();
+/// let note = note.clone_in(&kept);
+/// batch.reset();
+/// assert_eq!(note.text, "keep me");
+/// ```
+pub trait ArenaClone {
+    /// The type of the copy, borrowing from the target arena.
+    type Cloned<'arena>: 'arena;
+
+    /// Copies the value, and everything it borrows, into `arena`.
+    fn clone_in<'arena>(&self, arena: &'arena Arena) -> Self::Cloned<'arena>;
+}
+
+macro_rules! arena_clone_copy {
+    ($($ty:ty),*) => {
+        $(
+            impl ArenaClone for $ty {
+                type Cloned<'arena> = $ty;
+
+                #[inline]
+                fn clone_in(&self, _arena: &Arena) -> $ty {
+                    *self
+                }
+            }
+        )*
+    };
+}
+
+arena_clone_copy!(bool, u8, i32, i64, u32, u64, f32, f64);
+
+impl ArenaClone for &str {
+    type Cloned<'arena> = &'arena str;
+
+    #[inline]
+    fn clone_in<'arena>(&self, arena: &'arena Arena) -> &'arena str {
+        arena.alloc_str(self)
+    }
+}
+
+impl<T: ArenaClone> ArenaClone for &[T] {
+    type Cloned<'arena> = &'arena [T::Cloned<'arena>];
+
+    fn clone_in<'arena>(&self, arena: &'arena Arena) -> Self::Cloned<'arena> {
+        let mut vec = arena.new_vec_with_capacity(self.len());
+        vec.extend(self.iter().map(|value| value.clone_in(arena)));
+        vec.freeze()
+    }
+}
+
+impl<T: ArenaClone> ArenaClone for &T {
+    type Cloned<'arena> = &'arena T::Cloned<'arena>;
+
+    #[inline]
+    fn clone_in<'arena>(&self, arena: &'arena Arena) -> Self::Cloned<'arena> {
+        arena.alloc((**self).clone_in(arena))
+    }
+}
+
+impl<T: ArenaClone> ArenaClone for Option<T> {
+    type Cloned<'arena> = Option<T::Cloned<'arena>>;
+
+    #[inline]
+    fn clone_in<'arena>(&self, arena: &'arena Arena) -> Self::Cloned<'arena> {
+        self.as_ref().map(|value| value.clone_in(arena))
+    }
+}
+
+/// An arena bundled with a message view allocated in it, which can move to another thread.
+///
+/// A view borrows from its arena, which keeps both on the thread that decoded them. An
+/// `ArenaBox` owns the arena and holds the view alongside it, so the two move as a unit: the
+/// box is `Send` whenever the view is, and a thread-per-core runtime can hand a decoded request
+/// to another core without re-encoding it. The receiving side borrows the view from the box.
+///
+/// `M` is the view type at any lifetime, through [`ArenaClone::Cloned`], such as
+/// `Request<'static>`; the view itself is only ever handed out borrowing from the box.
+///
+/// ```
+/// use defiant::{ArenaBox, Encode, View};
+///
+/// #[derive(View)]
+/// struct Request<'arena> {
+///     #[defiant(string, tag = "1")]
+///     path: &'arena str,
+/// }
+///
+/// let bytes = b"\x0a\x06/items".to_vec();
+/// let request = ArenaBox::<Request<'static>>::decode(bytes.as_slice()).unwrap();
+/// let path = std::thread::spawn(move || request.view().path.to_string())
+///     .join()
+///     .unwrap();
+/// assert_eq!(path, "/items");
+/// ```
+pub struct ArenaBox<M: ArenaClone> {
+    // The view borrows from `arena`, which outlives it: views are only handed out borrowing
+    // from the box, and the arena's allocations stay put when the box moves
+    view: M::Cloned<'static>,
+    arena: Arena,
+}
+
+impl<M: ArenaClone> ArenaBox<M> {
+    /// Builds a view in `arena` and bundles the two.
+    ///
+    /// `build` must work for any lifetime of the arena, so the view can borrow from nothing but
+    /// the arena and `'static` data.
+    pub fn new(arena: Arena, build: impl for<'a> FnOnce(&'a Arena) -> M::Cloned<'a>) -> Self {
+        match Self::try_new(arena, |arena| {
+            Ok::<_, core::convert::Infallible>(build(arena))
+        }) {
+            Ok(boxed) => boxed,
+            Err(never) => match never {},
+        }
+    }
+
+    /// Builds a view in `arena`, such as by decoding it, and bundles the two.
+    pub fn try_new<E>(
+        arena: Arena,
+        build: impl for<'a> FnOnce(&'a Arena) -> Result<M::Cloned<'a>, E>,
+    ) -> Result<Self, E> {
+        let view = build(&arena)?;
+        // SAFETY: `view` borrows only from `arena` and `'static` data, as `build` cannot name the
+        // lifetime of the borrow. The arena is moved into the box below without moving its
+        // allocations, and dropped after the view, which is never handed out beyond a borrow of
+        // the box. `M::Cloned` differs only in its lifetime, so the layouts are the same.
+        let view = unsafe {
+            let view = core::mem::ManuallyDrop::new(view);
+            core::ptr::read((&*view as *const M::Cloned<'_>).cast::<M::Cloned<'static>>())
+        };
+        Ok(ArenaBox { view, arena })
+    }
+
+    /// Decodes a view into a fresh arena.
+    pub fn decode(buf: impl bytes::Buf) -> Result<Self, DecodeError>
+    where
+        for<'a> M::Cloned<'a>: crate::MessageView<'a>,
+    {
+        Self::try_new(Arena::new(), |arena| {
+            crate::MessageView::from_buf(buf, arena)
+        })
+    }
+
+    /// Borrows the view.
+    #[inline]
+    pub fn view(&self) -> &M::Cloned<'_> {
+        // SAFETY: the view is valid for as long as the arena, which lives as long as `self`
+        unsafe { &*(&self.view as *const M::Cloned<'static>).cast::<M::Cloned<'_>>() }
+    }
+
+    /// Borrows the arena, to build more messages alongside the view.
+    #[inline]
+    pub fn arena(&self) -> &Arena {
+        &self.arena
+    }
+
+    /// Drops the view and returns the arena, which can be reset and reused.
+    pub fn into_arena(self) -> Arena {
+        self.arena
+    }
+}
+
+impl<M: ArenaClone> core::fmt::Debug for ArenaBox<M>
+where
+    for<'a> M::Cloned<'a>: core::fmt::Debug,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("ArenaBox").field(self.view()).finish()
+    }
+}
+
 /// An immutable, arena-allocated map with sorted entries for efficient lookups.
 ///
 /// ArenaMap stores key-value pairs in a sorted slice, providing O(log n) lookups
@@ -350,10 +778,10 @@ pub struct ArenaMap<'arena, K, V> {
 impl<'arena, K, V> ArenaMap<'arena, K, V> {
     /// Creates a new ArenaMap from a slice of entries.
     ///
-    /// The entries must be sorted by key for binary search to work correctly.
-    /// During protobuf decoding, the builder sorts entries before creating the map.
+    /// The entries must be sorted by key, without duplicates, for binary search to
+    /// work correctly. Use [`ArenaMap::from_unsorted`] when that isn't guaranteed.
     #[inline]
-    pub fn new(entries: &'arena [(K, V)]) -> Self {
+    pub const fn new(entries: &'arena [(K, V)]) -> Self {
         ArenaMap { entries }
     }
 
@@ -395,6 +823,42 @@ impl<'arena, K, V> ArenaMap<'arena, K, V> {
 }
 
 impl<'arena, K: Ord, V> ArenaMap<'arena, K, V> {
+    /// Creates a new ArenaMap from entries in arbitrary order.
+    ///
+    /// The entries are sorted by key, and when a key occurs more than once the
+    /// last occurrence wins, matching protobuf's merge semantics for map fields.
+    ///
+    /// ```
+    /// use defiant::{Arena, ArenaMap};
+    ///
+    /// let arena = Arena::new();
+    /// let map = ArenaMap::from_unsorted(&arena, [("b", 1), ("a", 2), ("b", 3)]);
+    /// assert_eq!(map.as_slice(), &[("a", 2), ("b", 3)]);
+    /// ```
+    pub fn from_unsorted<I>(arena: &'arena Arena, entries: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+    {
+        let mut vec = arena.new_vec();
+        vec.extend(entries);
+        Self::from_vec(vec)
+    }
+
+    /// Creates a new ArenaMap from arena-allocated entries in arbitrary order.
+    ///
+    /// Same semantics as [`ArenaMap::from_unsorted`], without copying the entries.
+    /// This is what builders use when freezing a decoded map field.
+    pub fn from_vec(mut entries: ArenaVec<'arena, (K, V)>) -> Self {
+        // Reversing before the stable sort puts the last occurrence of each key
+        // first within its run, which is the one `dedup_by` keeps.
+        entries.0.reverse();
+        entries.0.sort_by(|(k1, _), (k2, _)| k1.cmp(k2));
+        entries.0.dedup_by(|(k1, _), (k2, _)| k1 == k2);
+        ArenaMap {
+            entries: entries.freeze(),
+        }
+    }
+
     /// Returns a reference to the value corresponding to the key.
     ///
     /// Uses binary search, so has O(log n) complexity.
@@ -408,8 +872,23 @@ impl<'arena, K: Ord, V> ArenaMap<'arena, K, V> {
 
     /// Returns true if the map contains a value for the specified key.
     #[inline]
-    pub fn contains_key(&self, key: &K) -> Option<bool> {
-        Some(self.entries.binary_search_by(|(k, _)| k.cmp(key)).is_ok())
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.entries.binary_search_by(|(k, _)| k.cmp(key)).is_ok()
+    }
+}
+
+impl<K: ArenaClone, V: ArenaClone> ArenaClone for ArenaMap<'_, K, V> {
+    type Cloned<'arena> = ArenaMap<'arena, K::Cloned<'arena>, V::Cloned<'arena>>;
+
+    /// Copies the entries, which keep their order.
+    fn clone_in<'arena>(&self, arena: &'arena Arena) -> Self::Cloned<'arena> {
+        let mut entries = arena.new_vec_with_capacity(self.len());
+        entries.extend(
+            self.entries
+                .iter()
+                .map(|(key, value)| (key.clone_in(arena), value.clone_in(arena))),
+        );
+        ArenaMap::new(entries.freeze())
     }
 }
 
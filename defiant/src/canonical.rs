@@ -0,0 +1,374 @@
+//! Canonical binary encoding, for hashing and signing.
+
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+
+use bytes::{Buf, BufMut};
+
+use crate::encoding::{decode_key, decode_varint, encode_key, encode_varint, WireType};
+use crate::schema::{FieldInfo, FieldSet, FieldType, MessageType, ScalarType};
+use crate::text_format::{ensure_remaining, length_delimited, matches_wire_type, scalar_wire_type};
+use crate::{DecodeError, DecodeOptions};
+
+/// Returns `true` if `bytes` are the canonical encoding of a message of type `M`, as written by
+/// [`Encode::encode_canonical`](crate::Encode::encode_canonical).
+///
+/// The canonical encoding depends only on the contents of the message, so equal messages have
+/// equal canonical bytes. It is the regular encoding with these rules:
+///
+/// - Fields, known and unknown, are ordered by tag. Fields with the same tag keep their order,
+///   and a oneof member is placed by its own tag.
+/// - A singular field appears at most once, and a oneof has at most one member.
+/// - Scalars without presence are left out when they hold their default value.
+/// - Repeated scalars and enums are packed into a single record, left out when empty.
+/// - Map entries are sorted by key, without duplicates, and hold their key and then their value
+///   even when those are default.
+/// - Varints, including tags and lengths, take as few bytes as possible, and hold values as
+///   their field's type would write them.
+/// - Unknown fields are kept as they are, apart from their tags and lengths.
+pub fn is_canonical<M: FieldSet>(bytes: &[u8]) -> bool {
+    let depth = DecodeOptions::new().recursion_limit;
+    let mut canonical = Vec::with_capacity(bytes.len());
+    match message(bytes, MessageType::of::<M>(), depth, &mut canonical) {
+        Ok(()) => canonical == bytes,
+        Err(_) => false,
+    }
+}
+
+/// Rewrites the encoding of a message of type `ty` in its canonical form, failing if messages
+/// are nested more than `depth` levels deep.
+pub(crate) fn canonicalize(
+    bytes: &[u8],
+    ty: MessageType,
+    depth: u32,
+) -> Result<Vec<u8>, DecodeError> {
+    let mut canonical = Vec::with_capacity(bytes.len());
+    message(bytes, ty, depth, &mut canonical)?;
+    Ok(canonical)
+}
+
+/// A field value read from wire data.
+#[derive(Clone, Copy)]
+enum Value<'b> {
+    Varint(u64),
+    Fixed32(u32),
+    Fixed64(u64),
+    Bytes(&'b [u8]),
+    /// The body of a group.
+    Group(&'b [u8]),
+}
+
+impl<'b> Value<'b> {
+    fn read(wire_type: WireType, tag: u32, buf: &mut &'b [u8]) -> Result<Value<'b>, DecodeError> {
+        Ok(match wire_type {
+            WireType::Varint => Value::Varint(decode_varint(buf)?),
+            WireType::ThirtyTwoBit => Value::Fixed32(ensure_remaining::<4>(buf)?.get_u32_le()),
+            WireType::SixtyFourBit => Value::Fixed64(ensure_remaining::<8>(buf)?.get_u64_le()),
+            WireType::LengthDelimited => Value::Bytes(length_delimited(buf)?),
+            WireType::StartGroup => Value::Group(group_body(tag, buf)?),
+            WireType::EndGroup => return Err(DecodeError::new("unexpected end group tag")),
+        })
+    }
+
+    /// The value of a map key or value that is missing from its entry.
+    fn default_of(ty: FieldType) -> Value<'static> {
+        match scalar_wire_type(ty) {
+            WireType::ThirtyTwoBit => Value::Fixed32(0),
+            WireType::SixtyFourBit => Value::Fixed64(0),
+            WireType::Varint => Value::Varint(0),
+            _ => Value::Bytes(&[]),
+        }
+    }
+
+    /// Rewrites a varint as the field's type would encode it after decoding it.
+    fn normalize(self, ty: FieldType) -> Value<'b> {
+        let Value::Varint(value) = self else {
+            return self;
+        };
+        Value::Varint(match ty {
+            FieldType::Scalar(ScalarType::Int32) | FieldType::Enum(_) => {
+                i64::from(value as i32) as u64
+            }
+            FieldType::Scalar(ScalarType::Uint32) => u64::from(value as u32),
+            FieldType::Scalar(ScalarType::Sint32) => {
+                let value = ((value >> 1) as i32) ^ -((value & 1) as i32);
+                u64::from(((value << 1) ^ (value >> 31)) as u32)
+            }
+            FieldType::Scalar(ScalarType::Bool) => u64::from(value != 0),
+            _ => value,
+        })
+    }
+
+    fn is_default(self, ty: FieldType) -> bool {
+        match (self, ty) {
+            (Value::Fixed32(bits), FieldType::Scalar(ScalarType::Float)) => {
+                f32::from_bits(bits) == 0.0
+            }
+            (Value::Fixed64(bits), FieldType::Scalar(ScalarType::Double)) => {
+                f64::from_bits(bits) == 0.0
+            }
+            (Value::Varint(value), _) => value == 0,
+            (Value::Fixed32(value), _) => value == 0,
+            (Value::Fixed64(value), _) => value == 0,
+            (Value::Bytes(bytes), _) => bytes.is_empty(),
+            (Value::Group(_), _) => false,
+        }
+    }
+
+    /// Writes the value without a key, as it is held in a packed field.
+    fn write(self, out: &mut Vec<u8>) {
+        match self {
+            Value::Varint(value) => encode_varint(value, out),
+            Value::Fixed32(value) => out.put_u32_le(value),
+            Value::Fixed64(value) => out.put_u64_le(value),
+            Value::Bytes(bytes) => {
+                encode_varint(bytes.len() as u64, out);
+                out.extend_from_slice(bytes);
+            }
+            Value::Group(_) => unreachable!("groups are written with their tags"),
+        }
+    }
+
+    fn write_field(self, tag: u32, out: &mut Vec<u8>) {
+        match self {
+            Value::Varint(_) => encode_key(tag, WireType::Varint, out),
+            Value::Fixed32(_) => encode_key(tag, WireType::ThirtyTwoBit, out),
+            Value::Fixed64(_) => encode_key(tag, WireType::SixtyFourBit, out),
+            Value::Bytes(_) => encode_key(tag, WireType::LengthDelimited, out),
+            Value::Group(body) => {
+                encode_key(tag, WireType::StartGroup, out);
+                out.extend_from_slice(body);
+                encode_key(tag, WireType::EndGroup, out);
+                return;
+            }
+        }
+        self.write(out);
+    }
+}
+
+/// Returns the body of a group whose start tag was just read, up to its end tag.
+pub(crate) fn group_body<'b>(tag: u32, buf: &mut &'b [u8]) -> Result<&'b [u8], DecodeError> {
+    let start = *buf;
+    loop {
+        if !buf.has_remaining() {
+            return Err(DecodeError::new("missing end group tag"));
+        }
+        let before = buf.len();
+        let (field, wire_type) = decode_key(buf)?;
+        if wire_type == WireType::EndGroup {
+            if field != tag {
+                return Err(DecodeError::new("unexpected end group tag"));
+            }
+            return Ok(&start[..start.len() - before]);
+        }
+        Value::read(wire_type, field, buf)?;
+    }
+}
+
+/// The values of one field, in the order they were read.
+struct Field<'b> {
+    tag: u32,
+    info: Option<FieldInfo>,
+    values: Vec<Value<'b>>,
+}
+
+fn message(buf: &[u8], ty: MessageType, depth: u32, out: &mut Vec<u8>) -> Result<(), DecodeError> {
+    if depth == 0 {
+        return Err(DecodeError::new("recursion limit reached"));
+    }
+    let mut buf = buf;
+    let mut fields: Vec<Field<'_>> = Vec::new();
+    // The member of each oneof that was read last
+    let mut oneofs: Vec<(&str, u32)> = Vec::new();
+    while buf.has_remaining() {
+        let (tag, wire_type) = decode_key(&mut buf)?;
+        let value = Value::read(wire_type, tag, &mut buf)?;
+        let info = ty.field_by_tag(tag);
+        if let Some(info) = info {
+            if !matches_wire_type(&info, wire_type) {
+                return Err(DecodeError::new("invalid wire type"));
+            }
+            if let Some(oneof) = info.oneof() {
+                // Setting another member of a oneof clears it
+                match oneofs.iter_mut().find(|(name, _)| *name == oneof) {
+                    Some((_, last)) if *last == tag => {}
+                    Some((_, last)) => {
+                        *last = tag;
+                        fields
+                            .iter_mut()
+                            .filter(|field| field.info.and_then(|info| info.oneof()) == Some(oneof))
+                            .for_each(|field| field.values.clear());
+                    }
+                    None => oneofs.push((oneof, tag)),
+                }
+            }
+        }
+        let index = match fields.iter().position(|field| field.tag == tag) {
+            Some(index) => index,
+            None => {
+                fields.push(Field {
+                    tag,
+                    info,
+                    values: Vec::new(),
+                });
+                fields.len() - 1
+            }
+        };
+        let field = &mut fields[index];
+        match (info, value) {
+            (Some(info), Value::Bytes(mut packed))
+                if scalar_wire_type(info.ty()) != WireType::LengthDelimited =>
+            {
+                let element = scalar_wire_type(info.ty());
+                while packed.has_remaining() {
+                    field.values.push(Value::read(element, tag, &mut packed)?);
+                }
+            }
+            _ => field.values.push(value),
+        }
+    }
+
+    fields.sort_by_key(|field| field.tag);
+    for field in &fields {
+        match field.info {
+            Some(info) => known(&info, &field.values, depth, out)?,
+            None => {
+                for value in &field.values {
+                    value.write_field(field.tag, out);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn known(
+    info: &FieldInfo,
+    values: &[Value<'_>],
+    depth: u32,
+    out: &mut Vec<u8>,
+) -> Result<(), DecodeError> {
+    let tag = info.tag();
+    let Some(&last) = values.last() else {
+        return Ok(());
+    };
+    match info.ty() {
+        FieldType::Map(key_scalar, value_ty) => {
+            let key_ty = FieldType::Scalar(key_scalar);
+            let value_ty = FieldType::from(value_ty);
+            let mut entries = Vec::with_capacity(values.len());
+            for entry in values {
+                let (mut key, mut value) = (Value::default_of(key_ty), Value::default_of(value_ty));
+                let mut buf = match entry {
+                    Value::Bytes(bytes) => *bytes,
+                    _ => return Err(DecodeError::new("invalid wire type")),
+                };
+                while buf.has_remaining() {
+                    let (entry_tag, wire_type) = decode_key(&mut buf)?;
+                    let read = Value::read(wire_type, entry_tag, &mut buf)?;
+                    let ty = match entry_tag {
+                        1 => key_ty,
+                        2 => value_ty,
+                        _ => continue,
+                    };
+                    if wire_type != scalar_wire_type(ty) {
+                        return Err(DecodeError::new("invalid wire type"));
+                    }
+                    match entry_tag {
+                        1 => key = read.normalize(ty),
+                        _ => value = read.normalize(ty),
+                    }
+                }
+                let mut bytes = Vec::new();
+                key.write_field(1, &mut bytes);
+                match (value_ty, value) {
+                    (FieldType::Message(ty), Value::Bytes(body)) => {
+                        let mut body_out = Vec::new();
+                        message(body, ty, depth - 1, &mut body_out)?;
+                        Value::Bytes(&body_out).write_field(2, &mut bytes);
+                    }
+                    _ => value.write_field(2, &mut bytes),
+                }
+                entries.push((key, bytes));
+            }
+            entries.sort_by(|(a, _), (b, _)| compare_keys(key_scalar, *a, *b));
+            for (index, (key, bytes)) in entries.iter().enumerate() {
+                // Of entries with the same key, the last one wins
+                let next = entries.get(index + 1);
+                if next.is_some_and(|(next, _)| {
+                    compare_keys(key_scalar, *key, *next) == Ordering::Equal
+                }) {
+                    continue;
+                }
+                Value::Bytes(bytes).write_field(tag, out);
+            }
+        }
+        FieldType::Message(ty) | FieldType::Group(ty, _) => {
+            // Occurrences of a singular message merge, as if their bodies were concatenated
+            let bodies: Vec<Vec<u8>> = if info.is_repeated() {
+                values.iter().map(|value| body(*value).to_vec()).collect()
+            } else {
+                alloc::vec![values
+                    .iter()
+                    .flat_map(|value| body(*value).iter().copied())
+                    .collect()]
+            };
+            for merged in bodies {
+                let mut body_out = Vec::new();
+                message(&merged, ty, depth - 1, &mut body_out)?;
+                match info.ty() {
+                    FieldType::Group(..) => Value::Group(&body_out).write_field(tag, out),
+                    _ => Value::Bytes(&body_out).write_field(tag, out),
+                }
+            }
+        }
+        ty if info.is_repeated() && scalar_wire_type(ty) != WireType::LengthDelimited => {
+            let mut packed = Vec::new();
+            for value in values {
+                value.normalize(ty).write(&mut packed);
+            }
+            Value::Bytes(&packed).write_field(tag, out);
+        }
+        _ if info.is_repeated() => {
+            for value in values {
+                value.write_field(tag, out);
+            }
+        }
+        ty => {
+            let value = last.normalize(ty);
+            if info.has_presence() || !value.is_default(ty) {
+                value.write_field(tag, out);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn body<'b>(value: Value<'b>) -> &'b [u8] {
+    match value {
+        Value::Bytes(body) | Value::Group(body) => body,
+        _ => &[],
+    }
+}
+
+/// Orders normalized map keys of type `ty` by value.
+fn compare_keys(ty: ScalarType, a: Value<'_>, b: Value<'_>) -> Ordering {
+    let zigzag = |value: u64| ((value >> 1) as i64) ^ -((value & 1) as i64);
+    match (a, b) {
+        (Value::Varint(a), Value::Varint(b)) => match ty {
+            ScalarType::Int32 | ScalarType::Int64 => (a as i64).cmp(&(b as i64)),
+            ScalarType::Sint32 | ScalarType::Sint64 => zigzag(a).cmp(&zigzag(b)),
+            _ => a.cmp(&b),
+        },
+        (Value::Fixed32(a), Value::Fixed32(b)) if ty == ScalarType::Sfixed32 => {
+            (a as i32).cmp(&(b as i32))
+        }
+        (Value::Fixed32(a), Value::Fixed32(b)) => a.cmp(&b),
+        (Value::Fixed64(a), Value::Fixed64(b)) if ty == ScalarType::Sfixed64 => {
+            (a as i64).cmp(&(b as i64))
+        }
+        (Value::Fixed64(a), Value::Fixed64(b)) => a.cmp(&b),
+        (Value::Bytes(a), Value::Bytes(b)) => a.cmp(b),
+        _ => Ordering::Equal,
+    }
+}
@@ -0,0 +1,552 @@
+//! Streams of length-delimited messages.
+//!
+//! Files and sockets commonly carry a sequence of messages, each preceded by its
+//! length. [`DelimitedWriter`] appends messages to such a stream and
+//! [`DelimitedReader`] reads them back, decoding each message into an arena.
+//!
+//! ```
+//! use defiant::{Arena, DelimitedReader, DelimitedWriter};
+//! # use defiant_derive::View;
+//! # #[derive(View)]
+//! # struct Event<'arena> {
+//! #     #[defiant(string, tag = 1)]
+//! #     name: &'arena str,
+//! # }
+//!
+//! let mut writer = DelimitedWriter::to_buf(Vec::new());
+//! writer.write(&Event { name: "start" }).unwrap();
+//! writer.write(&Event { name: "stop" }).unwrap();
+//! let bytes = writer.into_inner().into_inner();
+//!
+//! let arena = Arena::new();
+//! let mut reader = DelimitedReader::from_slice(&bytes);
+//! let names: Vec<_> = reader
+//!     .messages::<Event>(&arena)
+//!     .map(|event| event.unwrap().name)
+//!     .collect();
+//! assert_eq!(names, ["start", "stop"]);
+//! ```
+
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+
+use bytes::BufMut;
+
+use crate::arena::Arena;
+use crate::encoding::{decode_varint, encode_varint, encoded_len_varint, SizeCache};
+use crate::{DecodeError, DecodeLimit, Encode, EncodeError, MessageView};
+
+/// How the length of each message in a stream is encoded.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LengthPrefix {
+    /// A varint, as written by `Encode::encode_length_delimited`.
+    #[default]
+    Varint,
+    /// A little-endian `u32`, as used by the conformance test runner.
+    Fixed32Le,
+    /// A big-endian `u32`.
+    Fixed32Be,
+}
+
+impl LengthPrefix {
+    /// Returns the number of bytes used to encode a prefix for a message of `len` bytes.
+    pub fn encoded_len(self, len: usize) -> usize {
+        match self {
+            LengthPrefix::Varint => encoded_len_varint(len as u64),
+            LengthPrefix::Fixed32Le | LengthPrefix::Fixed32Be => 4,
+        }
+    }
+
+    /// Writes the prefix for a message of `len` bytes.
+    ///
+    /// Fails without writing anything if a fixed32 prefix is used for a message longer
+    /// than `u32::MAX` bytes.
+    pub fn encode(self, len: usize, buf: &mut impl BufMut) -> Result<(), EncodeError> {
+        match self {
+            LengthPrefix::Varint => encode_varint(len as u64, buf),
+            LengthPrefix::Fixed32Le => buf.put_u32_le(fixed32_len(len)?),
+            LengthPrefix::Fixed32Be => buf.put_u32(fixed32_len(len)?),
+        }
+        Ok(())
+    }
+
+    /// Decodes a prefix from the start of `buf`, returning the length of the prefix
+    /// and the length of the message which follows it.
+    ///
+    /// Returns `Ok(None)` if `buf` ends before the prefix does.
+    pub fn decode(self, buf: &[u8]) -> Result<Option<(usize, usize)>, DecodeError> {
+        match self {
+            LengthPrefix::Varint => {
+                let mut rest = buf;
+                match decode_varint(&mut rest) {
+                    Ok(len) => Ok(Some((buf.len() - rest.len(), message_len(len)?))),
+                    // A varint is at most 10 bytes, so a shorter buffer may just be incomplete
+                    Err(_) if buf.len() < 10 && buf.iter().all(|byte| byte & 0x80 != 0) => Ok(None),
+                    Err(error) => Err(error),
+                }
+            }
+            LengthPrefix::Fixed32Le | LengthPrefix::Fixed32Be => {
+                let Some(prefix) = buf.get(..4) else {
+                    return Ok(None);
+                };
+                let prefix = [prefix[0], prefix[1], prefix[2], prefix[3]];
+                let len = match self {
+                    LengthPrefix::Fixed32Le => u32::from_le_bytes(prefix),
+                    _ => u32::from_be_bytes(prefix),
+                };
+                Ok(Some((4, message_len(u64::from(len))?)))
+            }
+        }
+    }
+}
+
+fn fixed32_len(len: usize) -> Result<u32, EncodeError> {
+    u32::try_from(len).map_err(|_| EncodeError::prefix_overflow(len, u32::MAX as usize))
+}
+
+fn message_len(len: u64) -> Result<usize, DecodeError> {
+    usize::try_from(len)
+        .map_err(|_| DecodeError::new("length delimiter exceeds maximum usize value"))
+}
+
+/// Where a [`DelimitedReader`] reads its messages from.
+///
+/// Implemented for byte slices and, with the `std` feature, for any
+/// `std::io::Read`.
+pub trait Source: sealed::Sealed {
+    /// The error returned when a message can't be read or decoded.
+    type Error: From<DecodeError>;
+
+    /// Returns the next message's bytes, or `None` at the end of the stream.
+    #[doc(hidden)]
+    fn next_message(
+        &mut self,
+        prefix: LengthPrefix,
+        max_message_len: usize,
+    ) -> Result<Option<&[u8]>, Self::Error>;
+}
+
+/// Where a [`DelimitedWriter`] writes its messages to.
+///
+/// Implemented for any `BufMut` and, with the `std` feature, for any
+/// `std::io::Write`.
+pub trait Sink: sealed::Sealed {
+    /// The error returned when a message can't be written.
+    type Error;
+
+    /// Writes a message, preceded by its length.
+    #[doc(hidden)]
+    fn write_message<M>(&mut self, prefix: LengthPrefix, message: &M) -> Result<(), Self::Error>
+    where
+        M: Encode;
+}
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// Reads length-delimited messages out of a byte slice.
+#[derive(Clone, Debug)]
+pub struct SliceSource<'a> {
+    buf: &'a [u8],
+}
+
+impl sealed::Sealed for SliceSource<'_> {}
+
+impl Source for SliceSource<'_> {
+    type Error = DecodeError;
+
+    fn next_message(
+        &mut self,
+        prefix: LengthPrefix,
+        max_message_len: usize,
+    ) -> Result<Option<&[u8]>, DecodeError> {
+        if self.buf.is_empty() {
+            return Ok(None);
+        }
+        let (prefix_len, len) = prefix
+            .decode(self.buf)?
+            .ok_or_else(|| DecodeError::new("truncated length prefix"))?;
+        if len > max_message_len {
+            return Err(DecodeError::limit(DecodeLimit::InputLength));
+        }
+        let rest = &self.buf[prefix_len..];
+        if rest.len() < len {
+            return Err(DecodeError::new("truncated length-delimited message"));
+        }
+        let (message, rest) = rest.split_at(len);
+        self.buf = rest;
+        Ok(Some(message))
+    }
+}
+
+/// Writes length-delimited messages into a `BufMut`.
+#[derive(Clone, Debug)]
+pub struct BufSink<B> {
+    buf: B,
+}
+
+impl<B> BufSink<B> {
+    /// Returns the underlying buffer.
+    pub fn into_inner(self) -> B {
+        self.buf
+    }
+}
+
+impl<B: BufMut> sealed::Sealed for BufSink<B> {}
+
+impl<B: BufMut> Sink for BufSink<B> {
+    type Error = EncodeError;
+
+    fn write_message<M>(&mut self, prefix: LengthPrefix, message: &M) -> Result<(), EncodeError>
+    where
+        M: Encode,
+    {
+        let mut sizes = SizeCache::new();
+        let len = message.encoded_len_cached(&mut sizes);
+        let required = prefix.encoded_len(len) + len;
+        let remaining = self.buf.remaining_mut();
+        if required > remaining {
+            return Err(EncodeError::new(required, remaining));
+        }
+        prefix.encode(len, &mut self.buf)?;
+        message.encode_raw_cached(&mut self.buf, &mut sizes);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+pub use self::io::{IoSink, IoSource};
+
+#[cfg(feature = "std")]
+mod io {
+    use std::io::{Read, Write};
+    use std::vec::Vec;
+
+    use super::{sealed, LengthPrefix, Sink, Source};
+    use crate::encoding::SizeCache;
+    use crate::{DecodeError, DecodeLimit, Encode};
+
+    /// Reads length-delimited messages from a `std::io::Read`.
+    ///
+    /// The prefix is read a byte at a time, so unbuffered readers such as files
+    /// and sockets should be wrapped in a `std::io::BufReader`.
+    #[derive(Debug)]
+    pub struct IoSource<R> {
+        reader: R,
+        buf: Vec<u8>,
+    }
+
+    impl<R> IoSource<R> {
+        /// Returns the underlying reader.
+        pub fn into_inner(self) -> R {
+            self.reader
+        }
+    }
+
+    impl<R: Read> IoSource<R> {
+        pub(super) fn new(reader: R) -> Self {
+            IoSource {
+                reader,
+                buf: Vec::new(),
+            }
+        }
+
+        /// Reads a single byte, returning `None` at the end of the stream.
+        fn read_byte(&mut self) -> std::io::Result<Option<u8>> {
+            let mut byte = [0];
+            loop {
+                match self.reader.read(&mut byte) {
+                    Ok(0) => return Ok(None),
+                    Ok(_) => return Ok(Some(byte[0])),
+                    Err(error) if error.kind() == std::io::ErrorKind::Interrupted => {}
+                    Err(error) => return Err(error),
+                }
+            }
+        }
+    }
+
+    impl<R: Read> sealed::Sealed for IoSource<R> {}
+
+    impl<R: Read> Source for IoSource<R> {
+        type Error = std::io::Error;
+
+        fn next_message(
+            &mut self,
+            prefix: LengthPrefix,
+            max_message_len: usize,
+        ) -> std::io::Result<Option<&[u8]>> {
+            let mut header = [0u8; 10];
+            let mut header_len = 0;
+            let len = loop {
+                let Some(byte) = self.read_byte()? else {
+                    if header_len == 0 {
+                        return Ok(None);
+                    }
+                    return Err(std::io::ErrorKind::UnexpectedEof.into());
+                };
+                header[header_len] = byte;
+                header_len += 1;
+                if let Some((_, len)) = prefix.decode(&header[..header_len])? {
+                    break len;
+                }
+            };
+            if len > max_message_len {
+                return Err(DecodeError::limit(DecodeLimit::InputLength).into());
+            }
+            // The buffer only grows as the message arrives, so a corrupt or hostile
+            // prefix can't make it allocate more than the stream actually holds
+            self.buf.clear();
+            (&mut self.reader)
+                .take(len as u64)
+                .read_to_end(&mut self.buf)?;
+            if self.buf.len() < len {
+                return Err(std::io::ErrorKind::UnexpectedEof.into());
+            }
+            Ok(Some(&self.buf))
+        }
+    }
+
+    /// Writes length-delimited messages to a `std::io::Write`.
+    ///
+    /// Each message is encoded into a reusable buffer and written with a single
+    /// `write_all`.
+    #[derive(Debug)]
+    pub struct IoSink<W> {
+        writer: W,
+        buf: Vec<u8>,
+    }
+
+    impl<W> IoSink<W> {
+        pub(super) fn new(writer: W) -> Self {
+            IoSink {
+                writer,
+                buf: Vec::new(),
+            }
+        }
+
+        /// Returns the underlying writer.
+        pub fn into_inner(self) -> W {
+            self.writer
+        }
+    }
+
+    impl<W: Write> IoSink<W> {
+        /// Flushes the underlying writer.
+        pub fn flush(&mut self) -> std::io::Result<()> {
+            self.writer.flush()
+        }
+    }
+
+    impl<W: Write> sealed::Sealed for IoSink<W> {}
+
+    impl<W: Write> Sink for IoSink<W> {
+        type Error = std::io::Error;
+
+        fn write_message<M>(&mut self, prefix: LengthPrefix, message: &M) -> std::io::Result<()>
+        where
+            M: Encode,
+        {
+            let mut sizes = SizeCache::new();
+            let len = message.encoded_len_cached(&mut sizes);
+            self.buf.clear();
+            prefix.encode(len, &mut self.buf)?;
+            self.buf.reserve(len);
+            message.encode_raw_cached(&mut self.buf, &mut sizes);
+            self.writer.write_all(&self.buf)
+        }
+    }
+}
+
+/// Reads a stream of length-delimited messages, decoding each into an arena.
+///
+/// Messages are read with [`read`](Self::read) or [`messages`](Self::messages),
+/// which decode into an arena that lives as long as the messages do. Long
+/// streams can be processed in batches with [`read_batch`](Self::read_batch),
+/// which resets the arena before each batch.
+#[derive(Clone, Debug)]
+pub struct DelimitedReader<S> {
+    source: S,
+    prefix: LengthPrefix,
+    max_message_len: usize,
+}
+
+impl<'a> DelimitedReader<SliceSource<'a>> {
+    /// Creates a reader for the messages in `buf`.
+    pub fn from_slice(buf: &'a [u8]) -> Self {
+        DelimitedReader::new(SliceSource { buf })
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: std::io::Read> DelimitedReader<IoSource<R>> {
+    /// Creates a reader for the messages read from `reader`.
+    pub fn from_reader(reader: R) -> Self {
+        DelimitedReader::new(IoSource::new(reader))
+    }
+}
+
+impl<S: Source> DelimitedReader<S> {
+    fn new(source: S) -> Self {
+        DelimitedReader {
+            source,
+            prefix: LengthPrefix::Varint,
+            max_message_len: usize::MAX,
+        }
+    }
+
+    /// Sets how the length of each message is encoded. Defaults to a varint.
+    pub fn prefix(mut self, prefix: LengthPrefix) -> Self {
+        self.prefix = prefix;
+        self
+    }
+
+    /// Sets the maximum length of a single message, in bytes.
+    ///
+    /// A longer message fails with a `DecodeError` whose
+    /// [`limit_exceeded`](DecodeError::limit_exceeded) is
+    /// [`DecodeLimit::InputLength`], before anything is read into memory.
+    pub fn max_message_len(mut self, len: usize) -> Self {
+        self.max_message_len = len;
+        self
+    }
+
+    /// Reads and decodes the next message, or returns `None` at the end of the stream.
+    pub fn read<'arena, M>(&mut self, arena: &'arena Arena) -> Result<Option<M>, S::Error>
+    where
+        M: MessageView<'arena>,
+    {
+        match self
+            .source
+            .next_message(self.prefix, self.max_message_len)?
+        {
+            Some(buf) => Ok(Some(M::from_buf(buf, arena)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns an iterator which decodes the remaining messages into `arena`.
+    ///
+    /// The iterator stops after the first error.
+    pub fn messages<'r, 'arena, M>(&'r mut self, arena: &'arena Arena) -> Messages<'r, 'arena, S, M>
+    where
+        M: MessageView<'arena>,
+    {
+        Messages {
+            reader: self,
+            arena,
+            failed: false,
+            message: PhantomData,
+        }
+    }
+
+    /// Resets `arena`, then decodes up to `max` messages into it.
+    ///
+    /// Returns an empty batch at the end of the stream. The batch must be dropped
+    /// before the arena can be reset for the next one.
+    pub fn read_batch<'arena, M>(
+        &mut self,
+        arena: &'arena mut Arena,
+        max: usize,
+    ) -> Result<Vec<M>, S::Error>
+    where
+        M: MessageView<'arena>,
+    {
+        arena.reset();
+        let arena: &'arena Arena = arena;
+        let mut batch = Vec::new();
+        while batch.len() < max {
+            match self.read(arena)? {
+                Some(message) => batch.push(message),
+                None => break,
+            }
+        }
+        Ok(batch)
+    }
+
+    /// Returns the underlying source.
+    pub fn into_inner(self) -> S {
+        self.source
+    }
+}
+
+/// Iterator over the messages of a [`DelimitedReader`], see
+/// [`DelimitedReader::messages`].
+pub struct Messages<'r, 'arena, S, M> {
+    reader: &'r mut DelimitedReader<S>,
+    arena: &'arena Arena,
+    failed: bool,
+    message: PhantomData<fn() -> M>,
+}
+
+impl<'arena, S, M> Iterator for Messages<'_, 'arena, S, M>
+where
+    S: Source,
+    M: MessageView<'arena>,
+{
+    type Item = Result<M, S::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let result = self.reader.read(self.arena).transpose();
+        self.failed = matches!(result, Some(Err(_)));
+        result
+    }
+}
+
+/// Writes a stream of length-delimited messages.
+#[derive(Clone, Debug)]
+pub struct DelimitedWriter<S> {
+    sink: S,
+    prefix: LengthPrefix,
+}
+
+impl<B: BufMut> DelimitedWriter<BufSink<B>> {
+    /// Creates a writer which appends messages to `buf`.
+    pub fn to_buf(buf: B) -> Self {
+        DelimitedWriter::new(BufSink { buf })
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: std::io::Write> DelimitedWriter<IoSink<W>> {
+    /// Creates a writer which writes messages to `writer`.
+    pub fn to_writer(writer: W) -> Self {
+        DelimitedWriter::new(IoSink::new(writer))
+    }
+
+    /// Flushes the underlying writer.
+    pub fn flush(&mut self) -> std::io::Result<()> {
+        self.sink.flush()
+    }
+}
+
+impl<S: Sink> DelimitedWriter<S> {
+    fn new(sink: S) -> Self {
+        DelimitedWriter {
+            sink,
+            prefix: LengthPrefix::Varint,
+        }
+    }
+
+    /// Sets how the length of each message is encoded. Defaults to a varint.
+    pub fn prefix(mut self, prefix: LengthPrefix) -> Self {
+        self.prefix = prefix;
+        self
+    }
+
+    /// Writes `message`, preceded by its length.
+    pub fn write<M>(&mut self, message: &M) -> Result<(), S::Error>
+    where
+        M: Encode,
+    {
+        self.sink.write_message(self.prefix, message)
+    }
+
+    /// Returns the underlying sink.
+    pub fn into_inner(self) -> S {
+        self.sink
+    }
+}
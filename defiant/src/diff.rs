@@ -0,0 +1,555 @@
+//! Structural differences between two messages of the same type.
+//!
+//! Messages derived with `#[derive(View)]` compare themselves with `diff()`, which lists the
+//! fields that changed by path, with their old and new values in text format:
+//!
+//! ```
+//! use defiant::{ArenaMap, View};
+//!
+//! #[derive(View)]
+//! struct Team<'arena> {
+//!     #[defiant(string, repeated, tag = "1")]
+//!     members: &'arena [&'arena str],
+//!     #[defiant(arena_map = "string, uint32", tag = "2")]
+//!     ports: ArenaMap<'arena, &'arena str, u32>,
+//! }
+//!
+//! let old = Team {
+//!     members: &["ann", "bob"],
+//!     ports: ArenaMap::new(&[("http", 80)]),
+//! };
+//! let new = Team {
+//!     members: &["ann", "cid", "bob"],
+//!     ports: ArenaMap::new(&[("http", 8080)]),
+//! };
+//! let diffs: Vec<String> = old.diff(&new).iter().map(ToString::to_string).collect();
+//! assert_eq!(diffs, [
+//!     r#"members[1]: <unset> -> "cid""#,
+//!     r#"ports["http"]: 80 -> 8080"#,
+//! ]);
+//! ```
+//!
+//! Both messages are compared through their canonical encodings, so fields are listed in tag
+//! order, and a field differs exactly when it would be encoded differently. Repeated fields are
+//! aligned on the elements they have in common, so that an inserted element is reported once
+//! rather than as a change to every element after it. Map entries are matched by key, and a
+//! oneof which changed members is reported under the name of the oneof.
+//!
+//! [`assert_messages_eq!`](crate::assert_messages_eq) panics with the list of differences.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+use bytes::Buf;
+
+use crate::canonical::{canonicalize, group_body};
+use crate::encoding::{decode_key, decode_varint, WireType};
+use crate::schema::{FieldInfo, FieldSet, FieldType, MapValueType, MessageType, ScalarType};
+use crate::text_format::{
+    ensure_remaining, escape, is_packable, length_delimited, scalar, scalar_wire_type, to_line,
+    write_display,
+};
+use crate::{DecodeError, Encode};
+
+/// Repeated fields with more pairs of differing elements than this are compared element by
+/// element, rather than aligned.
+const ALIGN_LIMIT: usize = 1 << 20;
+
+/// A field which differs between two messages.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FieldDiff {
+    /// The path of the field from the root message, such as `employees[3].name` or
+    /// `settings["port"]`. Elements of repeated fields are indexed in the new message, or in the
+    /// old message if they were removed.
+    pub path: String,
+    /// The value in the old message, in text format, or `None` if it was unset.
+    pub old: Option<String>,
+    /// The value in the new message, in text format, or `None` if it is unset.
+    pub new: Option<String>,
+}
+
+impl fmt::Display for FieldDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = |value: &Option<String>| value.clone().unwrap_or_else(|| "<unset>".into());
+        write!(
+            f,
+            "{}: {} -> {}",
+            self.path,
+            value(&self.old),
+            value(&self.new)
+        )
+    }
+}
+
+/// Lists the differences between two messages, one per line, for [`assert_messages_eq!`].
+///
+/// [`assert_messages_eq!`]: crate::assert_messages_eq
+#[doc(hidden)]
+pub struct Report<'a>(pub &'a [FieldDiff]);
+
+impl fmt::Display for Report<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for diff in self.0 {
+            writeln!(f, "  {diff}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the fields which differ between `old` and `new`, in tag order.
+pub fn diff<M: Encode + FieldSet>(old: &M, new: &M) -> Vec<FieldDiff> {
+    let ty = MessageType::of::<M>();
+    // The messages are as deeply nested as the views, which are already in memory
+    let canonical = |message: &M| {
+        canonicalize(&message.encode_to_vec(), ty, u32::MAX)
+            .expect("an encoded message is well-formed")
+    };
+    let mut differ = Differ {
+        path: String::new(),
+        diffs: Vec::new(),
+    };
+    differ
+        .message(&canonical(old), &canonical(new), ty)
+        .expect("a canonical encoding is well-formed");
+    differ.diffs
+}
+
+/// Asserts that two messages are equal, as [`diff`](crate::diff::diff) compares them.
+///
+/// On failure, the panic message lists the fields which differ rather than both messages:
+///
+/// ```text
+/// assertion `left == right` failed: messages differ
+///   employees[3].name: "ann" -> "bob"
+///   settings["port"]: 80 -> <unset>
+/// ```
+#[macro_export]
+macro_rules! assert_messages_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        match (&$left, &$right) {
+            (left, right) => {
+                let diffs = $crate::diff::diff(left, right);
+                if !diffs.is_empty() {
+                    ::core::panic!(
+                        "assertion `left == right` failed: messages differ\n{}",
+                        $crate::diff::Report(&diffs)
+                    );
+                }
+            }
+        }
+    };
+    ($left:expr, $right:expr, $($arg:tt)+) => {
+        match (&$left, &$right) {
+            (left, right) => {
+                let diffs = $crate::diff::diff(left, right);
+                if !diffs.is_empty() {
+                    ::core::panic!(
+                        "assertion `left == right` failed: {}\n{}",
+                        ::core::format_args!($($arg)+),
+                        $crate::diff::Report(&diffs)
+                    );
+                }
+            }
+        }
+    };
+}
+
+/// A value as it is encoded: the body of length-delimited values and groups, and the encoded
+/// bytes of other values.
+pub(crate) type Value<'b> = (WireType, &'b [u8]);
+
+/// The values of one field, which are consecutive in a canonical encoding.
+pub(crate) struct Record<'b> {
+    pub(crate) tag: u32,
+    pub(crate) values: Vec<Value<'b>>,
+}
+
+pub(crate) fn read_value<'b>(
+    tag: u32,
+    wire_type: WireType,
+    buf: &mut &'b [u8],
+) -> Result<Value<'b>, DecodeError> {
+    let start = *buf;
+    match wire_type {
+        WireType::LengthDelimited => return Ok((wire_type, length_delimited(buf)?)),
+        WireType::StartGroup => return Ok((wire_type, group_body(tag, buf)?)),
+        WireType::Varint => {
+            decode_varint(buf)?;
+        }
+        WireType::ThirtyTwoBit => ensure_remaining::<4>(buf)?.advance(4),
+        WireType::SixtyFourBit => ensure_remaining::<8>(buf)?.advance(8),
+        WireType::EndGroup => return Err(DecodeError::new("unexpected end group tag")),
+    }
+    Ok((wire_type, &start[..start.len() - buf.len()]))
+}
+
+pub(crate) fn records(mut buf: &[u8]) -> Result<Vec<Record<'_>>, DecodeError> {
+    let mut records: Vec<Record<'_>> = Vec::new();
+    while buf.has_remaining() {
+        let (tag, wire_type) = decode_key(&mut buf)?;
+        let value = read_value(tag, wire_type, &mut buf)?;
+        match records.last_mut() {
+            Some(record) if record.tag == tag => record.values.push(value),
+            _ => records.push(Record {
+                tag,
+                values: alloc::vec![value],
+            }),
+        }
+    }
+    Ok(records)
+}
+
+/// Formats a value of a field of type `ty` as text format.
+pub(crate) fn format(ty: FieldType, (wire_type, mut buf): Value<'_>) -> String {
+    let mut out = String::new();
+    match ty {
+        FieldType::Message(message) | FieldType::Group(message, _) => {
+            return to_line(buf, Some(message))
+        }
+        FieldType::Map(..) => return to_line(buf, None),
+        FieldType::Enum(ty) => {
+            let number = decode_varint(&mut buf).unwrap_or_default() as i32;
+            match ty.name_of(number) {
+                Some(name) => out.push_str(name),
+                None => write_display(&mut out, number),
+            }
+        }
+        FieldType::Scalar(ty) if wire_type == WireType::LengthDelimited => {
+            out.push('"');
+            escape(buf, ty == ScalarType::String, &mut out);
+            out.push('"');
+        }
+        FieldType::Scalar(ty) => {
+            let _ = scalar(ty, wire_type, &mut buf, &mut out);
+        }
+    }
+    out
+}
+
+/// Formats the values of an unknown field, as text format prints them.
+fn format_unknown(values: &[Value<'_>]) -> Option<String> {
+    let format = |&(wire_type, mut buf): &Value<'_>| match wire_type {
+        WireType::Varint => {
+            let mut out = String::new();
+            write_display(&mut out, decode_varint(&mut buf).unwrap_or_default());
+            out
+        }
+        WireType::ThirtyTwoBit => alloc::format!("0x{:08x}", buf.get_u32_le()),
+        WireType::SixtyFourBit => alloc::format!("0x{:016x}", buf.get_u64_le()),
+        WireType::LengthDelimited => format(FieldType::Scalar(ScalarType::Bytes), (wire_type, buf)),
+        _ => to_line(buf, None),
+    };
+    match values {
+        [] => None,
+        [value] => Some(format(value)),
+        values => {
+            let values: Vec<String> = values.iter().map(format).collect();
+            Some(alloc::format!("[{}]", values.join(", ")))
+        }
+    }
+}
+
+/// The values of a repeated field, with packed values split into elements.
+pub(crate) fn elements<'b>(
+    ty: FieldType,
+    values: &[Value<'b>],
+) -> Result<Vec<Value<'b>>, DecodeError> {
+    let mut elements = Vec::with_capacity(values.len());
+    for &(wire_type, mut buf) in values {
+        if wire_type == WireType::LengthDelimited && is_packable(ty) {
+            while buf.has_remaining() {
+                elements.push(read_value(0, scalar_wire_type(ty), &mut buf)?);
+            }
+        } else {
+            elements.push((wire_type, buf));
+        }
+    }
+    Ok(elements)
+}
+
+/// An element of an edit from one sequence to another.
+enum Edit {
+    Changed(usize, usize),
+    Removed(usize),
+    Added(usize),
+}
+
+/// Aligns two sequences on their longest common subsequence. Elements which are removed and
+/// added at the same place are paired up as changed.
+fn align<T: PartialEq>(old: &[T], new: &[T]) -> Vec<Edit> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let a = &old[prefix..old.len() - suffix];
+    let b = &new[prefix..new.len() - suffix];
+
+    // The pairs of equal elements of `a` and `b` kept in place
+    let mut kept = Vec::new();
+    if a.len().saturating_mul(b.len()) <= ALIGN_LIMIT {
+        // lengths[i * width + j] is the length of the longest common subsequence of a[i..] and b[j..]
+        let width = b.len() + 1;
+        let mut lengths = alloc::vec![0u32; (a.len() + 1) * width];
+        for i in (0..a.len()).rev() {
+            for j in (0..b.len()).rev() {
+                lengths[i * width + j] = if a[i] == b[j] {
+                    lengths[(i + 1) * width + j + 1] + 1
+                } else {
+                    lengths[(i + 1) * width + j].max(lengths[i * width + j + 1])
+                };
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        while i < a.len() && j < b.len() {
+            if a[i] == b[j] {
+                kept.push((i, j));
+                i += 1;
+                j += 1;
+            } else if lengths[(i + 1) * width + j] >= lengths[i * width + j + 1] {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+    }
+    kept.push((a.len(), b.len()));
+
+    let mut edits = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    for (next_i, next_j) in kept {
+        let changed = (next_i - i).min(next_j - j);
+        edits.extend((0..changed).map(|k| Edit::Changed(prefix + i + k, prefix + j + k)));
+        edits.extend((i + changed..next_i).map(|i| Edit::Removed(prefix + i)));
+        edits.extend((j + changed..next_j).map(|j| Edit::Added(prefix + j)));
+        (i, j) = (next_i + 1, next_j + 1);
+    }
+    edits
+}
+
+struct Differ {
+    path: String,
+    diffs: Vec<FieldDiff>,
+}
+
+impl Differ {
+    fn push(&mut self, old: Option<String>, new: Option<String>) {
+        self.diffs.push(FieldDiff {
+            path: self.path.clone(),
+            old,
+            new,
+        });
+    }
+
+    /// Appends a field name to the path, returning the length of the path to restore.
+    fn enter(&mut self, name: impl fmt::Display) -> usize {
+        let len = self.path.len();
+        if len > 0 {
+            self.path.push('.');
+        }
+        write_display(&mut self.path, name);
+        len
+    }
+
+    /// Appends an index or a map key to the path, returning the length of the path to restore.
+    fn index(&mut self, index: impl fmt::Display) -> usize {
+        let len = self.path.len();
+        self.path.push('[');
+        write_display(&mut self.path, index);
+        self.path.push(']');
+        len
+    }
+
+    fn message(&mut self, old: &[u8], new: &[u8], ty: MessageType) -> Result<(), DecodeError> {
+        let (old, new) = (records(old)?, records(new)?);
+
+        // A oneof which changed members is reported once, rather than as one member unset and
+        // another one set
+        let mut replaced = Vec::new();
+        for old_record in &old {
+            let Some(old_info) = ty.field_by_tag(old_record.tag) else {
+                continue;
+            };
+            let Some(oneof) = old_info.oneof() else {
+                continue;
+            };
+            let new_member = new.iter().find_map(|new_record| {
+                let info = ty.field_by_tag(new_record.tag)?;
+                (new_record.tag != old_record.tag && info.oneof() == Some(oneof))
+                    .then_some((info, new_record))
+            });
+            if let Some((new_info, new_record)) = new_member {
+                let len = self.enter(oneof);
+                self.push(
+                    Some(member(&old_info, old_record)),
+                    Some(member(&new_info, new_record)),
+                );
+                self.path.truncate(len);
+                replaced.extend([old_record.tag, new_record.tag]);
+            }
+        }
+
+        let (mut old, mut new) = (old.iter().peekable(), new.iter().peekable());
+        loop {
+            let (old_record, new_record) = match (old.peek(), new.peek()) {
+                (None, None) => break,
+                (Some(o), Some(n)) if o.tag == n.tag => (old.next(), new.next()),
+                (Some(o), Some(n)) if o.tag < n.tag => (old.next(), None),
+                (Some(_), None) => (old.next(), None),
+                _ => (None, new.next()),
+            };
+            let tag = old_record.or(new_record).map_or(0, |record| record.tag);
+            let old_values = old_record.map_or(&[][..], |record| &record.values);
+            let new_values = new_record.map_or(&[][..], |record| &record.values);
+            if old_values == new_values || replaced.contains(&tag) {
+                continue;
+            }
+            let info = ty.field_by_tag(tag);
+            let len = match &info {
+                Some(info) => self.enter(info.name()),
+                None => self.enter(tag),
+            };
+            match info {
+                Some(info) => self.field(&info, old_values, new_values)?,
+                None => self.push(format_unknown(old_values), format_unknown(new_values)),
+            }
+            self.path.truncate(len);
+        }
+        Ok(())
+    }
+
+    fn field(
+        &mut self,
+        info: &FieldInfo,
+        old: &[Value<'_>],
+        new: &[Value<'_>],
+    ) -> Result<(), DecodeError> {
+        match info.ty() {
+            FieldType::Map(key, value) => self.map(key, value, old, new),
+            ty if info.is_repeated() => {
+                let (old, new) = (elements(ty, old)?, elements(ty, new)?);
+                for edit in align(&old, &new) {
+                    let len = match edit {
+                        Edit::Changed(i, j) => {
+                            let len = self.index(j);
+                            self.value(ty, Some(old[i]), Some(new[j]))?;
+                            len
+                        }
+                        Edit::Removed(i) => {
+                            let len = self.index(i);
+                            self.push(Some(format(ty, old[i])), None);
+                            len
+                        }
+                        Edit::Added(j) => {
+                            let len = self.index(j);
+                            self.push(None, Some(format(ty, new[j])));
+                            len
+                        }
+                    };
+                    self.path.truncate(len);
+                }
+                Ok(())
+            }
+            ty => self.value(ty, old.last().copied(), new.last().copied()),
+        }
+    }
+
+    /// Compares a singular value, looking into messages set on both sides.
+    fn value(
+        &mut self,
+        ty: FieldType,
+        old: Option<Value<'_>>,
+        new: Option<Value<'_>>,
+    ) -> Result<(), DecodeError> {
+        match (ty, old, new) {
+            (_, old, new) if old == new => Ok(()),
+            (
+                FieldType::Message(message) | FieldType::Group(message, _),
+                Some((_, old)),
+                Some((_, new)),
+            ) => self.message(old, new, message),
+            (ty, old, new) => {
+                self.push(
+                    old.map(|old| format(ty, old)),
+                    new.map(|new| format(ty, new)),
+                );
+                Ok(())
+            }
+        }
+    }
+
+    fn map(
+        &mut self,
+        key_ty: ScalarType,
+        value_ty: MapValueType,
+        old: &[Value<'_>],
+        new: &[Value<'_>],
+    ) -> Result<(), DecodeError> {
+        let (old, new) = (
+            entries(key_ty, value_ty, old)?,
+            entries(key_ty, value_ty, new)?,
+        );
+        let key_ty = FieldType::Scalar(key_ty);
+        for &(key, old_value) in &old {
+            let len = self.index(format(key_ty, key));
+            self.value(value_ty.into(), Some(old_value), find_entry(&new, key))?;
+            self.path.truncate(len);
+        }
+        for &(key, new_value) in &new {
+            if find_entry(&old, key).is_none() {
+                let len = self.index(format(key_ty, key));
+                self.value(value_ty.into(), None, Some(new_value))?;
+                self.path.truncate(len);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Reads the keys and values of map entries. The canonical encoding writes both in every entry.
+pub(crate) fn entries<'b>(
+    key_ty: ScalarType,
+    value_ty: MapValueType,
+    values: &[Value<'b>],
+) -> Result<Vec<(Value<'b>, Value<'b>)>, DecodeError> {
+    values
+        .iter()
+        .map(|&(_, body)| {
+            let mut key = (scalar_wire_type(FieldType::Scalar(key_ty)), &[][..]);
+            let mut value = (scalar_wire_type(value_ty.into()), &[][..]);
+            for record in records(body)? {
+                match (record.tag, record.values.last()) {
+                    (1, Some(&read)) => key = read,
+                    (2, Some(&read)) => value = read,
+                    _ => {}
+                }
+            }
+            Ok((key, value))
+        })
+        .collect()
+}
+
+fn find_entry<'b>(entries: &[(Value<'b>, Value<'b>)], key: Value<'_>) -> Option<Value<'b>> {
+    entries
+        .iter()
+        .find(|(entry_key, _)| *entry_key == key)
+        .map(|&(_, value)| value)
+}
+
+/// Formats the member of a oneof set in a message, as text format prints it.
+fn member(info: &FieldInfo, record: &Record<'_>) -> String {
+    let value = record
+        .values
+        .last()
+        .copied()
+        .unwrap_or((WireType::Varint, &[]));
+    match info.ty() {
+        FieldType::Message(_) | FieldType::Group(..) => {
+            alloc::format!("{} {}", info.name(), format(info.ty(), value))
+        }
+        ty => alloc::format!("{}: {}", info.name(), format(ty, value)),
+    }
+}
@@ -11,7 +11,8 @@ use core::str;
 
 use ::bytes::{Buf, BufMut, Bytes};
 
-use crate::DecodeError;
+use crate::arena::{ArenaVec, ReverseBuf};
+use crate::{DecodeError, DecodeLimit, DecodeOptions, ExtensionRegistry, FieldInfo, Projection};
 
 pub mod varint;
 pub use varint::{decode_varint, encode_varint, encoded_len_varint};
@@ -29,46 +30,117 @@ pub use wire_type::{check_wire_type, WireType};
 /// The context should be passed by value and can be freely cloned. When passing
 /// to a function which is decoding a nested object, then use `enter_recursion`.
 #[derive(Clone, Debug)]
-#[cfg_attr(feature = "no-recursion-limit", derive(Default))]
-pub struct DecodeContext {
+pub struct DecodeContext<'p, 'arena> {
     /// How many times we can recurse in the current decode stack before we hit
     /// the recursion limit.
     ///
-    /// The recursion limit is defined by `RECURSION_LIMIT` and cannot be
-    /// customized. The recursion limit can be ignored by building the Prost
-    /// crate with the `no-recursion-limit` feature.
-    #[cfg(not(feature = "no-recursion-limit"))]
+    /// The recursion limit defaults to `RECURSION_LIMIT`, and can be customized
+    /// with `DecodeOptions::recursion_limit`. The default limit can be ignored by
+    /// building the crate with the `no-recursion-limit` feature.
     recurse_count: u32,
+
+    /// The maximum number of elements in a repeated or map field.
+    max_repeated_len: usize,
+
+    /// The maximum length of a string or bytes field.
+    max_string_len: usize,
+
+    /// The input buffer when decoding with `Decode::decode_borrowed`. String and
+    /// bytes fields which lie entirely within it are borrowed from the input
+    /// instead of being copied into the arena.
+    borrowed: Option<&'arena [u8]>,
+
+    /// The fields to decode at the current level of nesting, set with
+    /// `DecodeOptions::projection`. `None` decodes every field.
+    projection: Option<&'p Projection>,
+
+    /// The extensions to check while decoding, set with `DecodeOptions::extensions`.
+    extensions: Option<&'p ExtensionRegistry>,
 }
 
-#[cfg(not(feature = "no-recursion-limit"))]
-impl Default for DecodeContext {
+impl Default for DecodeContext<'_, '_> {
     #[inline]
-    fn default() -> DecodeContext {
+    fn default() -> Self {
+        DecodeContext::with_options(&DecodeOptions::new())
+    }
+}
+
+impl<'p, 'arena> DecodeContext<'p, 'arena> {
+    /// Creates a context which enforces the limits in `options`, and decodes with its
+    /// projection and extensions.
+    ///
+    /// The input length limit is checked up front by `Decode::decode_with_options`.
+    pub fn with_options(options: &DecodeOptions<'p>) -> Self {
         DecodeContext {
-            recurse_count: crate::RECURSION_LIMIT,
+            recurse_count: options.recursion_limit,
+            max_repeated_len: options.max_repeated_len,
+            max_string_len: options.max_string_len,
+            borrowed: None,
+            projection: options.projection,
+            extensions: options.extensions,
+        }
+    }
+
+    /// Creates a context for decoding `input` without copying string and bytes fields.
+    pub(crate) fn borrowing(input: &'arena [u8]) -> Self {
+        DecodeContext {
+            borrowed: Some(input),
+            ..DecodeContext::default()
         }
     }
-}
 
-impl DecodeContext {
+    /// Looks up the registered extension of `extendee` with the given field number.
+    #[inline]
+    fn extension(&self, extendee: &str, number: u32) -> Option<FieldInfo> {
+        self.extensions?.find(extendee, number)
+    }
+
+    /// Narrows the context to the field with the given tag.
+    ///
+    /// Returns `None` if the field is not selected by the projection, in which
+    /// case it should be skipped. Sub-messages of a field selected in part are
+    /// decoded with the returned context.
+    #[inline]
+    pub fn project(&self, tag: u32) -> Option<Self> {
+        let projection = match self.projection {
+            None => return Some(self.clone()),
+            Some(projection) => projection,
+        };
+        projection.get(tag).map(|nested| DecodeContext {
+            projection: nested,
+            ..self.clone()
+        })
+    }
+
     /// Call this function before recursively decoding.
     ///
     /// There is no `exit` function since this function creates a new `DecodeContext`
     /// to be used at the next level of recursion. Continue to use the old context
     // at the previous level of recursion.
-    #[cfg(not(feature = "no-recursion-limit"))]
     #[inline]
-    pub fn enter_recursion(&self) -> DecodeContext {
+    pub fn enter_recursion(&self) -> Self {
         DecodeContext {
-            recurse_count: self.recurse_count - 1,
+            recurse_count: self.recurse_count.saturating_sub(1),
+            ..self.clone()
         }
     }
 
-    #[cfg(feature = "no-recursion-limit")]
+    /// Takes the next `len` bytes from `buf` without copying them, provided they
+    /// are part of the input passed to `Decode::decode_borrowed`.
+    ///
+    /// Returns `None`, leaving `buf` untouched, when the context is not borrowing
+    /// or the bytes are not contiguous within the input.
     #[inline]
-    pub fn enter_recursion(&self) -> DecodeContext {
-        DecodeContext {}
+    fn borrow_bytes(&self, buf: &mut impl Buf, len: usize) -> Option<&'arena [u8]> {
+        let input = self.borrowed?;
+        let chunk = buf.chunk();
+        if chunk.len() < len {
+            return None;
+        }
+        let start = (chunk.as_ptr() as usize).checked_sub(input.as_ptr() as usize)?;
+        let bytes = input.get(start..start.checked_add(len)?)?;
+        buf.advance(len);
+        Some(bytes)
     }
 
     /// Checks whether the recursion limit has been reached in the stack of
@@ -76,20 +148,75 @@ impl DecodeContext {
     ///
     /// Returns `Ok<()>` if it is ok to continue recursing.
     /// Returns `Err<DecodeError>` if the recursion limit has been reached.
-    #[cfg(not(feature = "no-recursion-limit"))]
     #[inline]
     pub fn limit_reached(&self) -> Result<(), DecodeError> {
         if self.recurse_count == 0 {
-            Err(DecodeError::new("recursion limit reached"))
+            Err(DecodeError::limit(DecodeLimit::RecursionDepth))
         } else {
             Ok(())
         }
     }
 
-    #[cfg(feature = "no-recursion-limit")]
+    /// Checks whether another element may be added to a repeated or map field
+    /// which already holds `len` elements.
     #[inline]
-    pub fn limit_reached(&self) -> Result<(), DecodeError> {
-        Ok(())
+    pub fn check_repeated_len(&self, len: usize) -> Result<(), DecodeError> {
+        if len >= self.max_repeated_len {
+            Err(DecodeError::limit(DecodeLimit::RepeatedLength))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Checks whether a string or bytes field of `len` bytes may be decoded.
+    #[inline]
+    pub fn check_string_len(&self, len: usize) -> Result<(), DecodeError> {
+        if len > self.max_string_len {
+            Err(DecodeError::limit(DecodeLimit::StringLength))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Lengths of nested messages, computed once by `Encode::encoded_len_cached` and
+/// consumed in the same order by `Encode::encode_raw_cached`.
+///
+/// Writing a nested message's length prefix otherwise recomputes the length of
+/// everything beneath it, which makes encoding quadratic in the nesting depth.
+#[derive(Clone, Debug, Default)]
+pub struct SizeCache {
+    sizes: Vec<usize>,
+    next: usize,
+}
+
+impl SizeCache {
+    /// Creates an empty cache. No memory is allocated until a length is recorded.
+    #[inline]
+    pub fn new() -> SizeCache {
+        SizeCache::default()
+    }
+
+    /// Reserves a slot for a nested message whose length isn't known until its own
+    /// fields have been measured.
+    #[inline]
+    pub fn reserve_slot(&mut self) -> usize {
+        self.sizes.push(0);
+        self.sizes.len() - 1
+    }
+
+    /// Records the length of the nested message for a slot from `reserve_slot`.
+    #[inline]
+    pub fn record(&mut self, slot: usize, len: usize) {
+        self.sizes[slot] = len;
+    }
+
+    /// Returns the next recorded length, in the order the slots were reserved.
+    #[inline]
+    pub fn next_len(&mut self) -> usize {
+        let len = self.sizes[self.next];
+        self.next += 1;
+        len
     }
 }
 
@@ -132,14 +259,14 @@ pub const fn key_len(tag: u32) -> usize {
 
 /// Helper function which abstracts reading a length delimiter prefix followed
 /// by decoding values until the length of bytes is exhausted.
-pub fn merge_loop<T, M, B>(
+pub fn merge_loop<'p, 'arena, T, M, B>(
     value: &mut T,
     buf: &mut B,
-    ctx: DecodeContext,
+    ctx: DecodeContext<'p, 'arena>,
     mut merge: M,
 ) -> Result<(), DecodeError>
 where
-    M: FnMut(&mut T, &mut B, DecodeContext) -> Result<(), DecodeError>,
+    M: FnMut(&mut T, &mut B, DecodeContext<'p, 'arena>) -> Result<(), DecodeError>,
     B: Buf,
 {
     let len = decode_varint(buf)?;
@@ -194,6 +321,97 @@ pub fn skip_field(
     Ok(())
 }
 
+/// Consumes an unrecognized field like `skip_field`, but appends the complete
+/// record (key included) to `unknown` so that it can be re-emitted when the
+/// message is encoded again.
+pub fn merge_unknown_field(
+    wire_type: WireType,
+    tag: u32,
+    buf: &mut impl Buf,
+    unknown: &mut ArenaVec<'_, u8>,
+    ctx: DecodeContext,
+) -> Result<(), DecodeError> {
+    ctx.limit_reached()?;
+    unknown.try_reserve(key_len(tag))?;
+    encode_key(tag, wire_type, unknown);
+    let len = match wire_type {
+        WireType::Varint => {
+            let value = decode_varint(buf)?;
+            unknown.try_reserve(encoded_len_varint(value))?;
+            encode_varint(value, unknown);
+            return Ok(());
+        }
+        WireType::ThirtyTwoBit => 4,
+        WireType::SixtyFourBit => 8,
+        WireType::LengthDelimited => {
+            let len = decode_varint(buf)?;
+            unknown.try_reserve(encoded_len_varint(len))?;
+            encode_varint(len, unknown);
+            len
+        }
+        WireType::StartGroup => loop {
+            let (inner_tag, inner_wire_type) = decode_key(buf)?;
+            match inner_wire_type {
+                WireType::EndGroup => {
+                    if inner_tag != tag {
+                        return Err(DecodeError::new("unexpected end group tag"));
+                    }
+                    unknown.try_reserve(key_len(inner_tag))?;
+                    encode_key(inner_tag, WireType::EndGroup, unknown);
+                    return Ok(());
+                }
+                _ => merge_unknown_field(
+                    inner_wire_type,
+                    inner_tag,
+                    buf,
+                    unknown,
+                    ctx.enter_recursion(),
+                )?,
+            }
+        },
+        WireType::EndGroup => return Err(DecodeError::new("unexpected end group tag")),
+    };
+
+    if len > buf.remaining() as u64 {
+        return Err(DecodeError::new("buffer underflow"));
+    }
+
+    let mut len = len as usize;
+    unknown.try_reserve(len)?;
+    while len > 0 {
+        let chunk = buf.chunk();
+        let n = chunk.len().min(len);
+        unknown.extend_from_slice(&chunk[..n]);
+        buf.advance(n);
+        len -= n;
+    }
+    Ok(())
+}
+
+/// Consumes an unrecognized field of the message named `extendee` like
+/// `merge_unknown_field`, and checks it if it is an extension registered with
+/// the registry passed to `DecodeOptions::extensions`.
+pub fn merge_extension_field(
+    extendee: &'static str,
+    wire_type: WireType,
+    tag: u32,
+    buf: &mut impl Buf,
+    unknown: &mut ArenaVec<'_, u8>,
+    ctx: DecodeContext,
+) -> Result<(), DecodeError> {
+    let extension = ctx.extension(extendee, tag);
+    let depth = ctx.recurse_count;
+    let start = unknown.len();
+    merge_unknown_field(wire_type, tag, buf, unknown, ctx)?;
+    match extension {
+        Some(info) => crate::extension::check(&info, &unknown[start..], depth).map_err(|mut error| {
+            error.push(extendee, info.name());
+            error
+        }),
+        None => Ok(()),
+    }
+}
+
 /// Helper macro which emits an `encode_repeated` function for the type.
 macro_rules! encode_repeated {
     ($ty:ty) => {
@@ -205,6 +423,29 @@ macro_rules! encode_repeated {
     };
 }
 
+/// A collection which decoded repeated values are appended to.
+///
+/// Appending to an `ArenaVec` fails, rather than aborting, once the arena's
+/// budget is exhausted (see `Arena::with_limit`).
+pub trait RepeatedValues<T>: core::ops::DerefMut<Target = [T]> {
+    fn try_push(&mut self, value: T) -> Result<(), DecodeError>;
+}
+
+impl<T> RepeatedValues<T> for ArenaVec<'_, T> {
+    #[inline]
+    fn try_push(&mut self, value: T) -> Result<(), DecodeError> {
+        ArenaVec::try_push(self, value)
+    }
+}
+
+impl<T> RepeatedValues<T> for Vec<T> {
+    #[inline]
+    fn try_push(&mut self, value: T) -> Result<(), DecodeError> {
+        self.push(value);
+        Ok(())
+    }
+}
+
 /// Helper macro which emits a `merge_repeated` function for the numeric type.
 macro_rules! merge_repeated_numeric {
     ($ty:ty,
@@ -218,23 +459,23 @@ macro_rules! merge_repeated_numeric {
             ctx: DecodeContext,
         ) -> Result<(), DecodeError>
         where
-            V: core::ops::DerefMut<Target = [$ty]> + core::iter::Extend<$ty>,
+            V: RepeatedValues<$ty>,
         {
             if wire_type == WireType::LengthDelimited {
                 // Packed.
                 merge_loop(values, buf, ctx, |values, buf, ctx| {
+                    ctx.check_repeated_len(values.len())?;
                     let mut value = Default::default();
                     $merge($wire_type, &mut value, buf, ctx)?;
-                    values.extend(core::iter::once(value));
-                    Ok(())
+                    values.try_push(value)
                 })
             } else {
                 // Unpacked.
                 check_wire_type($wire_type, wire_type)?;
+                ctx.check_repeated_len(values.len())?;
                 let mut value = Default::default();
                 $merge(wire_type, &mut value, buf, ctx)?;
-                values.extend(core::iter::once(value));
-                Ok(())
+                values.try_push(value)
             }
         }
     };
@@ -572,23 +813,9 @@ pub mod string {
         wire_type: WireType,
         buf: &mut impl Buf,
         arena: &'arena Arena,
-        _ctx: DecodeContext,
+        ctx: DecodeContext<'_, 'arena>,
     ) -> Result<&'arena str, DecodeError> {
-        check_wire_type(WireType::LengthDelimited, wire_type)?;
-
-        // Decode the length
-        let len = decode_varint(buf)?;
-        if len > buf.remaining() as u64 {
-            return Err(DecodeError::new("buffer underflow"));
-        }
-        let len = len as usize;
-
-        // Allocate uninitialized buffer and copy directly (single copy, no zero-fill)
-        let mut vec = arena.new_vec_with_capacity::<u8>(len);
-        unsafe {
-            vec.copy_from_buf_uninit(buf, len);
-        }
-        let bytes = vec.freeze();
+        let bytes = super::bytes::merge_arena(wire_type, buf, arena, ctx)?;
 
         // Validate UTF-8 and convert to &str
         str::from_utf8(bytes)
@@ -608,12 +835,12 @@ pub mod string {
         values: &mut crate::arena::ArenaVec<'a, &'a str>,
         buf: &mut impl Buf,
         arena: &'a crate::Arena,
-        ctx: DecodeContext,
+        ctx: DecodeContext<'_, 'a>,
     ) -> Result<(), DecodeError> {
         check_wire_type(WireType::LengthDelimited, wire_type)?;
+        ctx.check_repeated_len(values.len())?;
         let value = merge_arena(wire_type, buf, arena, ctx)?;
-        values.push(value);
-        Ok(())
+        values.try_push(value)
     }
 
     #[inline]
@@ -705,7 +932,7 @@ pub mod bytes {
         wire_type: WireType,
         buf: &mut impl Buf,
         arena: &'arena Arena,
-        _ctx: DecodeContext,
+        ctx: DecodeContext<'_, 'arena>,
     ) -> Result<&'arena [u8], DecodeError> {
         check_wire_type(WireType::LengthDelimited, wire_type)?;
 
@@ -715,9 +942,15 @@ pub mod bytes {
             return Err(DecodeError::new("buffer underflow"));
         }
         let len = len as usize;
+        ctx.check_string_len(len)?;
+
+        // Point straight into the input when decoding with `Decode::decode_borrowed`.
+        if let Some(bytes) = ctx.borrow_bytes(buf, len) {
+            return Ok(bytes);
+        }
 
         // Allocate uninitialized buffer and copy directly (single copy, no zero-fill)
-        let mut vec = arena.new_vec_with_capacity::<u8>(len);
+        let mut vec = arena.try_new_vec_with_capacity::<u8>(len)?;
         unsafe {
             vec.copy_from_buf_uninit(buf, len);
         }
@@ -751,12 +984,12 @@ pub mod bytes {
         values: &mut crate::arena::ArenaVec<'a, &'a [u8]>,
         buf: &mut impl Buf,
         arena: &'a crate::Arena,
-        ctx: DecodeContext,
+        ctx: DecodeContext<'_, 'a>,
     ) -> Result<(), DecodeError> {
         check_wire_type(WireType::LengthDelimited, wire_type)?;
+        ctx.check_repeated_len(values.len())?;
         let value = merge_arena(wire_type, buf, arena, ctx)?;
-        values.push(value);
-        Ok(())
+        values.try_push(value)
     }
 
     // Tests removed - bytes encoding only supports arena-allocated &[u8], not owned Vec/Bytes
@@ -781,7 +1014,7 @@ pub mod message {
         msg: &mut M,
         buf: &mut B,
         arena: &'arena Arena,
-        ctx: DecodeContext,
+        ctx: DecodeContext<'_, 'arena>,
     ) -> Result<(), DecodeError>
     where
         M: Decode<'arena>,
@@ -814,16 +1047,16 @@ pub mod message {
         messages: &mut crate::arena::ArenaVec<'arena, M>,
         buf: &mut impl Buf,
         arena: &'arena Arena,
-        ctx: DecodeContext,
+        ctx: DecodeContext<'_, 'arena>,
     ) -> Result<(), DecodeError>
     where
         M: Decode<'arena>,
     {
         check_wire_type(WireType::LengthDelimited, wire_type)?;
+        ctx.check_repeated_len(messages.len())?;
         let mut msg = M::new_in(arena);
         merge(WireType::LengthDelimited, &mut msg, buf, arena, ctx)?;
-        messages.push(msg);
-        Ok(())
+        messages.try_push(msg)
     }
 
     #[inline]
@@ -835,6 +1068,42 @@ pub mod message {
         key_len(tag) + encoded_len_varint(len as u64) + len
     }
 
+    /// Like `encode`, but takes the length prefix of `msg` and its nested messages
+    /// from `sizes` instead of recomputing them.
+    pub fn encode_cached<M>(tag: u32, msg: &M, buf: &mut impl BufMut, sizes: &mut SizeCache)
+    where
+        M: Encode,
+    {
+        encode_key(tag, WireType::LengthDelimited, buf);
+        encode_varint(sizes.next_len() as u64, buf);
+        msg.encode_raw_cached(buf, sizes);
+    }
+
+    /// Writes `msg` in front of the contents of `buf`, followed by its length prefix
+    /// and key, which end up before it.
+    pub fn encode_reverse<M>(tag: u32, msg: &M, buf: &mut ReverseBuf<'_>)
+    where
+        M: Encode,
+    {
+        let end = buf.len();
+        msg.encode_reverse(buf);
+        buf.put_varint((buf.len() - end) as u64);
+        buf.put_key(tag, WireType::LengthDelimited);
+    }
+
+    /// Like `encoded_len`, but records the length of `msg` and its nested messages
+    /// in `sizes` for `encode_cached`.
+    #[inline]
+    pub fn encoded_len_cached<M>(tag: u32, msg: &M, sizes: &mut SizeCache) -> usize
+    where
+        M: Encode,
+    {
+        let slot = sizes.reserve_slot();
+        let len = msg.encoded_len_cached(sizes);
+        sizes.record(slot, len);
+        key_len(tag) + encoded_len_varint(len as u64) + len
+    }
+
     #[inline]
     pub fn encoded_len_repeated<M>(tag: u32, messages: &[M]) -> usize
     where
@@ -849,6 +1118,59 @@ pub mod message {
     }
 }
 
+/// Sub-message fields which keep their encoded body, see [`LazyMessage`](crate::LazyMessage).
+///
+/// Encoding goes through [`message`], since `LazyMessage` implements `Encode` by writing the
+/// body verbatim.
+pub mod lazy_message {
+    use super::*;
+    use crate::{Arena, LazyMessage};
+
+    /// Reads the body of a sub-message without decoding it.
+    ///
+    /// A repeated occurrence of the field is appended to the bytes already held, which is how
+    /// the wire format merges two occurrences of a singular message field.
+    pub fn merge<'arena, M>(
+        wire_type: WireType,
+        value: &mut Option<LazyMessage<'arena, M>>,
+        buf: &mut impl Buf,
+        arena: &'arena Arena,
+        ctx: DecodeContext<'_, 'arena>,
+    ) -> Result<(), DecodeError> {
+        check_wire_type(WireType::LengthDelimited, wire_type)?;
+        ctx.limit_reached()?;
+
+        let len = decode_varint(buf)?;
+        if len > buf.remaining() as u64 {
+            return Err(DecodeError::new("buffer underflow"));
+        }
+        let len = len as usize;
+
+        let existing = value.map_or(&[][..], |value| value.as_bytes());
+        ctx.check_string_len(existing.len() + len)?;
+        let bytes = if existing.is_empty() {
+            match ctx.borrow_bytes(buf, len) {
+                Some(bytes) => bytes,
+                None => {
+                    let mut vec = arena.try_new_vec_with_capacity::<u8>(len)?;
+                    unsafe {
+                        vec.copy_from_buf_uninit(buf, len);
+                    }
+                    vec.freeze()
+                }
+            }
+        } else {
+            let mut vec = arena.try_new_vec_with_capacity::<u8>(existing.len() + len)?;
+            vec.extend_from_slice(existing);
+            vec.resize(existing.len() + len, 0);
+            buf.copy_to_slice(&mut vec[existing.len()..]);
+            vec.freeze()
+        };
+        *value = Some(LazyMessage::new(bytes));
+        Ok(())
+    }
+}
+
 pub mod group {
     use super::*;
     use crate::Arena;
@@ -869,7 +1191,7 @@ pub mod group {
         msg: &mut M,
         buf: &mut impl Buf,
         arena: &'arena Arena,
-        ctx: DecodeContext,
+        ctx: DecodeContext<'_, 'arena>,
     ) -> Result<(), DecodeError>
     where
         M: Decode<'arena>,
@@ -911,16 +1233,16 @@ pub mod group {
         messages: &mut crate::arena::ArenaVec<'arena, M>,
         buf: &mut impl Buf,
         arena: &'arena Arena,
-        ctx: DecodeContext,
+        ctx: DecodeContext<'_, 'arena>,
     ) -> Result<(), DecodeError>
     where
         M: Decode<'arena>,
     {
         check_wire_type(WireType::StartGroup, wire_type)?;
+        ctx.check_repeated_len(messages.len())?;
         let mut msg = M::new_in(arena);
         merge(tag, WireType::StartGroup, &mut msg, buf, arena, ctx)?;
-        messages.push(msg);
-        Ok(())
+        messages.try_push(msg)
     }
 
     #[inline]
@@ -931,6 +1253,35 @@ pub mod group {
         2 * key_len(tag) + msg.encoded_len()
     }
 
+    /// Like `encode`, but takes the lengths of nested messages from `sizes`.
+    pub fn encode_cached<M>(tag: u32, msg: &M, buf: &mut impl BufMut, sizes: &mut SizeCache)
+    where
+        M: Encode,
+    {
+        encode_key(tag, WireType::StartGroup, buf);
+        msg.encode_raw_cached(buf, sizes);
+        encode_key(tag, WireType::EndGroup, buf);
+    }
+
+    /// Like `encoded_len`, but records the lengths of nested messages in `sizes`.
+    #[inline]
+    pub fn encoded_len_cached<M>(tag: u32, msg: &M, sizes: &mut SizeCache) -> usize
+    where
+        M: Encode,
+    {
+        2 * key_len(tag) + msg.encoded_len_cached(sizes)
+    }
+
+    /// Writes `msg` in front of the contents of `buf`, between its start and end keys.
+    pub fn encode_reverse<M>(tag: u32, msg: &M, buf: &mut ReverseBuf<'_>)
+    where
+        M: Encode,
+    {
+        buf.put_key(tag, WireType::EndGroup);
+        msg.encode_reverse(buf);
+        buf.put_key(tag, WireType::StartGroup);
+    }
+
     #[inline]
     pub fn encoded_len_repeated<M>(tag: u32, messages: &[M]) -> usize
     where
@@ -945,7 +1296,7 @@ pub mod group {
 /// These functions work with ArenaVec during decoding (accumulating entries)
 /// and with slices during encoding (from ArenaMap).
 pub mod arena_map {
-    use crate::arena::ArenaVec;
+    use crate::arena::{ArenaVec, ReverseBuf};
     use crate::encoding::*;
 
     /// Generic protobuf map merge function for arena-allocated maps.
@@ -960,7 +1311,7 @@ pub mod arena_map {
         values: &mut ArenaVec<'arena, (K, V)>,
         buf: &mut B,
         arena: &'arena crate::Arena,
-        ctx: DecodeContext,
+        ctx: DecodeContext<'_, 'arena>,
     ) -> Result<(), DecodeError>
     where
         B: Buf,
@@ -969,19 +1320,20 @@ pub mod arena_map {
             &mut K,
             &mut B,
             &'arena crate::Arena,
-            DecodeContext,
+            DecodeContext<'_, 'arena>,
         ) -> Result<(), DecodeError>,
         VM: Fn(
             WireType,
             &mut V,
             &mut B,
             &'arena crate::Arena,
-            DecodeContext,
+            DecodeContext<'_, 'arena>,
         ) -> Result<(), DecodeError>,
     {
         let mut key = key_default;
         let mut val = val_default;
         ctx.limit_reached()?;
+        ctx.check_repeated_len(values.len())?;
         merge_loop(
             &mut (&mut key, &mut val),
             buf,
@@ -995,9 +1347,7 @@ pub mod arena_map {
                 }
             },
         )?;
-        values.push((key, val));
-
-        Ok(())
+        values.try_push((key, val))
     }
 
     /// Map merge function for message values - DEPRECATED
@@ -1014,7 +1364,7 @@ pub mod arena_map {
         _values: &mut ArenaVec<'arena, (K, VView)>,
         _buf: &mut B,
         _arena: &'arena crate::Arena,
-        _ctx: DecodeContext,
+        _ctx: DecodeContext<'_, 'arena>,
     ) -> Result<(), DecodeError>
     where
         B: Buf,
@@ -1023,14 +1373,14 @@ pub mod arena_map {
             &mut K,
             &mut B,
             &'arena crate::Arena,
-            DecodeContext,
+            DecodeContext<'_, 'arena>,
         ) -> Result<(), DecodeError>,
         VM: Fn(
             WireType,
             &mut VBuilder,
             &mut B,
             &'arena crate::Arena,
-            DecodeContext,
+            DecodeContext<'_, 'arena>,
         ) -> Result<(), DecodeError>,
         VN: Fn(&'arena crate::Arena) -> VBuilder,
         VF: Fn(VBuilder) -> VView,
@@ -1113,6 +1463,57 @@ pub mod arena_map {
         }
     }
 
+    /// Map encode function for message values, which takes the lengths of the
+    /// entries and their values from `sizes`.
+    pub fn encode_message_cached<K, V, B, KE>(
+        key_encode: KE,
+        key_default: &K,
+        tag: u32,
+        values: &[(K, V)],
+        buf: &mut B,
+        sizes: &mut SizeCache,
+    ) where
+        K: PartialEq,
+        V: crate::Encode,
+        B: BufMut,
+        KE: Fn(u32, &K, &mut B),
+    {
+        for (key, val) in values.iter() {
+            encode_key(tag, WireType::LengthDelimited, buf);
+            encode_varint(sizes.next_len() as u64, buf);
+            if key != key_default {
+                key_encode(1, key, buf);
+            }
+            message::encode_cached(2, val, buf, sizes);
+        }
+    }
+
+    /// Map encode function for message values, which writes the entries last to
+    /// first in front of the contents of `buf`.
+    pub fn encode_message_reverse<K, V, KE, KL>(
+        key_encode: KE,
+        key_encoded_len: KL,
+        key_default: &K,
+        tag: u32,
+        values: &[(K, V)],
+        buf: &mut ReverseBuf<'_>,
+    ) where
+        K: PartialEq,
+        V: crate::Encode,
+        KE: Fn(u32, &K, &mut &mut [u8]),
+        KL: Fn(u32, &K) -> usize,
+    {
+        for (key, val) in values.iter().rev() {
+            let end = buf.len();
+            message::encode_reverse(2, val, buf);
+            if key != key_default {
+                key_encode(1, key, &mut buf.prepend(key_encoded_len(1, key)));
+            }
+            buf.put_varint((buf.len() - end) as u64);
+            buf.put_key(tag, WireType::LengthDelimited);
+        }
+    }
+
     /// Generic protobuf map encoded length function with key and value defaults.
     pub fn encoded_len_with_defaults<K, V, KL, VL>(
         key_encoded_len: KL,
@@ -1176,6 +1577,36 @@ pub mod arena_map {
                 })
                 .sum::<usize>()
     }
+
+    /// Map encoded length function for message values, which records the lengths
+    /// of the entries and their values in `sizes` for `encode_message_cached`.
+    pub fn encoded_len_message_cached<K, V, KL>(
+        key_encoded_len: KL,
+        key_default: &K,
+        tag: u32,
+        values: &[(K, V)],
+        sizes: &mut SizeCache,
+    ) -> usize
+    where
+        K: PartialEq,
+        V: crate::Encode,
+        KL: Fn(u32, &K) -> usize,
+    {
+        key_len(tag) * values.len()
+            + values
+                .iter()
+                .map(|(key, val)| {
+                    let slot = sizes.reserve_slot();
+                    let len = (if key == key_default {
+                        0
+                    } else {
+                        key_encoded_len(1, key)
+                    }) + message::encoded_len_cached(2, val, sizes);
+                    sizes.record(slot, len);
+                    encoded_len_varint(len as u64) + len
+                })
+                .sum::<usize>()
+    }
 }
 
 #[cfg(test)]
@@ -1377,4 +1808,10 @@ mod test {
             assert_eq!(v, decode_varint(&mut c).unwrap());
         }
     }
+
+    #[test]
+    fn decode_context_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<DecodeContext<'_, '_>>();
+    }
 }
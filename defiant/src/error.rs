@@ -26,6 +26,47 @@ struct Inner {
     /// message type and field where decoding failed. The stack contains an
     /// entry per level of nesting.
     stack: Vec<(&'static str, &'static str)>,
+    /// The decoding limit that was exceeded, if that is why decoding failed.
+    limit: Option<DecodeLimit>,
+}
+
+/// A limit on decoding which untrusted input can exceed.
+///
+/// Returned by [`DecodeError::limit_exceeded`] so callers can tell resource limits
+/// apart from malformed input.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum DecodeLimit {
+    /// Messages, groups or map entries were nested too deeply.
+    RecursionDepth,
+    /// The input was longer than [`DecodeOptions::max_input_len`](crate::DecodeOptions::max_input_len).
+    InputLength,
+    /// A repeated or map field had more elements than
+    /// [`DecodeOptions::max_repeated_len`](crate::DecodeOptions::max_repeated_len).
+    RepeatedLength,
+    /// A string or bytes field was longer than
+    /// [`DecodeOptions::max_string_len`](crate::DecodeOptions::max_string_len).
+    StringLength,
+    /// The arena ran out of budget, see [`Arena::with_limit`](crate::Arena::with_limit).
+    ArenaBudget,
+}
+
+impl DecodeLimit {
+    fn description(self) -> &'static str {
+        match self {
+            DecodeLimit::RecursionDepth => "recursion limit reached",
+            DecodeLimit::InputLength => "input length limit exceeded",
+            DecodeLimit::RepeatedLength => "repeated field length limit exceeded",
+            DecodeLimit::StringLength => "string length limit exceeded",
+            DecodeLimit::ArenaBudget => "arena budget exceeded",
+        }
+    }
+}
+
+impl fmt::Display for DecodeLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.description())
+    }
 }
 
 impl DecodeError {
@@ -39,10 +80,28 @@ impl DecodeError {
             inner: Box::new(Inner {
                 description: description.into(),
                 stack: Vec::new(),
+                limit: None,
             }),
         }
     }
 
+    /// Creates a new `DecodeError` for an exceeded decoding limit.
+    ///
+    /// Meant to be used only by `Message` implementations.
+    #[doc(hidden)]
+    #[cold]
+    pub fn limit(limit: DecodeLimit) -> DecodeError {
+        let mut error = DecodeError::new(limit.description());
+        error.inner.limit = Some(limit);
+        error
+    }
+
+    /// Returns the decoding limit that was exceeded, or `None` if decoding failed
+    /// for another reason.
+    pub fn limit_exceeded(&self) -> Option<DecodeLimit> {
+        self.inner.limit
+    }
+
     /// Pushes a (message, field) name location pair on to the location stack.
     ///
     /// Meant to be used only by `Message` implementations.
@@ -83,13 +142,20 @@ impl From<DecodeError> for std::io::Error {
 
 /// A Protobuf message encoding error.
 ///
-/// `EncodeError` always indicates that a message failed to encode because the
-/// provided buffer had insufficient capacity. Message encoding is otherwise
-/// infallible.
+/// `EncodeError` indicates that a message failed to encode because the provided
+/// buffer had insufficient capacity, because the message was too long for the
+/// length prefix of a [`DelimitedWriter`](crate::DelimitedWriter), or because a
+/// message encoded with `encode_checked` was missing required fields. Message
+/// encoding is otherwise infallible.
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub struct EncodeError {
     required: usize,
     remaining: usize,
+    /// Whether `remaining` is the largest length a length prefix can hold, rather than the
+    /// capacity of a buffer.
+    prefix_overflow: bool,
+    /// Whether the message was missing required fields, rather than too long.
+    missing_required: bool,
 }
 
 impl EncodeError {
@@ -99,6 +165,30 @@ impl EncodeError {
         EncodeError {
             required,
             remaining,
+            prefix_overflow: false,
+            missing_required: false,
+        }
+    }
+
+    /// Creates an error for a message which is missing required fields.
+    #[doc(hidden)]
+    pub fn missing_required_fields() -> EncodeError {
+        EncodeError {
+            required: 0,
+            remaining: 0,
+            prefix_overflow: false,
+            missing_required: true,
+        }
+    }
+
+    /// Creates an error for a message of `required` bytes, which is longer than the
+    /// `max` bytes its length prefix can hold.
+    pub(crate) fn prefix_overflow(required: usize, max: usize) -> EncodeError {
+        EncodeError {
+            required,
+            remaining: max,
+            prefix_overflow: true,
+            missing_required: false,
         }
     }
 
@@ -111,10 +201,25 @@ impl EncodeError {
     pub fn remaining(&self) -> usize {
         self.remaining
     }
+
+    /// Returns `true` if the message wasn't encoded because required fields are missing from it.
+    pub fn is_missing_required_fields(&self) -> bool {
+        self.missing_required
+    }
 }
 
 impl fmt::Display for EncodeError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.missing_required {
+            return f.write_str("failed to encode Protobuf message; missing required fields");
+        }
+        if self.prefix_overflow {
+            return write!(
+                f,
+                "failed to encode Protobuf message; too long for its length prefix (required: {}, maximum: {})",
+                self.required, self.remaining
+            );
+        }
         write!(
             f,
             "failed to encode Protobuf message; insufficient buffer capacity (required: {}, remaining: {})",
@@ -0,0 +1,453 @@
+//! Proto2 extensions.
+//!
+//! An extension is a field declared outside of the message it extends, in an `extend` block.
+//! `defiant-build` generates an [`Extension`] constant for each one, and the messages it extends
+//! keep the encoded extensions along with their unknown fields. Views and builders read and
+//! write them through the typed constant:
+//!
+//! ```
+//! use defiant::{Arena, Extension, FieldInfo, FieldType, ScalarType, UnknownFields, View};
+//!
+//! #[derive(View)]
+//! #[defiant(full_name = "acme.Envelope")]
+//! struct Envelope<'arena> {
+//!     #[defiant(string, tag = "1")]
+//!     route: &'arena str,
+//!     #[defiant(unknown_fields)]
+//!     unknown_fields: UnknownFields<'arena>,
+//! }
+//!
+//! // extend Envelope { optional uint32 priority = 100; }
+//! const PRIORITY: Extension<Envelope<'static>, u32> = Extension::new(
+//!     "acme.Envelope",
+//!     FieldInfo::new("acme.priority", 100, FieldType::Scalar(ScalarType::Uint32)),
+//! );
+//!
+//! let arena = Arena::new();
+//! let mut builder = Envelope::builder(&arena);
+//! builder.set_extension(&PRIORITY, 7);
+//! let envelope = builder.freeze();
+//! assert!(envelope.has_extension(&PRIORITY));
+//! assert_eq!(envelope.get_extension(&PRIORITY, &arena).unwrap(), Some(7));
+//! ```
+//!
+//! Extensions are decoded when they are read. Decoding with an [`ExtensionRegistry`] checks the
+//! registered extensions as the message is decoded instead, so that malformed ones are reported
+//! by the decode.
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::fmt;
+use core::marker::PhantomData;
+
+use bytes::{Buf, BufMut};
+
+use crate::canonical::canonicalize;
+use crate::diff::{elements, read_value, records, Value};
+use crate::encoding::{
+    check_wire_type, decode_key, decode_varint, encode_key, encode_varint, group, message, WireType,
+};
+use crate::schema::{FieldInfo, FieldType, ScalarType};
+use crate::text_format::{ensure_remaining, matches_wire_type, scalar_wire_type};
+use crate::{Arena, ArenaClone, ArenaVec, DecodeError, Encode, UnknownFields};
+
+/// An extension of messages of type `M`, holding values of type `T`.
+///
+/// `M` is the view of the extended message and `T` the type of the extension's values, both
+/// with `'static` lifetimes, such as `Extension<Envelope<'static>, &'static str>`. Repeated
+/// extensions hold slices, such as `&'static [u32]`, and enum extensions hold `i32` like enum
+/// fields do.
+pub struct Extension<M, T> {
+    extendee: &'static str,
+    info: FieldInfo,
+    _marker: PhantomData<fn() -> (M, T)>,
+}
+
+impl<M, T> Extension<M, T> {
+    /// Creates an extension of the message named `extendee`, such as `acme.Envelope`.
+    ///
+    /// The name in `info` is the fully qualified name of the extension, and its tag is the
+    /// extension's field number.
+    pub const fn new(extendee: &'static str, info: FieldInfo) -> Self {
+        Extension {
+            extendee,
+            info,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns the fully qualified `.proto` name of the extended message.
+    pub const fn extendee(&self) -> &'static str {
+        self.extendee
+    }
+
+    /// Returns the fully qualified `.proto` name of the extension.
+    pub const fn name(&self) -> &'static str {
+        self.info.name()
+    }
+
+    /// Returns the field number of the extension.
+    pub const fn number(&self) -> u32 {
+        self.info.tag()
+    }
+
+    /// Returns the extension as a field of the extended message.
+    pub const fn info(&self) -> FieldInfo {
+        self.info
+    }
+
+    /// Returns `true` if `fields` hold a value of the extension.
+    pub fn has(&self, fields: UnknownFields<'_>) -> bool {
+        values(fields.as_bytes(), self.number()).is_ok_and(|values| !values.is_empty())
+    }
+
+    /// Removes the values of the extension from `fields`.
+    pub fn clear(&self, fields: &mut ArenaVec<'_, u8>) {
+        let kept = self.others(fields);
+        fields.clear();
+        fields.extend_from_slice(&kept);
+    }
+
+    /// Copies the fields other than the extension.
+    fn others(&self, fields: &[u8]) -> Vec<u8> {
+        let mut kept = Vec::with_capacity(fields.len());
+        let mut buf = fields;
+        while buf.has_remaining() {
+            let start = buf;
+            let read = decode_key(&mut buf)
+                .and_then(|(tag, wire_type)| read_value(tag, wire_type, &mut buf).map(|_| tag));
+            match read {
+                Ok(tag) if tag == self.number() => {}
+                Ok(_) => kept.extend_from_slice(&start[..start.len() - buf.len()]),
+                // Keep whatever can't be read as it is
+                Err(_) => {
+                    kept.extend_from_slice(start);
+                    break;
+                }
+            }
+        }
+        kept
+    }
+}
+
+impl<M, T: ExtensionValue> Extension<M, T> {
+    /// Decodes the value of the extension from `fields`, or returns `None` if it isn't set.
+    ///
+    /// Like fields, a singular extension set more than once takes its last value, or the merge
+    /// of all of them for messages.
+    pub fn get<'arena>(
+        &self,
+        fields: UnknownFields<'arena>,
+        arena: &'arena Arena,
+    ) -> Result<Option<T::Cloned<'arena>>, DecodeError> {
+        let values = values(fields.as_bytes(), self.number())?;
+        if values.is_empty() {
+            return Ok(None);
+        }
+        T::decode(self.info.ty(), &values, arena)
+            .map(Some)
+            .map_err(|mut error| {
+                error.push(self.extendee, self.name());
+                error
+            })
+    }
+
+    /// Replaces the value of the extension in `fields`.
+    pub fn set<'arena>(&self, fields: &mut ArenaVec<'arena, u8>, value: &T::Cloned<'arena>) {
+        // An `ArenaVec` only reports its spare capacity as writable, so encode out of place
+        let mut kept = self.others(fields);
+        T::encode(value, self.info.ty(), self.number(), &mut kept);
+        fields.clear();
+        fields.extend_from_slice(&kept);
+    }
+}
+
+impl<M, T> Clone for Extension<M, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<M, T> Copy for Extension<M, T> {}
+
+impl<M, T> fmt::Debug for Extension<M, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Extension")
+            .field("extendee", &self.extendee)
+            .field("name", &self.name())
+            .field("number", &self.number())
+            .finish()
+    }
+}
+
+/// The types of values an [`Extension`] can hold.
+///
+/// Implemented for the scalar types, `&str`, `&[u8]`, slices of those for repeated extensions,
+/// and by `#[derive(View)]` for messages. Values are returned as [`ArenaClone::Cloned`], which
+/// borrows from the arena holding the extended message.
+pub trait ExtensionValue: ArenaClone {
+    /// Decodes a value from the encoded values of the extension, in the order they were read.
+    /// `values` is never empty.
+    #[doc(hidden)]
+    fn decode<'arena>(
+        ty: FieldType,
+        values: &[(WireType, &'arena [u8])],
+        arena: &'arena Arena,
+    ) -> Result<Self::Cloned<'arena>, DecodeError>;
+
+    /// Encodes a value of the extension, key included.
+    #[doc(hidden)]
+    fn encode(value: &Self::Cloned<'_>, ty: FieldType, tag: u32, buf: &mut impl BufMut);
+}
+
+/// The encoded values of the field `number` in `bytes`.
+fn values(bytes: &[u8], number: u32) -> Result<Vec<Value<'_>>, DecodeError> {
+    Ok(records(bytes)?
+        .into_iter()
+        .filter(|record| record.tag == number)
+        .flat_map(|record| record.values)
+        .collect())
+}
+
+/// Reads a numeric value, undoing the zigzag encoding of `sint32` and `sint64`.
+fn read_number(ty: FieldType, (wire_type, mut buf): Value<'_>) -> Result<u64, DecodeError> {
+    check_wire_type(scalar_wire_type(ty), wire_type)?;
+    let value = match wire_type {
+        WireType::ThirtyTwoBit => ensure_remaining::<4>(&mut buf)?.get_u32_le().into(),
+        WireType::SixtyFourBit => ensure_remaining::<8>(&mut buf)?.get_u64_le(),
+        _ => decode_varint(&mut buf)?,
+    };
+    Ok(match ty {
+        FieldType::Scalar(ScalarType::Sint32 | ScalarType::Sint64) => {
+            (value >> 1) ^ (value & 1).wrapping_neg()
+        }
+        _ => value,
+    })
+}
+
+/// Writes a numeric value. Signed values are sign-extended to 64 bits.
+fn write_number(ty: FieldType, tag: u32, value: u64, buf: &mut impl BufMut) {
+    let wire_type = scalar_wire_type(ty);
+    encode_key(tag, wire_type, buf);
+    match wire_type {
+        WireType::ThirtyTwoBit => buf.put_u32_le(value as u32),
+        WireType::SixtyFourBit => buf.put_u64_le(value),
+        _ => encode_varint(
+            match ty {
+                FieldType::Scalar(ScalarType::Sint32 | ScalarType::Sint64) => {
+                    (value << 1) ^ ((value as i64 >> 63) as u64)
+                }
+                _ => value,
+            },
+            buf,
+        ),
+    }
+}
+
+macro_rules! numeric_extension_value {
+    ($($ty:ty: |$number:ident| $decode:expr, |$value:ident| $encode:expr;)*) => {
+        $(
+            impl ExtensionValue for $ty {
+                fn decode<'arena>(
+                    ty: FieldType,
+                    values: &[(WireType, &'arena [u8])],
+                    _arena: &'arena Arena,
+                ) -> Result<$ty, DecodeError> {
+                    let $number = read_number(ty, values[values.len() - 1])?;
+                    Ok($decode)
+                }
+
+                fn encode(&$value: &$ty, ty: FieldType, tag: u32, buf: &mut impl BufMut) {
+                    write_number(ty, tag, $encode, buf);
+                }
+            }
+        )*
+    };
+}
+
+numeric_extension_value! {
+    bool: |number| number != 0, |value| value.into();
+    i32: |number| number as i32, |value| value as i64 as u64;
+    i64: |number| number as i64, |value| value as u64;
+    u32: |number| number as u32, |value| value.into();
+    u64: |number| number, |value| value;
+    f32: |number| f32::from_bits(number as u32), |value| value.to_bits().into();
+    f64: |number| f64::from_bits(number), |value| value.to_bits();
+}
+
+impl ExtensionValue for &str {
+    fn decode<'arena>(
+        ty: FieldType,
+        values: &[(WireType, &'arena [u8])],
+        _arena: &'arena Arena,
+    ) -> Result<&'arena str, DecodeError> {
+        let (wire_type, body) = values[values.len() - 1];
+        check_wire_type(scalar_wire_type(ty), wire_type)?;
+        core::str::from_utf8(body)
+            .map_err(|_| DecodeError::new("invalid string value: data is not UTF-8 encoded"))
+    }
+
+    fn encode(value: &&str, _ty: FieldType, tag: u32, buf: &mut impl BufMut) {
+        crate::encoding::string::encode(tag, value, buf);
+    }
+}
+
+impl ExtensionValue for &[u8] {
+    fn decode<'arena>(
+        ty: FieldType,
+        values: &[(WireType, &'arena [u8])],
+        _arena: &'arena Arena,
+    ) -> Result<&'arena [u8], DecodeError> {
+        let (wire_type, body) = values[values.len() - 1];
+        check_wire_type(scalar_wire_type(ty), wire_type)?;
+        Ok(body)
+    }
+
+    fn encode(value: &&[u8], _ty: FieldType, tag: u32, buf: &mut impl BufMut) {
+        crate::encoding::bytes::encode(tag, value, buf);
+    }
+}
+
+impl<T: ExtensionValue> ExtensionValue for &[T] {
+    fn decode<'arena>(
+        ty: FieldType,
+        values: &[(WireType, &'arena [u8])],
+        arena: &'arena Arena,
+    ) -> Result<&'arena [T::Cloned<'arena>], DecodeError> {
+        let elements = elements(ty, values)?;
+        let mut vec = arena.new_vec_with_capacity(elements.len());
+        for element in &elements {
+            vec.push(T::decode(ty, core::slice::from_ref(element), arena)?);
+        }
+        Ok(vec.freeze())
+    }
+
+    fn encode(values: &&[T::Cloned<'_>], ty: FieldType, tag: u32, buf: &mut impl BufMut) {
+        for value in values.iter() {
+            T::encode(value, ty, tag, buf);
+        }
+    }
+}
+
+/// Returns the body of a message extension, merging the bodies of every value.
+///
+/// Meant to be used only by `ExtensionValue` implementations.
+#[doc(hidden)]
+pub fn message_body<'arena>(
+    ty: FieldType,
+    values: &[(WireType, &'arena [u8])],
+    arena: &'arena Arena,
+) -> Result<&'arena [u8], DecodeError> {
+    for &(wire_type, _) in values {
+        check_wire_type(scalar_wire_type(ty), wire_type)?;
+    }
+    match values {
+        [(_, body)] => Ok(body),
+        _ => {
+            // The message decoded from the concatenated bodies is their merge
+            let mut vec = arena.new_vec();
+            for (_, body) in values {
+                vec.extend_from_slice(body);
+            }
+            Ok(vec.freeze())
+        }
+    }
+}
+
+/// Encodes a message extension as a message or a group, according to `ty`.
+///
+/// Meant to be used only by `ExtensionValue` implementations.
+#[doc(hidden)]
+pub fn encode_message<M: Encode>(value: &M, ty: FieldType, tag: u32, buf: &mut impl BufMut) {
+    match ty {
+        FieldType::Group(..) => group::encode(tag, value, buf),
+        _ => message::encode(tag, value, buf),
+    }
+}
+
+/// A set of extensions, consulted while decoding with
+/// [`Decode::decode_with_extensions`](crate::Decode::decode_with_extensions) or
+/// [`DecodeOptions::extensions`](crate::DecodeOptions::extensions).
+///
+/// Every extension of a message is kept when it is decoded, registered or not. Registered ones
+/// are checked as they are read: their wire type must match, strings must be UTF-8 and
+/// messages must be well formed, or decoding fails with an error naming the extension.
+///
+/// ```
+/// use defiant::{Extension, ExtensionRegistry, FieldInfo, FieldType, ScalarType};
+///
+/// # struct Envelope;
+/// const PRIORITY: Extension<Envelope, u32> = Extension::new(
+///     "acme.Envelope",
+///     FieldInfo::new("acme.priority", 100, FieldType::Scalar(ScalarType::Uint32)),
+/// );
+///
+/// let mut registry = ExtensionRegistry::new();
+/// registry.register(&PRIORITY);
+/// assert_eq!(registry.find("acme.Envelope", 100).unwrap().name(), "acme.priority");
+/// assert_eq!(registry.find_by_name("acme.priority").unwrap().tag(), 100);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ExtensionRegistry {
+    /// The extensions of each message, by extendee name and field number.
+    extensions: BTreeMap<&'static str, BTreeMap<u32, FieldInfo>>,
+}
+
+impl ExtensionRegistry {
+    /// Creates an empty registry.
+    pub const fn new() -> Self {
+        ExtensionRegistry {
+            extensions: BTreeMap::new(),
+        }
+    }
+
+    /// Adds an extension, replacing any extension of the same message with the same number.
+    pub fn register<M, T>(&mut self, extension: &Extension<M, T>) -> &mut Self {
+        self.extensions
+            .entry(extension.extendee())
+            .or_default()
+            .insert(extension.number(), extension.info());
+        self
+    }
+
+    /// Looks up the extension of the message named `extendee` with the given field number.
+    pub fn find(&self, extendee: &str, number: u32) -> Option<FieldInfo> {
+        self.extensions.get(extendee)?.get(&number).copied()
+    }
+
+    /// Looks up an extension by its fully qualified name.
+    pub fn find_by_name(&self, name: &str) -> Option<FieldInfo> {
+        self.extensions
+            .values()
+            .flat_map(BTreeMap::values)
+            .find(|info| info.name() == name)
+            .copied()
+    }
+
+    /// Returns the number of registered extensions.
+    pub fn len(&self) -> usize {
+        self.extensions.values().map(BTreeMap::len).sum()
+    }
+
+    /// Returns `true` if no extension is registered.
+    pub fn is_empty(&self) -> bool {
+        self.extensions.is_empty()
+    }
+}
+
+/// Checks an encoded field record against the extension it belongs to, allowing messages in it
+/// to be nested `depth` levels deep.
+pub(crate) fn check(info: &FieldInfo, mut record: &[u8], depth: u32) -> Result<(), DecodeError> {
+    let (tag, wire_type) = decode_key(&mut record)?;
+    let (_, body) = read_value(tag, wire_type, &mut record)?;
+    if !matches_wire_type(info, wire_type) {
+        check_wire_type(scalar_wire_type(info.ty()), wire_type)?;
+    }
+    match info.ty() {
+        FieldType::Message(ty) | FieldType::Group(ty, _) => canonicalize(body, ty, depth).map(|_| ()),
+        FieldType::Scalar(ScalarType::String) => core::str::from_utf8(body)
+            .map(|_| ())
+            .map_err(|_| DecodeError::new("invalid string value: data is not UTF-8 encoded")),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,1849 @@
+//! Canonical Protobuf JSON mapping.
+//!
+//! Messages derived with `#[derive(View)]` print themselves with `to_json()`, and their builders
+//! parse JSON with `parse_json()`:
+//!
+//! ```
+//! use defiant::{Arena, View};
+//!
+//! #[derive(View)]
+//! struct Point<'arena> {
+//!     #[defiant(string, tag = "1")]
+//!     label: &'arena str,
+//!     #[defiant(sint64, repeated, tag = "2")]
+//!     coords: &'arena [i64],
+//! }
+//!
+//! let arena = Arena::new();
+//! let point = PointBuilder::parse_json(r#"{"label": "a", "coords": [1, "-2"]}"#, &arena)
+//!     .unwrap()
+//!     .freeze();
+//! assert_eq!(point.coords, &[1, -2]);
+//! assert_eq!(point.to_json().unwrap(), r#"{"label":"a","coords":["1","-2"]}"#);
+//! ```
+//!
+//! Like [`text_format`](crate::text_format), both directions go through the binary encoding.
+//! Fields are printed with their lowerCamelCase JSON name, and parsed by either that or their
+//! proto name. The well-known types of `google.protobuf` are recognized by their full name and
+//! use their special JSON forms. Messages packed in an `Any` are resolved through the types
+//! registered with [`JsonOptions::register`]; the well-known types need no registration.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{self, Write as _};
+use core::str;
+
+use bytes::{Buf, BufMut};
+
+use crate::encoding::{decode_key, decode_varint, encode_key, encode_varint, WireType};
+use crate::schema::{EnumType, FieldInfo, FieldSet, FieldType, MessageType, ScalarType};
+use crate::text_format::{
+    ensure_remaining, length_delimited, matches_wire_type, scalar_wire_type, write_display,
+};
+use crate::{Arena, DecodeError, DecodeOptions, Encode};
+
+/// Formats a message as compact JSON.
+pub fn to_string<M: Encode + FieldSet>(message: &M) -> Result<String, SerializeError> {
+    JsonOptions::new().to_string(message)
+}
+
+/// Parses JSON into the binary encoding of a message of type `M`, allocated in `arena`.
+pub fn to_wire<'arena, M: FieldSet>(
+    json: &str,
+    arena: &'arena Arena,
+) -> Result<&'arena [u8], ParseError> {
+    JsonOptions::new().to_wire::<M>(json, arena)
+}
+
+/// Options for printing and parsing JSON.
+///
+/// # Example
+///
+/// ```
+/// use defiant::json::JsonOptions;
+/// # use defiant::View;
+/// # #[derive(View)]
+/// # #[defiant(full_name = "example.Point")]
+/// # struct Point {
+/// #     #[defiant(int32, tag = "1")]
+/// #     x: i32,
+/// # }
+///
+/// let options = JsonOptions::new()
+///     .register::<Point>()
+///     .ignore_unknown_fields(true);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct JsonOptions {
+    types: Vec<MessageType>,
+    ignore_unknown_fields: bool,
+}
+
+impl JsonOptions {
+    /// Creates options with no registered types, rejecting unknown fields.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a message type so it can be printed and parsed inside an `Any`.
+    ///
+    /// The type is looked up by its full name, so it needs `#[defiant(full_name = "...")]`,
+    /// which generated code always has. Types without one are ignored.
+    pub fn register<M: FieldSet>(mut self) -> Self {
+        if M::full_name().is_some() {
+            self.types.push(MessageType::of::<M>());
+        }
+        self
+    }
+
+    /// Whether parsing skips fields and enum values it does not know instead of failing.
+    pub fn ignore_unknown_fields(mut self, ignore: bool) -> Self {
+        self.ignore_unknown_fields = ignore;
+        self
+    }
+
+    /// Formats a message as compact JSON.
+    pub fn to_string<M: Encode + FieldSet>(&self, message: &M) -> Result<String, SerializeError> {
+        let bytes = message.encode_to_vec();
+        let mut printer = Printer {
+            options: self,
+            out: String::new(),
+            depth: DecodeOptions::new().recursion_limit,
+        };
+        printer.message(&bytes, MessageType::of::<M>())?;
+        Ok(printer.out)
+    }
+
+    /// Parses JSON into the binary encoding of a message of type `M`, allocated in `arena`.
+    pub fn to_wire<'arena, M: FieldSet>(
+        &self,
+        json: &str,
+        arena: &'arena Arena,
+    ) -> Result<&'arena [u8], ParseError> {
+        let depth = DecodeOptions::new().recursion_limit;
+        let mut reader = Reader {
+            text: json,
+            pos: 0,
+            depth,
+        };
+        let value = reader.document()?;
+        let mut transcoder = Transcoder {
+            options: self,
+            text: json,
+            depth,
+        };
+        let mut wire = Vec::new();
+        transcoder.message(&value, MessageType::of::<M>(), &mut wire)?;
+        let mut bytes = arena.new_vec_with_capacity(wire.len());
+        bytes.extend_from_slice(&wire);
+        Ok(bytes.freeze())
+    }
+
+    fn resolve(&self, type_url: &str) -> Option<AnyType> {
+        let name = &type_url[type_url.rfind('/').map_or(0, |index| index + 1)..];
+        if let Some(wkt) = WellKnown::from_name(name) {
+            return Some(AnyType::WellKnown(wkt));
+        }
+        if name == "google.protobuf.Empty" {
+            return Some(AnyType::Message(MessageType::of::<Empty>()));
+        }
+        self.types
+            .iter()
+            .find(|ty| ty.full_name() == Some(name))
+            .map(|&ty| AnyType::Message(ty))
+    }
+}
+
+/// JSON could not be parsed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    message: String,
+    line: usize,
+    column: usize,
+}
+
+impl ParseError {
+    /// Returns the line of the error, starting at 1, or 0 if the JSON parsed but could not be
+    /// decoded.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Returns the column of the error in characters, starting at 1.
+    pub fn column(&self) -> usize {
+        self.column
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.line > 0 {
+            write!(f, "{}:{}: ", self.line, self.column)?;
+        }
+        f.write_str(&self.message)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseError {}
+
+impl From<DecodeError> for ParseError {
+    fn from(error: DecodeError) -> ParseError {
+        let mut message = String::new();
+        write_display(&mut message, error);
+        ParseError {
+            message,
+            line: 0,
+            column: 0,
+        }
+    }
+}
+
+/// A message has no JSON representation, such as a `Timestamp` out of range or an `Any` of an
+/// unregistered type.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SerializeError {
+    message: String,
+}
+
+impl SerializeError {
+    fn new(message: impl fmt::Display) -> SerializeError {
+        let mut text = String::new();
+        write_display(&mut text, message);
+        SerializeError { message: text }
+    }
+}
+
+impl fmt::Display for SerializeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SerializeError {}
+
+impl From<DecodeError> for SerializeError {
+    fn from(error: DecodeError) -> SerializeError {
+        SerializeError::new(error)
+    }
+}
+
+/// The well-known types with a special JSON form.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum WellKnown {
+    Any,
+    Timestamp,
+    Duration,
+    FieldMask,
+    Struct,
+    Value,
+    ListValue,
+    /// A wrapper, with the type of its `value` field.
+    Wrapper(ScalarType),
+}
+
+impl WellKnown {
+    fn from_name(name: &str) -> Option<WellKnown> {
+        let name = name.strip_prefix("google.protobuf.")?;
+        Some(match name {
+            "Any" => WellKnown::Any,
+            "Timestamp" => WellKnown::Timestamp,
+            "Duration" => WellKnown::Duration,
+            "FieldMask" => WellKnown::FieldMask,
+            "Struct" => WellKnown::Struct,
+            "Value" => WellKnown::Value,
+            "ListValue" => WellKnown::ListValue,
+            "DoubleValue" => WellKnown::Wrapper(ScalarType::Double),
+            "FloatValue" => WellKnown::Wrapper(ScalarType::Float),
+            "Int64Value" => WellKnown::Wrapper(ScalarType::Int64),
+            "UInt64Value" => WellKnown::Wrapper(ScalarType::Uint64),
+            "Int32Value" => WellKnown::Wrapper(ScalarType::Int32),
+            "UInt32Value" => WellKnown::Wrapper(ScalarType::Uint32),
+            "BoolValue" => WellKnown::Wrapper(ScalarType::Bool),
+            "StringValue" => WellKnown::Wrapper(ScalarType::String),
+            "BytesValue" => WellKnown::Wrapper(ScalarType::Bytes),
+            _ => return None,
+        })
+    }
+
+    fn of(ty: MessageType) -> Option<WellKnown> {
+        ty.full_name().and_then(WellKnown::from_name)
+    }
+}
+
+/// The type of the message packed in an `Any`.
+enum AnyType {
+    WellKnown(WellKnown),
+    Message(MessageType),
+}
+
+/// `google.protobuf.Empty`, which has no fields and so needs no registration.
+struct Empty;
+
+impl FieldSet for Empty {
+    fn field(_: &str) -> Option<FieldInfo> {
+        None
+    }
+
+    fn field_by_tag(_: u32) -> Option<FieldInfo> {
+        None
+    }
+
+    fn full_name() -> Option<&'static str> {
+        Some("google.protobuf.Empty")
+    }
+}
+
+/// Whether `ty` is `google.protobuf.Value`, whose JSON form includes `null`.
+fn is_value(ty: FieldType) -> bool {
+    match ty {
+        FieldType::Message(ty) => WellKnown::of(ty) == Some(WellKnown::Value),
+        _ => false,
+    }
+}
+
+/// Whether `ty` is `google.protobuf.NullValue`, which is written as `null`.
+fn is_null_value(ty: EnumType) -> bool {
+    ty.full_name() == Some("google.protobuf.NullValue")
+}
+
+/// A field value read from wire data. Groups are held as their body.
+#[derive(Clone, Copy)]
+enum Raw<'b> {
+    Varint(u64),
+    Fixed32(u32),
+    Fixed64(u64),
+    Bytes(&'b [u8]),
+}
+
+impl<'b> Raw<'b> {
+    fn read(wire_type: WireType, tag: u32, buf: &mut &'b [u8]) -> Result<Raw<'b>, DecodeError> {
+        Ok(match wire_type {
+            WireType::Varint => Raw::Varint(decode_varint(buf)?),
+            WireType::ThirtyTwoBit => Raw::Fixed32(ensure_remaining::<4>(buf)?.get_u32_le()),
+            WireType::SixtyFourBit => Raw::Fixed64(ensure_remaining::<8>(buf)?.get_u64_le()),
+            WireType::LengthDelimited => Raw::Bytes(length_delimited(buf)?),
+            WireType::StartGroup => Raw::Bytes(group_body(tag, buf)?),
+            WireType::EndGroup => return Err(DecodeError::new("unexpected end group tag")),
+        })
+    }
+
+    /// The value of a field missing from a map entry.
+    fn default_of(ty: FieldType) -> Raw<'static> {
+        match scalar_wire_type(ty) {
+            WireType::ThirtyTwoBit => Raw::Fixed32(0),
+            WireType::SixtyFourBit => Raw::Fixed64(0),
+            WireType::Varint => Raw::Varint(0),
+            _ => Raw::Bytes(&[]),
+        }
+    }
+
+    fn varint(self) -> u64 {
+        match self {
+            Raw::Varint(value) => value,
+            _ => 0,
+        }
+    }
+
+    fn bytes(self) -> &'b [u8] {
+        match self {
+            Raw::Bytes(bytes) => bytes,
+            _ => &[],
+        }
+    }
+}
+
+/// Returns the body of a group whose start tag was just read, up to its end tag.
+fn group_body<'b>(tag: u32, buf: &mut &'b [u8]) -> Result<&'b [u8], DecodeError> {
+    let start = *buf;
+    loop {
+        if !buf.has_remaining() {
+            return Err(DecodeError::new("missing end group tag"));
+        }
+        let before = buf.len();
+        let (field, wire_type) = decode_key(buf)?;
+        if wire_type == WireType::EndGroup {
+            if field != tag {
+                return Err(DecodeError::new("unexpected end group tag"));
+            }
+            return Ok(&start[..start.len() - before]);
+        }
+        Raw::read(wire_type, field, buf)?;
+    }
+}
+
+/// Calls `f` with each field in `buf`, in order.
+fn for_each_field<'b>(
+    mut buf: &'b [u8],
+    mut f: impl FnMut(u32, WireType, Raw<'b>) -> Result<(), SerializeError>,
+) -> Result<(), SerializeError> {
+    while buf.has_remaining() {
+        let (tag, wire_type) = decode_key(&mut buf)?;
+        let raw = Raw::read(wire_type, tag, &mut buf)?;
+        f(tag, wire_type, raw)?;
+    }
+    Ok(())
+}
+
+/// Collects the known fields of a message, in the order they first appear, with all values of
+/// repeated fields and the last value of singular ones. Packed values are unpacked.
+fn collect_fields(
+    buf: &[u8],
+    ty: MessageType,
+) -> Result<Vec<(FieldInfo, Vec<Raw<'_>>)>, SerializeError> {
+    let mut fields: Vec<(FieldInfo, Vec<Raw<'_>>)> = Vec::new();
+    for_each_field(buf, |tag, wire_type, raw| {
+        let field = match ty.field_by_tag(tag) {
+            Some(field) if matches_wire_type(&field, wire_type) => field,
+            _ => return Ok(()),
+        };
+        let index = match fields.iter().position(|(known, _)| known.tag() == tag) {
+            Some(index) => index,
+            None => {
+                fields.push((field, Vec::new()));
+                fields.len() - 1
+            }
+        };
+        let values = &mut fields[index].1;
+        if !field.is_repeated() && !matches!(field.ty(), FieldType::Map(..)) {
+            values.clear();
+        }
+        let element = scalar_wire_type(field.ty());
+        if wire_type == WireType::LengthDelimited && element != WireType::LengthDelimited {
+            let mut packed = raw.bytes();
+            while packed.has_remaining() {
+                values.push(Raw::read(element, tag, &mut packed)?);
+            }
+        } else {
+            values.push(raw);
+        }
+        Ok(())
+    })?;
+    Ok(fields)
+}
+
+struct Printer<'o> {
+    options: &'o JsonOptions,
+    out: String,
+    /// The number of further message nesting levels allowed.
+    depth: u32,
+}
+
+impl Printer<'_> {
+    fn message(&mut self, body: &[u8], ty: MessageType) -> Result<(), SerializeError> {
+        if self.depth == 0 {
+            return Err(SerializeError::new("recursion limit reached"));
+        }
+        self.depth -= 1;
+        let result = match WellKnown::of(ty) {
+            Some(wkt) => self.well_known(body, wkt),
+            None => self.object(body, ty, None),
+        };
+        self.depth += 1;
+        result
+    }
+
+    /// Prints the fields of a message as an object, starting with `@type` if given.
+    fn object(
+        &mut self,
+        body: &[u8],
+        ty: MessageType,
+        type_url: Option<&str>,
+    ) -> Result<(), SerializeError> {
+        self.out.push('{');
+        if let Some(type_url) = type_url {
+            self.out.push_str("\"@type\":");
+            write_string(type_url, &mut self.out);
+        }
+        for (index, (field, values)) in collect_fields(body, ty)?.iter().enumerate() {
+            if index > 0 || type_url.is_some() {
+                self.out.push(',');
+            }
+            write_string(field.json_name(), &mut self.out);
+            self.out.push(':');
+            match field.ty() {
+                FieldType::Map(key, value) => {
+                    self.out.push('{');
+                    for (index, entry) in values.iter().enumerate() {
+                        if index > 0 {
+                            self.out.push(',');
+                        }
+                        let (mut key_raw, mut value_raw) = (
+                            Raw::default_of(FieldType::Scalar(key)),
+                            Raw::default_of(value.into()),
+                        );
+                        for_each_field(entry.bytes(), |tag, _, raw| {
+                            match tag {
+                                1 => key_raw = raw,
+                                2 => value_raw = raw,
+                                _ => {}
+                            }
+                            Ok(())
+                        })?;
+                        let mut name = String::new();
+                        match key_raw {
+                            Raw::Bytes(bytes) => name.push_str(utf8(bytes)?),
+                            raw => scalar(key, raw, &mut name)?,
+                        }
+                        write_string(name.trim_matches('"'), &mut self.out);
+                        self.out.push(':');
+                        self.value(value.into(), value_raw)?;
+                    }
+                    self.out.push('}');
+                }
+                ty if field.is_repeated() => {
+                    self.out.push('[');
+                    for (index, &raw) in values.iter().enumerate() {
+                        if index > 0 {
+                            self.out.push(',');
+                        }
+                        self.value(ty, raw)?;
+                    }
+                    self.out.push(']');
+                }
+                ty => self.value(ty, values[0])?,
+            }
+        }
+        self.out.push('}');
+        Ok(())
+    }
+
+    fn value(&mut self, ty: FieldType, raw: Raw<'_>) -> Result<(), SerializeError> {
+        match ty {
+            FieldType::Scalar(ty) => scalar(ty, raw, &mut self.out),
+            FieldType::Enum(ty) => {
+                let number = raw.varint() as i32;
+                match ty.name_of(number) {
+                    _ if is_null_value(ty) => self.out.push_str("null"),
+                    Some(name) => write_string(name, &mut self.out),
+                    None => write_display(&mut self.out, number),
+                }
+                Ok(())
+            }
+            FieldType::Message(ty) | FieldType::Group(ty, _) => self.message(raw.bytes(), ty),
+            FieldType::Map(..) => unreachable!("map entries are printed by `object`"),
+        }
+    }
+
+    fn well_known(&mut self, body: &[u8], wkt: WellKnown) -> Result<(), SerializeError> {
+        match wkt {
+            WellKnown::Any => self.any(body),
+            WellKnown::Timestamp => {
+                let (seconds, nanos) = seconds_and_nanos(body)?;
+                let timestamp = format_timestamp(seconds, nanos)
+                    .ok_or_else(|| SerializeError::new("timestamp out of range"))?;
+                write_string(&timestamp, &mut self.out);
+                Ok(())
+            }
+            WellKnown::Duration => {
+                let (seconds, nanos) = seconds_and_nanos(body)?;
+                let duration = format_duration(seconds, nanos)
+                    .ok_or_else(|| SerializeError::new("duration out of range"))?;
+                write_string(&duration, &mut self.out);
+                Ok(())
+            }
+            WellKnown::FieldMask => {
+                let mut paths = String::new();
+                for_each_field(body, |tag, _, raw| {
+                    if tag == 1 {
+                        if !paths.is_empty() {
+                            paths.push(',');
+                        }
+                        let path = utf8(raw.bytes())?;
+                        to_camel_case(path, &mut paths).ok_or_else(|| {
+                            SerializeError::new(format_args!(
+                                "field mask path has no JSON form: {path}"
+                            ))
+                        })?;
+                    }
+                    Ok(())
+                })?;
+                write_string(&paths, &mut self.out);
+                Ok(())
+            }
+            WellKnown::Struct => {
+                self.out.push('{');
+                let mut first = true;
+                for_each_field(body, |tag, _, entry| {
+                    if tag != 1 {
+                        return Ok(());
+                    }
+                    let (mut key, mut value) = ("", &[][..]);
+                    for_each_field(entry.bytes(), |tag, _, raw| {
+                        match tag {
+                            1 => key = utf8(raw.bytes())?,
+                            2 => value = raw.bytes(),
+                            _ => {}
+                        }
+                        Ok(())
+                    })?;
+                    if !first {
+                        self.out.push(',');
+                    }
+                    first = false;
+                    write_string(key, &mut self.out);
+                    self.out.push(':');
+                    self.nested(value, WellKnown::Value)
+                })?;
+                self.out.push('}');
+                Ok(())
+            }
+            WellKnown::Value => {
+                let mut kind = None;
+                for_each_field(body, |tag, _, raw| {
+                    if (1..=6).contains(&tag) {
+                        kind = Some((tag, raw));
+                    }
+                    Ok(())
+                })?;
+                match kind {
+                    Some((1, _)) => self.out.push_str("null"),
+                    Some((2, Raw::Fixed64(bits))) => {
+                        let number = f64::from_bits(bits);
+                        if !number.is_finite() {
+                            return Err(SerializeError::new("Value number is not finite"));
+                        }
+                        write_debug(&mut self.out, number);
+                    }
+                    Some((3, raw)) => write_string(utf8(raw.bytes())?, &mut self.out),
+                    Some((4, raw)) => {
+                        self.out
+                            .push_str(if raw.varint() != 0 { "true" } else { "false" })
+                    }
+                    Some((5, raw)) => self.nested(raw.bytes(), WellKnown::Struct)?,
+                    Some((6, raw)) => self.nested(raw.bytes(), WellKnown::ListValue)?,
+                    _ => return Err(SerializeError::new("Value has no kind set")),
+                }
+                Ok(())
+            }
+            WellKnown::ListValue => {
+                self.out.push('[');
+                let mut first = true;
+                for_each_field(body, |tag, _, raw| {
+                    if tag != 1 {
+                        return Ok(());
+                    }
+                    if !first {
+                        self.out.push(',');
+                    }
+                    first = false;
+                    self.nested(raw.bytes(), WellKnown::Value)
+                })?;
+                self.out.push(']');
+                Ok(())
+            }
+            WellKnown::Wrapper(ty) => {
+                let mut value = Raw::default_of(FieldType::Scalar(ty));
+                for_each_field(body, |tag, wire_type, raw| {
+                    if tag == 1 && wire_type == scalar_wire_type(FieldType::Scalar(ty)) {
+                        value = raw;
+                    }
+                    Ok(())
+                })?;
+                scalar(ty, value, &mut self.out)
+            }
+        }
+    }
+
+    fn nested(&mut self, body: &[u8], wkt: WellKnown) -> Result<(), SerializeError> {
+        if self.depth == 0 {
+            return Err(SerializeError::new("recursion limit reached"));
+        }
+        self.depth -= 1;
+        let result = self.well_known(body, wkt);
+        self.depth += 1;
+        result
+    }
+
+    fn any(&mut self, body: &[u8]) -> Result<(), SerializeError> {
+        let (mut type_url, mut value) = ("", &[][..]);
+        for_each_field(body, |tag, _, raw| {
+            match tag {
+                1 => type_url = utf8(raw.bytes())?,
+                2 => value = raw.bytes(),
+                _ => {}
+            }
+            Ok(())
+        })?;
+        if type_url.is_empty() {
+            if !value.is_empty() {
+                return Err(SerializeError::new("Any has a value but no type URL"));
+            }
+            self.out.push_str("{}");
+            return Ok(());
+        }
+        match self.options.resolve(type_url) {
+            Some(AnyType::WellKnown(wkt)) => {
+                self.out.push_str("{\"@type\":");
+                write_string(type_url, &mut self.out);
+                self.out.push_str(",\"value\":");
+                self.nested(value, wkt)?;
+                self.out.push('}');
+                Ok(())
+            }
+            Some(AnyType::Message(ty)) => self.object(value, ty, Some(type_url)),
+            None => Err(SerializeError::new(format_args!(
+                "type of Any is not registered: {type_url}"
+            ))),
+        }
+    }
+}
+
+fn utf8(bytes: &[u8]) -> Result<&str, SerializeError> {
+    str::from_utf8(bytes).map_err(|_| SerializeError::new("string is not valid UTF-8"))
+}
+
+/// Formats a scalar value. 64-bit integers are quoted, as are special float values.
+fn scalar(ty: ScalarType, raw: Raw<'_>, out: &mut String) -> Result<(), SerializeError> {
+    match (ty, raw) {
+        (ScalarType::Int32, Raw::Varint(value)) => write_display(out, value as i32),
+        (ScalarType::Int64, Raw::Varint(value)) => write_quoted(out, value as i64),
+        (ScalarType::Uint32, Raw::Varint(value)) => write_display(out, value as u32),
+        (ScalarType::Uint64, Raw::Varint(value)) => write_quoted(out, value),
+        (ScalarType::Sint32, Raw::Varint(value)) => {
+            let value = value as u32;
+            write_display(out, (value >> 1) as i32 ^ -((value & 1) as i32));
+        }
+        (ScalarType::Sint64, Raw::Varint(value)) => {
+            write_quoted(out, (value >> 1) as i64 ^ -((value & 1) as i64));
+        }
+        (ScalarType::Bool, Raw::Varint(value)) => {
+            out.push_str(if value != 0 { "true" } else { "false" })
+        }
+        (ScalarType::Fixed32, Raw::Fixed32(value)) => write_display(out, value),
+        (ScalarType::Sfixed32, Raw::Fixed32(value)) => write_display(out, value as i32),
+        (ScalarType::Float, Raw::Fixed32(bits)) => {
+            let value = f32::from_bits(bits);
+            write_float(out, f64::from(value), |out| write_debug(out, value));
+        }
+        (ScalarType::Fixed64, Raw::Fixed64(value)) => write_quoted(out, value),
+        (ScalarType::Sfixed64, Raw::Fixed64(value)) => write_quoted(out, value as i64),
+        (ScalarType::Double, Raw::Fixed64(bits)) => {
+            let value = f64::from_bits(bits);
+            write_float(out, value, |out| write_debug(out, value));
+        }
+        (ScalarType::String, Raw::Bytes(bytes)) => write_string(utf8(bytes)?, out),
+        (ScalarType::Bytes, Raw::Bytes(bytes)) => {
+            out.push('"');
+            base64_encode(bytes, out);
+            out.push('"');
+        }
+        _ => unreachable!("wire types are checked when fields are collected"),
+    }
+    Ok(())
+}
+
+fn write_quoted(out: &mut String, value: impl fmt::Display) {
+    let _ = write!(out, "\"{value}\"");
+}
+
+/// Writes the shortest representation that reads back to the same value, in exponent form for
+/// very large and very small values.
+fn write_debug(out: &mut String, value: impl fmt::Debug) {
+    let _ = write!(out, "{value:?}");
+}
+
+fn write_float(out: &mut String, value: f64, write: impl FnOnce(&mut String)) {
+    if value.is_nan() {
+        out.push_str("\"NaN\"");
+    } else if value.is_infinite() {
+        out.push_str(if value < 0.0 {
+            "\"-Infinity\""
+        } else {
+            "\"Infinity\""
+        });
+    } else {
+        write(out);
+    }
+}
+
+/// Writes a quoted JSON string.
+fn write_string(value: &str, out: &mut String) {
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '\u{8}' => out.push_str("\\b"),
+            '\u{c}' => out.push_str("\\f"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+fn seconds_and_nanos(body: &[u8]) -> Result<(i64, i32), SerializeError> {
+    let (mut seconds, mut nanos) = (0, 0);
+    for_each_field(body, |tag, _, raw| {
+        match tag {
+            1 => seconds = raw.varint() as i64,
+            2 => nanos = raw.varint() as i32,
+            _ => {}
+        }
+        Ok(())
+    })?;
+    Ok((seconds, nanos))
+}
+
+/// The seconds of 0001-01-01T00:00:00Z and 9999-12-31T23:59:59Z since the Unix epoch.
+const TIMESTAMP_RANGE: (i64, i64) = (-62_135_596_800, 253_402_300_799);
+const MAX_DURATION_SECONDS: i64 = 315_576_000_000;
+
+/// Formats the fraction of a second with 0, 3, 6 or 9 digits.
+fn write_nanos(out: &mut String, nanos: u32) {
+    if nanos == 0 {
+    } else if nanos % 1_000_000 == 0 {
+        let _ = write!(out, ".{:03}", nanos / 1_000_000);
+    } else if nanos % 1_000 == 0 {
+        let _ = write!(out, ".{:06}", nanos / 1_000);
+    } else {
+        let _ = write!(out, ".{nanos:09}");
+    }
+}
+
+fn format_timestamp(seconds: i64, nanos: i32) -> Option<String> {
+    if !(TIMESTAMP_RANGE.0..=TIMESTAMP_RANGE.1).contains(&seconds)
+        || !(0..1_000_000_000).contains(&nanos)
+    {
+        return None;
+    }
+    let days = seconds.div_euclid(86_400);
+    let time = seconds.rem_euclid(86_400);
+    let (year, month, day) = civil_from_days(days);
+    let mut out = String::new();
+    let _ = write!(
+        out,
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}",
+        time / 3600,
+        time / 60 % 60,
+        time % 60
+    );
+    write_nanos(&mut out, nanos as u32);
+    out.push('Z');
+    Some(out)
+}
+
+fn format_duration(seconds: i64, nanos: i32) -> Option<String> {
+    if !(-MAX_DURATION_SECONDS..=MAX_DURATION_SECONDS).contains(&seconds)
+        || !(-999_999_999..=999_999_999).contains(&nanos)
+        || (seconds < 0 && nanos > 0)
+        || (seconds > 0 && nanos < 0)
+    {
+        return None;
+    }
+    let mut out = String::new();
+    if seconds < 0 || nanos < 0 {
+        out.push('-');
+    }
+    write_display(&mut out, seconds.unsigned_abs());
+    write_nanos(&mut out, nanos.unsigned_abs());
+    out.push('s');
+    Some(out)
+}
+
+/// Converts days since the Unix epoch to a proleptic Gregorian date.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Converts a proleptic Gregorian date to days since the Unix epoch.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let shifted_month = i64::from((month + 9) % 12);
+    let day_of_year = (153 * shifted_month + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Converts a `snake_case` field mask path to `lowerCamelCase`, if it converts back unchanged.
+fn to_camel_case(path: &str, out: &mut String) -> Option<()> {
+    let mut upper = false;
+    for c in path.chars() {
+        match c {
+            '_' if upper => return None,
+            '_' => upper = true,
+            'a'..='z' if upper => {
+                out.push(c.to_ascii_uppercase());
+                upper = false;
+            }
+            'A'..='Z' => return None,
+            _ if upper => return None,
+            c => out.push(c),
+        }
+    }
+    (!upper).then_some(())
+}
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn base64_encode(bytes: &[u8], out: &mut String) {
+    for chunk in bytes.chunks(3) {
+        let group = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |group, (index, &byte)| {
+                group | u32::from(byte) << (16 - 8 * index)
+            });
+        for index in 0..4 {
+            if index <= chunk.len() {
+                out.push(char::from(
+                    BASE64[(group >> (18 - 6 * index)) as usize & 0x3f],
+                ));
+            } else {
+                out.push('=');
+            }
+        }
+    }
+}
+
+/// Decodes base64 in the standard or URL-safe alphabet, with or without padding.
+fn base64_decode(text: &str) -> Option<Vec<u8>> {
+    let text = text.trim_end_matches('=');
+    let mut out = Vec::with_capacity(text.len() * 3 / 4);
+    let (mut group, mut bits) = (0u32, 0);
+    for byte in text.bytes() {
+        let value = match byte {
+            b'A'..=b'Z' => byte - b'A',
+            b'a'..=b'z' => byte - b'a' + 26,
+            b'0'..=b'9' => byte - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            _ => return None,
+        };
+        group = group << 6 | u32::from(value);
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((group >> bits) as u8);
+        }
+    }
+    // A single leftover character cannot encode a byte
+    (bits < 6).then_some(out)
+}
+
+/// A parsed JSON value and its byte offset in the text.
+struct Json<'t> {
+    pos: usize,
+    value: JsonValue<'t>,
+}
+
+enum JsonValue<'t> {
+    Null,
+    Bool(bool),
+    /// A number, kept as written so that integers do not lose precision.
+    Number(&'t str),
+    String(String),
+    Array(Vec<Json<'t>>),
+    /// Members in order, with the offset of each name.
+    Object(Vec<(String, usize, Json<'t>)>),
+}
+
+impl Json<'_> {
+    fn is_null(&self) -> bool {
+        matches!(self.value, JsonValue::Null)
+    }
+}
+
+fn error_at(text: &str, pos: usize, message: impl fmt::Display) -> ParseError {
+    let before = &text[..pos];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+    let mut message_text = String::new();
+    write_display(&mut message_text, message);
+    ParseError {
+        message: message_text,
+        line,
+        column: before[line_start..].chars().count() + 1,
+    }
+}
+
+/// Reads JSON text, strictly as specified by RFC 8259.
+struct Reader<'t> {
+    text: &'t str,
+    pos: usize,
+    /// The number of further array and object nesting levels allowed.
+    depth: u32,
+}
+
+impl<'t> Reader<'t> {
+    fn error(&self, message: impl fmt::Display) -> ParseError {
+        error_at(self.text, self.pos, message)
+    }
+
+    /// Skips whitespace and returns the next byte.
+    fn peek(&mut self) -> Option<u8> {
+        let bytes = self.text.as_bytes();
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = bytes.get(self.pos) {
+            self.pos += 1;
+        }
+        bytes.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, c: u8) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: u8) -> Result<(), ParseError> {
+        if self.eat(c) {
+            Ok(())
+        } else if self.peek().is_none() {
+            Err(self.error("unexpected end of input"))
+        } else {
+            Err(self.error(format_args!("expected '{}'", char::from(c))))
+        }
+    }
+
+    fn document(&mut self) -> Result<Json<'t>, ParseError> {
+        let value = self.value()?;
+        if self.peek().is_some() {
+            return Err(self.error("unexpected text after the value"));
+        }
+        Ok(value)
+    }
+
+    fn value(&mut self) -> Result<Json<'t>, ParseError> {
+        let Some(next) = self.peek() else {
+            return Err(self.error("unexpected end of input"));
+        };
+        let pos = self.pos;
+        let value = match next {
+            b'{' | b'[' => {
+                if self.depth == 0 {
+                    return Err(self.error("recursion limit reached"));
+                }
+                self.depth -= 1;
+                let value = if next == b'{' {
+                    self.object()?
+                } else {
+                    self.array()?
+                };
+                self.depth += 1;
+                value
+            }
+            b'"' => JsonValue::String(self.string()?),
+            b'-' | b'0'..=b'9' => JsonValue::Number(self.number()?),
+            _ => {
+                let rest = &self.text[pos..];
+                let (literal, value) = [
+                    ("null", JsonValue::Null),
+                    ("true", JsonValue::Bool(true)),
+                    ("false", JsonValue::Bool(false)),
+                ]
+                .into_iter()
+                .find(|(literal, _)| rest.starts_with(literal))
+                .ok_or_else(|| self.error("expected a value"))?;
+                self.pos += literal.len();
+                value
+            }
+        };
+        Ok(Json { pos, value })
+    }
+
+    fn object(&mut self) -> Result<JsonValue<'t>, ParseError> {
+        self.expect(b'{')?;
+        let mut members = Vec::new();
+        if self.eat(b'}') {
+            return Ok(JsonValue::Object(members));
+        }
+        loop {
+            if self.peek() != Some(b'"') {
+                return Err(self.error("expected a member name"));
+            }
+            let pos = self.pos;
+            let name = self.string()?;
+            self.expect(b':')?;
+            members.push((name, pos, self.value()?));
+            if !self.eat(b',') {
+                self.expect(b'}')?;
+                return Ok(JsonValue::Object(members));
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<JsonValue<'t>, ParseError> {
+        self.expect(b'[')?;
+        let mut elements = Vec::new();
+        if self.eat(b']') {
+            return Ok(JsonValue::Array(elements));
+        }
+        loop {
+            elements.push(self.value()?);
+            if !self.eat(b',') {
+                self.expect(b']')?;
+                return Ok(JsonValue::Array(elements));
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<&'t str, ParseError> {
+        let bytes = self.text.as_bytes();
+        let start = self.pos;
+        let digits = |pos: &mut usize| {
+            let first = *pos;
+            while bytes.get(*pos).is_some_and(u8::is_ascii_digit) {
+                *pos += 1;
+            }
+            *pos > first
+        };
+        let mut pos = start;
+        if bytes[pos] == b'-' {
+            pos += 1;
+        }
+        let int_start = pos;
+        if !digits(&mut pos) || (bytes[int_start] == b'0' && pos > int_start + 1) {
+            return Err(self.error("invalid number"));
+        }
+        if bytes.get(pos) == Some(&b'.') {
+            pos += 1;
+            if !digits(&mut pos) {
+                return Err(self.error("invalid number"));
+            }
+        }
+        if let Some(b'e' | b'E') = bytes.get(pos) {
+            pos += 1;
+            if let Some(b'+' | b'-') = bytes.get(pos) {
+                pos += 1;
+            }
+            if !digits(&mut pos) {
+                return Err(self.error("invalid number"));
+            }
+        }
+        self.pos = pos;
+        Ok(&self.text[start..pos])
+    }
+
+    fn string(&mut self) -> Result<String, ParseError> {
+        self.expect(b'"')?;
+        let mut value = String::new();
+        loop {
+            let rest = &self.text[self.pos..];
+            let Some(c) = rest.chars().next() else {
+                return Err(self.error("unexpected end of input"));
+            };
+            match c {
+                '"' => {
+                    self.pos += 1;
+                    return Ok(value);
+                }
+                '\\' => {
+                    let escape = rest.as_bytes().get(1).copied();
+                    let unescaped = match escape {
+                        Some(b'"') => '"',
+                        Some(b'\\') => '\\',
+                        Some(b'/') => '/',
+                        Some(b'b') => '\u{8}',
+                        Some(b'f') => '\u{c}',
+                        Some(b'n') => '\n',
+                        Some(b'r') => '\r',
+                        Some(b't') => '\t',
+                        Some(b'u') => {
+                            self.pos += 2;
+                            let c = self.unicode_escape()?;
+                            value.push(c);
+                            continue;
+                        }
+                        _ => return Err(self.error("invalid escape")),
+                    };
+                    value.push(unescaped);
+                    self.pos += 2;
+                }
+                c if (c as u32) < 0x20 => {
+                    return Err(self.error("control character in string"));
+                }
+                c => {
+                    value.push(c);
+                    self.pos += c.len_utf8();
+                }
+            }
+        }
+    }
+
+    /// Reads the hex digits of a `\u` escape, and of the low surrogate that follows a high one.
+    fn unicode_escape(&mut self) -> Result<char, ParseError> {
+        let start = self.pos - 2;
+        let high = self.hex4()?;
+        let code = match high {
+            0xd800..=0xdbff => {
+                if !self.text[self.pos..].starts_with("\\u") {
+                    return Err(error_at(self.text, start, "unpaired surrogate"));
+                }
+                self.pos += 2;
+                let low = self.hex4()?;
+                if !(0xdc00..=0xdfff).contains(&low) {
+                    return Err(error_at(self.text, start, "unpaired surrogate"));
+                }
+                0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00)
+            }
+            0xdc00..=0xdfff => return Err(error_at(self.text, start, "unpaired surrogate")),
+            code => code,
+        };
+        char::from_u32(code).ok_or_else(|| error_at(self.text, start, "invalid escape"))
+    }
+
+    fn hex4(&mut self) -> Result<u32, ParseError> {
+        let digits = self
+            .text
+            .get(self.pos..self.pos + 4)
+            .filter(|digits| digits.bytes().all(|byte| byte.is_ascii_hexdigit()))
+            .ok_or_else(|| self.error("invalid escape"))?;
+        self.pos += 4;
+        Ok(u32::from_str_radix(digits, 16).expect("hex digits"))
+    }
+}
+
+/// Writes the wire data of messages read as JSON.
+struct Transcoder<'o, 't> {
+    options: &'o JsonOptions,
+    text: &'t str,
+    /// The number of further message nesting levels allowed.
+    depth: u32,
+}
+
+impl Transcoder<'_, '_> {
+    fn error(&self, json: &Json<'_>, message: impl fmt::Display) -> ParseError {
+        error_at(self.text, json.pos, message)
+    }
+
+    fn message(
+        &mut self,
+        json: &Json<'_>,
+        ty: MessageType,
+        out: &mut Vec<u8>,
+    ) -> Result<(), ParseError> {
+        if let Some(wkt) = WellKnown::of(ty) {
+            return self.well_known(json, wkt, out);
+        }
+        let JsonValue::Object(members) = &json.value else {
+            return Err(self.error(json, "expected an object"));
+        };
+        self.fields(members, ty, false, out)
+    }
+
+    /// Writes the members of an object as fields of `ty`, skipping the `@type` member of an
+    /// `Any`.
+    fn fields(
+        &mut self,
+        members: &[(String, usize, Json<'_>)],
+        ty: MessageType,
+        in_any: bool,
+        out: &mut Vec<u8>,
+    ) -> Result<(), ParseError> {
+        let mut seen = Vec::new();
+        let mut oneofs = Vec::new();
+        for (name, pos, value) in members {
+            let pos = *pos;
+            if in_any && name == "@type" {
+                continue;
+            }
+            let Some(field) = ty.field(name) else {
+                if self.options.ignore_unknown_fields {
+                    continue;
+                }
+                return Err(error_at(
+                    self.text,
+                    pos,
+                    format_args!("unknown field: {name}"),
+                ));
+            };
+            if seen.contains(&field.tag()) {
+                return Err(error_at(
+                    self.text,
+                    pos,
+                    format_args!("duplicate field: {name}"),
+                ));
+            }
+            seen.push(field.tag());
+            // Null means the field is unset, except for singular fields whose type includes null
+            let accepts_null = !field.is_repeated()
+                && match field.ty() {
+                    FieldType::Enum(ty) => is_null_value(ty),
+                    ty => is_value(ty),
+                };
+            if value.is_null() && !accepts_null {
+                continue;
+            }
+            if let Some(oneof) = field.oneof() {
+                if oneofs.contains(&oneof) {
+                    return Err(error_at(
+                        self.text,
+                        pos,
+                        format_args!("multiple fields of oneof {oneof}"),
+                    ));
+                }
+                oneofs.push(oneof);
+            }
+            self.field(&field, value, out)?;
+        }
+        Ok(())
+    }
+
+    fn field(
+        &mut self,
+        field: &FieldInfo,
+        json: &Json<'_>,
+        out: &mut Vec<u8>,
+    ) -> Result<(), ParseError> {
+        match (field.ty(), &json.value) {
+            (FieldType::Map(key, value), JsonValue::Object(entries)) => {
+                for (name, pos, item) in entries {
+                    if item.is_null() && !is_value(value.into()) {
+                        return Err(self.error(item, "map value is null"));
+                    }
+                    let mut entry = Vec::new();
+                    let key_json = Json {
+                        pos: *pos,
+                        value: JsonValue::String(name.clone()),
+                    };
+                    self.map_key(key, &key_json, &mut entry)?;
+                    let len = entry.len();
+                    self.value(value.into(), 2, item, &mut entry)?;
+                    if entry.len() == len {
+                        // An unknown enum value that is ignored drops the whole entry
+                        continue;
+                    }
+                    encode_key(field.tag(), WireType::LengthDelimited, out);
+                    encode_varint(entry.len() as u64, out);
+                    out.extend_from_slice(&entry);
+                }
+                Ok(())
+            }
+            (FieldType::Map(..), _) => Err(self.error(json, "expected an object")),
+            (ty, JsonValue::Array(elements)) if field.is_repeated() => {
+                for element in elements {
+                    if element.is_null() && !is_value(ty) {
+                        return Err(self.error(element, "repeated field element is null"));
+                    }
+                    self.value(ty, field.tag(), element, out)?;
+                }
+                Ok(())
+            }
+            _ if field.is_repeated() => Err(self.error(json, "expected an array")),
+            (ty, _) => self.value(ty, field.tag(), json, out),
+        }
+    }
+
+    fn map_key(
+        &mut self,
+        ty: ScalarType,
+        json: &Json<'_>,
+        out: &mut Vec<u8>,
+    ) -> Result<(), ParseError> {
+        match (ty, &json.value) {
+            (ScalarType::Bool, JsonValue::String(key)) => {
+                let value = match key.as_str() {
+                    "true" => true,
+                    "false" => false,
+                    _ => return Err(self.error(json, "invalid bool map key")),
+                };
+                encode_key(1, WireType::Varint, out);
+                encode_varint(u64::from(value), out);
+                Ok(())
+            }
+            (ScalarType::Bytes | ScalarType::Float | ScalarType::Double, _) => {
+                Err(self.error(json, "invalid map key type"))
+            }
+            _ => self.scalar(ty, 1, json, out),
+        }
+    }
+
+    /// Writes a single value, or nothing for an unknown enum value that is ignored.
+    fn value(
+        &mut self,
+        ty: FieldType,
+        tag: u32,
+        json: &Json<'_>,
+        out: &mut Vec<u8>,
+    ) -> Result<(), ParseError> {
+        match ty {
+            FieldType::Scalar(ty) => self.scalar(ty, tag, json, out),
+            FieldType::Enum(ty) => {
+                let number = match &json.value {
+                    JsonValue::Null if is_null_value(ty) => 0,
+                    JsonValue::String(name) => match ty.value_of(name) {
+                        Some(number) => number,
+                        None if self.options.ignore_unknown_fields => return Ok(()),
+                        None => {
+                            return Err(self.error(json, format_args!("unknown enum value: {name}")))
+                        }
+                    },
+                    JsonValue::Number(_) => {
+                        self.integer(json, i32::MIN.into(), i32::MAX.into())? as i32
+                    }
+                    _ => return Err(self.error(json, "expected an enum value")),
+                };
+                encode_key(tag, WireType::Varint, out);
+                encode_varint(number as u64, out);
+                Ok(())
+            }
+            FieldType::Message(ty) => {
+                let mut body = Vec::new();
+                self.nested(json, ty, &mut body)?;
+                encode_key(tag, WireType::LengthDelimited, out);
+                encode_varint(body.len() as u64, out);
+                out.extend_from_slice(&body);
+                Ok(())
+            }
+            FieldType::Group(ty, _) => {
+                encode_key(tag, WireType::StartGroup, out);
+                self.nested(json, ty, out)?;
+                encode_key(tag, WireType::EndGroup, out);
+                Ok(())
+            }
+            FieldType::Map(..) => unreachable!("map entries are written by `field`"),
+        }
+    }
+
+    fn nested(
+        &mut self,
+        json: &Json<'_>,
+        ty: MessageType,
+        out: &mut Vec<u8>,
+    ) -> Result<(), ParseError> {
+        if self.depth == 0 {
+            return Err(self.error(json, "recursion limit reached"));
+        }
+        self.depth -= 1;
+        let result = self.message(json, ty, out);
+        self.depth += 1;
+        result
+    }
+
+    fn scalar(
+        &mut self,
+        ty: ScalarType,
+        tag: u32,
+        json: &Json<'_>,
+        out: &mut Vec<u8>,
+    ) -> Result<(), ParseError> {
+        match ty {
+            ScalarType::Int32 | ScalarType::Int64 | ScalarType::Uint32 | ScalarType::Uint64 => {
+                let value = match ty {
+                    ScalarType::Int32 => self.integer(json, i32::MIN.into(), i32::MAX.into())?,
+                    ScalarType::Int64 => self.integer(json, i64::MIN.into(), i64::MAX.into())?,
+                    ScalarType::Uint32 => self.integer(json, 0, u32::MAX.into())?,
+                    _ => self.integer(json, 0, u64::MAX.into())?,
+                };
+                encode_key(tag, WireType::Varint, out);
+                encode_varint(value as u64, out);
+            }
+            ScalarType::Sint32 => {
+                let value = self.integer(json, i32::MIN.into(), i32::MAX.into())? as i32;
+                encode_key(tag, WireType::Varint, out);
+                encode_varint(u64::from(((value << 1) ^ (value >> 31)) as u32), out);
+            }
+            ScalarType::Sint64 => {
+                let value = self.integer(json, i64::MIN.into(), i64::MAX.into())? as i64;
+                encode_key(tag, WireType::Varint, out);
+                encode_varint(((value << 1) ^ (value >> 63)) as u64, out);
+            }
+            ScalarType::Fixed32 | ScalarType::Sfixed32 => {
+                let value = if ty == ScalarType::Fixed32 {
+                    self.integer(json, 0, u32::MAX.into())?
+                } else {
+                    self.integer(json, i32::MIN.into(), i32::MAX.into())?
+                };
+                encode_key(tag, WireType::ThirtyTwoBit, out);
+                out.put_u32_le(value as u32);
+            }
+            ScalarType::Fixed64 | ScalarType::Sfixed64 => {
+                let value = if ty == ScalarType::Fixed64 {
+                    self.integer(json, 0, u64::MAX.into())?
+                } else {
+                    self.integer(json, i64::MIN.into(), i64::MAX.into())?
+                };
+                encode_key(tag, WireType::SixtyFourBit, out);
+                out.put_u64_le(value as u64);
+            }
+            ScalarType::Float => {
+                let value = self.float(json)?;
+                // Values just past `f32::MAX`, such as its shortest form, round down to it
+                if value.is_finite() && (value as f32).is_infinite() {
+                    return Err(self.error(json, "float out of range"));
+                }
+                encode_key(tag, WireType::ThirtyTwoBit, out);
+                out.put_f32_le(value as f32);
+            }
+            ScalarType::Double => {
+                let value = self.float(json)?;
+                encode_key(tag, WireType::SixtyFourBit, out);
+                out.put_f64_le(value);
+            }
+            ScalarType::Bool => {
+                let JsonValue::Bool(value) = json.value else {
+                    return Err(self.error(json, "expected a bool"));
+                };
+                encode_key(tag, WireType::Varint, out);
+                encode_varint(u64::from(value), out);
+            }
+            ScalarType::String | ScalarType::Bytes => {
+                let JsonValue::String(value) = &json.value else {
+                    return Err(self.error(json, "expected a string"));
+                };
+                let bytes = if ty == ScalarType::Bytes {
+                    base64_decode(value).ok_or_else(|| self.error(json, "invalid base64"))?
+                } else {
+                    value.as_bytes().to_vec()
+                };
+                encode_key(tag, WireType::LengthDelimited, out);
+                encode_varint(bytes.len() as u64, out);
+                out.extend_from_slice(&bytes);
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads an integer, written as a number or a string, in the range `min..=max`.
+    fn integer(&self, json: &Json<'_>, min: i128, max: i128) -> Result<i128, ParseError> {
+        let text = match &json.value {
+            JsonValue::Number(text) => *text,
+            JsonValue::String(text) if is_number(text) => text.as_str(),
+            _ => return Err(self.error(json, "expected an integer")),
+        };
+        let value = match text.parse::<i128>() {
+            Ok(value) => value,
+            Err(_) => {
+                // Exponents and zero fractions are allowed, if the value is exact
+                let value: f64 = text
+                    .parse()
+                    .map_err(|_| self.error(json, "expected an integer"))?;
+                if !(-1e20..=1e20).contains(&value) || value as i128 as f64 != value {
+                    return Err(self.error(json, "expected an integer"));
+                }
+                value as i128
+            }
+        };
+        if !(min..=max).contains(&value) {
+            return Err(self.error(json, "integer out of range"));
+        }
+        Ok(value)
+    }
+
+    /// Reads a float, written as a number, a string, or one of the special value strings.
+    fn float(&self, json: &Json<'_>) -> Result<f64, ParseError> {
+        let text = match &json.value {
+            JsonValue::Number(text) => *text,
+            JsonValue::String(text) => match text.as_str() {
+                "NaN" => return Ok(f64::NAN),
+                "Infinity" => return Ok(f64::INFINITY),
+                "-Infinity" => return Ok(f64::NEG_INFINITY),
+                text if is_number(text) => text,
+                _ => return Err(self.error(json, "expected a number")),
+            },
+            _ => return Err(self.error(json, "expected a number")),
+        };
+        match text.parse::<f64>() {
+            Ok(value) if value.is_finite() => Ok(value),
+            _ => Err(self.error(json, "number out of range")),
+        }
+    }
+
+    fn string<'j>(&self, json: &'j Json<'_>) -> Result<&'j str, ParseError> {
+        match &json.value {
+            JsonValue::String(value) => Ok(value),
+            _ => Err(self.error(json, "expected a string")),
+        }
+    }
+
+    fn well_known(
+        &mut self,
+        json: &Json<'_>,
+        wkt: WellKnown,
+        out: &mut Vec<u8>,
+    ) -> Result<(), ParseError> {
+        match wkt {
+            WellKnown::Any => self.any(json, out),
+            WellKnown::Timestamp => {
+                let text = self.string(json)?;
+                let (seconds, nanos) =
+                    parse_timestamp(text).ok_or_else(|| self.error(json, "invalid timestamp"))?;
+                write_seconds_and_nanos(seconds, nanos, out);
+                Ok(())
+            }
+            WellKnown::Duration => {
+                let text = self.string(json)?;
+                let (seconds, nanos) =
+                    parse_duration(text).ok_or_else(|| self.error(json, "invalid duration"))?;
+                write_seconds_and_nanos(seconds, nanos, out);
+                Ok(())
+            }
+            WellKnown::FieldMask => {
+                let text = self.string(json)?;
+                for path in text.split(',').filter(|path| !path.is_empty()) {
+                    let mut snake = String::new();
+                    for c in path.chars() {
+                        match c {
+                            'A'..='Z' => {
+                                snake.push('_');
+                                snake.push(c.to_ascii_lowercase());
+                            }
+                            '_' => return Err(self.error(json, "invalid field mask path")),
+                            c => snake.push(c),
+                        }
+                    }
+                    encode_key(1, WireType::LengthDelimited, out);
+                    encode_varint(snake.len() as u64, out);
+                    out.extend_from_slice(snake.as_bytes());
+                }
+                Ok(())
+            }
+            WellKnown::Struct => {
+                let JsonValue::Object(members) = &json.value else {
+                    return Err(self.error(json, "expected an object"));
+                };
+                for (name, _, value) in members {
+                    let mut entry = Vec::new();
+                    encode_key(1, WireType::LengthDelimited, &mut entry);
+                    encode_varint(name.len() as u64, &mut entry);
+                    entry.extend_from_slice(name.as_bytes());
+                    self.embedded(2, value, WellKnown::Value, &mut entry)?;
+                    encode_key(1, WireType::LengthDelimited, out);
+                    encode_varint(entry.len() as u64, out);
+                    out.extend_from_slice(&entry);
+                }
+                Ok(())
+            }
+            WellKnown::Value => match &json.value {
+                JsonValue::Null => {
+                    encode_key(1, WireType::Varint, out);
+                    encode_varint(0, out);
+                    Ok(())
+                }
+                JsonValue::Number(_) => self.scalar(ScalarType::Double, 2, json, out),
+                JsonValue::String(_) => self.scalar(ScalarType::String, 3, json, out),
+                JsonValue::Bool(_) => self.scalar(ScalarType::Bool, 4, json, out),
+                JsonValue::Object(_) => self.embedded(5, json, WellKnown::Struct, out),
+                JsonValue::Array(_) => self.embedded(6, json, WellKnown::ListValue, out),
+            },
+            WellKnown::ListValue => {
+                let JsonValue::Array(elements) = &json.value else {
+                    return Err(self.error(json, "expected an array"));
+                };
+                for element in elements {
+                    self.embedded(1, element, WellKnown::Value, out)?;
+                }
+                Ok(())
+            }
+            WellKnown::Wrapper(ty) => self.scalar(ty, 1, json, out),
+        }
+    }
+
+    /// Writes a well-known type as a message field.
+    fn embedded(
+        &mut self,
+        tag: u32,
+        json: &Json<'_>,
+        wkt: WellKnown,
+        out: &mut Vec<u8>,
+    ) -> Result<(), ParseError> {
+        if self.depth == 0 {
+            return Err(self.error(json, "recursion limit reached"));
+        }
+        self.depth -= 1;
+        let mut body = Vec::new();
+        let result = self.well_known(json, wkt, &mut body);
+        self.depth += 1;
+        result?;
+        encode_key(tag, WireType::LengthDelimited, out);
+        encode_varint(body.len() as u64, out);
+        out.extend_from_slice(&body);
+        Ok(())
+    }
+
+    fn any(&mut self, json: &Json<'_>, out: &mut Vec<u8>) -> Result<(), ParseError> {
+        let JsonValue::Object(members) = &json.value else {
+            return Err(self.error(json, "expected an object"));
+        };
+        let Some((_, _, type_url)) = members.iter().find(|(name, _, _)| name == "@type") else {
+            if members.is_empty() {
+                return Ok(());
+            }
+            return Err(self.error(json, "Any is missing @type"));
+        };
+        let url = self.string(type_url)?;
+        let mut body = Vec::new();
+        match self.options.resolve(url) {
+            Some(AnyType::WellKnown(wkt)) => {
+                let mut value = None;
+                for (name, pos, member) in members {
+                    match name.as_str() {
+                        "@type" => {}
+                        "value" => value = Some(member),
+                        _ => {
+                            return Err(error_at(
+                                self.text,
+                                *pos,
+                                format_args!("unknown field: {name}"),
+                            ))
+                        }
+                    }
+                }
+                let value = value.ok_or_else(|| self.error(json, "Any is missing value"))?;
+                self.well_known(value, wkt, &mut body)?;
+            }
+            Some(AnyType::Message(ty)) => self.fields(members, ty, true, &mut body)?,
+            None => {
+                return Err(self.error(
+                    type_url,
+                    format_args!("type of Any is not registered: {url}"),
+                ))
+            }
+        }
+        encode_key(1, WireType::LengthDelimited, out);
+        encode_varint(url.len() as u64, out);
+        out.extend_from_slice(url.as_bytes());
+        encode_key(2, WireType::LengthDelimited, out);
+        encode_varint(body.len() as u64, out);
+        out.extend_from_slice(&body);
+        Ok(())
+    }
+}
+
+/// Whether a string holds a number as JSON would write it, without surrounding whitespace.
+fn is_number(text: &str) -> bool {
+    !text.is_empty()
+        && text
+            .bytes()
+            .all(|byte| matches!(byte, b'0'..=b'9' | b'-' | b'+' | b'.' | b'e' | b'E'))
+}
+
+fn write_seconds_and_nanos(seconds: i64, nanos: i32, out: &mut Vec<u8>) {
+    if seconds != 0 {
+        encode_key(1, WireType::Varint, out);
+        encode_varint(seconds as u64, out);
+    }
+    if nanos != 0 {
+        encode_key(2, WireType::Varint, out);
+        encode_varint(nanos as i64 as u64, out);
+    }
+}
+
+/// Splits `text` after `len` ASCII digits and returns their value.
+fn digits(text: &str, len: usize) -> Option<(u32, &str)> {
+    let digits = text.get(..len)?;
+    if !digits.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    Some((digits.parse().ok()?, &text[len..]))
+}
+
+/// Parses the digits of a fraction of a second, up to nanoseconds.
+fn fraction(text: &str) -> Option<(u32, &str)> {
+    let Some(rest) = text.strip_prefix('.') else {
+        return Some((0, text));
+    };
+    let len = rest.bytes().take_while(u8::is_ascii_digit).count();
+    if !(1..=9).contains(&len) {
+        return None;
+    }
+    let (value, rest) = digits(rest, len)?;
+    Some((value * 10u32.pow(9 - len as u32), rest))
+}
+
+/// Parses an RFC 3339 timestamp such as `1972-01-01T10:00:20.021-05:00`.
+fn parse_timestamp(text: &str) -> Option<(i64, i32)> {
+    let (year, rest) = digits(text, 4)?;
+    let (month, rest) = digits(rest.strip_prefix('-')?, 2)?;
+    let (day, rest) = digits(rest.strip_prefix('-')?, 2)?;
+    let (hour, rest) = digits(rest.strip_prefix('T')?, 2)?;
+    let (minute, rest) = digits(rest.strip_prefix(':')?, 2)?;
+    let (second, rest) = digits(rest.strip_prefix(':')?, 2)?;
+    let (nanos, rest) = fraction(rest)?;
+    let offset = match rest.as_bytes().first()? {
+        b'Z' if rest.len() == 1 => 0,
+        sign @ (b'+' | b'-') => {
+            let (hours, rest) = digits(&rest[1..], 2)?;
+            let (minutes, rest) = digits(rest.strip_prefix(':')?, 2)?;
+            if !rest.is_empty() || hours > 23 || minutes > 59 {
+                return None;
+            }
+            let offset = i64::from(hours * 3600 + minutes * 60);
+            if *sign == b'-' {
+                -offset
+            } else {
+                offset
+            }
+        }
+        _ => return None,
+    };
+    let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let month_days = [
+        31,
+        if leap { 29 } else { 28 },
+        31,
+        30,
+        31,
+        30,
+        31,
+        31,
+        30,
+        31,
+        30,
+        31,
+    ];
+    if !(1..=12).contains(&month)
+        || !(1..=month_days[month as usize - 1]).contains(&day)
+        || hour > 23
+        || minute > 59
+        || second > 59
+    {
+        return None;
+    }
+    let seconds = days_from_civil(year.into(), month, day) * 86_400
+        + i64::from(hour * 3600 + minute * 60 + second)
+        - offset;
+    (TIMESTAMP_RANGE.0..=TIMESTAMP_RANGE.1)
+        .contains(&seconds)
+        .then_some((seconds, nanos as i32))
+}
+
+/// Parses a duration such as `-1.5s`.
+fn parse_duration(text: &str) -> Option<(i64, i32)> {
+    let (negative, text) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text),
+    };
+    let text = text.strip_suffix('s')?;
+    let len = text.bytes().take_while(u8::is_ascii_digit).count();
+    if len == 0 {
+        return None;
+    }
+    let seconds: i64 = text[..len].parse().ok()?;
+    let (nanos, rest) = fraction(&text[len..])?;
+    if !rest.is_empty() || seconds > MAX_DURATION_SECONDS {
+        return None;
+    }
+    let nanos = nanos as i32;
+    Some(if negative {
+        (-seconds, -nanos)
+    } else {
+        (seconds, nanos)
+    })
+}
@@ -0,0 +1,169 @@
+//! Sub-message fields which are decoded on demand.
+
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::marker::PhantomData;
+
+use bytes::BufMut;
+
+use crate::{
+    Arena, ArenaClone, DecodeError, DecodeOptions, Encode, FieldSet, MessageType, MessageView,
+    MissingRequired,
+};
+
+/// The still-encoded body of a sub-message field.
+///
+/// Fields marked `#[defiant(message, lazy)]` keep the raw bytes of the sub-message instead of
+/// decoding it along with the parent. The bytes are decoded each time [`get`](Self::get) is
+/// called, and are written back untouched when the parent is encoded:
+///
+/// ```
+/// use defiant::{Arena, Encode, LazyMessage, View};
+///
+/// #[derive(View)]
+/// struct Payload<'arena> {
+///     #[defiant(string, tag = "1")]
+///     body: &'arena str,
+/// }
+///
+/// #[derive(View)]
+/// struct Envelope<'arena> {
+///     #[defiant(string, tag = "1")]
+///     route: &'arena str,
+///     #[defiant(message, lazy, optional, tag = "2")]
+///     payload: Option<LazyMessage<'arena, Payload<'arena>>>,
+/// }
+///
+/// let wire = [0x0a, 0x01, b'a', 0x12, 0x04, 0x0a, 0x02, b'h', b'i'];
+/// let arena = Arena::new();
+/// let envelope = Envelope::from_buf(&wire[..], &arena).unwrap();
+/// assert_eq!(envelope.route, "a");
+///
+/// let payload = envelope.payload.unwrap().get(&arena).unwrap();
+/// assert_eq!(payload.body, "hi");
+/// assert_eq!(envelope.encode_to_vec(), wire);
+/// ```
+///
+/// Nothing inside the sub-message is validated until it is decoded, so an envelope holding a
+/// malformed payload decodes successfully and the error is reported by `get`. The body counts as
+/// a bytes field against the parent's [`max_string_len`](DecodeOptions::max_string_len); the
+/// other limits of the parent's options only apply to the sub-message when it is decoded with
+/// [`get_with_options`](Self::get_with_options).
+pub struct LazyMessage<'arena, M> {
+    bytes: &'arena [u8],
+    _marker: PhantomData<fn() -> M>,
+}
+
+impl<'arena, M> LazyMessage<'arena, M> {
+    /// Wraps the encoded body of a message, without its key or length prefix.
+    #[inline]
+    pub const fn new(bytes: &'arena [u8]) -> Self {
+        LazyMessage {
+            bytes,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Encodes `message` into the arena.
+    pub fn from_message(message: &M, arena: &'arena Arena) -> Self
+    where
+        M: Encode,
+    {
+        Self::new(message.arena_encode(arena))
+    }
+
+    /// Returns the encoded message body.
+    #[inline]
+    pub fn as_bytes(&self) -> &'arena [u8] {
+        self.bytes
+    }
+
+    /// Decodes the message.
+    ///
+    /// The bytes are decoded from scratch on every call; keep the result around if it is needed
+    /// more than once.
+    pub fn get(&self, arena: &'arena Arena) -> Result<M, DecodeError>
+    where
+        M: MessageView<'arena>,
+    {
+        M::from_buf(self.bytes, arena)
+    }
+
+    /// Decodes the message, enforcing the limits in `options`.
+    ///
+    /// The limits apply to the sub-message on its own, so its nesting depth is counted from the
+    /// sub-message rather than from the message holding it.
+    pub fn get_with_options(
+        &self,
+        arena: &'arena Arena,
+        options: DecodeOptions,
+    ) -> Result<M, DecodeError>
+    where
+        M: MessageView<'arena>,
+    {
+        M::from_buf_with_options(self.bytes, arena, options)
+    }
+
+    /// Collects the required fields missing from the message, without decoding it. A body which
+    /// isn't a valid encoding is reported as missing as a whole.
+    #[doc(hidden)]
+    pub fn collect_missing_required(&self, missing: &mut MissingRequired)
+    where
+        M: FieldSet,
+    {
+        missing.encoded(self.bytes, MessageType::of::<M>());
+    }
+}
+
+impl<M> Clone for LazyMessage<'_, M> {
+    #[inline]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<M> Copy for LazyMessage<'_, M> {}
+
+impl<M> PartialEq for LazyMessage<'_, M> {
+    /// Compares the encoded bytes, which may differ for equal messages.
+    fn eq(&self, other: &Self) -> bool {
+        self.bytes == other.bytes
+    }
+}
+
+impl<M> Eq for LazyMessage<'_, M> {}
+
+impl<M> Hash for LazyMessage<'_, M> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.bytes.hash(state);
+    }
+}
+
+impl<M: ArenaClone> ArenaClone for LazyMessage<'_, M> {
+    type Cloned<'arena> = LazyMessage<'arena, M::Cloned<'arena>>;
+
+    /// Copies the encoded body, without decoding it.
+    fn clone_in<'arena>(&self, arena: &'arena Arena) -> Self::Cloned<'arena> {
+        LazyMessage::new(self.bytes.clone_in(arena))
+    }
+}
+
+impl<M> fmt::Debug for LazyMessage<'_, M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LazyMessage")
+            .field("len", &self.bytes.len())
+            .finish()
+    }
+}
+
+impl<M> Encode for LazyMessage<'_, M> {
+    #[inline]
+    fn encode_raw(&self, buf: &mut impl BufMut) {
+        buf.put_slice(self.bytes);
+    }
+
+    #[inline]
+    fn encoded_len(&self) -> usize {
+        self.bytes.len()
+    }
+}
@@ -14,21 +14,48 @@ pub extern crate alloc;
 pub use bytes;
 
 pub mod arena;
+mod canonical;
+pub mod delimited;
+pub mod diff;
 mod error;
+pub mod extension;
+pub mod json;
+mod lazy;
 mod message;
 mod name;
+mod options;
+mod owned;
+mod projection;
+mod required;
+mod schema;
+pub mod text_format;
 mod types;
+mod unknown;
 
 #[doc(hidden)]
 pub mod encoding;
 
-pub use crate::arena::{Arena, ArenaFrom, ArenaInto, ArenaMap, ArenaVec};
+pub use crate::arena::{Arena, ArenaBox, ArenaClone, ArenaFrom, ArenaInto, ArenaMap, ArenaVec};
+pub use crate::canonical::is_canonical;
+pub use crate::delimited::{DelimitedReader, DelimitedWriter, LengthPrefix};
 pub use crate::encoding::length_delimiter::{
     decode_length_delimiter, encode_length_delimiter, length_delimiter_len,
 };
-pub use crate::error::{DecodeError, EncodeError, UnknownEnumValue};
+pub use crate::error::{DecodeError, DecodeLimit, EncodeError, UnknownEnumValue};
+pub use crate::extension::{Extension, ExtensionRegistry, ExtensionValue};
+pub use crate::lazy::LazyMessage;
 pub use crate::message::{Decode, Encode, MessageView};
 pub use crate::name::Name;
+pub use crate::options::DecodeOptions;
+pub use crate::owned::ToOwnedValue;
+pub use crate::projection::{Projection, ProjectionError};
+pub use crate::required::missing_required_fields;
+#[doc(hidden)]
+pub use crate::required::MissingRequired;
+pub use crate::schema::{
+    EnumType, Enumeration, FieldInfo, FieldSet, FieldType, MapValueType, MessageType, ScalarType,
+};
+pub use crate::unknown::UnknownFields;
 
 /// Trait for types that can create a builder with default values in an arena.
 ///
@@ -3,12 +3,13 @@ use alloc::vec::Vec;
 
 use bytes::{Buf, BufMut};
 
-use crate::arena::Arena;
+use crate::arena::{Arena, ReverseBuf};
 use crate::encoding::varint::{encode_varint, encoded_len_varint};
 use crate::encoding::wire_type::WireType;
-use crate::encoding::{decode_key, message, DecodeContext};
-use crate::DecodeError;
+use crate::encoding::{decode_key, message, DecodeContext, SizeCache};
+use crate::schema::{FieldSet, MessageType};
 use crate::EncodeError;
+use crate::{DecodeError, DecodeOptions, ExtensionRegistry, Projection};
 
 /// Trait for encoding protobuf messages.
 ///
@@ -29,7 +30,8 @@ use crate::EncodeError;
 pub trait Encode {
     /// Encodes the message to a buffer without a length delimiter.
     ///
-    /// This method will panic if the buffer has insufficient capacity.
+    /// This method will panic if the buffer has insufficient capacity. It doesn't allocate, but
+    /// measures nested messages again at every level; the `encode*` methods measure them once.
     ///
     /// Meant to be used only by `Encode` implementations.
     #[doc(hidden)]
@@ -38,62 +40,136 @@ pub trait Encode {
     /// Returns the encoded length of the message without a length delimiter.
     fn encoded_len(&self) -> usize;
 
+    /// Returns the encoded length of the message, recording the length of every
+    /// nested message in `sizes` so that `encode_raw_cached` can reuse them.
+    ///
+    /// Meant to be used only by `Encode` implementations.
+    #[doc(hidden)]
+    fn encoded_len_cached(&self, sizes: &mut SizeCache) -> usize {
+        let _ = sizes;
+        self.encoded_len()
+    }
+
+    /// Encodes the message like `encode_raw`, taking nested message lengths from
+    /// `sizes`, which must have been filled in by `encoded_len_cached`.
+    ///
+    /// Meant to be used only by `Encode` implementations.
+    #[doc(hidden)]
+    fn encode_raw_cached(&self, buf: &mut impl BufMut, sizes: &mut SizeCache) {
+        let _ = sizes;
+        self.encode_raw(buf)
+    }
+
     /// Encodes the message to a buffer.
     ///
     /// An error will be returned if the buffer does not have sufficient capacity.
     fn encode(&self, buf: &mut impl BufMut) -> Result<(), EncodeError> {
-        let required = self.encoded_len();
+        let mut sizes = SizeCache::new();
+        let required = self.encoded_len_cached(&mut sizes);
         let remaining = buf.remaining_mut();
         if required > remaining {
             return Err(EncodeError::new(required, remaining));
         }
 
-        self.encode_raw(buf);
+        self.encode_raw_cached(buf, &mut sizes);
         Ok(())
     }
 
     /// Encodes the message to a newly allocated buffer.
     fn encode_to_vec(&self) -> Vec<u8> {
-        let mut buf = Vec::with_capacity(self.encoded_len());
-        self.encode_raw(&mut buf);
+        let mut sizes = SizeCache::new();
+        let mut buf = Vec::with_capacity(self.encoded_len_cached(&mut sizes));
+        self.encode_raw_cached(&mut buf, &mut sizes);
         buf
     }
 
+    /// Encodes the message in its canonical form to a buffer.
+    ///
+    /// Equal messages have equal canonical encodings, which makes them suitable for hashing and
+    /// signing. See [`is_canonical`](crate::is_canonical) for the rules.
+    ///
+    /// An error will be returned if the buffer does not have sufficient capacity.
+    fn encode_canonical(&self, buf: &mut impl BufMut) -> Result<(), EncodeError>
+    where
+        Self: Sized + FieldSet,
+    {
+        let canonical = self.encode_canonical_to_vec();
+        let remaining = buf.remaining_mut();
+        if canonical.len() > remaining {
+            return Err(EncodeError::new(canonical.len(), remaining));
+        }
+        buf.put_slice(&canonical);
+        Ok(())
+    }
+
+    /// Encodes the message in its canonical form to a newly allocated buffer.
+    fn encode_canonical_to_vec(&self) -> Vec<u8>
+    where
+        Self: Sized + FieldSet,
+    {
+        let bytes = self.encode_to_vec();
+        crate::canonical::canonicalize(&bytes, MessageType::of::<Self>(), u32::MAX)
+            .expect("an encoded message is well-formed")
+    }
+
     /// Encodes the message with a length-delimiter to a buffer.
     ///
     /// An error will be returned if the buffer does not have sufficient capacity.
     fn encode_length_delimited(&self, buf: &mut impl BufMut) -> Result<(), EncodeError> {
-        let len = self.encoded_len();
+        let mut sizes = SizeCache::new();
+        let len = self.encoded_len_cached(&mut sizes);
         let required = len + encoded_len_varint(len as u64);
         let remaining = buf.remaining_mut();
         if required > remaining {
             return Err(EncodeError::new(required, remaining));
         }
         encode_varint(len as u64, buf);
-        self.encode_raw(buf);
+        self.encode_raw_cached(buf, &mut sizes);
         Ok(())
     }
 
     /// Encodes the message with a length-delimiter to a newly allocated buffer.
     fn encode_length_delimited_to_vec(&self) -> Vec<u8> {
-        let len = self.encoded_len();
+        let mut sizes = SizeCache::new();
+        let len = self.encoded_len_cached(&mut sizes);
         let mut buf = Vec::with_capacity(len + encoded_len_varint(len as u64));
 
         encode_varint(len as u64, &mut buf);
-        self.encode_raw(&mut buf);
+        self.encode_raw_cached(&mut buf, &mut sizes);
         buf
     }
 
     /// Encodes the message directly into arena-allocated memory.
     ///
-    /// Encodes directly to an ArenaVec in the arena (zero heap allocation),
-    /// then freezes it to an immutable slice.
+    /// Encodes directly to an ArenaVec in the arena, then freezes it to an
+    /// immutable slice. The lengths of nested messages are measured first into a
+    /// temporary heap buffer, which `arena_encode_reverse` does without.
     fn arena_encode<'arena>(&self, arena: &'arena Arena) -> &'arena [u8] {
-        let len = self.encoded_len();
+        let mut sizes = SizeCache::new();
+        let len = self.encoded_len_cached(&mut sizes);
         let mut buf = arena.new_vec_with_capacity::<u8>(len);
-        self.encode_raw(&mut buf); // ArenaVec<u8> implements BufMut!
+        self.encode_raw_cached(&mut buf, &mut sizes); // ArenaVec<u8> implements BufMut!
         buf.freeze()
     }
+
+    /// Encodes the message into arena-allocated memory, writing the last field first.
+    ///
+    /// Each nested message is written before its length prefix, so no lengths are
+    /// computed ahead of time. The result is identical to `arena_encode`.
+    fn arena_encode_reverse<'arena>(&self, arena: &'arena Arena) -> &'arena [u8] {
+        let mut buf = ReverseBuf::new(arena);
+        self.encode_reverse(&mut buf);
+        buf.finish()
+    }
+
+    /// Writes the message in front of the contents of `buf`, last field first.
+    ///
+    /// Meant to be used only by `Encode` implementations.
+    #[doc(hidden)]
+    fn encode_reverse(&self, buf: &mut ReverseBuf<'_>) {
+        let len = self.encoded_len();
+        self.encode_raw(&mut buf.prepend(len));
+    }
 }
 
 /// Trait for decoding protobuf messages.
@@ -126,6 +202,15 @@ pub trait Decode<'arena>: Sized + 'arena {
     #[doc(hidden)]
     fn new_in(arena: &'arena Arena) -> Self;
 
+    /// Returns the schema of the message, which decoding with
+    /// [`DecodeOptions::check_required`] uses to find required fields.
+    ///
+    /// Meant to be used only by `Decode` implementations.
+    #[doc(hidden)]
+    fn message_type() -> Option<MessageType> {
+        None
+    }
+
     /// Decodes a field from a buffer, and merges it into `self`.
     ///
     /// The arena is used to allocate any variable-length data (strings, bytes,
@@ -139,7 +224,7 @@ pub trait Decode<'arena>: Sized + 'arena {
         wire_type: WireType,
         buf: &mut impl Buf,
         arena: &'arena Arena,
-        ctx: DecodeContext,
+        ctx: DecodeContext<'_, 'arena>,
     ) -> Result<(), DecodeError>;
 
     /// Decodes an instance of the message from a buffer using the provided arena.
@@ -154,6 +239,80 @@ pub trait Decode<'arena>: Sized + 'arena {
         Self::merge(&mut message, &mut buf, arena).map(|_| message)
     }
 
+    /// Decodes an instance of the message, enforcing the limits in `options`.
+    ///
+    /// Use this instead of [`Decode::decode`] for untrusted input. Exceeding a limit
+    /// returns a [`DecodeError`] whose [`limit_exceeded`](DecodeError::limit_exceeded)
+    /// identifies the limit. A [`projection`](DecodeOptions::projection) or an
+    /// [`extension registry`](DecodeOptions::extensions) set on `options` applies as well.
+    ///
+    /// The entire buffer will be consumed.
+    fn decode_with_options(
+        mut buf: impl Buf,
+        arena: &'arena Arena,
+        options: DecodeOptions,
+    ) -> Result<Self, DecodeError> {
+        options.check_input_len(buf.remaining())?;
+        if let (true, Some(ty)) = (options.check_required, Self::message_type()) {
+            if buf.chunk().len() < buf.remaining() {
+                // The check reads the input in one piece
+                let buf = buf.copy_to_bytes(buf.remaining());
+                return Self::decode_with_options(buf, arena, options);
+            }
+            crate::required::check(buf.chunk(), ty, options.recursion_limit)?;
+        }
+        let mut message = Self::new_in(arena);
+        let ctx = DecodeContext::with_options(&options);
+        merge_fields(&mut message, buf, arena, ctx)?;
+        Ok(message)
+    }
+
+    /// Decodes only the fields selected by `projection`.
+    ///
+    /// Unselected fields are skipped without allocating, and keep their default values.
+    /// Unknown fields are skipped as well, even by messages which preserve them. To combine a
+    /// projection with decode limits, use [`DecodeOptions::projection`].
+    ///
+    /// The entire buffer will be consumed.
+    fn decode_projected(
+        buf: impl Buf,
+        arena: &'arena Arena,
+        projection: &Projection,
+    ) -> Result<Self, DecodeError> {
+        Self::decode_with_options(buf, arena, DecodeOptions::new().projection(projection))
+    }
+
+    /// Decodes an instance of the message, checking the extensions in `registry`
+    /// as they are read.
+    ///
+    /// Extensions are kept by messages which preserve unknown fields, registered
+    /// or not; a registered extension whose value doesn't match its type fails
+    /// the decode. To combine a registry with decode limits, use
+    /// [`DecodeOptions::extensions`].
+    ///
+    /// The entire buffer will be consumed.
+    fn decode_with_extensions(
+        buf: impl Buf,
+        arena: &'arena Arena,
+        registry: &ExtensionRegistry,
+    ) -> Result<Self, DecodeError> {
+        Self::decode_with_options(buf, arena, DecodeOptions::new().extensions(registry))
+    }
+
+    /// Decodes an instance of the message, borrowing string and bytes fields
+    /// directly from `buf` instead of copying them into the arena.
+    ///
+    /// Because the decoded fields point into the input, the input must outlive
+    /// the arena. Repeated fields, maps and nested messages are still allocated
+    /// from the arena; only the string and bytes payloads are borrowed.
+    ///
+    /// The entire buffer will be consumed.
+    fn decode_borrowed(buf: &'arena [u8], arena: &'arena Arena) -> Result<Self, DecodeError> {
+        let mut message = Self::new_in(arena);
+        merge_fields(&mut message, buf, arena, DecodeContext::borrowing(buf))?;
+        Ok(message)
+    }
+
     /// Decodes a length-delimited instance of the message from the buffer.
     fn decode_length_delimited(buf: impl Buf, arena: &'arena Arena) -> Result<Self, DecodeError> {
         let mut message = Self::new_in(arena);
@@ -166,13 +325,8 @@ pub trait Decode<'arena>: Sized + 'arena {
     /// The arena is used to allocate any variable-length data.
     ///
     /// The entire buffer will be consumed.
-    fn merge(&mut self, mut buf: impl Buf, arena: &'arena Arena) -> Result<(), DecodeError> {
-        let ctx = DecodeContext::default();
-        while buf.has_remaining() {
-            let (tag, wire_type) = decode_key(&mut buf)?;
-            self.merge_field(tag, wire_type, &mut buf, arena, ctx.clone())?;
-        }
-        Ok(())
+    fn merge(&mut self, buf: impl Buf, arena: &'arena Arena) -> Result<(), DecodeError> {
+        merge_fields(self, buf, arena, DecodeContext::default())
     }
 
     /// Decodes a length-delimited instance of the message from buffer, and
@@ -192,6 +346,20 @@ pub trait Decode<'arena>: Sized + 'arena {
     }
 }
 
+/// Decodes the fields in `buf` and merges them into `message`, until the buffer is exhausted.
+fn merge_fields<'arena, M: Decode<'arena>>(
+    message: &mut M,
+    mut buf: impl Buf,
+    arena: &'arena Arena,
+    ctx: DecodeContext<'_, 'arena>,
+) -> Result<(), DecodeError> {
+    while buf.has_remaining() {
+        let (tag, wire_type) = decode_key(&mut buf)?;
+        message.merge_field(tag, wire_type, &mut buf, arena, ctx.clone())?;
+    }
+    Ok(())
+}
+
 /// Links a view type to its corresponding builder type.
 ///
 /// This trait associates an immutable view (which implements `Encode`) with
@@ -202,4 +370,11 @@ pub trait MessageView<'arena>: Sized {
 
     /// Constructs a View from encoded bytes
     fn from_buf(buf: impl bytes::Buf, arena: &'arena Arena) -> Result<Self, DecodeError>;
+
+    /// Constructs a View from encoded bytes, enforcing the limits in `options`
+    fn from_buf_with_options(
+        buf: impl bytes::Buf,
+        arena: &'arena Arena,
+        options: DecodeOptions,
+    ) -> Result<Self, DecodeError>;
 }
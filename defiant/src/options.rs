@@ -0,0 +1,127 @@
+//! Runtime limits applied while decoding untrusted input.
+
+use crate::{DecodeError, DecodeLimit, ExtensionRegistry, Projection};
+
+/// Limits applied while decoding a message, see [`Decode::decode_with_options`].
+///
+/// The defaults match [`Decode::decode`]: a recursion limit of 100 (or none when
+/// built with the `no-recursion-limit` feature), and no limit on sizes or counts.
+///
+/// ```
+/// use defiant::DecodeOptions;
+///
+/// let options = DecodeOptions::new()
+///     .recursion_limit(16)
+///     .max_input_len(64 * 1024)
+///     .max_repeated_len(1024)
+///     .max_string_len(4096);
+/// ```
+///
+/// Exceeding any limit fails the decode with a [`DecodeError`] whose
+/// [`limit_exceeded`](crate::DecodeError::limit_exceeded) returns the corresponding
+/// [`DecodeLimit`](crate::DecodeLimit).
+///
+/// The options also carry the [`Projection`] and [`ExtensionRegistry`] to decode with, so that
+/// either can be combined with the limits.
+///
+/// [`Decode::decode`]: crate::Decode::decode
+/// [`Decode::decode_with_options`]: crate::Decode::decode_with_options
+/// [`DecodeError`]: crate::DecodeError
+#[derive(Clone, Copy, Debug)]
+pub struct DecodeOptions<'a> {
+    pub(crate) recursion_limit: u32,
+    pub(crate) max_input_len: usize,
+    pub(crate) max_repeated_len: usize,
+    pub(crate) max_string_len: usize,
+    pub(crate) check_required: bool,
+    pub(crate) projection: Option<&'a Projection>,
+    pub(crate) extensions: Option<&'a ExtensionRegistry>,
+}
+
+impl<'a> DecodeOptions<'a> {
+    /// Creates options with the default limits.
+    pub const fn new() -> Self {
+        DecodeOptions {
+            #[cfg(not(feature = "no-recursion-limit"))]
+            recursion_limit: crate::RECURSION_LIMIT,
+            #[cfg(feature = "no-recursion-limit")]
+            recursion_limit: u32::MAX,
+            max_input_len: usize::MAX,
+            max_repeated_len: usize::MAX,
+            max_string_len: usize::MAX,
+            check_required: false,
+            projection: None,
+            extensions: None,
+        }
+    }
+
+    /// Sets how deeply messages, groups and map entries may be nested.
+    pub const fn recursion_limit(mut self, limit: u32) -> Self {
+        self.recursion_limit = limit;
+        self
+    }
+
+    /// Sets the maximum length of the encoded input, in bytes.
+    pub const fn max_input_len(mut self, len: usize) -> Self {
+        self.max_input_len = len;
+        self
+    }
+
+    /// Sets the maximum number of elements in any single repeated or map field.
+    pub const fn max_repeated_len(mut self, len: usize) -> Self {
+        self.max_repeated_len = len;
+        self
+    }
+
+    /// Sets the maximum length of any single string or bytes field, in bytes.
+    pub const fn max_string_len(mut self, len: usize) -> Self {
+        self.max_string_len = len;
+        self
+    }
+
+    /// Sets whether to fail when a required field is missing from the message or from a
+    /// message nested in it, as the C++ implementation does. The error lists the paths of the
+    /// missing fields, see [`missing_required_fields`](crate::missing_required_fields).
+    ///
+    /// Off by default. The input is checked before it is decoded, which takes an extra pass.
+    pub const fn check_required(mut self, check: bool) -> Self {
+        self.check_required = check;
+        self
+    }
+
+    /// Decodes only the fields selected by `projection`, as
+    /// [`Decode::decode_projected`](crate::Decode::decode_projected) does.
+    pub const fn projection(mut self, projection: &'a Projection) -> Self {
+        self.projection = Some(projection);
+        self
+    }
+
+    /// Checks the extensions in `registry` as they are read, as
+    /// [`Decode::decode_with_extensions`](crate::Decode::decode_with_extensions) does.
+    pub const fn extensions(mut self, registry: &'a ExtensionRegistry) -> Self {
+        self.extensions = Some(registry);
+        self
+    }
+
+    /// Returns `true` if required fields are checked, see [`check_required`](Self::check_required).
+    #[doc(hidden)]
+    pub const fn is_checking_required(&self) -> bool {
+        self.check_required
+    }
+
+    /// Fails if an input of `len` bytes is longer than the input length limit.
+    #[doc(hidden)]
+    pub fn check_input_len(&self, len: usize) -> Result<(), DecodeError> {
+        if len > self.max_input_len {
+            Err(DecodeError::limit(DecodeLimit::InputLength))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl Default for DecodeOptions<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,199 @@
+//! Owned counterparts of views, which borrow from no arena.
+//!
+//! Views are tied to the arena they were decoded in, which keeps them on one thread. With
+//! `defiant_build::Config::owned_types`, every generated message `Foo<'arena>` gets a twin
+//! `FooOwned` built from `String`, `Vec`, `BTreeMap` and `Box`, which is `Send + 'static`:
+//!
+//! ```ignore
+//! let owned: FooOwned = view.to_owned();
+//! std::thread::spawn(move || {
+//!     let arena = Arena::new();
+//!     let view = owned.view_in(&arena);
+//! });
+//! ```
+//!
+//! [`ToOwnedValue`] converts a view into its twin, field by field, and [`ArenaFrom`] converts
+//! a borrowed twin back into a view in the given arena.
+
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::{Arena, ArenaFrom, ArenaMap, LazyMessage, UnknownFields};
+
+/// Converts a view, or one of its fields, into a value which borrows from no arena.
+///
+/// Generated owned messages implement this for their view, and [`ArenaFrom`] for the way back.
+/// `#[derive(View)]` and `#[derive(Oneof)]` implement it for types without an `'arena`
+/// lifetime, which are their own owned counterpart.
+pub trait ToOwnedValue {
+    /// The owned counterpart.
+    type Owned: Send + 'static;
+
+    /// Copies the value out of its arena.
+    fn to_owned_value(&self) -> Self::Owned;
+}
+
+macro_rules! owned_copy {
+    ($($ty:ty),*) => {
+        $(
+            impl ToOwnedValue for $ty {
+                type Owned = $ty;
+
+                #[inline]
+                fn to_owned_value(&self) -> $ty {
+                    *self
+                }
+            }
+
+            impl<'arena> ArenaFrom<'arena, &$ty> for $ty {
+                #[inline]
+                fn arena_from(value: &$ty, _arena: &'arena Arena) -> $ty {
+                    *value
+                }
+            }
+        )*
+    };
+}
+
+owned_copy!(bool, u8, i32, i64, u32, u64, f32, f64);
+
+impl ToOwnedValue for &str {
+    type Owned = String;
+
+    #[inline]
+    fn to_owned_value(&self) -> String {
+        String::from(*self)
+    }
+}
+
+impl<'arena> ArenaFrom<'arena, &String> for &'arena str {
+    #[inline]
+    fn arena_from(value: &String, arena: &'arena Arena) -> Self {
+        arena.alloc_str(value)
+    }
+}
+
+impl<T: ToOwnedValue> ToOwnedValue for &[T] {
+    type Owned = Vec<T::Owned>;
+
+    fn to_owned_value(&self) -> Vec<T::Owned> {
+        self.iter().map(ToOwnedValue::to_owned_value).collect()
+    }
+}
+
+impl<'arena, 'a, T, U> ArenaFrom<'arena, &'a Vec<T>> for &'arena [U]
+where
+    U: ArenaFrom<'arena, &'a T>,
+{
+    fn arena_from(values: &'a Vec<T>, arena: &'arena Arena) -> Self {
+        let mut vec = arena.new_vec_with_capacity(values.len());
+        vec.extend(values.iter().map(|value| U::arena_from(value, arena)));
+        vec.freeze()
+    }
+}
+
+/// Messages held by reference in a view are held by value in their owned counterpart, or in a
+/// `Box` where the message type is recursive.
+impl<T: ToOwnedValue> ToOwnedValue for &T {
+    type Owned = T::Owned;
+
+    #[inline]
+    fn to_owned_value(&self) -> T::Owned {
+        (**self).to_owned_value()
+    }
+}
+
+impl<'arena, 'a, T, M> ArenaFrom<'arena, &'a T> for &'arena M
+where
+    M: ArenaFrom<'arena, &'a T>,
+{
+    #[inline]
+    fn arena_from(value: &'a T, arena: &'arena Arena) -> Self {
+        arena.alloc(M::arena_from(value, arena))
+    }
+}
+
+impl<T: ToOwnedValue> ToOwnedValue for Option<T> {
+    type Owned = Option<T::Owned>;
+
+    #[inline]
+    fn to_owned_value(&self) -> Option<T::Owned> {
+        self.as_ref().map(ToOwnedValue::to_owned_value)
+    }
+}
+
+impl<'arena, 'a, T, U> ArenaFrom<'arena, &'a Option<T>> for Option<U>
+where
+    U: ArenaFrom<'arena, &'a T>,
+{
+    #[inline]
+    fn arena_from(value: &'a Option<T>, arena: &'arena Arena) -> Self {
+        value.as_ref().map(|value| U::arena_from(value, arena))
+    }
+}
+
+impl<K, V> ToOwnedValue for ArenaMap<'_, K, V>
+where
+    K: ToOwnedValue,
+    K::Owned: Ord,
+    V: ToOwnedValue,
+{
+    type Owned = BTreeMap<K::Owned, V::Owned>;
+
+    fn to_owned_value(&self) -> Self::Owned {
+        self.iter()
+            .map(|(key, value)| (key.to_owned_value(), value.to_owned_value()))
+            .collect()
+    }
+}
+
+impl<'arena, 'a, K, V, K2, V2> ArenaFrom<'arena, &'a BTreeMap<K, V>> for ArenaMap<'arena, K2, V2>
+where
+    K2: ArenaFrom<'arena, &'a K>,
+    V2: ArenaFrom<'arena, &'a V>,
+{
+    /// Keeps the order of the `BTreeMap`, which is the order of the map's keys.
+    fn arena_from(map: &'a BTreeMap<K, V>, arena: &'arena Arena) -> Self {
+        let mut entries = arena.new_vec_with_capacity(map.len());
+        entries.extend(
+            map.iter()
+                .map(|(key, value)| (K2::arena_from(key, arena), V2::arena_from(value, arena))),
+        );
+        ArenaMap::new(entries.freeze())
+    }
+}
+
+/// Unknown fields are kept as their wire-format records.
+impl ToOwnedValue for UnknownFields<'_> {
+    type Owned = Vec<u8>;
+
+    #[inline]
+    fn to_owned_value(&self) -> Vec<u8> {
+        self.as_bytes().to_vec()
+    }
+}
+
+impl<'arena> ArenaFrom<'arena, &Vec<u8>> for UnknownFields<'arena> {
+    #[inline]
+    fn arena_from(bytes: &Vec<u8>, arena: &'arena Arena) -> Self {
+        UnknownFields::new(ArenaFrom::arena_from(bytes, arena))
+    }
+}
+
+/// Lazy sub-messages stay encoded, and are kept as their encoded body.
+impl<M> ToOwnedValue for LazyMessage<'_, M> {
+    type Owned = Vec<u8>;
+
+    #[inline]
+    fn to_owned_value(&self) -> Vec<u8> {
+        self.as_bytes().to_vec()
+    }
+}
+
+impl<'arena, M> ArenaFrom<'arena, &Vec<u8>> for LazyMessage<'arena, M> {
+    #[inline]
+    fn arena_from(bytes: &Vec<u8>, arena: &'arena Arena) -> Self {
+        LazyMessage::new(ArenaFrom::arena_from(bytes, arena))
+    }
+}
@@ -0,0 +1,203 @@
+//! Decoding a subset of a message's fields.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::schema::{FieldSet, MessageType};
+
+/// The set of fields to keep when decoding with
+/// [`Decode::decode_projected`](crate::Decode::decode_projected), or with
+/// [`DecodeOptions::projection`](crate::DecodeOptions::projection) alongside decode limits.
+///
+/// Fields which are not selected are skipped on the wire: nothing is allocated in the arena for
+/// them, and they are left at their defaults in the decoded message. Selecting a message field
+/// with [`nested`](Self::nested) decodes only the chosen fields of the sub-message.
+///
+/// ```
+/// use defiant::{Arena, Encode, Projection, View};
+///
+/// #[derive(View)]
+/// struct Header<'arena> {
+///     #[defiant(string, tag = "1")]
+///     trace_id: &'arena str,
+///     #[defiant(string, tag = "2")]
+///     user_agent: &'arena str,
+/// }
+///
+/// #[derive(View)]
+/// struct Request<'arena> {
+///     #[defiant(message, optional, tag = "1")]
+///     header: Option<&'arena Header<'arena>>,
+///     #[defiant(bytes, tag = "2")]
+///     body: &'arena [u8],
+/// }
+///
+/// let header = Header { trace_id: "abc", user_agent: "curl" };
+/// let wire = Request { header: Some(&header), body: b"..." }.encode_to_vec();
+///
+/// // Equivalent to `Projection::new().nested(1, Projection::new().field(1))`
+/// let projection = Projection::from_paths::<Request, _, _>(["header.trace_id"]).unwrap();
+/// let arena = Arena::new();
+/// let request = Request::from_buf_projected(&wire[..], &arena, &projection).unwrap();
+/// assert_eq!(request.header.unwrap().trace_id, "abc");
+/// assert_eq!(request.header.unwrap().user_agent, "");
+/// assert!(request.body.is_empty());
+/// ```
+///
+/// Projections apply to singular message and group fields, and to message fields inside a
+/// `oneof`. Repeated, map and lazy fields are either decoded whole or skipped.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Projection {
+    /// The selected tags in ascending order, with the sub-projection for partially selected
+    /// message fields.
+    fields: Vec<(u32, Option<Projection>)>,
+}
+
+impl Projection {
+    /// Creates a projection which selects no fields.
+    pub const fn new() -> Self {
+        Projection { fields: Vec::new() }
+    }
+
+    /// Selects the whole field with the given tag.
+    pub fn field(mut self, tag: u32) -> Self {
+        self.insert(tag, None);
+        self
+    }
+
+    /// Selects the fields of `projection` within the message field with the given tag.
+    ///
+    /// Selecting a field both whole and nested keeps the whole field.
+    pub fn nested(mut self, tag: u32, projection: Projection) -> Self {
+        self.insert(tag, Some(projection));
+        self
+    }
+
+    /// Builds a projection from dotted field paths, such as `"header.trace_id"`.
+    ///
+    /// Path components are the field names from the `.proto` definition, resolved with the
+    /// [`FieldSet`] generated for each message. These are the paths of a `google.protobuf.FieldMask`.
+    pub fn from_paths<M, I, S>(paths: I) -> Result<Self, ProjectionError>
+    where
+        M: FieldSet,
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut projection = Projection::new();
+        for path in paths {
+            let path = path.as_ref();
+            projection
+                .insert_path(MessageType::of::<M>(), path)
+                .ok_or_else(|| ProjectionError {
+                    path: String::from(path),
+                })?;
+        }
+        Ok(projection)
+    }
+
+    /// Returns `true` if the field with the given tag is selected, in whole or in part.
+    pub fn selects(&self, tag: u32) -> bool {
+        self.find(tag).is_ok()
+    }
+
+    /// Returns the sub-projection for a selected field, or `None` if the field is not selected.
+    ///
+    /// A field that is selected whole returns `Some(None)`.
+    pub fn get(&self, tag: u32) -> Option<Option<&Projection>> {
+        self.find(tag)
+            .ok()
+            .map(|index| self.fields[index].1.as_ref())
+    }
+
+    /// Returns `true` if no fields are selected.
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    fn find(&self, tag: u32) -> Result<usize, usize> {
+        self.fields.binary_search_by_key(&tag, |&(tag, _)| tag)
+    }
+
+    fn insert(&mut self, tag: u32, projection: Option<Projection>) {
+        match self.find(tag) {
+            Ok(index) => match (&mut self.fields[index].1, projection) {
+                (Some(existing), Some(projection)) => {
+                    for (tag, nested) in projection.fields {
+                        existing.insert(tag, nested);
+                    }
+                }
+                (existing, None) => *existing = None,
+                (None, Some(_)) => {}
+            },
+            Err(index) => self.fields.insert(index, (tag, projection)),
+        }
+    }
+
+    /// Adds a dotted path, returning `None` if any component does not resolve.
+    fn insert_path(&mut self, message: MessageType, path: &str) -> Option<()> {
+        let (name, rest) = match path.split_once('.') {
+            Some((name, rest)) => (name, Some(rest)),
+            None => (path, None),
+        };
+        let field = message.field(name)?;
+        match rest {
+            None => self.insert(field.tag(), None),
+            Some(rest) => {
+                let mut nested = Projection::new();
+                nested.insert_path(field.nested()?, rest)?;
+                self.insert(field.tag(), Some(nested));
+            }
+        }
+        Some(())
+    }
+}
+
+/// A path passed to [`Projection::from_paths`] does not name a field.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProjectionError {
+    path: String,
+}
+
+impl ProjectionError {
+    /// Returns the path which could not be resolved.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+}
+
+impl fmt::Display for ProjectionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown field path: {}", self.path)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ProjectionError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_projection_merges_selections() {
+        let projection = Projection::new()
+            .nested(2, Projection::new().field(1))
+            .field(5)
+            .nested(2, Projection::new().field(3))
+            .nested(5, Projection::new().field(1));
+
+        assert!(!projection.selects(1));
+        assert!(projection.selects(5));
+        assert_eq!(projection.get(5), Some(None));
+        assert_eq!(
+            projection.get(2),
+            Some(Some(&Projection::new().field(1).field(3)))
+        );
+
+        // Selecting the whole field replaces a nested selection
+        let projection = projection.field(2);
+        assert_eq!(projection.get(2), Some(None));
+        assert_eq!(projection, Projection::new().field(5).field(2));
+    }
+}
@@ -0,0 +1,186 @@
+//! Checks for proto2 required fields.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{self, Write};
+
+use crate::canonical::canonicalize;
+use crate::diff::{entries, format, records};
+use crate::schema::{FieldSet, FieldType, MapValueType, MessageType};
+use crate::text_format::write_display;
+use crate::{DecodeError, DecodeOptions};
+
+/// Returns the paths of the required fields missing from `bytes`, an encoded message of type
+/// `M`, such as `id` or `items[2].owner.name`.
+///
+/// A field is missing when it doesn't appear in the message, or in a message nested in it which
+/// is set. Occurrences of a singular message field are merged before checking, as decoding
+/// merges them, and map values are checked under their key, such as `users["ann"].id`.
+///
+/// Views hold a value for every required field, so required fields are checked on the encoded
+/// message, before it is decoded; [`DecodeOptions::check_required`](crate::DecodeOptions::check_required)
+/// does so while decoding.
+///
+/// Returns an error if `bytes` aren't a valid encoding of `M`, or nest messages deeper than the
+/// default recursion limit.
+pub fn missing_required_fields<M: FieldSet>(bytes: &[u8]) -> Result<Vec<String>, DecodeError> {
+    let depth = DecodeOptions::new().recursion_limit;
+    missing(bytes, MessageType::of::<M>(), depth)
+}
+
+/// Fails with an error listing the missing required fields, if there are any.
+pub(crate) fn check(bytes: &[u8], ty: MessageType, depth: u32) -> Result<(), DecodeError> {
+    let missing = missing(bytes, ty, depth)?;
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(DecodeError::new(alloc::format!(
+            "missing required fields: {}",
+            missing.join(", ")
+        )))
+    }
+}
+
+fn missing(bytes: &[u8], ty: MessageType, depth: u32) -> Result<Vec<String>, DecodeError> {
+    let mut missing = Vec::new();
+    encoded(bytes, ty, depth, &mut String::new(), &mut missing)?;
+    Ok(missing)
+}
+
+/// Collects the missing required fields of an encoded message under `path`.
+fn encoded(
+    bytes: &[u8],
+    ty: MessageType,
+    depth: u32,
+    path: &mut String,
+    missing: &mut Vec<String>,
+) -> Result<(), DecodeError> {
+    // The canonical encoding has merged singular messages and deduplicated map entries
+    let canonical = canonicalize(bytes, ty, depth)?;
+    message(&canonical, ty, depth, path, missing)
+}
+
+/// Collects the paths of the required fields missing from a view and the messages nested in it,
+/// for the `missing_required_fields` method generated on views.
+#[doc(hidden)]
+#[derive(Debug, Default)]
+pub struct MissingRequired {
+    path: String,
+    missing: Vec<String>,
+}
+
+impl MissingRequired {
+    /// Checks the field `name` with `check`.
+    pub fn field(&mut self, name: &str, check: impl FnOnce(&mut Self)) {
+        let len = enter(&mut self.path, name);
+        check(self);
+        self.path.truncate(len);
+    }
+
+    /// Checks an element of a repeated field, or a map value under its key, with `check`.
+    pub fn element(&mut self, key: impl fmt::Debug, check: impl FnOnce(&mut Self)) {
+        let len = self.path.len();
+        let _ = write!(self.path, "[{key:?}]");
+        check(self);
+        self.path.truncate(len);
+    }
+
+    /// Checks an encoded message of type `ty`. A message which isn't a valid encoding, or is
+    /// nested too deeply, is reported as missing as a whole.
+    pub(crate) fn encoded(&mut self, bytes: &[u8], ty: MessageType) {
+        let depth = DecodeOptions::new().recursion_limit;
+        let len = self.missing.len();
+        if encoded(bytes, ty, depth, &mut self.path, &mut self.missing).is_err() {
+            self.missing.truncate(len);
+            self.missing.push(self.path.clone());
+        }
+    }
+
+    /// Returns the paths of the missing fields.
+    pub fn into_paths(self) -> Vec<String> {
+        self.missing
+    }
+}
+
+/// Appends a field name to the path, returning the length of the path to restore.
+fn enter(path: &mut String, name: &str) -> usize {
+    let len = path.len();
+    if len > 0 {
+        path.push('.');
+    }
+    path.push_str(name);
+    len
+}
+
+/// Appends an index or a map key to the path, returning the length of the path to restore.
+fn index(path: &mut String, index: impl core::fmt::Display) -> usize {
+    let len = path.len();
+    path.push('[');
+    write_display(path, index);
+    path.push(']');
+    len
+}
+
+fn message(
+    buf: &[u8],
+    ty: MessageType,
+    depth: u32,
+    path: &mut String,
+    missing: &mut Vec<String>,
+) -> Result<(), DecodeError> {
+    if depth == 0 {
+        return Err(DecodeError::new("recursion limit reached"));
+    }
+    let records = records(buf)?;
+    let mut required = ty.required_tags().iter().copied().peekable();
+    let mut report = |tag: u32, path: &mut String| {
+        let name = ty.field_by_tag(tag).map_or("", |info| info.name());
+        let len = enter(path, name);
+        missing.push(path.clone());
+        path.truncate(len);
+    };
+
+    let mut nested = Vec::new();
+    for record in &records {
+        while let Some(tag) = required.next_if(|&tag| tag <= record.tag) {
+            if tag < record.tag {
+                report(tag, path);
+            }
+        }
+        if let Some(info) = ty.field_by_tag(record.tag) {
+            nested.push((info, record));
+        }
+    }
+    for tag in required {
+        report(tag, path);
+    }
+
+    for (info, record) in nested {
+        match info.ty() {
+            FieldType::Message(nested) | FieldType::Group(nested, _) => {
+                let len = enter(path, info.name());
+                if info.is_repeated() {
+                    for (i, &(_, body)) in record.values.iter().enumerate() {
+                        let len = index(path, i);
+                        message(body, nested, depth - 1, path, missing)?;
+                        path.truncate(len);
+                    }
+                } else if let Some(&(_, body)) = record.values.last() {
+                    message(body, nested, depth - 1, path, missing)?;
+                }
+                path.truncate(len);
+            }
+            FieldType::Map(key_ty, value_ty @ MapValueType::Message(nested)) => {
+                let len = enter(path, info.name());
+                for (key, (_, body)) in entries(key_ty, value_ty, &record.values)? {
+                    let len = index(path, format(FieldType::Scalar(key_ty), key));
+                    message(body, nested, depth - 1, path, missing)?;
+                    path.truncate(len);
+                }
+                path.truncate(len);
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,318 @@
+//! Field metadata generated by the derive macros.
+//!
+//! The schema is what name-based formats such as [`Projection::from_paths`](crate::Projection::from_paths)
+//! and [`text_format`](crate::text_format) need to know about a message that its Rust type does
+//! not already say: the `.proto` field names, the Protobuf type of each field, and the names of
+//! enum values.
+
+/// Resolves the fields of a message by name or by tag.
+///
+/// Implemented by `#[derive(View)]` and `#[derive(Oneof)]`.
+pub trait FieldSet {
+    /// Looks up a field by its name in the `.proto` definition, or by its JSON name.
+    fn field(name: &str) -> Option<FieldInfo>;
+
+    /// Looks up a field by its tag.
+    fn field_by_tag(tag: u32) -> Option<FieldInfo>;
+
+    /// Returns the fully qualified `.proto` name of the message, such as
+    /// `google.protobuf.Timestamp`, if it was given with `#[defiant(full_name = "...")]`.
+    fn full_name() -> Option<&'static str> {
+        None
+    }
+
+    /// Returns the tags of the message's required fields, in increasing order.
+    fn required_tags() -> &'static [u32] {
+        &[]
+    }
+}
+
+/// The names of an enum's values, as written in the `.proto` definition.
+///
+/// Implemented by `#[derive(Enumeration)]`. Names default to the variant name in
+/// `SCREAMING_SNAKE_CASE`, and can be set with `#[defiant(name = "...")]` on the variant.
+pub trait Enumeration {
+    /// Returns the name of the value with the given number.
+    fn name_of(value: i32) -> Option<&'static str>;
+
+    /// Returns the number of the value with the given name.
+    fn value_of(name: &str) -> Option<i32>;
+
+    /// Returns the fully qualified `.proto` name of the enum, if it was given with
+    /// `#[defiant(full_name = "...")]`.
+    fn full_name() -> Option<&'static str> {
+        None
+    }
+}
+
+/// A field found by [`FieldSet::field`] or [`FieldSet::field_by_tag`].
+#[derive(Clone, Copy, Debug)]
+pub struct FieldInfo {
+    name: &'static str,
+    json_name: &'static str,
+    oneof: Option<&'static str>,
+    tag: u32,
+    ty: FieldType,
+    repeated: bool,
+    lazy: bool,
+    implicit_presence: bool,
+    required: bool,
+}
+
+impl FieldInfo {
+    /// A singular field.
+    pub const fn new(name: &'static str, tag: u32, ty: FieldType) -> Self {
+        FieldInfo {
+            name,
+            json_name: name,
+            oneof: None,
+            tag,
+            ty,
+            repeated: false,
+            lazy: false,
+            implicit_presence: false,
+            required: false,
+        }
+    }
+
+    /// Sets the field's JSON name, which defaults to its name.
+    pub const fn with_json_name(mut self, json_name: &'static str) -> Self {
+        self.json_name = json_name;
+        self
+    }
+
+    /// Marks the field as a member of the named oneof.
+    pub const fn in_oneof(mut self, oneof: &'static str) -> Self {
+        self.oneof = Some(oneof);
+        self
+    }
+
+    /// Marks the field as repeated.
+    pub const fn repeated(mut self) -> Self {
+        self.repeated = true;
+        self
+    }
+
+    /// Marks a message field as lazily decoded.
+    pub const fn lazy(mut self) -> Self {
+        self.lazy = true;
+        self
+    }
+
+    /// Marks a singular scalar field as having no presence, so that it is unset when it holds
+    /// its default value, as proto3 fields without `optional` are.
+    pub const fn implicit_presence(mut self) -> Self {
+        self.implicit_presence = true;
+        self
+    }
+
+    /// Marks the field as required, as proto2 `required` fields are.
+    pub const fn required(mut self) -> Self {
+        self.required = true;
+        self
+    }
+
+    /// Returns the field's name in the `.proto` definition.
+    pub const fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Returns the field's name in the JSON mapping.
+    pub const fn json_name(&self) -> &'static str {
+        self.json_name
+    }
+
+    /// Returns the name of the oneof the field belongs to, if any.
+    pub const fn oneof(&self) -> Option<&'static str> {
+        self.oneof
+    }
+
+    /// Returns the field's tag.
+    pub const fn tag(&self) -> u32 {
+        self.tag
+    }
+
+    /// Returns the field's type.
+    pub const fn ty(&self) -> FieldType {
+        self.ty
+    }
+
+    /// Returns `true` for repeated fields. Map fields are not repeated.
+    pub const fn is_repeated(&self) -> bool {
+        self.repeated
+    }
+
+    /// Returns `true` for lazily decoded message fields.
+    pub const fn is_lazy(&self) -> bool {
+        self.lazy
+    }
+
+    /// Returns `true` for required fields.
+    pub const fn is_required(&self) -> bool {
+        self.required
+    }
+
+    /// Returns `true` if setting the field to its default value is distinct from leaving it
+    /// unset. Repeated and map fields have no presence, and neither do scalars marked with
+    /// [`implicit_presence`](Self::implicit_presence) unless they are in a oneof.
+    pub const fn has_presence(&self) -> bool {
+        !self.repeated
+            && !matches!(self.ty, FieldType::Map(..))
+            && (!self.implicit_presence || self.oneof.is_some())
+    }
+
+    /// Returns the message type if the field is a singular, eagerly decoded message or group,
+    /// whose own fields a projection can select.
+    pub fn nested(&self) -> Option<MessageType> {
+        match self.ty {
+            FieldType::Message(message) | FieldType::Group(message, _)
+                if !self.repeated && !self.lazy =>
+            {
+                Some(message)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The type of a field.
+#[derive(Clone, Copy, Debug)]
+pub enum FieldType {
+    Scalar(ScalarType),
+    Enum(EnumType),
+    Message(MessageType),
+    /// A group, and the name of its message type, which text format uses in place of the
+    /// field name.
+    Group(MessageType, &'static str),
+    /// A map, with the type of its keys and values.
+    Map(ScalarType, MapValueType),
+}
+
+/// The type of the values of a map field.
+#[derive(Clone, Copy, Debug)]
+pub enum MapValueType {
+    Scalar(ScalarType),
+    Enum(EnumType),
+    Message(MessageType),
+}
+
+impl From<MapValueType> for FieldType {
+    fn from(ty: MapValueType) -> FieldType {
+        match ty {
+            MapValueType::Scalar(ty) => FieldType::Scalar(ty),
+            MapValueType::Enum(ty) => FieldType::Enum(ty),
+            MapValueType::Message(ty) => FieldType::Message(ty),
+        }
+    }
+}
+
+/// A scalar Protobuf type.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScalarType {
+    Double,
+    Float,
+    Int32,
+    Int64,
+    Uint32,
+    Uint64,
+    Sint32,
+    Sint64,
+    Fixed32,
+    Fixed64,
+    Sfixed32,
+    Sfixed64,
+    Bool,
+    String,
+    Bytes,
+}
+
+/// A message type, whose fields are resolved through its [`FieldSet`].
+#[derive(Clone, Copy)]
+pub struct MessageType {
+    field: fn(&str) -> Option<FieldInfo>,
+    field_by_tag: fn(u32) -> Option<FieldInfo>,
+    full_name: fn() -> Option<&'static str>,
+    required_tags: fn() -> &'static [u32],
+}
+
+impl MessageType {
+    /// Returns the type of messages of type `M`.
+    pub const fn of<M: FieldSet>() -> Self {
+        MessageType {
+            field: M::field,
+            field_by_tag: M::field_by_tag,
+            full_name: M::full_name,
+            required_tags: M::required_tags,
+        }
+    }
+
+    /// Returns the fully qualified `.proto` name of the message, if known.
+    pub fn full_name(&self) -> Option<&'static str> {
+        (self.full_name)()
+    }
+
+    /// Looks up a field by its name in the `.proto` definition.
+    pub fn field(&self, name: &str) -> Option<FieldInfo> {
+        (self.field)(name)
+    }
+
+    /// Looks up a field by its tag.
+    pub fn field_by_tag(&self, tag: u32) -> Option<FieldInfo> {
+        (self.field_by_tag)(tag)
+    }
+
+    /// Returns the tags of the message's required fields, in increasing order.
+    pub fn required_tags(&self) -> &'static [u32] {
+        (self.required_tags)()
+    }
+}
+
+impl core::fmt::Debug for MessageType {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("MessageType")
+            .field("full_name", &self.full_name())
+            .finish_non_exhaustive()
+    }
+}
+
+/// An enum type, whose values are named through its [`Enumeration`] implementation.
+#[derive(Clone, Copy)]
+pub struct EnumType {
+    name_of: fn(i32) -> Option<&'static str>,
+    value_of: fn(&str) -> Option<i32>,
+    full_name: fn() -> Option<&'static str>,
+}
+
+impl EnumType {
+    /// Returns the type of enum fields holding values of `E`.
+    pub const fn of<E: Enumeration>() -> Self {
+        EnumType {
+            name_of: E::name_of,
+            value_of: E::value_of,
+            full_name: E::full_name,
+        }
+    }
+
+    /// Returns the fully qualified `.proto` name of the enum, if known.
+    pub fn full_name(&self) -> Option<&'static str> {
+        (self.full_name)()
+    }
+
+    /// Returns the name of the value with the given number.
+    pub fn name_of(&self, value: i32) -> Option<&'static str> {
+        (self.name_of)(value)
+    }
+
+    /// Returns the number of the value with the given name.
+    pub fn value_of(&self, name: &str) -> Option<i32> {
+        (self.value_of)(name)
+    }
+}
+
+impl core::fmt::Debug for EnumType {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("EnumType")
+            .field("full_name", &self.full_name())
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,966 @@
+//! Protobuf text format.
+//!
+//! Messages derived with `#[derive(View)]` print themselves with `to_text_format()`, and their
+//! builders parse text format with `parse_text()`:
+//!
+//! ```
+//! use defiant::{Arena, View};
+//!
+//! #[derive(View)]
+//! struct Point<'arena> {
+//!     #[defiant(string, tag = "1")]
+//!     label: &'arena str,
+//!     #[defiant(sint32, repeated, tag = "2")]
+//!     coords: &'arena [i32],
+//! }
+//!
+//! let arena = Arena::new();
+//! let point = PointBuilder::parse_text(r#"label: "a\tb" coords: [1, -2]"#, &arena)
+//!     .unwrap()
+//!     .freeze();
+//! assert_eq!(point.label, "a\tb");
+//! assert_eq!(point.to_text_format(), "label: \"a\\tb\"\ncoords: 1\ncoords: -2\n");
+//! ```
+//!
+//! Both directions go through the binary encoding: printing encodes the message and walks the
+//! wire data with the message's [`FieldSet`], and parsing writes wire data which is then decoded
+//! as usual. Fields are printed in the order they are encoded, unknown fields by their number.
+//! Extensions and expanded `Any` messages are not supported.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{self, Write as _};
+use core::str;
+
+use bytes::{Buf, BufMut};
+
+use crate::encoding::{decode_key, decode_varint, encode_key, encode_varint, WireType};
+use crate::schema::{FieldInfo, FieldSet, FieldType, MapValueType, MessageType, ScalarType};
+use crate::{Arena, DecodeError, DecodeOptions, Encode};
+
+/// Formats a message as text format, one field per line.
+pub fn to_string<M: Encode + FieldSet>(message: &M) -> String {
+    let bytes = message.encode_to_vec();
+    let mut printer = Printer {
+        out: String::new(),
+        indent: 0,
+        depth: DecodeOptions::new().recursion_limit,
+    };
+    // The bytes were just encoded, so only malformed unknown fields can fail to print; whatever
+    // was printed up to that point is kept
+    let _ = printer.message(
+        &mut &bytes[..],
+        Fields::Message(MessageType::of::<M>()),
+        None,
+    );
+    printer.out
+}
+
+/// Formats the body of a message of type `ty`, or of an unknown type, as text format on a single
+/// line: `{ name: "a" nested { id: 1 } }`.
+pub(crate) fn to_line(body: &[u8], ty: Option<MessageType>) -> String {
+    let mut printer = Printer {
+        out: String::new(),
+        indent: 0,
+        depth: DecodeOptions::new().recursion_limit,
+    };
+    let fields = ty.map_or(Fields::Unknown, Fields::Message);
+    let _ = printer.message(&mut &body[..], fields, None);
+    let mut line = String::from("{");
+    for field in printer.out.lines() {
+        line.push(' ');
+        line.push_str(field.trim_start());
+    }
+    line.push_str(if line.len() == 1 { "}" } else { " }" });
+    line
+}
+
+/// Parses text format into the binary encoding of a message of type `M`, allocated in `arena`.
+pub fn to_wire<'arena, M: FieldSet>(
+    text: &str,
+    arena: &'arena Arena,
+) -> Result<&'arena [u8], ParseError> {
+    let mut parser = Parser {
+        text,
+        pos: 0,
+        depth: DecodeOptions::new().recursion_limit,
+    };
+    let mut wire = Vec::new();
+    parser.message(Fields::Message(MessageType::of::<M>()), None, &mut wire)?;
+    let mut bytes = arena.new_vec_with_capacity(wire.len());
+    bytes.extend_from_slice(&wire);
+    Ok(bytes.freeze())
+}
+
+/// Unescapes a string literal body in the C-style escaping used by text format and by default
+/// values in descriptors.
+///
+/// Returns a description of the problem if the string contains an invalid escape sequence.
+pub fn unescape_c_escape_string(s: &str) -> Result<Vec<u8>, &'static str> {
+    let src = s.as_bytes();
+    let len = src.len();
+    let mut dst = Vec::with_capacity(len);
+
+    let mut p = 0;
+    while p < len {
+        if src[p] != b'\\' {
+            dst.push(src[p]);
+            p += 1;
+            continue;
+        }
+        p += 1;
+        if p == len {
+            return Err("ends with '\\'");
+        }
+        match src[p] {
+            b'a' => dst.push(0x07),
+            b'b' => dst.push(0x08),
+            b'f' => dst.push(0x0C),
+            b'n' => dst.push(0x0A),
+            b'r' => dst.push(0x0D),
+            b't' => dst.push(0x09),
+            b'v' => dst.push(0x0B),
+            b'\\' => dst.push(0x5C),
+            b'?' => dst.push(0x3F),
+            b'\'' => dst.push(0x27),
+            b'"' => dst.push(0x22),
+            b'0'..=b'7' => {
+                let mut octal: u32 = 0;
+                for _ in 0..3 {
+                    if p < len && (b'0'..=b'7').contains(&src[p]) {
+                        octal = octal * 8 + u32::from(src[p] - b'0');
+                        p += 1;
+                    } else {
+                        break;
+                    }
+                }
+                // Values above \377 wrap, as in the C++ implementation
+                dst.push(octal as u8);
+                continue;
+            }
+            b'x' | b'X' => {
+                if p + 3 > len {
+                    return Err("incomplete hex value");
+                }
+                let hex = str::from_utf8(&src[p + 1..p + 3]).map_err(|_| "invalid hex value")?;
+                let byte = u8::from_str_radix(hex, 16).map_err(|_| "invalid hex value")?;
+                dst.push(byte);
+                p += 3;
+                continue;
+            }
+            _ => return Err("invalid escape"),
+        }
+        p += 1;
+    }
+    Ok(dst)
+}
+
+/// Text format could not be parsed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    message: String,
+    line: usize,
+    column: usize,
+}
+
+impl ParseError {
+    /// Returns the line of the error, starting at 1, or 0 if the text parsed but could not be
+    /// decoded.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Returns the column of the error in characters, starting at 1.
+    pub fn column(&self) -> usize {
+        self.column
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.line > 0 {
+            write!(f, "{}:{}: ", self.line, self.column)?;
+        }
+        f.write_str(&self.message)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseError {}
+
+impl From<DecodeError> for ParseError {
+    fn from(error: DecodeError) -> ParseError {
+        let mut message = String::new();
+        let _ = write!(message, "{error}");
+        ParseError {
+            message,
+            line: 0,
+            column: 0,
+        }
+    }
+}
+
+/// The fields of a message being printed or parsed.
+#[derive(Clone, Copy)]
+enum Fields {
+    /// A message whose type is not known, such as an unknown length-delimited field.
+    Unknown,
+    Message(MessageType),
+    /// An entry of a map field, with the types of its key and value.
+    MapEntry(ScalarType, MapValueType),
+}
+
+impl Fields {
+    fn by_tag(self, tag: u32) -> Option<FieldInfo> {
+        match (self, tag) {
+            (Fields::Unknown, _) => None,
+            (Fields::Message(message), _) => message.field_by_tag(tag),
+            (Fields::MapEntry(key, _), 1) => Some(FieldInfo::new("key", 1, FieldType::Scalar(key))),
+            (Fields::MapEntry(_, value), 2) => Some(FieldInfo::new("value", 2, value.into())),
+            (Fields::MapEntry(..), _) => None,
+        }
+    }
+
+    fn by_name(self, name: &str) -> Option<FieldInfo> {
+        match self {
+            Fields::Message(message) => message.field(name).or_else(|| {
+                // Groups are written with the name of their type
+                let mut lowercase = String::from(name);
+                lowercase.make_ascii_lowercase();
+                message
+                    .field(&lowercase)
+                    .filter(|field| matches!(field.ty(), FieldType::Group(_, ty) if ty == name))
+            }),
+            Fields::MapEntry(..) => ["key", "value"]
+                .iter()
+                .position(|&field| field == name)
+                .and_then(|index| self.by_tag(index as u32 + 1)),
+            Fields::Unknown => None,
+        }
+    }
+}
+
+struct Printer {
+    out: String,
+    indent: usize,
+    /// The number of further message nesting levels allowed.
+    depth: u32,
+}
+
+impl Printer {
+    /// Prints the fields in `buf`, up to the end group tag `end_group` if given.
+    fn message(
+        &mut self,
+        buf: &mut &[u8],
+        fields: Fields,
+        end_group: Option<u32>,
+    ) -> Result<(), DecodeError> {
+        if self.depth == 0 {
+            return Err(DecodeError::new("recursion limit reached"));
+        }
+        self.depth -= 1;
+        let result = self.fields(buf, fields, end_group);
+        self.depth += 1;
+        result
+    }
+
+    fn fields(
+        &mut self,
+        buf: &mut &[u8],
+        fields: Fields,
+        end_group: Option<u32>,
+    ) -> Result<(), DecodeError> {
+        while buf.has_remaining() {
+            let (tag, wire_type) = decode_key(buf)?;
+            if wire_type == WireType::EndGroup {
+                return match end_group {
+                    Some(end) if end == tag => Ok(()),
+                    _ => Err(DecodeError::new("unexpected end group tag")),
+                };
+            }
+            match fields.by_tag(tag) {
+                Some(field) if matches_wire_type(&field, wire_type) => {
+                    self.field(&field, wire_type, buf)?
+                }
+                _ => self.unknown(tag, wire_type, buf)?,
+            }
+        }
+        match end_group {
+            None => Ok(()),
+            Some(_) => Err(DecodeError::new("missing end group tag")),
+        }
+    }
+
+    fn line_start(&mut self, name: &str) {
+        for _ in 0..self.indent {
+            self.out.push_str("  ");
+        }
+        self.out.push_str(name);
+    }
+
+    fn field(
+        &mut self,
+        field: &FieldInfo,
+        wire_type: WireType,
+        buf: &mut &[u8],
+    ) -> Result<(), DecodeError> {
+        match field.ty() {
+            FieldType::Message(message) => {
+                let body = length_delimited(buf)?;
+                self.nested(field.name(), body, Fields::Message(message))
+            }
+            FieldType::Map(key, value) => {
+                let body = length_delimited(buf)?;
+                self.nested(field.name(), body, Fields::MapEntry(key, value))
+            }
+            FieldType::Group(message, name) => {
+                self.line_start(name);
+                self.out.push_str(" {\n");
+                self.indent += 1;
+                let result = self.message(buf, Fields::Message(message), Some(field.tag()));
+                self.indent -= 1;
+                result?;
+                self.line_start("}\n");
+                Ok(())
+            }
+            _ if wire_type == WireType::LengthDelimited && is_packable(field.ty()) => {
+                let mut values = length_delimited(buf)?;
+                let wire_type = scalar_wire_type(field.ty());
+                while values.has_remaining() {
+                    self.value(field, wire_type, &mut values)?;
+                }
+                Ok(())
+            }
+            _ => self.value(field, wire_type, buf),
+        }
+    }
+
+    /// Prints a message field, falling back to its raw bytes if it does not decode or is nested
+    /// too deeply.
+    fn nested(&mut self, name: &str, body: &[u8], fields: Fields) -> Result<(), DecodeError> {
+        let start = self.out.len();
+        self.line_start(name);
+        self.out.push_str(" {\n");
+        self.indent += 1;
+        let result = self.message(&mut &body[..], fields, None);
+        self.indent -= 1;
+        match result {
+            Ok(()) => self.line_start("}\n"),
+            Err(_) => {
+                self.out.truncate(start);
+                self.line_start(name);
+                self.out.push_str(": \"");
+                escape(body, false, &mut self.out);
+                self.out.push_str("\"\n");
+            }
+        }
+        Ok(())
+    }
+
+    fn value(
+        &mut self,
+        field: &FieldInfo,
+        wire_type: WireType,
+        buf: &mut &[u8],
+    ) -> Result<(), DecodeError> {
+        let mut value = String::new();
+        match field.ty() {
+            FieldType::Enum(ty) => {
+                let number = decode_varint(buf)? as i32;
+                match ty.name_of(number) {
+                    Some(name) => value.push_str(name),
+                    None => write_display(&mut value, number),
+                }
+            }
+            FieldType::Scalar(ty) => scalar(ty, wire_type, buf, &mut value)?,
+            _ => unreachable!("message fields are printed by `field`"),
+        }
+        self.line_start(field.name());
+        self.out.push_str(": ");
+        self.out.push_str(&value);
+        self.out.push('\n');
+        Ok(())
+    }
+
+    fn unknown(
+        &mut self,
+        tag: u32,
+        wire_type: WireType,
+        buf: &mut &[u8],
+    ) -> Result<(), DecodeError> {
+        let mut name = String::new();
+        write_display(&mut name, tag);
+        match wire_type {
+            WireType::Varint => {
+                let value = decode_varint(buf)?;
+                self.line_start(&name);
+                let _ = writeln!(self.out, ": {value}");
+            }
+            WireType::ThirtyTwoBit => {
+                let value = ensure_remaining::<4>(buf)?.get_u32_le();
+                self.line_start(&name);
+                let _ = writeln!(self.out, ": 0x{value:08x}");
+            }
+            WireType::SixtyFourBit => {
+                let value = ensure_remaining::<8>(buf)?.get_u64_le();
+                self.line_start(&name);
+                let _ = writeln!(self.out, ": 0x{value:016x}");
+            }
+            WireType::LengthDelimited => {
+                let body = length_delimited(buf)?;
+                if body.is_empty() {
+                    self.line_start(&name);
+                    self.out.push_str(": \"\"\n");
+                } else {
+                    self.nested(&name, body, Fields::Unknown)?;
+                }
+            }
+            WireType::StartGroup => {
+                self.line_start(&name);
+                self.out.push_str(" {\n");
+                self.indent += 1;
+                let result = self.message(buf, Fields::Unknown, Some(tag));
+                self.indent -= 1;
+                result?;
+                self.line_start("}\n");
+            }
+            WireType::EndGroup => return Err(DecodeError::new("unexpected end group tag")),
+        }
+        Ok(())
+    }
+}
+
+/// Formats a scalar value read from `buf`.
+pub(crate) fn scalar(
+    ty: ScalarType,
+    wire_type: WireType,
+    buf: &mut &[u8],
+    out: &mut String,
+) -> Result<(), DecodeError> {
+    match wire_type {
+        WireType::Varint => {
+            let value = decode_varint(buf)?;
+            match ty {
+                ScalarType::Int32 => write_display(out, value as i32),
+                ScalarType::Int64 => write_display(out, value as i64),
+                ScalarType::Uint32 => write_display(out, value as u32),
+                ScalarType::Sint32 => {
+                    let value = value as u32;
+                    write_display(out, (value >> 1) as i32 ^ -((value & 1) as i32))
+                }
+                ScalarType::Sint64 => {
+                    write_display(out, (value >> 1) as i64 ^ -((value & 1) as i64))
+                }
+                ScalarType::Bool => out.push_str(if value != 0 { "true" } else { "false" }),
+                _ => write_display(out, value),
+            }
+        }
+        WireType::ThirtyTwoBit => {
+            let value = ensure_remaining::<4>(buf)?.get_u32_le();
+            match ty {
+                ScalarType::Float => write_float(out, f64::from(f32::from_bits(value)), || {
+                    format_debug(f32::from_bits(value))
+                }),
+                ScalarType::Sfixed32 => write_display(out, value as i32),
+                _ => write_display(out, value),
+            }
+        }
+        WireType::SixtyFourBit => {
+            let value = ensure_remaining::<8>(buf)?.get_u64_le();
+            match ty {
+                ScalarType::Double => write_float(out, f64::from_bits(value), || {
+                    format_debug(f64::from_bits(value))
+                }),
+                ScalarType::Sfixed64 => write_display(out, value as i64),
+                _ => write_display(out, value),
+            }
+        }
+        _ => {
+            let value = length_delimited(buf)?;
+            out.push('"');
+            escape(value, ty == ScalarType::String, out);
+            out.push('"');
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn write_display(out: &mut String, value: impl fmt::Display) {
+    let _ = write!(out, "{value}");
+}
+
+fn format_debug(value: impl fmt::Debug) -> String {
+    let mut out = String::new();
+    let _ = write!(out, "{value:?}");
+    out
+}
+
+/// Writes a float with the shortest representation that reads back to the same value.
+fn write_float(out: &mut String, value: f64, format: impl FnOnce() -> String) {
+    if value.is_nan() {
+        out.push_str("nan");
+    } else if value.is_infinite() {
+        out.push_str(if value < 0.0 { "-inf" } else { "inf" });
+    } else {
+        out.push_str(&format());
+    }
+}
+
+/// Escapes a string or bytes value. Printable UTF-8 is kept as is in strings.
+pub(crate) fn escape(bytes: &[u8], utf8: bool, out: &mut String) {
+    let text = if utf8 {
+        str::from_utf8(bytes).ok()
+    } else {
+        None
+    };
+    let push_escaped = |c: char, out: &mut String| match c {
+        '\n' => out.push_str("\\n"),
+        '\r' => out.push_str("\\r"),
+        '\t' => out.push_str("\\t"),
+        '"' => out.push_str("\\\""),
+        '\'' => out.push_str("\\'"),
+        '\\' => out.push_str("\\\\"),
+        ' '..='~' => out.push(c),
+        c if (c as u32) < 0x80 => {
+            let _ = write!(out, "\\{:03o}", c as u32);
+        }
+        c => out.push(c),
+    };
+    match text {
+        Some(text) => text.chars().for_each(|c| push_escaped(c, out)),
+        None => {
+            for &byte in bytes {
+                if byte < 0x80 {
+                    push_escaped(char::from(byte), out);
+                } else {
+                    let _ = write!(out, "\\{byte:03o}");
+                }
+            }
+        }
+    }
+}
+
+pub(crate) fn length_delimited<'a>(buf: &mut &'a [u8]) -> Result<&'a [u8], DecodeError> {
+    let len = decode_varint(buf)?;
+    if len > buf.len() as u64 {
+        return Err(DecodeError::new("buffer underflow"));
+    }
+    let (body, rest) = buf.split_at(len as usize);
+    *buf = rest;
+    Ok(body)
+}
+
+/// Checks that `buf` holds at least `N` more bytes, for reading a fixed-width value out of it.
+pub(crate) fn ensure_remaining<'b, 'a, const N: usize>(
+    buf: &'b mut &'a [u8],
+) -> Result<&'b mut &'a [u8], DecodeError> {
+    if buf.len() < N {
+        return Err(DecodeError::new("buffer underflow"));
+    }
+    Ok(buf)
+}
+
+pub(crate) fn is_packable(ty: FieldType) -> bool {
+    match ty {
+        FieldType::Scalar(ty) => !matches!(ty, ScalarType::String | ScalarType::Bytes),
+        FieldType::Enum(_) => true,
+        _ => false,
+    }
+}
+
+pub(crate) fn scalar_wire_type(ty: FieldType) -> WireType {
+    match ty {
+        FieldType::Scalar(ScalarType::Float | ScalarType::Fixed32 | ScalarType::Sfixed32) => {
+            WireType::ThirtyTwoBit
+        }
+        FieldType::Scalar(ScalarType::Double | ScalarType::Fixed64 | ScalarType::Sfixed64) => {
+            WireType::SixtyFourBit
+        }
+        FieldType::Scalar(ScalarType::String | ScalarType::Bytes)
+        | FieldType::Message(_)
+        | FieldType::Map(..) => WireType::LengthDelimited,
+        FieldType::Group(..) => WireType::StartGroup,
+        FieldType::Scalar(_) | FieldType::Enum(_) => WireType::Varint,
+    }
+}
+
+pub(crate) fn matches_wire_type(field: &FieldInfo, wire_type: WireType) -> bool {
+    wire_type == scalar_wire_type(field.ty())
+        || (wire_type == WireType::LengthDelimited && is_packable(field.ty()))
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+    /// The number of further message nesting levels allowed.
+    depth: u32,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, message: impl fmt::Display) -> ParseError {
+        self.error_at(self.pos, message)
+    }
+
+    /// Returns an error located at the byte offset `pos`.
+    fn error_at(&self, pos: usize, message: impl fmt::Display) -> ParseError {
+        let before = &self.text[..pos];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+        let mut text = String::new();
+        write_display(&mut text, message);
+        ParseError {
+            message: text,
+            line,
+            column: before[line_start..].chars().count() + 1,
+        }
+    }
+
+    /// Skips whitespace and comments, and returns the next character.
+    fn peek(&mut self) -> Option<u8> {
+        let bytes = self.text.as_bytes();
+        while let Some(&byte) = bytes.get(self.pos) {
+            if byte == b'#' {
+                while self.pos < bytes.len() && bytes[self.pos] != b'\n' {
+                    self.pos += 1;
+                }
+            } else if byte.is_ascii_whitespace() {
+                self.pos += 1;
+            } else {
+                return Some(byte);
+            }
+        }
+        None
+    }
+
+    fn eat(&mut self, c: u8) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: u8) -> Result<(), ParseError> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.error(format_args!("expected '{}'", char::from(c))))
+        }
+    }
+
+    /// Reads a run of identifier characters, which also covers the body of a number.
+    fn word(&mut self) -> &'a str {
+        self.peek();
+        let start = self.pos;
+        let bytes = self.text.as_bytes();
+        while self.pos < bytes.len()
+            && (bytes[self.pos].is_ascii_alphanumeric() || bytes[self.pos] == b'_')
+        {
+            self.pos += 1;
+        }
+        &self.text[start..self.pos]
+    }
+
+    fn identifier(&mut self) -> Result<&'a str, ParseError> {
+        match self.peek() {
+            Some(c) if c.is_ascii_alphabetic() || c == b'_' => Ok(self.word()),
+            Some(b'[') => Err(self.error("extensions and Any expansions are not supported")),
+            _ => Err(self.error("expected a field name")),
+        }
+    }
+
+    /// Parses the fields of a message up to the closing delimiter `end`, or the end of the text.
+    fn message(
+        &mut self,
+        fields: Fields,
+        end: Option<u8>,
+        out: &mut Vec<u8>,
+    ) -> Result<(), ParseError> {
+        loop {
+            match self.peek() {
+                None if end.is_none() => return Ok(()),
+                None => return Err(self.error("unexpected end of input")),
+                Some(c) if Some(c) == end => {
+                    self.pos += 1;
+                    return Ok(());
+                }
+                _ => {}
+            }
+
+            let name = self.identifier()?;
+            let start = self.pos - name.len();
+            let field = fields
+                .by_name(name)
+                .ok_or_else(|| self.error_at(start, format_args!("unknown field: {name}")))?;
+            let colon = self.eat(b':');
+            if self.peek() == Some(b'[') {
+                if !field.is_repeated() && !matches!(field.ty(), FieldType::Map(..)) {
+                    return Err(self.error_at(start, format_args!("field {name} is not repeated")));
+                }
+                self.pos += 1;
+                if !self.eat(b']') {
+                    loop {
+                        self.value(&field, colon, out)?;
+                        if self.eat(b']') {
+                            break;
+                        }
+                        self.expect(b',')?;
+                    }
+                }
+            } else {
+                self.value(&field, colon, out)?;
+            }
+            if !self.eat(b',') {
+                self.eat(b';');
+            }
+        }
+    }
+
+    fn value(
+        &mut self,
+        field: &FieldInfo,
+        colon: bool,
+        out: &mut Vec<u8>,
+    ) -> Result<(), ParseError> {
+        let tag = field.tag();
+        let fields = match field.ty() {
+            FieldType::Message(message) => Fields::Message(message),
+            FieldType::Map(key, value) => Fields::MapEntry(key, value),
+            FieldType::Group(message, _) => {
+                encode_key(tag, WireType::StartGroup, out);
+                self.nested(Fields::Message(message), out)?;
+                encode_key(tag, WireType::EndGroup, out);
+                return Ok(());
+            }
+            FieldType::Scalar(ty) => {
+                if !colon {
+                    return Err(self.error("expected ':'"));
+                }
+                return self.scalar(ty, tag, out);
+            }
+            FieldType::Enum(ty) => {
+                if !colon {
+                    return Err(self.error("expected ':'"));
+                }
+                let value = match self.peek() {
+                    Some(c) if c.is_ascii_alphabetic() => {
+                        let start = self.pos;
+                        let name = self.word();
+                        ty.value_of(name).ok_or_else(|| {
+                            self.error_at(start, format_args!("unknown enum value: {name}"))
+                        })?
+                    }
+                    _ => self.integer(i64::from(i32::MIN), u64::from(i32::MAX as u32))? as i32,
+                };
+                encode_key(tag, WireType::Varint, out);
+                encode_varint(value as i64 as u64, out);
+                return Ok(());
+            }
+        };
+        let mut body = Vec::new();
+        self.nested(fields, &mut body)?;
+        encode_key(tag, WireType::LengthDelimited, out);
+        encode_varint(body.len() as u64, out);
+        out.extend_from_slice(&body);
+        Ok(())
+    }
+
+    /// Parses a `{ ... }` or `< ... >` message body.
+    fn nested(&mut self, fields: Fields, out: &mut Vec<u8>) -> Result<(), ParseError> {
+        let end = match self.peek() {
+            Some(b'{') => b'}',
+            Some(b'<') => b'>',
+            _ => return Err(self.error("expected '{'")),
+        };
+        if self.depth == 0 {
+            return Err(self.error("recursion limit reached"));
+        }
+        self.pos += 1;
+        self.depth -= 1;
+        self.message(fields, Some(end), out)?;
+        self.depth += 1;
+        Ok(())
+    }
+
+    fn scalar(&mut self, ty: ScalarType, tag: u32, out: &mut Vec<u8>) -> Result<(), ParseError> {
+        match ty {
+            ScalarType::String | ScalarType::Bytes => {
+                self.peek();
+                let start = self.pos;
+                let value = self.string()?;
+                if ty == ScalarType::String && str::from_utf8(&value).is_err() {
+                    return Err(self.error_at(start, "invalid UTF-8 in string"));
+                }
+                encode_key(tag, WireType::LengthDelimited, out);
+                encode_varint(value.len() as u64, out);
+                out.extend_from_slice(&value);
+            }
+            ScalarType::Bool => {
+                let value = match self.word() {
+                    "true" | "True" | "t" | "1" => true,
+                    "false" | "False" | "f" | "0" => false,
+                    _ => return Err(self.error("expected a boolean")),
+                };
+                encode_key(tag, WireType::Varint, out);
+                encode_varint(u64::from(value), out);
+            }
+            ScalarType::Float => {
+                let value = self.float()? as f32;
+                encode_key(tag, WireType::ThirtyTwoBit, out);
+                out.put_f32_le(value);
+            }
+            ScalarType::Double => {
+                let value = self.float()?;
+                encode_key(tag, WireType::SixtyFourBit, out);
+                out.put_f64_le(value);
+            }
+            ScalarType::Int32 | ScalarType::Sint32 | ScalarType::Sfixed32 => {
+                let value = self.integer(i64::from(i32::MIN), i32::MAX as u64)? as i32;
+                match ty {
+                    ScalarType::Int32 => {
+                        encode_key(tag, WireType::Varint, out);
+                        encode_varint(value as i64 as u64, out);
+                    }
+                    ScalarType::Sint32 => {
+                        encode_key(tag, WireType::Varint, out);
+                        encode_varint(((value << 1) ^ (value >> 31)) as u32 as u64, out);
+                    }
+                    _ => {
+                        encode_key(tag, WireType::ThirtyTwoBit, out);
+                        out.put_i32_le(value);
+                    }
+                }
+            }
+            ScalarType::Int64 | ScalarType::Sint64 | ScalarType::Sfixed64 => {
+                let value = self.integer(i64::MIN, i64::MAX as u64)?;
+                match ty {
+                    ScalarType::Int64 => {
+                        encode_key(tag, WireType::Varint, out);
+                        encode_varint(value as u64, out);
+                    }
+                    ScalarType::Sint64 => {
+                        encode_key(tag, WireType::Varint, out);
+                        encode_varint(((value << 1) ^ (value >> 63)) as u64, out);
+                    }
+                    _ => {
+                        encode_key(tag, WireType::SixtyFourBit, out);
+                        out.put_i64_le(value);
+                    }
+                }
+            }
+            ScalarType::Uint32 | ScalarType::Fixed32 => {
+                let value = self.unsigned(u64::from(u32::MAX))? as u32;
+                if ty == ScalarType::Uint32 {
+                    encode_key(tag, WireType::Varint, out);
+                    encode_varint(u64::from(value), out);
+                } else {
+                    encode_key(tag, WireType::ThirtyTwoBit, out);
+                    out.put_u32_le(value);
+                }
+            }
+            ScalarType::Uint64 | ScalarType::Fixed64 => {
+                let value = self.unsigned(u64::MAX)?;
+                if ty == ScalarType::Uint64 {
+                    encode_key(tag, WireType::Varint, out);
+                    encode_varint(value, out);
+                } else {
+                    encode_key(tag, WireType::SixtyFourBit, out);
+                    out.put_u64_le(value);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Parses one or more adjacent quoted strings.
+    fn string(&mut self) -> Result<Vec<u8>, ParseError> {
+        let mut value = Vec::new();
+        let mut any = false;
+        while let Some(quote @ (b'"' | b'\'')) = self.peek() {
+            let bytes = self.text.as_bytes();
+            let start = self.pos + 1;
+            let mut end = start;
+            while end < bytes.len() && bytes[end] != quote {
+                match bytes[end] {
+                    b'\n' => break,
+                    b'\\' => end += 2,
+                    _ => end += 1,
+                }
+            }
+            if end >= bytes.len() || bytes[end] != quote {
+                return Err(self.error("unterminated string"));
+            }
+            let unescaped = unescape_c_escape_string(&self.text[start..end])
+                .map_err(|error| self.error(format_args!("invalid string: {error}")))?;
+            value.extend_from_slice(&unescaped);
+            self.pos = end + 1;
+            any = true;
+        }
+        if any {
+            Ok(value)
+        } else {
+            Err(self.error("expected a string"))
+        }
+    }
+
+    /// Parses an integer within `min..=max`.
+    fn integer(&mut self, min: i64, max: u64) -> Result<i64, ParseError> {
+        let negative = self.eat(b'-');
+        let magnitude = self.unsigned(if negative { min.unsigned_abs() } else { max })?;
+        Ok(if negative {
+            (magnitude as i64).wrapping_neg()
+        } else {
+            magnitude as i64
+        })
+    }
+
+    /// Parses a non-negative integer up to `max`, in decimal, hexadecimal or octal.
+    fn unsigned(&mut self, max: u64) -> Result<u64, ParseError> {
+        self.peek();
+        let start = self.pos;
+        let word = self.word();
+        let (digits, radix) =
+            if let Some(hex) = word.strip_prefix("0x").or_else(|| word.strip_prefix("0X")) {
+                (hex, 16)
+            } else if word.len() > 1 && word.starts_with('0') {
+                (&word[1..], 8)
+            } else {
+                (word, 10)
+            };
+        match u64::from_str_radix(digits, radix) {
+            Ok(value) if value <= max => Ok(value),
+            Ok(_) => Err(self.error_at(start, "integer out of range")),
+            Err(_) => Err(self.error_at(start, "expected an integer")),
+        }
+    }
+
+    fn float(&mut self) -> Result<f64, ParseError> {
+        let negative = self.eat(b'-');
+        self.peek();
+        let start = self.pos;
+        let bytes = self.text.as_bytes();
+        while let Some(&c) = bytes.get(self.pos) {
+            let exponent_sign = (c == b'-' || c == b'+')
+                && matches!(bytes.get(self.pos - 1), Some(b'e' | b'E'))
+                && self.pos > start;
+            if c.is_ascii_alphanumeric() || c == b'.' || c == b'_' || exponent_sign {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+        let word = &self.text[start..self.pos];
+        let lowercase = word.to_ascii_lowercase();
+        let value = match lowercase.as_str() {
+            "inf" | "infinity" => f64::INFINITY,
+            "nan" => f64::NAN,
+            number => number
+                .strip_suffix('f')
+                .unwrap_or(number)
+                .parse::<f64>()
+                .map_err(|_| self.error_at(start, "expected a number"))?,
+        };
+        Ok(if negative { -value } else { value })
+    }
+}
@@ -0,0 +1,87 @@
+//! Storage for fields that were not recognized while decoding a message.
+
+use core::fmt;
+
+use bytes::BufMut;
+
+use crate::{Arena, ArenaClone};
+
+/// The wire-format records of every field a message did not recognize while decoding.
+///
+/// Views opt in by declaring a field marked `#[defiant(unknown_fields)]`:
+///
+/// ```
+/// use defiant::{Arena, Encode, UnknownFields, View};
+///
+/// #[derive(View)]
+/// struct Old<'arena> {
+///     #[defiant(string, tag = "1")]
+///     name: &'arena str,
+///     #[defiant(unknown_fields)]
+///     unknown_fields: UnknownFields<'arena>,
+/// }
+///
+/// // Field 2 is unknown to `Old`, but survives the roundtrip.
+/// let wire = [0x0a, 0x01, b'a', 0x10, 0x2a];
+/// let arena = Arena::new();
+/// let old = Old::from_buf(&wire[..], &arena).unwrap();
+/// assert_eq!(old.unknown_fields.as_bytes(), &[0x10, 0x2a]);
+/// assert_eq!(old.encode_to_vec(), wire);
+/// ```
+///
+/// The records (keys included) are kept in the order they were read, and are written back after
+/// all known fields when the view is encoded. Types that do not declare the field discard
+/// unknown data, which avoids the extra copy on hot paths.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct UnknownFields<'arena> {
+    bytes: &'arena [u8],
+}
+
+impl<'arena> UnknownFields<'arena> {
+    /// Wraps already-encoded field records.
+    ///
+    /// The bytes must be a sequence of complete protobuf fields (key followed by value), as
+    /// produced by [`encoding::merge_unknown_field`](crate::encoding::merge_unknown_field).
+    #[inline]
+    pub const fn new(bytes: &'arena [u8]) -> Self {
+        UnknownFields { bytes }
+    }
+
+    /// Returns `true` if no unknown fields were encountered.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    /// Returns the raw wire-format records.
+    #[inline]
+    pub fn as_bytes(&self) -> &'arena [u8] {
+        self.bytes
+    }
+
+    /// Writes the records back out, verbatim.
+    #[inline]
+    pub fn encode_raw(&self, buf: &mut impl BufMut) {
+        buf.put_slice(self.bytes);
+    }
+
+    /// Returns the number of bytes `encode_raw` will write.
+    #[inline]
+    pub fn encoded_len(&self) -> usize {
+        self.bytes.len()
+    }
+}
+
+impl ArenaClone for UnknownFields<'_> {
+    type Cloned<'arena> = UnknownFields<'arena>;
+
+    fn clone_in<'arena>(&self, arena: &'arena Arena) -> UnknownFields<'arena> {
+        UnknownFields::new(self.bytes.clone_in(arena))
+    }
+}
+
+impl fmt::Debug for UnknownFields<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("UnknownFields").field(&self.bytes).finish()
+    }
+}
@@ -0,0 +1,80 @@
+//! Test moving an arena and the view allocated in it between threads
+
+use std::sync::mpsc;
+
+use defiant::{Arena, ArenaBox, ArenaMap, DecodeError, Encode};
+use defiant_derive::View;
+
+#[derive(View)]
+struct Request<'arena> {
+    #[defiant(string, tag = 1)]
+    path: &'arena str,
+    #[defiant(arena_map = "string, string", tag = 2)]
+    headers: ArenaMap<'arena, &'arena str, &'arena str>,
+    #[defiant(message, optional, tag = 3)]
+    body: Option<&'arena Body<'arena>>,
+}
+
+#[derive(View)]
+struct Body<'arena> {
+    #[defiant(bytes, tag = 1)]
+    data: &'arena [u8],
+}
+
+fn request_bytes() -> Vec<u8> {
+    let body = Body { data: b"payload" };
+    Request {
+        path: "/upload",
+        headers: ArenaMap::new(&[("host", "example.com")]),
+        body: Some(&body),
+    }
+    .encode_to_vec()
+}
+
+#[test]
+fn test_send_decoded_request_to_another_thread() {
+    let bytes = request_bytes();
+    let (sender, receiver) = mpsc::channel::<ArenaBox<Request<'static>>>();
+    let worker = std::thread::spawn(move || {
+        let request = receiver.recv().unwrap();
+        let view = request.view();
+        assert_eq!(view.path, "/upload");
+        assert_eq!(view.headers.get(&"host"), Some(&"example.com"));
+        assert_eq!(view.body.unwrap().data, b"payload");
+        view.encode_to_vec()
+    });
+
+    let request = ArenaBox::<Request<'static>>::decode(bytes.as_slice()).unwrap();
+    sender.send(request).unwrap();
+    assert_eq!(worker.join().unwrap(), bytes);
+}
+
+#[test]
+fn test_build_and_reuse_arena() {
+    let arena = Arena::new();
+    arena.alloc_str("scratch");
+    let request = ArenaBox::<Request<'static>>::new(arena, |arena| Request {
+        path: arena.alloc_str("/built"),
+        headers: ArenaMap::default(),
+        body: None,
+    });
+    let request = std::thread::spawn(move || request).join().unwrap();
+    assert_eq!(request.view().path, "/built");
+
+    let extra = request.arena().alloc_str("alongside");
+    assert_eq!(extra, "alongside");
+
+    let mut arena = request.into_arena();
+    arena.reset();
+    assert_eq!(arena.alloc_str("reused"), "reused");
+}
+
+#[test]
+fn test_decode_errors_are_returned() {
+    let truncated = &request_bytes()[..4];
+    let error: DecodeError = ArenaBox::<Request<'static>>::decode(truncated).unwrap_err();
+    assert!(!error.to_string().is_empty());
+
+    let result = ArenaBox::<Body<'static>>::try_new(Arena::new(), |_| Err("no body"));
+    assert_eq!(result.err(), Some("no body"));
+}
@@ -0,0 +1,110 @@
+//! Test that decoding into a budgeted arena fails cleanly instead of aborting
+
+use defiant::{Arena, DecodeLimit, Encode, UnknownFields};
+use defiant_derive::View;
+
+#[derive(View)]
+struct Upload<'arena> {
+    #[defiant(string, tag = 1)]
+    name: &'arena str,
+    #[defiant(bytes, tag = 2)]
+    data: &'arena [u8],
+    #[defiant(uint64, repeated, tag = 3)]
+    offsets: &'arena [u64],
+    #[defiant(message, repeated, tag = 4)]
+    parts: &'arena [&'arena Part<'arena>],
+}
+
+#[derive(View)]
+struct Part<'arena> {
+    #[defiant(string, tag = 1)]
+    etag: &'arena str,
+}
+
+#[derive(View)]
+struct Opaque<'arena> {
+    #[defiant(unknown_fields)]
+    unknown_fields: UnknownFields<'arena>,
+}
+
+const BUDGET: usize = 16 * 1024;
+
+#[test]
+fn test_arena_budget_small_message_fits() {
+    let part = Part { etag: "abc" };
+    let upload = Upload {
+        name: "small",
+        data: b"payload",
+        offsets: &[0, 7],
+        parts: &[&part],
+    };
+    let bytes = upload.encode_to_vec();
+
+    let arena = Arena::with_limit(BUDGET);
+    let decoded = Upload::from_buf(bytes.as_slice(), &arena).unwrap();
+    assert_eq!(decoded.name, "small");
+    assert_eq!(decoded.parts[0].etag, "abc");
+}
+
+#[test]
+fn test_arena_budget_large_bytes() {
+    let data = vec![0u8; 4 * BUDGET];
+    let upload = Upload {
+        name: "large",
+        data: &data,
+        offsets: &[],
+        parts: &[],
+    };
+    let bytes = upload.encode_to_vec();
+
+    let arena = Arena::with_limit(BUDGET);
+    let error = Upload::from_buf(bytes.as_slice(), &arena).unwrap_err();
+    assert_eq!(error.limit_exceeded(), Some(DecodeLimit::ArenaBudget));
+    assert!(error.to_string().contains("arena budget exceeded"));
+}
+
+#[test]
+fn test_arena_budget_many_repeated() {
+    let offsets: Vec<u64> = (0..BUDGET as u64).collect();
+    let upload = Upload {
+        name: "",
+        data: &[],
+        offsets: &offsets,
+        parts: &[],
+    };
+    let bytes = upload.encode_to_vec();
+
+    let arena = Arena::with_limit(BUDGET);
+    let error = Upload::from_buf(bytes.as_slice(), &arena).unwrap_err();
+    assert_eq!(error.limit_exceeded(), Some(DecodeLimit::ArenaBudget));
+
+    let part = Part { etag: "" };
+    let parts = vec![&part; BUDGET];
+    let upload = Upload {
+        name: "",
+        data: &[],
+        offsets: &[],
+        parts: &parts,
+    };
+    let bytes = upload.encode_to_vec();
+
+    let arena = Arena::with_limit(BUDGET);
+    let error = Upload::from_buf(bytes.as_slice(), &arena).unwrap_err();
+    assert_eq!(error.limit_exceeded(), Some(DecodeLimit::ArenaBudget));
+}
+
+#[test]
+fn test_arena_budget_unknown_fields() {
+    let data = vec![0u8; 4 * BUDGET];
+    let upload = Upload {
+        name: "",
+        data: &data,
+        offsets: &[],
+        parts: &[],
+    };
+    let bytes = upload.encode_to_vec();
+
+    let arena = Arena::with_limit(BUDGET);
+    let error = Opaque::from_buf(bytes.as_slice(), &arena).unwrap_err();
+    assert_eq!(error.limit_exceeded(), Some(DecodeLimit::ArenaBudget));
+}
@@ -0,0 +1,120 @@
+//! Test deep-copying views into another arena
+
+use defiant::{Arena, ArenaClone, ArenaMap, Encode, LazyMessage, Oneof, UnknownFields};
+use defiant_derive::View;
+
+#[derive(View)]
+struct Document<'arena> {
+    #[defiant(string, tag = 1)]
+    title: &'arena str,
+    #[defiant(bytes, tag = 2)]
+    digest: &'arena [u8],
+    #[defiant(string, repeated, tag = 3)]
+    tags: &'arena [&'arena str],
+    #[defiant(arena_map = "string, message", tag = 4)]
+    sections: ArenaMap<'arena, &'arena str, &'arena Section<'arena>>,
+    #[defiant(oneof = "Source", tags = "5, 6")]
+    source: Option<Source<'arena>>,
+    #[defiant(message, optional, tag = 7)]
+    revision: Option<Revision>,
+    #[defiant(message, lazy, optional, tag = 8)]
+    appendix: Option<LazyMessage<'arena, Section<'arena>>>,
+    #[defiant(unknown_fields)]
+    unknown_fields: UnknownFields<'arena>,
+}
+
+#[derive(View)]
+struct Section<'arena> {
+    #[defiant(string, tag = 1)]
+    heading: &'arena str,
+    #[defiant(uint32, repeated, tag = 2)]
+    pages: &'arena [u32],
+}
+
+#[derive(Clone, Oneof)]
+enum Source<'arena> {
+    #[defiant(string, tag = 5)]
+    Url(&'arena str),
+    #[defiant(message, tag = 6)]
+    Original(&'arena Section<'arena>),
+}
+
+#[derive(Clone, Copy, View)]
+struct Revision {
+    #[defiant(uint64, tag = 1)]
+    number: u64,
+}
+
+fn document(arena: &Arena) -> Vec<u8> {
+    let intro = Section {
+        heading: "intro",
+        pages: &[1, 2],
+    };
+    let original = Section {
+        heading: "draft",
+        pages: &[],
+    };
+    let mut bytes = Document {
+        title: "report",
+        digest: b"\x01\x02",
+        tags: &["a", "b"],
+        sections: ArenaMap::new(arena.alloc([("intro", &intro)])),
+        source: Some(Source::Original(&original)),
+        revision: Some(Revision { number: 3 }),
+        appendix: Some(LazyMessage::from_message(&original, arena)),
+        unknown_fields: UnknownFields::default(),
+    }
+    .encode_to_vec();
+    // An unknown varint field 20
+    bytes.extend_from_slice(&[0xa0, 0x01, 0x07]);
+    bytes
+}
+
+#[test]
+fn test_clone_outlives_source_arena() {
+    let target = Arena::new();
+    let mut source = Arena::new();
+    let bytes = document(&source);
+    let original = Document::from_buf(bytes.as_slice(), &source).unwrap();
+    let cloned = original.clone_in(&target);
+    source.reset();
+
+    assert_eq!(cloned.title, "report");
+    assert_eq!(cloned.digest, b"\x01\x02");
+    assert_eq!(cloned.tags, &["a", "b"]);
+    let intro = cloned.sections.get(&"intro").unwrap();
+    assert_eq!((intro.heading, intro.pages), ("intro", &[1, 2][..]));
+    assert!(matches!(cloned.source, Some(Source::Original(section)) if section.heading == "draft"));
+    assert_eq!(cloned.revision.unwrap().number, 3);
+    let appendix = cloned.appendix.unwrap().get(&target).unwrap();
+    assert_eq!(appendix.heading, "draft");
+    assert_eq!(cloned.unknown_fields.as_bytes(), &[0xa0, 0x01, 0x07]);
+
+    assert_eq!(cloned.encode_to_vec(), bytes);
+}
+
+#[test]
+fn test_clone_oneof() {
+    let arena = Arena::new();
+    let url = String::from("https://example.com");
+    let source = Source::Url(&url).clone_in(&arena);
+    drop(url);
+    assert!(matches!(source, Source::Url("https://example.com")));
+}
+
+#[test]
+fn test_clone_compacts_edited_message() {
+    let scratch = Arena::new();
+    let mut builder = SectionBuilder::new_in(&scratch);
+    for i in 0..100 {
+        builder.set_heading(&format!("heading revision {i}"));
+    }
+    builder.push_pages(7);
+    let section = builder.freeze();
+
+    let compact = Arena::new();
+    let cloned = section.clone_in(&compact);
+    assert_eq!(cloned.heading, "heading revision 99");
+    assert_eq!(cloned.pages, &[7]);
+    assert!(compact.allocated_bytes() < scratch.allocated_bytes());
+}
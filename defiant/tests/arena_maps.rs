@@ -138,3 +138,34 @@ fn test_map_lookup() {
 
     println!("Successfully tested map lookups");
 }
+
+#[test]
+fn test_map_duplicate_keys_last_wins() {
+    let arena = Arena::new();
+
+    let first = UserProfile {
+        username: "first",
+        metadata: ArenaMap::new(&[]),
+        tags: ArenaMap::new(&[(1, "old"), (2, "kept")]),
+    };
+    let second = UserProfile {
+        username: "second",
+        metadata: ArenaMap::new(&[]),
+        tags: ArenaMap::new(&[(1, "new")]),
+    };
+
+    // Concatenated messages merge, so key 1 appears twice on the wire
+    let mut encoded = first.encode_to_vec();
+    encoded.extend_from_slice(&second.encode_to_vec());
+
+    let decoded = UserProfileBuilder::decode(encoded.as_slice(), &arena)
+        .expect("Failed to decode")
+        .freeze();
+
+    assert_eq!(decoded.username, "second");
+    assert_eq!(decoded.tags.len(), 2);
+    assert_eq!(decoded.tags.get(&1), Some(&"new"));
+    assert_eq!(decoded.tags.get(&2), Some(&"kept"));
+    assert!(decoded.tags.contains_key(&2));
+    assert!(!decoded.tags.contains_key(&3));
+}
@@ -0,0 +1,105 @@
+//! Test that borrowed decoding points string and bytes fields into the input buffer
+
+use defiant::{Arena, ArenaMap, Decode, Encode};
+use defiant_derive::View;
+
+#[derive(View)]
+struct Attachment<'arena> {
+    #[defiant(string, tag = 1)]
+    name: &'arena str,
+    #[defiant(bytes, tag = 2)]
+    data: &'arena [u8],
+}
+
+#[derive(View)]
+struct Mail<'arena> {
+    #[defiant(string, tag = 1)]
+    subject: &'arena str,
+    #[defiant(string, repeated, tag = 2)]
+    recipients: &'arena [&'arena str],
+    #[defiant(message, tag = 3)]
+    attachment: Option<&'arena Attachment<'arena>>,
+    #[defiant(arena_map = "string, string", tag = 4)]
+    headers: ArenaMap<'arena, &'arena str, &'arena str>,
+    #[defiant(uint32, tag = 5)]
+    size: u32,
+}
+
+fn encoded_mail() -> Vec<u8> {
+    let attachment = Attachment {
+        name: "report.pdf",
+        data: b"%PDF-1.7",
+    };
+    let headers: &[(&str, &str)] = &[("from", "alice"), ("reply-to", "bob")];
+    let mail = Mail {
+        subject: "quarterly numbers",
+        recipients: &["carol", "dave"],
+        attachment: Some(&attachment),
+        headers: ArenaMap::new(headers),
+        size: 1234,
+    };
+    mail.encode_to_vec()
+}
+
+fn is_within(input: &[u8], field: &[u8]) -> bool {
+    let range = input.as_ptr_range();
+    range.start <= field.as_ptr() && field.as_ptr_range().end <= range.end
+}
+
+#[test]
+fn test_borrowed_decode_points_into_input() {
+    let bytes = encoded_mail();
+    let arena = Arena::new();
+
+    let mail = Mail::from_buf_borrowed(&bytes, &arena).unwrap();
+    assert!(is_within(&bytes, mail.subject.as_bytes()));
+    for recipient in mail.recipients {
+        assert!(is_within(&bytes, recipient.as_bytes()));
+    }
+
+    let attachment = mail.attachment.unwrap();
+    assert!(is_within(&bytes, attachment.name.as_bytes()));
+    assert!(is_within(&bytes, attachment.data));
+
+    for (key, value) in mail.headers.iter() {
+        assert!(is_within(&bytes, key.as_bytes()));
+        assert!(is_within(&bytes, value.as_bytes()));
+    }
+}
+
+#[test]
+fn test_borrowed_decode_matches_copying_decode() {
+    let bytes = encoded_mail();
+    let arena = Arena::new();
+
+    let borrowed = Mail::from_buf_borrowed(&bytes, &arena).unwrap();
+    let copied = Mail::from_buf(bytes.as_slice(), &arena).unwrap();
+
+    assert!(!is_within(&bytes, copied.subject.as_bytes()));
+    assert_eq!(borrowed.subject, copied.subject);
+    assert_eq!(borrowed.recipients, copied.recipients);
+    assert_eq!(borrowed.attachment.unwrap().name, "report.pdf");
+    assert_eq!(borrowed.attachment.unwrap().data, b"%PDF-1.7");
+    assert_eq!(borrowed.headers.get(&"reply-to"), Some(&"bob"));
+    assert_eq!(borrowed.size, 1234);
+    assert_eq!(borrowed.encode_to_vec(), bytes);
+}
+
+#[test]
+fn test_borrowed_decode_builder() {
+    let bytes = encoded_mail();
+    let arena = Arena::new();
+
+    let builder = MailBuilder::decode_borrowed(&bytes, &arena).unwrap();
+    let mail = builder.freeze();
+    assert_eq!(mail.subject, "quarterly numbers");
+    assert!(is_within(&bytes, mail.subject.as_bytes()));
+}
+
+#[test]
+fn test_borrowed_decode_invalid_utf8() {
+    let bytes = [0x0a, 0x02, 0xff, 0xfe];
+    let arena = Arena::new();
+
+    assert!(Mail::from_buf_borrowed(&bytes, &arena).is_err());
+}
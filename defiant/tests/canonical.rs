@@ -0,0 +1,184 @@
+//! Test the canonical encoding
+
+use defiant::{is_canonical, Arena, ArenaMap, Encode, Oneof, UnknownFields};
+use defiant_derive::View;
+use proptest::prelude::*;
+
+// Fields are declared out of tag order, which the canonical encoding does not follow
+#[derive(View)]
+struct Record<'arena> {
+    #[defiant(int32, tag = 3)]
+    count: i32,
+    #[defiant(string, tag = 1)]
+    name: &'arena str,
+    #[defiant(oneof = "Choice", tags = "9, 2")]
+    choice: Option<Choice<'arena>>,
+    #[defiant(sint64, repeated, tag = 5)]
+    samples: &'arena [i64],
+    #[defiant(arena_map = "string, int64", tag = 4)]
+    labels: ArenaMap<'arena, &'arena str, i64>,
+    #[defiant(message, optional, tag = 7)]
+    child: Option<&'arena Child<'arena>>,
+    #[defiant(bool, optional, tag = 6)]
+    flag: Option<bool>,
+    #[defiant(unknown_fields)]
+    unknown_fields: UnknownFields<'arena>,
+}
+
+#[derive(Clone, Oneof)]
+enum Choice<'arena> {
+    #[defiant(string, tag = 9)]
+    Label(&'arena str),
+    #[defiant(uint32, tag = 2)]
+    Index(u32),
+}
+
+#[derive(View)]
+struct Child<'arena> {
+    #[defiant(double, tag = 2)]
+    weight: f64,
+    #[defiant(bytes, tag = 1)]
+    payload: &'arena [u8],
+}
+
+fn slice<'arena, T: Copy>(arena: &'arena Arena, values: &[T]) -> &'arena [T] {
+    let mut vec = arena.new_vec_with_capacity(values.len());
+    vec.extend_from_slice(values);
+    vec.freeze()
+}
+
+#[allow(clippy::too_many_arguments)]
+fn record<'arena>(
+    arena: &'arena Arena,
+    count: i32,
+    name: &str,
+    choice: Option<Result<String, u32>>,
+    samples: &[i64],
+    labels: Vec<(String, i64)>,
+    child: Option<(f64, Vec<u8>)>,
+    flag: Option<bool>,
+    unknown: &[u8],
+) -> Record<'arena> {
+    let labels = labels
+        .into_iter()
+        .map(|(key, value)| (arena.alloc_str(&key), value));
+    Record {
+        count,
+        name: arena.alloc_str(name),
+        choice: choice.map(|choice| match choice {
+            Ok(label) => Choice::Label(arena.alloc_str(&label)),
+            Err(index) => Choice::Index(index),
+        }),
+        samples: slice(arena, samples),
+        labels: ArenaMap::from_unsorted(arena, labels),
+        child: child.map(|(weight, payload)| {
+            &*arena.alloc(Child {
+                weight,
+                payload: slice(arena, &payload),
+            })
+        }),
+        flag,
+        unknown_fields: UnknownFields::new(slice(arena, unknown)),
+    }
+}
+
+/// Unknown fields 8 (varint) and 20 (length-delimited), in reverse tag order.
+const UNKNOWN: &[u8] = &[0xa2, 0x01, 0x01, b'u', 0x40, 0x05];
+
+proptest! {
+    #[test]
+    fn canonical_encoding_roundtrips(
+        count: i32,
+        name in ".{0,4}",
+        choice in proptest::option::of(prop_oneof![
+            ".{0,4}".prop_map(Ok),
+            any::<u32>().prop_map(Err),
+        ]),
+        samples: Vec<i64>,
+        labels in proptest::collection::vec((".{0,3}", any::<i64>()), 0..5),
+        child in proptest::option::of((any::<f64>(), proptest::collection::vec(any::<u8>(), 0..4))),
+        flag: Option<bool>,
+        unknown: bool,
+    ) {
+        let arena = Arena::new();
+        let unknown = if unknown { UNKNOWN } else { &[] };
+        let record = record(&arena, count, &name, choice, &samples, labels, child, flag, unknown);
+
+        let canonical = record.encode_canonical_to_vec();
+        prop_assert!(is_canonical::<Record>(&canonical));
+
+        let decoded = Record::from_buf(canonical.as_slice(), &arena).unwrap();
+        prop_assert_eq!(decoded.encode_canonical_to_vec(), canonical);
+    }
+}
+
+#[test]
+fn orders_fields_by_tag() {
+    let arena = Arena::new();
+    let record = record(
+        &arena,
+        7,
+        "n",
+        Some(Err(3)),
+        &[-1, 2],
+        vec![("b".into(), 2), ("a".into(), 1)],
+        None,
+        Some(false),
+        UNKNOWN,
+    );
+    let mut buf = Vec::new();
+    record.encode_canonical(&mut buf).unwrap();
+    assert_eq!(
+        buf,
+        [
+            0x0a, 0x01, b'n', // name
+            0x10, 0x03, // index, a oneof member placed by its own tag
+            0x18, 0x07, // count
+            0x22, 0x05, 0x0a, 0x01, b'a', 0x10, 0x01, // labels, sorted by key
+            0x22, 0x05, 0x0a, 0x01, b'b', 0x10, 0x02, // each with its key, then its value
+            0x2a, 0x02, 0x01, 0x04, // samples, packed
+            0x30, 0x00, // flag, which has presence
+            0x40, 0x05, // unknown fields, by tag
+            0xa2, 0x01, 0x01, b'u',
+        ]
+    );
+    assert!(is_canonical::<Record>(&buf));
+    assert!(!is_canonical::<Record>(&record.encode_to_vec()));
+}
+
+#[test]
+fn rejects_non_canonical_bytes() {
+    let non_canonical: &[&[u8]] = &[
+        // Out of tag order
+        &[0x18, 0x07, 0x0a, 0x01, b'n'],
+        // A varint longer than it needs to be
+        &[0x18, 0x87, 0x00],
+        // A repeated scalar that is not packed
+        &[0x28, 0x01, 0x28, 0x04],
+        // An empty packed field
+        &[0x2a, 0x00],
+        // A default proto3 scalar
+        &[0x18, 0x00],
+        // A singular field repeated
+        &[0x18, 0x01, 0x18, 0x02],
+        // Two members of a oneof
+        &[0x10, 0x03, 0x4a, 0x00],
+        // Map entries out of order
+        &[
+            0x22, 0x05, 0x0a, 0x01, b'b', 0x10, 0x02, 0x22, 0x05, 0x0a, 0x01, b'a', 0x10, 0x01,
+        ],
+        // A map entry without its value
+        &[0x22, 0x03, 0x0a, 0x01, b'a'],
+        // A nested message with a non-canonical body
+        &[
+            0x3a, 0x0c, 0x11, 0, 0, 0, 0, 0, 0, 0xf0, 0x3f, 0x0a, 0x01, b'x',
+        ],
+        // Not a message at all
+        &[0x0a, 0x05],
+    ];
+    for bytes in non_canonical {
+        assert!(!is_canonical::<Record>(bytes), "{bytes:02x?}");
+    }
+    assert!(is_canonical::<Record>(&[]));
+    assert!(is_canonical::<Record>(&[0x18, 0x07, 0x3a, 0x00]));
+}
@@ -0,0 +1,205 @@
+//! Test runtime decode limits configured with DecodeOptions
+
+use defiant::{Arena, ArenaMap, Decode, DecodeLimit, DecodeOptions, Encode};
+use defiant_derive::View;
+
+#[derive(View)]
+struct Node<'arena> {
+    #[defiant(string, tag = 1)]
+    name: &'arena str,
+    #[defiant(message, tag = 2)]
+    child: Option<&'arena Node<'arena>>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, View)]
+#[defiant(skip_debug)]
+struct Point {
+    #[defiant(int32, tag = 1)]
+    x: i32,
+}
+
+#[derive(View)]
+struct Batch<'arena> {
+    #[defiant(int32, repeated, tag = 1)]
+    ids: &'arena [i32],
+    #[defiant(string, repeated, tag = 2)]
+    names: &'arena [&'arena str],
+    #[defiant(message, repeated, tag = 3)]
+    points: &'arena [Point],
+    #[defiant(arena_map = "int32, string", tag = 4)]
+    labels: ArenaMap<'arena, i32, &'arena str>,
+    #[defiant(bytes, tag = 5)]
+    payload: &'arena [u8],
+}
+
+fn encoded_chain(depth: usize) -> Vec<u8> {
+    let arena = Arena::new();
+    let mut node = Node {
+        name: "leaf",
+        child: None,
+    };
+    for _ in 1..depth {
+        node = Node {
+            name: "node",
+            child: Some(&*arena.alloc(node)),
+        };
+    }
+    node.encode_to_vec()
+}
+
+fn encoded_batch() -> Vec<u8> {
+    let points = [Point { x: 1 }, Point { x: 2 }, Point { x: 3 }];
+    let batch = Batch {
+        ids: &[1, 2, 3],
+        names: &["a", "b", "c"],
+        points: &points,
+        labels: ArenaMap::new(&[(1, "one"), (2, "two"), (3, "three")]),
+        payload: b"0123456789",
+    };
+    batch.encode_to_vec()
+}
+
+fn limit_of<T>(result: Result<T, defiant::DecodeError>) -> Option<DecodeLimit> {
+    result.err().and_then(|error| error.limit_exceeded())
+}
+
+#[test]
+fn test_decode_options_defaults() {
+    let bytes = encoded_batch();
+    let arena = Arena::new();
+
+    let batch = Batch::from_buf_with_options(bytes.as_slice(), &arena, DecodeOptions::default())
+        .expect("default options accept the message");
+    assert_eq!(batch.ids, &[1, 2, 3]);
+    assert_eq!(batch.labels.get(&3), Some(&"three"));
+}
+
+#[test]
+fn test_decode_options_recursion_limit() {
+    let bytes = encoded_chain(10);
+    let arena = Arena::new();
+
+    let options = DecodeOptions::new().recursion_limit(9);
+    assert!(Node::from_buf_with_options(bytes.as_slice(), &arena, options).is_ok());
+
+    let options = DecodeOptions::new().recursion_limit(8);
+    assert_eq!(
+        limit_of(Node::from_buf_with_options(
+            bytes.as_slice(),
+            &arena,
+            options
+        )),
+        Some(DecodeLimit::RecursionDepth)
+    );
+}
+
+#[test]
+fn test_decode_options_input_length() {
+    let bytes = encoded_batch();
+    let arena = Arena::new();
+
+    let options = DecodeOptions::new().max_input_len(bytes.len());
+    assert!(BatchBuilder::decode_with_options(bytes.as_slice(), &arena, options).is_ok());
+
+    let options = DecodeOptions::new().max_input_len(bytes.len() - 1);
+    assert_eq!(
+        limit_of(BatchBuilder::decode_with_options(
+            bytes.as_slice(),
+            &arena,
+            options
+        )),
+        Some(DecodeLimit::InputLength)
+    );
+}
+
+#[test]
+fn test_decode_options_repeated_length() {
+    let arena = Arena::new();
+    let points = [Point { x: 1 }, Point { x: 2 }, Point { x: 3 }];
+    let empty = Batch {
+        ids: &[],
+        names: &[],
+        points: &[],
+        labels: ArenaMap::default(),
+        payload: &[],
+    };
+
+    // Every kind of repeated field is limited
+    let cases = [
+        (
+            "ids",
+            Batch {
+                ids: &[1, 2, 3],
+                ..empty
+            },
+        ),
+        (
+            "names",
+            Batch {
+                names: &["a", "b", "c"],
+                ..empty
+            },
+        ),
+        (
+            "points",
+            Batch {
+                points: &points,
+                ..empty
+            },
+        ),
+        (
+            "labels",
+            Batch {
+                labels: ArenaMap::new(&[(1, "one"), (2, "two"), (3, "three")]),
+                ..empty
+            },
+        ),
+    ];
+    for (field, batch) in cases {
+        let bytes = batch.encode_to_vec();
+
+        let options = DecodeOptions::new().max_repeated_len(3);
+        assert!(Batch::from_buf_with_options(bytes.as_slice(), &arena, options).is_ok());
+
+        let options = DecodeOptions::new().max_repeated_len(2);
+        let error = Batch::from_buf_with_options(bytes.as_slice(), &arena, options).unwrap_err();
+        assert_eq!(
+            error.limit_exceeded(),
+            Some(DecodeLimit::RepeatedLength),
+            "{field}"
+        );
+        assert!(
+            error.to_string().contains(&format!("Batch.{field}")),
+            "{error}"
+        );
+    }
+}
+
+#[test]
+fn test_decode_options_string_length() {
+    let bytes = encoded_batch();
+    let arena = Arena::new();
+
+    let options = DecodeOptions::new().max_string_len(10);
+    assert!(Batch::from_buf_with_options(bytes.as_slice(), &arena, options).is_ok());
+
+    let options = DecodeOptions::new().max_string_len(9);
+    assert_eq!(
+        limit_of(Batch::from_buf_with_options(
+            bytes.as_slice(),
+            &arena,
+            options
+        )),
+        Some(DecodeLimit::StringLength)
+    );
+}
+
+#[test]
+fn test_decode_options_malformed_input() {
+    let arena = Arena::new();
+
+    // Truncated input is not a limit violation
+    let error = Batch::from_buf_with_options(&[0x2a, 0x05, 0x00][..], &arena, DecodeOptions::new())
+        .unwrap_err();
+    assert_eq!(error.limit_exceeded(), None);
+}
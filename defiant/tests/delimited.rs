@@ -0,0 +1,225 @@
+//! Test reading and writing streams of length-delimited messages
+
+use std::io::{BufReader, Cursor};
+
+use defiant::{
+    Arena, DecodeLimit, DelimitedReader, DelimitedWriter, Encode, EncodeError, LengthPrefix,
+};
+use defiant_derive::View;
+
+#[derive(View)]
+struct Event<'arena> {
+    #[defiant(string, tag = 1)]
+    name: &'arena str,
+    #[defiant(uint64, tag = 2)]
+    sequence: u64,
+}
+
+const PREFIXES: [LengthPrefix; 3] = [
+    LengthPrefix::Varint,
+    LengthPrefix::Fixed32Le,
+    LengthPrefix::Fixed32Be,
+];
+
+fn events() -> Vec<Event<'static>> {
+    let mut events: Vec<_> = (0..5)
+        .map(|sequence| Event {
+            name: "tick",
+            sequence,
+        })
+        .collect();
+    // An empty message encodes to zero bytes
+    events.push(Event {
+        name: "",
+        sequence: 0,
+    });
+    events.push(Event {
+        name: Box::leak("x".repeat(300).into_boxed_str()),
+        sequence: 5,
+    });
+    events
+}
+
+fn write_events(prefix: LengthPrefix) -> Vec<u8> {
+    let mut writer = DelimitedWriter::to_buf(Vec::new()).prefix(prefix);
+    for event in &events() {
+        writer.write(event).unwrap();
+    }
+    writer.into_inner().into_inner()
+}
+
+fn assert_events<'arena>(decoded: impl IntoIterator<Item = Event<'arena>>) {
+    let expected = events();
+    let decoded: Vec<_> = decoded.into_iter().collect();
+    assert_eq!(decoded.len(), expected.len());
+    for (decoded, expected) in decoded.iter().zip(&expected) {
+        assert_eq!(decoded.name, expected.name);
+        assert_eq!(decoded.sequence, expected.sequence);
+    }
+}
+
+#[test]
+fn test_delimited_prefix_formats() {
+    let event = Event {
+        name: "a",
+        sequence: 1,
+    };
+    let body = event.encode_to_vec();
+    let len = body.len() as u8;
+
+    for (prefix, header) in [
+        (LengthPrefix::Varint, vec![len]),
+        (LengthPrefix::Fixed32Le, vec![len, 0, 0, 0]),
+        (LengthPrefix::Fixed32Be, vec![0, 0, 0, len]),
+    ] {
+        let mut writer = DelimitedWriter::to_buf(Vec::new()).prefix(prefix);
+        writer.write(&event).unwrap();
+        assert_eq!(
+            writer.into_inner().into_inner(),
+            [header, body.clone()].concat()
+        );
+    }
+
+    // The default matches encode_length_delimited
+    let mut writer = DelimitedWriter::to_buf(Vec::new());
+    writer.write(&event).unwrap();
+    assert_eq!(
+        writer.into_inner().into_inner(),
+        event.encode_length_delimited_to_vec()
+    );
+}
+
+#[test]
+fn test_delimited_slice_roundtrip() {
+    for prefix in PREFIXES {
+        let bytes = write_events(prefix);
+        let arena = Arena::new();
+
+        let mut reader = DelimitedReader::from_slice(&bytes).prefix(prefix);
+        assert_events(reader.messages::<Event>(&arena).map(Result::unwrap));
+        assert!(reader.read::<Event>(&arena).unwrap().is_none());
+    }
+}
+
+#[test]
+fn test_delimited_io_roundtrip() {
+    for prefix in PREFIXES {
+        let mut writer = DelimitedWriter::to_writer(Vec::new()).prefix(prefix);
+        for event in &events() {
+            writer.write(event).unwrap();
+        }
+        writer.flush().unwrap();
+        let bytes = writer.into_inner().into_inner();
+        assert_eq!(bytes, write_events(prefix));
+
+        let arena = Arena::new();
+        let mut reader =
+            DelimitedReader::from_reader(BufReader::new(Cursor::new(bytes))).prefix(prefix);
+        assert_events(reader.messages::<Event>(&arena).map(Result::unwrap));
+    }
+}
+
+#[test]
+fn test_delimited_read_batch() {
+    let bytes = write_events(LengthPrefix::Varint);
+    let mut arena = Arena::new();
+    let mut reader = DelimitedReader::from_reader(bytes.as_slice());
+
+    let mut names = Vec::new();
+    let mut batches = 0;
+    loop {
+        let batch: Vec<Event> = reader.read_batch(&mut arena, 3).unwrap();
+        if batch.is_empty() {
+            break;
+        }
+        assert!(batch.len() <= 3);
+        names.extend(batch.iter().map(|event| event.name.len()));
+        batches += 1;
+    }
+    assert_eq!(batches, 3);
+    assert_eq!(names, [4, 4, 4, 4, 4, 0, 300]);
+}
+
+#[test]
+fn test_delimited_truncated() {
+    for prefix in PREFIXES {
+        let bytes = write_events(prefix);
+        let truncated = &bytes[..bytes.len() - 1];
+        let arena = Arena::new();
+
+        let mut reader = DelimitedReader::from_slice(truncated).prefix(prefix);
+        let results: Vec<_> = reader.messages::<Event>(&arena).collect();
+        assert_eq!(results.len(), events().len());
+        assert!(results.last().unwrap().is_err());
+
+        let mut reader = DelimitedReader::from_reader(truncated).prefix(prefix);
+        let error = reader
+            .messages::<Event>(&arena)
+            .find_map(Result::err)
+            .unwrap();
+        assert_eq!(error.kind(), std::io::ErrorKind::UnexpectedEof);
+    }
+
+    // A stream which ends inside a prefix
+    let arena = Arena::new();
+    let mut reader = DelimitedReader::from_slice(&[0x80]);
+    assert!(reader.read::<Event>(&arena).is_err());
+    let mut reader = DelimitedReader::from_reader(&[0, 0][..]).prefix(LengthPrefix::Fixed32Le);
+    let error = reader.read::<Event>(&arena).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::UnexpectedEof);
+}
+
+#[test]
+fn test_delimited_max_message_len() {
+    let bytes = write_events(LengthPrefix::Fixed32Be);
+    let arena = Arena::new();
+
+    let mut reader = DelimitedReader::from_slice(&bytes)
+        .prefix(LengthPrefix::Fixed32Be)
+        .max_message_len(100);
+    let error = reader
+        .messages::<Event>(&arena)
+        .find_map(Result::err)
+        .unwrap();
+    assert_eq!(error.limit_exceeded(), Some(DecodeLimit::InputLength));
+
+    // A huge prefix is rejected before anything is allocated
+    let mut reader = DelimitedReader::from_reader(&[0xff, 0xff, 0xff, 0xff][..])
+        .prefix(LengthPrefix::Fixed32Le)
+        .max_message_len(1 << 20);
+    let error = reader.read::<Event>(&arena).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+
+    // Without a limit, a huge prefix only reads as much as the stream holds
+    let mut reader = DelimitedReader::from_reader(&[0xff, 0xff, 0xff, 0xff, 0x0f, 0x08][..]);
+    let error = reader.read::<Event>(&arena).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::UnexpectedEof);
+}
+
+#[test]
+fn test_delimited_buf_capacity() {
+    let event = Event {
+        name: "capacity",
+        sequence: 1,
+    };
+    let mut storage = [0u8; 8];
+    let mut writer = DelimitedWriter::to_buf(&mut storage[..]);
+    let error: EncodeError = writer.write(&event).unwrap_err();
+    assert_eq!(error.required_capacity(), 1 + event.encoded_len());
+    assert_eq!(error.remaining(), 8);
+}
+
+#[test]
+#[cfg(target_pointer_width = "64")]
+fn test_delimited_fixed32_overflow() {
+    let len = u32::MAX as usize + 1;
+    let mut buf = Vec::new();
+    for prefix in [LengthPrefix::Fixed32Le, LengthPrefix::Fixed32Be] {
+        let error = prefix.encode(len, &mut buf).unwrap_err();
+        assert_eq!(error.required_capacity(), len);
+        assert_eq!(error.remaining(), u32::MAX as usize);
+    }
+    assert!(buf.is_empty());
+    LengthPrefix::Varint.encode(len, &mut buf).unwrap();
+    assert_eq!(buf, [0x80, 0x80, 0x80, 0x80, 0x10]);
+}
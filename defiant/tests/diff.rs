@@ -0,0 +1,169 @@
+//! Test structural differences between messages
+
+use defiant::diff::FieldDiff;
+use defiant::{assert_messages_eq, ArenaMap, Enumeration, Oneof};
+use defiant_derive::View;
+
+#[derive(View)]
+struct Company<'arena> {
+    #[defiant(string, tag = 1)]
+    name: &'arena str,
+    #[defiant(message, repeated, tag = 2)]
+    employees: &'arena [&'arena Employee<'arena>],
+    #[defiant(arena_map = "string, string", tag = 3)]
+    settings: ArenaMap<'arena, &'arena str, &'arena str>,
+    #[defiant(oneof = "Contact", tags = "4, 5")]
+    contact: Option<Contact<'arena>>,
+    #[defiant(sint32, repeated, tag = 6)]
+    scores: &'arena [i32],
+}
+
+#[derive(View)]
+struct Employee<'arena> {
+    #[defiant(string, tag = 1)]
+    name: &'arena str,
+    #[defiant(enumeration = "Role", tag = 2)]
+    role: i32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Enumeration)]
+#[repr(i32)]
+enum Role {
+    Engineer = 0,
+    Manager = 1,
+}
+
+#[derive(Clone, Oneof)]
+enum Contact<'arena> {
+    #[defiant(string, tag = 4)]
+    Email(&'arena str),
+    #[defiant(uint32, tag = 5)]
+    Extension(u32),
+}
+
+fn diff(path: &str, old: Option<&str>, new: Option<&str>) -> FieldDiff {
+    FieldDiff {
+        path: path.into(),
+        old: old.map(Into::into),
+        new: new.map(Into::into),
+    }
+}
+
+const ANN: Employee<'static> = Employee {
+    name: "ann",
+    role: 0,
+};
+const BOB: Employee<'static> = Employee {
+    name: "bob",
+    role: 0,
+};
+const CID: Employee<'static> = Employee {
+    name: "cid",
+    role: 1,
+};
+
+fn company() -> Company<'static> {
+    Company {
+        name: "acme",
+        employees: &[&ANN, &BOB],
+        settings: ArenaMap::new(&[("port", "80"), ("tls", "off")]),
+        contact: Some(Contact::Email("info@acme.test")),
+        scores: &[1, 2, 3],
+    }
+}
+
+#[test]
+fn test_equal_messages_have_no_diff() {
+    assert!(company().diff(&company()).is_empty());
+    assert_messages_eq!(company(), company());
+}
+
+#[test]
+fn test_nested_paths() {
+    let bobby = Employee {
+        name: "bobby",
+        role: 1,
+    };
+    let new = Company {
+        name: "acme inc",
+        employees: &[&ANN, &CID, &bobby],
+        settings: ArenaMap::new(&[("port", "8080"), ("debug", "on")]),
+        scores: &[1, 3, -4],
+        ..company()
+    };
+    assert_eq!(
+        company().diff(&new),
+        [
+            diff("name", Some("\"acme\""), Some("\"acme inc\"")),
+            diff("employees[1].name", Some("\"bob\""), Some("\"cid\"")),
+            diff("employees[1].role", None, Some("MANAGER")),
+            diff(
+                "employees[2]",
+                None,
+                Some("{ name: \"bobby\" role: MANAGER }")
+            ),
+            diff("settings[\"port\"]", Some("\"80\""), Some("\"8080\"")),
+            diff("settings[\"tls\"]", Some("\"off\""), None),
+            diff("settings[\"debug\"]", None, Some("\"on\"")),
+            diff("scores[1]", Some("2"), None),
+            diff("scores[2]", None, Some("-4")),
+        ]
+    );
+}
+
+#[test]
+fn test_repeated_insertion_is_reported_once() {
+    let new = Company {
+        employees: &[&CID, &ANN, &BOB],
+        ..company()
+    };
+    assert_eq!(
+        company().diff(&new),
+        [diff(
+            "employees[0]",
+            None,
+            Some("{ name: \"cid\" role: MANAGER }")
+        )]
+    );
+}
+
+#[test]
+fn test_oneof_case_change() {
+    let new = Company {
+        contact: Some(Contact::Extension(42)),
+        ..company()
+    };
+    assert_eq!(
+        company().diff(&new),
+        [diff(
+            "contact",
+            Some("email: \"info@acme.test\""),
+            Some("extension: 42")
+        )]
+    );
+
+    let unset = Company {
+        contact: None,
+        ..company()
+    };
+    assert_eq!(
+        company().diff(&unset),
+        [diff("email", Some("\"info@acme.test\""), None)]
+    );
+}
+
+#[test]
+#[should_panic(expected = "messages differ\n  employees[0].name: \"ann\" -> \"cid\"\n")]
+fn test_assert_messages_eq_lists_differences() {
+    let new = Company {
+        employees: &[
+            &Employee {
+                name: "cid",
+                role: 0,
+            },
+            &BOB,
+        ],
+        ..company()
+    };
+    assert_messages_eq!(company(), new);
+}
@@ -0,0 +1,161 @@
+//! Test proto2 extensions and the extension registry
+
+use defiant::{
+    Arena, DecodeLimit, DecodeOptions, Encode, EnumType, Enumeration, Extension, ExtensionRegistry,
+    FieldInfo, FieldType, MessageType, ScalarType, UnknownFields,
+};
+use defiant_derive::View;
+
+#[derive(View)]
+#[defiant(full_name = "acme.Envelope")]
+struct Envelope<'arena> {
+    #[defiant(string, tag = 1)]
+    route: &'arena str,
+    #[defiant(unknown_fields)]
+    unknown_fields: UnknownFields<'arena>,
+}
+
+#[derive(View)]
+#[defiant(full_name = "acme.Trace")]
+struct Trace<'arena> {
+    #[defiant(string, tag = 1)]
+    id: &'arena str,
+    #[defiant(uint32, tag = 2)]
+    hops: u32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Enumeration)]
+#[repr(i32)]
+enum Priority {
+    Low = 0,
+    High = 1,
+}
+
+const PRIORITY: Extension<Envelope<'static>, i32> = Extension::new(
+    "acme.Envelope",
+    FieldInfo::new(
+        "acme.priority",
+        100,
+        FieldType::Enum(EnumType::of::<Priority>()),
+    ),
+);
+const DEADLINE: Extension<Envelope<'static>, i64> = Extension::new(
+    "acme.Envelope",
+    FieldInfo::new("acme.deadline", 101, FieldType::Scalar(ScalarType::Sint64)),
+);
+const TAGS: Extension<Envelope<'static>, &'static [&'static str]> = Extension::new(
+    "acme.Envelope",
+    FieldInfo::new("acme.tags", 102, FieldType::Scalar(ScalarType::String)).repeated(),
+);
+const TRACE: Extension<Envelope<'static>, Trace<'static>> = Extension::new(
+    "acme.Envelope",
+    FieldInfo::new(
+        "acme.trace",
+        103,
+        FieldType::Message(MessageType::of::<Trace<'static>>()),
+    ),
+);
+
+#[test]
+fn test_set_and_get_extensions() {
+    let arena = Arena::new();
+    let mut builder = Envelope::builder(&arena);
+    builder.set_route("orders");
+    builder.set_extension(&PRIORITY, Priority::High as i32);
+    builder.set_extension(&DEADLINE, -30);
+    builder.set_extension(&TAGS, &["a", "b"]);
+    builder.set_extension(&TRACE, Trace { id: "t-1", hops: 3 });
+    // Setting an extension again replaces its value
+    builder.set_extension(&DEADLINE, -60);
+    let bytes = builder.freeze().encode_to_vec();
+
+    let envelope = Envelope::from_buf(bytes.as_slice(), &arena).unwrap();
+    assert_eq!(envelope.route, "orders");
+    assert_eq!(
+        envelope.get_extension(&PRIORITY, &arena).unwrap(),
+        Some(Priority::High as i32)
+    );
+    assert_eq!(
+        envelope.get_extension(&DEADLINE, &arena).unwrap(),
+        Some(-60)
+    );
+    assert_eq!(
+        envelope.get_extension(&TAGS, &arena).unwrap(),
+        Some(&["a", "b"][..])
+    );
+    let trace = envelope.get_extension(&TRACE, &arena).unwrap().unwrap();
+    assert_eq!((trace.id, trace.hops), ("t-1", 3));
+}
+
+#[test]
+fn test_clear_extension() {
+    let arena = Arena::new();
+    let mut builder = Envelope::builder(&arena);
+    builder.set_extension(&PRIORITY, Priority::High as i32);
+    builder.set_extension(&TAGS, &["a"]);
+    builder.clear_extension(&TAGS);
+    let envelope = builder.freeze();
+
+    assert!(envelope.has_extension(&PRIORITY));
+    assert!(!envelope.has_extension(&TAGS));
+    assert_eq!(envelope.get_extension(&TAGS, &arena).unwrap(), None);
+}
+
+#[test]
+fn test_message_extensions_are_merged() {
+    let arena = Arena::new();
+    let mut bytes = Vec::new();
+    for trace in [Trace { id: "t-1", hops: 0 }, Trace { id: "", hops: 2 }] {
+        let mut builder = Envelope::builder(&arena);
+        builder.set_extension(&TRACE, trace);
+        bytes.extend(builder.freeze().encode_to_vec());
+    }
+
+    let envelope = Envelope::from_buf(bytes.as_slice(), &arena).unwrap();
+    let trace = envelope.get_extension(&TRACE, &arena).unwrap().unwrap();
+    assert_eq!((trace.id, trace.hops), ("t-1", 2));
+}
+
+#[test]
+fn test_registry_checks_extensions_while_decoding() {
+    let arena = Arena::new();
+    let mut registry = ExtensionRegistry::new();
+    registry.register(&PRIORITY).register(&TRACE);
+    assert_eq!(registry.len(), 2);
+
+    // Field 103 as a varint, where `acme.trace` is a message
+    let bytes = [0xb8, 0x06, 0x01];
+    let envelope = Envelope::from_buf(&bytes[..], &arena).unwrap();
+    assert!(envelope.has_extension(&TRACE));
+    assert!(envelope.get_extension(&TRACE, &arena).is_err());
+
+    let error = Envelope::from_buf_with_extensions(&bytes[..], &arena, &registry).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "failed to decode Protobuf message: acme.Envelope.acme.trace: \
+         invalid wire type: Varint (expected LengthDelimited)"
+    );
+
+    // Unregistered extensions are kept without being checked
+    let mut builder = Envelope::builder(&arena);
+    builder.set_extension(&DEADLINE, 5);
+    let bytes = builder.freeze().encode_to_vec();
+    let envelope = Envelope::from_buf_with_extensions(bytes.as_slice(), &arena, &registry).unwrap();
+    assert_eq!(envelope.get_extension(&DEADLINE, &arena).unwrap(), Some(5));
+}
+
+#[test]
+fn test_registry_with_decode_options() {
+    let arena = Arena::new();
+    let mut registry = ExtensionRegistry::new();
+    registry.register(&TRACE);
+
+    let bytes = [0xb8, 0x06, 0x01];
+    let options = DecodeOptions::new().max_input_len(3).extensions(&registry);
+    let error = Envelope::from_buf_with_options(&bytes[..], &arena, options).unwrap_err();
+    assert!(error.to_string().contains("acme.trace"));
+
+    let options = DecodeOptions::new().max_input_len(2).extensions(&registry);
+    let error = Envelope::from_buf_with_options(&bytes[..], &arena, options).unwrap_err();
+    assert_eq!(error.limit_exceeded(), Some(DecodeLimit::InputLength));
+}
@@ -0,0 +1,260 @@
+//! Test printing and parsing the canonical JSON mapping
+
+use defiant::json::JsonOptions;
+use defiant::{Arena, ArenaMap, Enumeration, Oneof};
+use defiant_derive::View;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Enumeration)]
+#[repr(i32)]
+enum Color {
+    #[defiant(name = "COLOR_UNSPECIFIED")]
+    Unspecified = 0,
+    #[defiant(name = "COLOR_RED")]
+    Red = 1,
+}
+
+#[derive(View)]
+#[defiant(full_name = "test.Point")]
+struct Point<'arena> {
+    #[defiant(string, tag = 1)]
+    label: &'arena str,
+    #[defiant(sint32, tag = 2)]
+    x: i32,
+}
+
+#[derive(View)]
+struct Shape<'arena> {
+    #[defiant(string, tag = 1, json_name = "shapeName")]
+    name: &'arena str,
+    #[defiant(int64, repeated, tag = 2)]
+    big_numbers: &'arena [i64],
+    #[defiant(enumeration = "Color", tag = 3)]
+    color: i32,
+    #[defiant(bytes, tag = 4)]
+    checksum: &'arena [u8],
+    #[defiant(double, repeated, tag = 5)]
+    weights: &'arena [f64],
+    #[defiant(arena_map = "string, message", tag = 6)]
+    points: ArenaMap<'arena, &'arena str, &'arena Point<'arena>>,
+    #[defiant(oneof = "Fill", tags = "7, 8")]
+    fill: Option<Fill<'arena>>,
+    #[defiant(message, optional, tag = 9)]
+    created: Option<Timestamp>,
+    #[defiant(message, optional, tag = 10)]
+    lifetime: Option<Duration>,
+    #[defiant(message, optional, tag = 11)]
+    extra: Option<&'arena Any<'arena>>,
+}
+
+#[derive(Clone, Oneof)]
+enum Fill<'arena> {
+    #[defiant(string, tag = 7)]
+    Pattern(&'arena str),
+    #[defiant(message, tag = 8)]
+    Gradient(&'arena Point<'arena>),
+}
+
+#[derive(View)]
+struct Floats {
+    #[defiant(float, tag = 1)]
+    single: f32,
+    #[defiant(double, tag = 2)]
+    double: f64,
+}
+
+// Local copies of well-known types, recognized by their full name
+
+#[derive(Clone, Copy, View)]
+#[defiant(full_name = "google.protobuf.Timestamp")]
+struct Timestamp {
+    #[defiant(int64, tag = 1)]
+    seconds: i64,
+    #[defiant(int32, tag = 2)]
+    nanos: i32,
+}
+
+#[derive(Clone, Copy, View)]
+#[defiant(full_name = "google.protobuf.Duration")]
+struct Duration {
+    #[defiant(int64, tag = 1)]
+    seconds: i64,
+    #[defiant(int32, tag = 2)]
+    nanos: i32,
+}
+
+#[derive(View)]
+#[defiant(full_name = "google.protobuf.Any")]
+struct Any<'arena> {
+    #[defiant(string, tag = 1)]
+    type_url: &'arena str,
+    #[defiant(bytes, tag = 2)]
+    value: &'arena [u8],
+}
+
+const SHAPE: &str = r#"{"shapeName":"triangle","bigNumbers":["1","-9007199254740993"],"color":"COLOR_RED","checksum":"AP9hYgo=","weights":[0.5,"NaN","-Infinity"],"points":{"7":{"label":"a","x":-1}},"gradient":{"x":2},"created":"1972-01-01T10:00:20.021Z","lifetime":"-1.500s"}"#;
+
+#[test]
+fn prints_what_it_parses() {
+    let arena = Arena::new();
+    let shape = ShapeBuilder::parse_json(SHAPE, &arena).unwrap().freeze();
+
+    assert_eq!(shape.name, "triangle");
+    assert_eq!(shape.big_numbers, &[1, -9007199254740993]);
+    assert_eq!(shape.color, Color::Red as i32);
+    assert_eq!(shape.checksum, b"\0\xffab\n");
+    assert_eq!(shape.points.get(&"7").unwrap().label, "a");
+    assert!(matches!(shape.fill, Some(Fill::Gradient(point)) if point.x == 2));
+    let created = shape.created.unwrap();
+    assert_eq!((created.seconds, created.nanos), (63_108_020, 21_000_000));
+    let lifetime = shape.lifetime.unwrap();
+    assert_eq!((lifetime.seconds, lifetime.nanos), (-1, -500_000_000));
+
+    assert_eq!(shape.to_json().unwrap(), SHAPE);
+}
+
+#[test]
+fn prints_floats_in_shortest_form() {
+    let arena = Arena::new();
+    let cases = [
+        (
+            f32::MAX,
+            f64::MAX,
+            r#"{"single":3.4028235e38,"double":1.7976931348623157e308}"#,
+        ),
+        (
+            f32::MIN,
+            f64::MIN_POSITIVE,
+            r#"{"single":-3.4028235e38,"double":2.2250738585072014e-308}"#,
+        ),
+        (1e-7, 0.25, r#"{"single":1e-7,"double":0.25}"#),
+    ];
+    for (single, double, json) in cases {
+        let mut floats = FloatsBuilder::new_in(&arena);
+        floats.set_single(single);
+        floats.set_double(double);
+        assert_eq!(floats.freeze().to_json().unwrap(), json);
+
+        let parsed = FloatsBuilder::parse_json(json, &arena).unwrap().freeze();
+        assert_eq!((parsed.single, parsed.double), (single, double));
+    }
+
+    let error = FloatsBuilder::parse_json(r#"{"single":3.5e38}"#, &arena)
+        .err()
+        .unwrap();
+    assert_eq!(error.to_string(), "1:11: float out of range");
+}
+
+#[test]
+fn accepts_alternative_forms() {
+    let arena = Arena::new();
+    let json = r#"{
+        "name": "proto name",
+        "big_numbers": [1e3, "20", 3.0],
+        "color": 1,
+        "checksum": "AP9hYgo",
+        "weights": ["1.5", 2],
+        "created": "1972-01-01T12:00:20+02:00",
+        "pattern": null,
+        "extra": null
+    }"#;
+    let shape = ShapeBuilder::parse_json(json, &arena).unwrap().freeze();
+
+    assert_eq!(shape.name, "proto name");
+    assert_eq!(shape.big_numbers, &[1000, 20, 3]);
+    assert_eq!(shape.color, Color::Red as i32);
+    assert_eq!(shape.checksum, b"\0\xffab\n");
+    assert_eq!(shape.weights, &[1.5, 2.0]);
+    assert_eq!(shape.created.unwrap().seconds, 63_108_020);
+    assert!(shape.fill.is_none());
+    assert!(shape.extra.is_none());
+}
+
+#[test]
+fn packs_registered_types_in_any() {
+    let arena = Arena::new();
+    let options = JsonOptions::new().register::<Point>();
+    let json = r#"{"extra":{"@type":"type.googleapis.com/test.Point","label":"p"}}"#;
+
+    let bytes = options.to_wire::<Shape>(json, &arena).unwrap();
+    let shape = Shape::from_buf(bytes, &arena).unwrap();
+    let any = shape.extra.unwrap();
+    assert_eq!(any.type_url, "type.googleapis.com/test.Point");
+    assert_eq!(options.to_string(&shape).unwrap(), json);
+
+    // Unregistered types cannot be printed or parsed
+    assert!(ShapeBuilder::parse_json(json, &arena).is_err());
+    assert!(shape.to_json().is_err());
+
+    // Well-known types need no registration, and are held under "value"
+    let json = r#"{"extra":{"@type":"type.googleapis.com/google.protobuf.Duration","value":"3s"}}"#;
+    let shape = ShapeBuilder::parse_json(json, &arena).unwrap().freeze();
+    assert_eq!(shape.to_json().unwrap(), json);
+}
+
+#[test]
+fn ignores_unknown_fields_when_asked() {
+    let arena = Arena::new();
+    let json = r#"{"shade": 1, "color": "COLOR_BLUE", "name": "n"}"#;
+    assert!(ShapeBuilder::parse_json(json, &arena).is_err());
+
+    let options = JsonOptions::new().ignore_unknown_fields(true);
+    let bytes = options.to_wire::<Shape>(json, &arena).unwrap();
+    let shape = Shape::from_buf(bytes, &arena).unwrap();
+    assert_eq!(shape.name, "n");
+    assert_eq!(shape.color, 0);
+}
+
+#[test]
+fn reports_errors_with_position() {
+    let arena = Arena::new();
+    let error = |json| ShapeBuilder::parse_json(json, &arena).err().unwrap();
+
+    let unknown = error("{\n  \"shade\": 1}");
+    assert_eq!(unknown.to_string(), "2:3: unknown field: shade");
+    assert_eq!((unknown.line(), unknown.column()), (2, 3));
+
+    assert_eq!(
+        error(r#"{"name": "a", "shapeName": "b"}"#).to_string(),
+        "1:15: duplicate field: shapeName"
+    );
+    assert_eq!(
+        error(r#"{"pattern": "a", "gradient": {}}"#).to_string(),
+        "1:18: multiple fields of oneof fill"
+    );
+    assert_eq!(
+        error(r#"{"bigNumbers": ["9223372036854775808"]}"#).to_string(),
+        "1:17: integer out of range"
+    );
+    assert_eq!(
+        error(r#"{"bigNumbers": [1.5]}"#).to_string(),
+        "1:17: expected an integer"
+    );
+    assert_eq!(
+        error(r#"{"created": "1972-01-01T10:00:20z"}"#).to_string(),
+        "1:13: invalid timestamp"
+    );
+    assert_eq!(
+        error(r#"{"name": "a",}"#).to_string(),
+        "1:14: expected a member name"
+    );
+    assert_eq!(
+        error(r#"{"name": "\ud800"}"#).to_string(),
+        "1:11: unpaired surrogate"
+    );
+}
+
+#[test]
+fn rejects_values_without_a_json_form() {
+    let arena = Arena::new();
+    let timestamp = Timestamp {
+        seconds: 253_402_300_800,
+        nanos: 0,
+    };
+    let shape = ShapeBuilder::parse_json("{}", &arena).unwrap();
+    let mut shape = shape.freeze();
+    shape.created = Some(timestamp);
+    assert_eq!(
+        shape.to_json().unwrap_err().to_string(),
+        "timestamp out of range"
+    );
+}
@@ -0,0 +1,201 @@
+//! Test sub-message fields which are decoded on demand
+
+use defiant::{Arena, ArenaMap, DecodeLimit, DecodeOptions, Encode, LazyMessage, MessageView};
+use defiant_derive::View;
+
+#[derive(View)]
+struct Payload<'arena> {
+    #[defiant(string, tag = 1)]
+    body: &'arena str,
+    #[defiant(uint32, repeated, tag = 2)]
+    codes: &'arena [u32],
+    #[defiant(arena_map = "string, string", tag = 3)]
+    headers: ArenaMap<'arena, &'arena str, &'arena str>,
+}
+
+#[derive(View)]
+struct Envelope<'arena> {
+    #[defiant(string, tag = 1)]
+    route: &'arena str,
+    #[defiant(message, lazy, optional, tag = 2)]
+    payload: Option<LazyMessage<'arena, Payload<'arena>>>,
+    #[defiant(uint64, tag = 3)]
+    sequence: u64,
+}
+
+/// The same wire format as `Envelope`, decoded eagerly.
+#[derive(View)]
+struct EagerEnvelope<'arena> {
+    #[defiant(string, tag = 1)]
+    route: &'arena str,
+    #[defiant(message, optional, tag = 2)]
+    payload: Option<&'arena Payload<'arena>>,
+    #[defiant(uint64, tag = 3)]
+    sequence: u64,
+}
+
+fn payload<'arena>() -> Payload<'arena> {
+    Payload {
+        body: "hello",
+        codes: &[1, 2, 300],
+        headers: ArenaMap::new(&[("a", "1"), ("b", "2")]),
+    }
+}
+
+#[test]
+fn test_lazy_message_roundtrip() {
+    let arena = Arena::new();
+    let payload = payload();
+    let wire = EagerEnvelope {
+        route: "/rpc",
+        payload: Some(&payload),
+        sequence: 7,
+    }
+    .encode_to_vec();
+
+    let envelope = Envelope::from_buf(wire.as_slice(), &arena).unwrap();
+    assert_eq!(envelope.route, "/rpc");
+    assert_eq!(envelope.sequence, 7);
+
+    let lazy = envelope.payload.unwrap();
+    assert_eq!(lazy.as_bytes(), payload.encode_to_vec().as_slice());
+    let decoded = lazy.get(&arena).unwrap();
+    assert_eq!(decoded.body, "hello");
+    assert_eq!(decoded.codes, &[1, 2, 300]);
+    assert_eq!(decoded.headers.get(&"b"), Some(&"2"));
+
+    // The raw bytes are written back untouched, by every encoder
+    assert_eq!(envelope.encode_to_vec(), wire);
+    assert_eq!(envelope.arena_encode(&arena), wire.as_slice());
+    assert_eq!(envelope.arena_encode_reverse(&arena), wire.as_slice());
+    assert_eq!(envelope.encoded_len(), wire.len());
+}
+
+#[test]
+fn test_lazy_message_from_message() {
+    let arena = Arena::new();
+    let payload = payload();
+    let envelope = Envelope {
+        route: "/rpc",
+        payload: Some(LazyMessage::from_message(&payload, &arena)),
+        sequence: 0,
+    };
+    let eager = EagerEnvelope::from_buf(envelope.encode_to_vec().as_slice(), &arena).unwrap();
+    assert_eq!(eager.payload.unwrap().body, "hello");
+
+    let empty = Envelope {
+        route: "",
+        payload: Some(LazyMessage::new(&[])),
+        sequence: 0,
+    };
+    assert_eq!(empty.encode_to_vec(), [0x12, 0x00]);
+    assert_eq!(empty.payload.unwrap().get(&arena).unwrap().body, "");
+}
+
+#[test]
+fn test_lazy_message_merges_occurrences() {
+    let arena = Arena::new();
+    let first = EagerEnvelope {
+        route: "",
+        payload: Some(&Payload {
+            body: "first",
+            codes: &[1],
+            headers: ArenaMap::default(),
+        }),
+        sequence: 0,
+    };
+    let second = EagerEnvelope {
+        route: "",
+        payload: Some(&Payload {
+            body: "",
+            codes: &[2],
+            headers: ArenaMap::default(),
+        }),
+        sequence: 0,
+    };
+    let wire = [first.encode_to_vec(), second.encode_to_vec()].concat();
+
+    let lazy = Envelope::from_buf(wire.as_slice(), &arena).unwrap();
+    let eager = EagerEnvelope::from_buf(wire.as_slice(), &arena).unwrap();
+    let merged = lazy.payload.unwrap().get(&arena).unwrap();
+    assert_eq!(merged.body, eager.payload.unwrap().body);
+    assert_eq!(merged.codes, eager.payload.unwrap().codes);
+    assert_eq!(merged.codes, &[1, 2]);
+}
+
+#[test]
+fn test_lazy_message_borrowed() {
+    let arena = Arena::new();
+    let payload = payload();
+    let wire = EagerEnvelope {
+        route: "/rpc",
+        payload: Some(&payload),
+        sequence: 1,
+    }
+    .encode_to_vec();
+
+    let envelope = Envelope::from_buf_borrowed(&wire, &arena).unwrap();
+    let bytes = envelope.payload.unwrap().as_bytes();
+    let range = wire.as_ptr_range();
+    assert!(range.contains(&bytes.as_ptr()));
+    assert_eq!(envelope.payload.unwrap().get(&arena).unwrap().body, "hello");
+}
+
+#[test]
+fn test_lazy_message_defers_errors() {
+    let arena = Arena::new();
+    // Field 2 holds a truncated string
+    let wire = [0x12, 0x02, 0x0a, 0x05];
+    let envelope = Envelope::from_buf(&wire[..], &arena).unwrap();
+    assert!(envelope.payload.unwrap().get(&arena).is_err());
+    assert!(EagerEnvelope::from_buf(&wire[..], &arena).is_err());
+
+    // The envelope itself must still be well-formed
+    let wire = [0x12, 0x05, 0x0a];
+    assert!(Envelope::from_buf(&wire[..], &arena).is_err());
+}
+
+#[test]
+fn test_lazy_message_get_through_trait() {
+    fn decode_all<'arena, M: MessageView<'arena>>(
+        lazy: &[LazyMessage<'arena, M>],
+        arena: &'arena Arena,
+    ) -> usize {
+        lazy.iter().filter(|lazy| lazy.get(arena).is_ok()).count()
+    }
+
+    let arena = Arena::new();
+    let bytes = payload().arena_encode(&arena);
+    let lazy = [
+        LazyMessage::<Payload>::new(bytes),
+        LazyMessage::new(&[0xff]),
+    ];
+    assert_eq!(decode_all(&lazy, &arena), 1);
+}
+
+#[test]
+fn test_lazy_message_options() {
+    let arena = Arena::new();
+    let payload = payload();
+    let wire = EagerEnvelope {
+        route: "/rpc",
+        payload: Some(&payload),
+        sequence: 7,
+    }
+    .encode_to_vec();
+    let len = payload.encoded_len();
+
+    // The body is limited like a bytes field while the envelope is decoded
+    let options = DecodeOptions::new().max_string_len(len - 1);
+    let error = Envelope::from_buf_with_options(wire.as_slice(), &arena, options).unwrap_err();
+    assert_eq!(error.limit_exceeded(), Some(DecodeLimit::StringLength));
+
+    // and its fields when it is decoded
+    let options = DecodeOptions::new().max_string_len(len);
+    let envelope = Envelope::from_buf_with_options(wire.as_slice(), &arena, options).unwrap();
+    let lazy = envelope.payload.unwrap();
+    let options = DecodeOptions::new().max_string_len(4);
+    let error = lazy.get_with_options(&arena, options).unwrap_err();
+    assert_eq!(error.limit_exceeded(), Some(DecodeLimit::StringLength));
+    assert_eq!(lazy.get(&arena).unwrap().body, "hello");
+}
@@ -0,0 +1,152 @@
+//! Test merging views into each other without a wire roundtrip
+
+use defiant::{Arena, ArenaMap, Encode, LazyMessage, Oneof};
+use defiant_derive::View;
+
+#[derive(View)]
+struct Config<'arena> {
+    #[defiant(string, tag = 1)]
+    name: &'arena str,
+    #[defiant(uint32, tag = 2)]
+    workers: u32,
+    #[defiant(bool, optional, tag = 3)]
+    verbose: Option<bool>,
+    #[defiant(string, repeated, tag = 4)]
+    hosts: &'arena [&'arena str],
+    #[defiant(arena_map = "string, string", tag = 5)]
+    env: ArenaMap<'arena, &'arena str, &'arena str>,
+    #[defiant(message, optional, tag = 6)]
+    limits: Option<&'arena Limits<'arena>>,
+    #[defiant(message, optional, tag = 7)]
+    retry: Option<Retry>,
+    #[defiant(oneof = "Output", tags = "8, 9")]
+    output: Option<Output<'arena>>,
+    #[defiant(message, lazy, optional, tag = 10)]
+    defaults: Option<LazyMessage<'arena, Limits<'arena>>>,
+}
+
+#[derive(View)]
+struct Limits<'arena> {
+    #[defiant(uint64, tag = 1)]
+    memory: u64,
+    #[defiant(uint64, tag = 2)]
+    cpu: u64,
+    #[defiant(string, repeated, tag = 3)]
+    notes: &'arena [&'arena str],
+}
+
+#[derive(Clone, Copy, View)]
+struct Retry {
+    #[defiant(uint32, tag = 1)]
+    attempts: u32,
+    #[defiant(uint32, tag = 2)]
+    backoff_ms: u32,
+}
+
+#[derive(Clone, Oneof)]
+enum Output<'arena> {
+    #[defiant(string, tag = 8)]
+    Path(&'arena str),
+    #[defiant(message, tag = 9)]
+    Limited(&'arena Limits<'arena>),
+}
+
+fn base<'arena>(arena: &'arena Arena) -> Config<'arena> {
+    let limits = arena.alloc(Limits {
+        memory: 512,
+        cpu: 2,
+        notes: &["base"],
+    });
+    Config {
+        name: "service",
+        workers: 4,
+        verbose: Some(false),
+        hosts: &["a"],
+        env: ArenaMap::new(&[("MODE", "prod"), ("REGION", "eu")]),
+        limits: Some(limits),
+        retry: Some(Retry {
+            attempts: 3,
+            backoff_ms: 100,
+        }),
+        output: Some(Output::Limited(limits)),
+        defaults: Some(LazyMessage::from_message(limits, arena)),
+    }
+}
+
+fn overrides<'arena>(arena: &'arena Arena) -> Config<'arena> {
+    let limits = arena.alloc(Limits {
+        memory: 0,
+        cpu: 8,
+        notes: &["override"],
+    });
+    Config {
+        name: "",
+        workers: 16,
+        verbose: None,
+        hosts: &["b"],
+        env: ArenaMap::new(&[("MODE", "dev")]),
+        limits: Some(limits),
+        retry: Some(Retry {
+            attempts: 0,
+            backoff_ms: 250,
+        }),
+        output: Some(Output::Limited(limits)),
+        defaults: Some(LazyMessage::from_message(limits, arena)),
+    }
+}
+
+#[test]
+fn test_merged_matches_merging_encodings() {
+    let arena = Arena::new();
+    let (base, overrides) = (base(&arena), overrides(&arena));
+    let merged = base.merged(&overrides, &arena);
+
+    assert_eq!(merged.name, "service");
+    assert_eq!(merged.workers, 16);
+    assert_eq!(merged.verbose, Some(false));
+    assert_eq!(merged.hosts, &["a", "b"]);
+    assert_eq!(merged.env.as_slice(), &[("MODE", "dev"), ("REGION", "eu")]);
+    let limits = merged.limits.unwrap();
+    assert_eq!((limits.memory, limits.cpu), (512, 8));
+    assert_eq!(limits.notes, &["base", "override"]);
+    let retry = merged.retry.unwrap();
+    assert_eq!((retry.attempts, retry.backoff_ms), (3, 250));
+    assert!(
+        matches!(merged.output, Some(Output::Limited(output)) if output.cpu == 8 && output.memory == 512)
+    );
+    let defaults = merged.defaults.unwrap().get(&arena).unwrap();
+    assert_eq!(defaults.notes, &["base", "override"]);
+
+    let mut bytes = base.encode_to_vec();
+    bytes.extend(overrides.encode_to_vec());
+    assert_eq!(
+        merged.encode_to_vec(),
+        Config::from_buf(bytes.as_slice(), &arena)
+            .unwrap()
+            .encode_to_vec()
+    );
+}
+
+#[test]
+fn test_oneof_member_is_replaced() {
+    let arena = Arena::new();
+    let mut builder = base(&arena).to_builder(&arena);
+    let path = Config {
+        output: Some(Output::Path("/var/log")),
+        ..overrides(&arena)
+    };
+    builder.merge_from(&path);
+    assert!(matches!(
+        builder.freeze().output,
+        Some(Output::Path("/var/log"))
+    ));
+}
+
+#[test]
+fn test_merge_into_empty_builder_copies() {
+    let arena = Arena::new();
+    let base = base(&arena);
+    let mut builder = ConfigBuilder::new_in(&arena);
+    builder.merge_from(&base);
+    assert_eq!(builder.freeze().encode_to_vec(), base.encode_to_vec());
+}
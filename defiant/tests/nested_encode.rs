@@ -0,0 +1,151 @@
+//! Test that encoding deeply nested messages with cached lengths produces the same bytes on every path
+
+use defiant::{Arena, ArenaMap, Encode, Oneof};
+use defiant_derive::View;
+
+#[derive(View)]
+struct Node<'arena> {
+    #[defiant(string, tag = 1)]
+    name: &'arena str,
+    #[defiant(message, tag = 2)]
+    child: Option<&'arena Node<'arena>>,
+    #[defiant(message, repeated, tag = 3)]
+    children: &'arena [&'arena Node<'arena>],
+    #[defiant(arena_map = "string, message", tag = 4)]
+    named: ArenaMap<'arena, &'arena str, &'arena Node<'arena>>,
+    #[defiant(oneof = "Extra", tags = "5, 6")]
+    extra: Option<Extra<'arena>>,
+    #[defiant(group, optional, tag = 7)]
+    group: Option<&'arena Meta<'arena>>,
+}
+
+#[derive(Clone, Oneof)]
+enum Extra<'arena> {
+    #[defiant(message, tag = 5)]
+    Node(&'arena Node<'arena>),
+    #[defiant(int32, tag = 6)]
+    Weight(i32),
+}
+
+#[derive(View)]
+struct Meta<'arena> {
+    #[defiant(string, tag = 8)]
+    label: &'arena str,
+    #[defiant(message, tag = 9)]
+    node: Option<&'arena Node<'arena>>,
+}
+
+fn leaf<'arena>(name: &'arena str) -> Node<'arena> {
+    Node {
+        name,
+        child: None,
+        children: &[],
+        named: ArenaMap::default(),
+        extra: None,
+        group: None,
+    }
+}
+
+/// Builds a tree which nests every kind of message field `depth` levels deep.
+fn tree<'arena>(arena: &'arena Arena, depth: usize) -> &'arena Node<'arena> {
+    let mut node: &Node = arena.alloc(leaf("leaf"));
+    for level in 0..depth {
+        let name = arena.alloc_str(&format!("level-{level}"));
+        let mut children = arena.new_vec();
+        children.push(node);
+        children.push(&*arena.alloc(leaf("sibling")));
+        let mut named = arena.new_vec();
+        named.push(("a", node));
+        named.push(("b", &*arena.alloc(leaf(""))));
+        let meta = arena.alloc(Meta {
+            label: "meta",
+            node: Some(node),
+        });
+        let extra = if level % 2 == 0 {
+            Extra::Node(node)
+        } else {
+            Extra::Weight(level as i32)
+        };
+        node = arena.alloc(Node {
+            name,
+            child: Some(node),
+            children: children.freeze(),
+            named: ArenaMap::from_vec(named),
+            extra: Some(extra),
+            group: Some(meta),
+        });
+    }
+    node
+}
+
+/// Builds a chain of messages nested through a single optional field.
+fn chain<'arena>(arena: &'arena Arena, depth: usize) -> &'arena Node<'arena> {
+    let mut node: &Node = arena.alloc(leaf("leaf"));
+    for _ in 0..depth {
+        node = arena.alloc(Node {
+            child: Some(node),
+            ..leaf("node")
+        });
+    }
+    node
+}
+
+#[test]
+fn test_nested_encode_paths_agree() {
+    let arena = Arena::new();
+    let root = tree(&arena, 6);
+
+    let expected = root.encode_to_vec();
+    assert_eq!(expected.len(), root.encoded_len());
+
+    let mut raw = Vec::new();
+    root.encode_raw(&mut raw);
+    assert_eq!(raw, expected);
+
+    let mut buf = Vec::new();
+    root.encode(&mut buf).unwrap();
+    assert_eq!(buf, expected);
+
+    assert_eq!(root.arena_encode(&arena), expected.as_slice());
+    assert_eq!(root.arena_encode_reverse(&arena), expected.as_slice());
+
+    let delimited = root.encode_length_delimited_to_vec();
+    assert!(delimited.ends_with(&expected));
+}
+
+#[test]
+fn test_nested_encode_roundtrip() {
+    let arena = Arena::new();
+    let root = tree(&arena, 6);
+    let bytes = root.encode_to_vec();
+
+    let decoded = Node::from_buf(bytes.as_slice(), &arena).unwrap();
+    assert_eq!(decoded.name, "level-5");
+    assert_eq!(decoded.children.len(), 2);
+    assert_eq!(decoded.named.get(&"a").unwrap().name, "level-4");
+    assert!(matches!(decoded.extra, Some(Extra::Weight(5))));
+    assert_eq!(decoded.group.unwrap().node.unwrap().name, "level-4");
+    assert_eq!(decoded.encode_to_vec(), bytes);
+}
+
+#[test]
+fn test_nested_encode_deep_chain() {
+    let arena = Arena::new();
+    let depth = 2_000;
+    let root = chain(&arena, depth);
+    let bytes = root.encode_to_vec();
+    assert_eq!(bytes.len(), root.encoded_len());
+
+    // Walk the encoded chain by hand, checking each length prefix
+    let mut rest = bytes.as_slice();
+    for _ in 0..depth {
+        assert_eq!(&rest[..6], b"\x0a\x04node");
+        rest = &rest[6..];
+        assert_eq!(rest[0], 0x12);
+        let mut len_buf = &rest[1..];
+        let len = defiant::encoding::decode_varint(&mut len_buf).unwrap() as usize;
+        assert_eq!(len, len_buf.len());
+        rest = len_buf;
+    }
+    assert_eq!(rest, b"\x0a\x04leaf");
+}
@@ -0,0 +1,64 @@
+//! Test converting views to owned values and back
+
+use std::collections::BTreeMap;
+
+use defiant::{Arena, ArenaFrom, ArenaMap, Oneof, ToOwnedValue, UnknownFields};
+use defiant_derive::View;
+
+#[derive(Clone, Copy, PartialEq, View)]
+struct Point {
+    #[defiant(sint32, tag = 1)]
+    x: i32,
+    #[defiant(oneof = "Unit", tags = "2, 3")]
+    unit: Option<Unit>,
+}
+
+#[derive(Clone, Copy, PartialEq, Oneof)]
+enum Unit {
+    #[defiant(float, tag = 2)]
+    Scale(f32),
+    #[defiant(bool, tag = 3)]
+    Pixels(bool),
+}
+
+#[test]
+fn test_fields_round_trip() {
+    let arena = Arena::new();
+    let names: &[&str] = &["a", "b"];
+    let owned = names.to_owned_value();
+    assert_eq!(owned, vec![String::from("a"), String::from("b")]);
+    let view: &[&str] = ArenaFrom::arena_from(&owned, &arena);
+    assert_eq!(view, names);
+
+    let map = ArenaMap::new(&[(1, "one"), (2, "two")]);
+    let owned = map.to_owned_value();
+    assert_eq!(
+        owned,
+        BTreeMap::from([(1, "one".into()), (2, "two".into())])
+    );
+    let view: ArenaMap<'_, i32, &str> = ArenaFrom::arena_from(&owned, &arena);
+    assert_eq!(view.as_slice(), map.as_slice());
+
+    let unknown = UnknownFields::new(&[0x40, 0x05]);
+    let owned = unknown.to_owned_value();
+    let view: UnknownFields = ArenaFrom::arena_from(&owned, &arena);
+    assert_eq!(view.as_bytes(), unknown.as_bytes());
+}
+
+#[test]
+fn test_messages_without_lifetime_are_their_own_owned_type() {
+    let arena = Arena::new();
+    let point = Point {
+        x: -3,
+        unit: Some(Unit::Scale(0.5)),
+    };
+    let points: &[&Point] = &[&point];
+    let owned: Vec<Point> = points.to_owned_value();
+    assert_eq!(owned, vec![point]);
+
+    let view: &[&Point] = std::thread::spawn(move || owned)
+        .join()
+        .map(|owned| ArenaFrom::arena_from(&owned, &arena))
+        .unwrap();
+    assert_eq!(view, points);
+}
@@ -0,0 +1,155 @@
+//! Test field presence, default-aware getters and builder clear methods
+
+use defiant::{Arena, Encode, Enumeration, Oneof};
+use defiant_derive::View;
+
+#[derive(View)]
+struct Server<'arena> {
+    #[defiant(string, optional, tag = 1, default = "localhost")]
+    host: Option<&'arena str>,
+    #[defiant(uint32, optional, tag = 2, default = "8080")]
+    port: Option<u32>,
+    #[defiant(uint32, tag = 3)]
+    workers: u32,
+    #[defiant(enumeration = "Mode", optional, tag = 4, default = "Fast")]
+    mode: Option<i32>,
+    #[defiant(message, optional, tag = 5)]
+    tls: Option<&'arena Tls<'arena>>,
+    #[defiant(message, optional, tag = 6)]
+    limits: Option<Limits>,
+    #[defiant(string, repeated, tag = 7)]
+    aliases: &'arena [&'arena str],
+    #[defiant(oneof = "Auth", tags = "8, 9")]
+    auth: Option<Auth<'arena>>,
+    #[defiant(string, required, tag = 10)]
+    name: &'arena str,
+}
+
+#[derive(View)]
+struct Tls<'arena> {
+    #[defiant(string, tag = 1)]
+    certificate: &'arena str,
+}
+
+#[derive(Clone, Copy, View)]
+struct Limits {
+    #[defiant(uint64, optional, tag = 1, default = "1024")]
+    memory: Option<u64>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Enumeration)]
+#[repr(i32)]
+enum Mode {
+    Safe = 0,
+    Fast = 1,
+}
+
+/// An enum whose first variant, its default, is not `0`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Enumeration)]
+#[repr(i32)]
+enum Level {
+    High = 3,
+    Low = 4,
+}
+
+#[derive(Clone, Copy, View)]
+struct Alarm {
+    #[defiant(enumeration = "Level", tag = 1)]
+    level: i32,
+}
+
+#[derive(Clone, Oneof)]
+enum Auth<'arena> {
+    #[defiant(string, tag = 8)]
+    Token(&'arena str),
+    #[defiant(bool, tag = 9)]
+    Anonymous(bool),
+}
+
+#[test]
+fn test_unset_fields_return_defaults() {
+    let arena = Arena::new();
+    let server = ServerBuilder::new_in(&arena).freeze();
+
+    assert!(!server.has_host());
+    assert_eq!(server.host(), "localhost");
+    assert!(!server.has_port());
+    assert_eq!(server.port(), 8080);
+    assert!(!server.has_workers());
+    assert_eq!(server.workers(), 0);
+    assert!(!server.has_mode());
+    assert_eq!(server.mode(), Mode::Fast);
+    assert!(!server.has_tls());
+    assert_eq!(server.tls().certificate, "");
+    assert!(std::ptr::eq(server.tls(), &Tls::DEFAULT));
+    assert!(!server.has_limits());
+    assert_eq!(server.limits().memory(), 1024);
+    assert!(!server.has_auth());
+    assert!(server.has_name());
+    assert_eq!(server.name(), "");
+}
+
+#[test]
+fn test_explicit_defaults_are_present() {
+    let arena = Arena::new();
+    let mut builder = ServerBuilder::new_in(&arena);
+    builder.set_port(0);
+    builder.set_workers(4);
+    builder.set_tls(Some(&Tls::DEFAULT));
+    let server = Server::from_buf(builder.freeze().encode_to_vec().as_slice(), &arena).unwrap();
+
+    assert!(server.has_port());
+    assert_eq!(server.port(), 0);
+    assert!(server.has_workers());
+    assert_eq!(server.workers(), 4);
+    assert!(server.has_tls());
+    assert!(!server.has_host());
+}
+
+#[test]
+fn test_clear_fields() {
+    let arena = Arena::new();
+    let tls = Tls { certificate: "pem" };
+    let server = Server {
+        host: Some("example.com"),
+        port: Some(443),
+        workers: 8,
+        mode: Some(Mode::Safe as i32),
+        tls: Some(&tls),
+        limits: Some(Limits { memory: Some(1) }),
+        aliases: &["www"],
+        auth: Some(Auth::Token("secret")),
+        name: "web",
+    };
+
+    let mut builder = server.to_builder(&arena);
+    builder.clear_host();
+    builder.clear_port();
+    builder.clear_workers();
+    builder.clear_mode();
+    builder.clear_tls();
+    builder.clear_limits();
+    builder.clear_aliases();
+    builder.clear_auth();
+    builder.clear_name();
+    let cleared = builder.freeze();
+
+    assert_eq!(
+        cleared.encode_to_vec(),
+        ServerBuilder::new_in(&arena).freeze().encode_to_vec()
+    );
+    assert_eq!(cleared.host(), "localhost");
+    assert_eq!(cleared.mode(), Mode::Fast);
+    assert!(cleared.aliases.is_empty());
+    assert!(!cleared.has_auth());
+}
+
+#[test]
+fn test_default_instance_uses_the_enum_default() {
+    let arena = Arena::new();
+    assert_eq!(Alarm::DEFAULT.level, Level::High as i32);
+    assert_eq!(
+        AlarmBuilder::new_in(&arena).freeze().level,
+        Level::High as i32
+    );
+}
@@ -0,0 +1,207 @@
+//! Test decoding only the fields selected by a projection
+
+use defiant::{
+    Arena, ArenaMap, DecodeLimit, DecodeOptions, Encode, FieldSet, Oneof, Projection, UnknownFields,
+};
+use defiant_derive::View;
+
+#[derive(View)]
+struct Header<'arena> {
+    #[defiant(string, tag = 1)]
+    trace_id: &'arena str,
+    #[defiant(string, tag = 2)]
+    user_agent: &'arena str,
+    #[defiant(message, optional, tag = 3)]
+    deadline: Option<Deadline>,
+}
+
+#[derive(Clone, Copy, View)]
+struct Deadline {
+    #[defiant(int64, tag = 1)]
+    seconds: i64,
+    #[defiant(int32, tag = 2)]
+    nanos: i32,
+}
+
+#[derive(View)]
+struct Request<'arena> {
+    #[defiant(message, optional, tag = 1)]
+    header: Option<&'arena Header<'arena>>,
+    #[defiant(string, tag = 2)]
+    method: &'arena str,
+    #[defiant(bytes, tag = 3)]
+    body: &'arena [u8],
+    #[defiant(string, repeated, tag = 4)]
+    tags: &'arena [&'arena str],
+    #[defiant(arena_map = "string, string", tag = 5)]
+    metadata: ArenaMap<'arena, &'arena str, &'arena str>,
+    #[defiant(oneof = "Route", tags = "6, 7")]
+    route: Option<Route<'arena>>,
+    #[defiant(unknown_fields)]
+    unknown_fields: UnknownFields<'arena>,
+}
+
+#[derive(Clone, Oneof)]
+enum Route<'arena> {
+    #[defiant(string, tag = 6)]
+    Path(&'arena str),
+    #[defiant(message, tag = 7)]
+    Forward(&'arena Header<'arena>),
+}
+
+fn request_bytes() -> Vec<u8> {
+    let header = Header {
+        trace_id: "trace-1",
+        user_agent: "curl/8.0",
+        deadline: Some(Deadline {
+            seconds: 30,
+            nanos: 5,
+        }),
+    };
+    let forward = Header {
+        trace_id: "trace-2",
+        user_agent: "proxy",
+        deadline: None,
+    };
+    let request = Request {
+        header: Some(&header),
+        method: "POST",
+        body: &[0xab; 4096],
+        tags: &["a", "b"],
+        metadata: ArenaMap::new(&[("k", "v")]),
+        route: Some(Route::Forward(&forward)),
+        unknown_fields: UnknownFields::default(),
+    };
+    let mut bytes = request.encode_to_vec();
+    // An unknown field, which a projection skips even though `Request` preserves them
+    bytes.extend_from_slice(&[0x40, 0x01]);
+    bytes
+}
+
+fn decode<'arena>(bytes: &[u8], arena: &'arena Arena, projection: &Projection) -> Request<'arena> {
+    Request::from_buf_projected(bytes, arena, projection).unwrap()
+}
+
+#[test]
+fn test_projection_nested_path() {
+    let bytes = request_bytes();
+    let arena = Arena::new();
+    let projection = Projection::from_paths::<Request, _, _>(["header.trace_id"]).unwrap();
+    assert_eq!(
+        projection,
+        Projection::new().nested(1, Projection::new().field(1))
+    );
+
+    let request = decode(&bytes, &arena, &projection);
+    let header = request.header.unwrap();
+    assert_eq!(header.trace_id, "trace-1");
+    assert_eq!(header.user_agent, "");
+    assert!(header.deadline.is_none());
+    assert_eq!(request.method, "");
+    assert!(request.body.is_empty());
+    assert!(request.tags.is_empty());
+    assert!(request.metadata.is_empty());
+    assert!(request.route.is_none());
+    assert!(request.unknown_fields.is_empty());
+}
+
+#[test]
+fn test_projection_whole_fields() {
+    let bytes = request_bytes();
+    let arena = Arena::new();
+    let projection = Projection::from_paths::<Request, _, _>([
+        "method",
+        "tags",
+        "metadata",
+        "header.deadline.nanos",
+        "header",
+    ])
+    .unwrap();
+
+    let request = decode(&bytes, &arena, &projection);
+    assert_eq!(request.method, "POST");
+    assert_eq!(request.tags, &["a", "b"]);
+    assert_eq!(request.metadata.get(&"k"), Some(&"v"));
+    // Selecting `header` whole overrides the narrower path
+    let header = request.header.unwrap();
+    assert_eq!(header.user_agent, "curl/8.0");
+    assert_eq!(header.deadline.as_ref().unwrap().seconds, 30);
+    assert!(request.body.is_empty());
+}
+
+#[test]
+fn test_projection_oneof() {
+    let bytes = request_bytes();
+    let arena = Arena::new();
+
+    let projection = Projection::from_paths::<Request, _, _>(["forward.user_agent"]).unwrap();
+    let request = decode(&bytes, &arena, &projection);
+    match request.route {
+        Some(Route::Forward(header)) => {
+            assert_eq!(header.user_agent, "proxy");
+            assert_eq!(header.trace_id, "");
+        }
+        _ => panic!("expected a forward route"),
+    }
+    assert!(request.header.is_none());
+
+    // Selecting the other member of the oneof skips it entirely
+    let projection = Projection::from_paths::<Request, _, _>(["path"]).unwrap();
+    assert!(decode(&bytes, &arena, &projection).route.is_none());
+}
+
+#[test]
+fn test_projection_skips_allocation() {
+    let bytes = request_bytes();
+
+    let full = Arena::new();
+    Request::from_buf(bytes.as_slice(), &full).unwrap();
+
+    let projected = Arena::new();
+    let projection = Projection::from_paths::<Request, _, _>(["header.deadline"]).unwrap();
+    let request = decode(&bytes, &projected, &projection);
+    assert_eq!(request.header.unwrap().deadline.as_ref().unwrap().nanos, 5);
+    assert!(projected.allocated_bytes() < full.allocated_bytes());
+    assert!(projected.allocated_bytes() < 4096);
+
+    // Nothing selected, nothing decoded
+    let empty = decode(&bytes, &projected, &Projection::new());
+    assert!(empty.header.is_none() && empty.route.is_none());
+}
+
+#[test]
+fn test_projection_with_options() {
+    let bytes = request_bytes();
+    let arena = Arena::new();
+    let projection = Projection::from_paths::<Request, _, _>(["method", "header"]).unwrap();
+
+    // The body is longer than the limit, but isn't decoded
+    let options = DecodeOptions::new().max_string_len(16);
+    let error = Request::from_buf_with_options(bytes.as_slice(), &arena, options).unwrap_err();
+    assert_eq!(error.limit_exceeded(), Some(DecodeLimit::StringLength));
+
+    let request =
+        Request::from_buf_with_options(bytes.as_slice(), &arena, options.projection(&projection))
+            .unwrap();
+    assert_eq!(request.method, "POST");
+    assert_eq!(request.header.unwrap().trace_id, "trace-1");
+    assert!(request.body.is_empty());
+
+    // The limits still apply to the selected fields
+    let options = DecodeOptions::new()
+        .max_string_len(4)
+        .projection(&projection);
+    let error = Request::from_buf_with_options(bytes.as_slice(), &arena, options).unwrap_err();
+    assert_eq!(error.limit_exceeded(), Some(DecodeLimit::StringLength));
+}
+
+#[test]
+fn test_projection_unknown_paths() {
+    for path in ["missing", "header.missing", "method.length", "tags.x", ""] {
+        let error = Projection::from_paths::<Request, _, _>([path]).unwrap_err();
+        assert_eq!(error.path(), path);
+    }
+    assert_eq!(Request::field("body").map(|field| field.tag()), Some(3));
+    assert_eq!(Request::field("forward").map(|field| field.tag()), Some(7));
+    assert!(Request::field("route").is_none());
+}
@@ -0,0 +1,242 @@
+//! Test checking proto2 required fields
+
+use defiant::encoding::{encode_varint, encoded_len_varint};
+use defiant::{missing_required_fields, Arena, ArenaMap, DecodeOptions, Encode, LazyMessage};
+use defiant_derive::View;
+
+#[derive(View)]
+struct Order<'arena> {
+    #[defiant(string, required, tag = 1)]
+    id: &'arena str,
+    #[defiant(message, repeated, tag = 2)]
+    items: &'arena [&'arena Item<'arena>],
+    #[defiant(message, optional, tag = 3)]
+    customer: Option<&'arena Customer<'arena>>,
+    #[defiant(arena_map = "string, message", tag = 4)]
+    addresses: ArenaMap<'arena, &'arena str, &'arena Customer<'arena>>,
+}
+
+#[derive(View)]
+struct Item<'arena> {
+    #[defiant(string, required, tag = 1)]
+    sku: &'arena str,
+    #[defiant(uint32, optional, tag = 2)]
+    quantity: Option<u32>,
+}
+
+#[derive(View)]
+struct Customer<'arena> {
+    #[defiant(string, required, tag = 1)]
+    name: &'arena str,
+    #[defiant(string, required, tag = 2)]
+    email: &'arena str,
+}
+
+/// `Order` with every field optional, to write incomplete messages.
+#[derive(View)]
+struct PartialOrder<'arena> {
+    #[defiant(string, optional, tag = 1)]
+    id: Option<&'arena str>,
+    #[defiant(message, repeated, tag = 2)]
+    items: &'arena [&'arena PartialItem<'arena>],
+    #[defiant(message, optional, tag = 3)]
+    customer: Option<&'arena PartialCustomer<'arena>>,
+    #[defiant(arena_map = "string, message", tag = 4)]
+    addresses: ArenaMap<'arena, &'arena str, &'arena PartialCustomer<'arena>>,
+}
+
+#[derive(View)]
+struct PartialItem<'arena> {
+    #[defiant(string, optional, tag = 1)]
+    sku: Option<&'arena str>,
+    #[defiant(uint32, optional, tag = 2)]
+    quantity: Option<u32>,
+}
+
+#[derive(View)]
+struct PartialCustomer<'arena> {
+    #[defiant(string, optional, tag = 1)]
+    name: Option<&'arena str>,
+    #[defiant(string, optional, tag = 2)]
+    email: Option<&'arena str>,
+}
+
+#[derive(View)]
+struct Envelope<'arena> {
+    #[defiant(message, lazy, optional, tag = 1)]
+    order: Option<LazyMessage<'arena, Order<'arena>>>,
+}
+
+#[derive(View)]
+struct Node<'arena> {
+    #[defiant(message, optional, tag = 1)]
+    child: Option<&'arena Node<'arena>>,
+    #[defiant(string, required, tag = 2)]
+    name: &'arena str,
+}
+
+#[derive(View)]
+struct LazyEnvelope<'arena> {
+    #[defiant(message, lazy, optional, tag = 1)]
+    node: Option<LazyMessage<'arena, Node<'arena>>>,
+}
+
+const SKU: PartialItem<'static> = PartialItem {
+    sku: Some("sku-1"),
+    quantity: Some(1),
+};
+const NO_SKU: PartialItem<'static> = PartialItem {
+    sku: None,
+    quantity: Some(2),
+};
+const NAME_ONLY: PartialCustomer<'static> = PartialCustomer {
+    name: Some("ann"),
+    email: None,
+};
+
+fn partial() -> PartialOrder<'static> {
+    PartialOrder {
+        id: None,
+        items: &[&SKU, &NO_SKU],
+        customer: Some(&NAME_ONLY),
+        addresses: ArenaMap::new(&[("home", &NAME_ONLY)]),
+    }
+}
+
+#[test]
+fn test_missing_fields_are_listed_by_path() {
+    let bytes = partial().encode_to_vec();
+    assert_eq!(
+        missing_required_fields::<Order>(&bytes).unwrap(),
+        [
+            "id",
+            "items[1].sku",
+            "customer.email",
+            "addresses[\"home\"].email"
+        ]
+    );
+}
+
+#[test]
+fn test_strict_decode() {
+    let arena = Arena::new();
+    let bytes = partial().encode_to_vec();
+
+    // Decoding doesn't check required fields unless asked to
+    let order = Order::from_buf(bytes.as_slice(), &arena).unwrap();
+    assert_eq!(order.id, "");
+
+    let strict = DecodeOptions::new().check_required(true);
+    let error = Order::from_buf_with_options(bytes.as_slice(), &arena, strict).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "failed to decode Protobuf message: missing required fields: id, items[1].sku, \
+         customer.email, addresses[\"home\"].email"
+    );
+
+    let complete = Order::from_buf(bytes.as_slice(), &arena)
+        .unwrap()
+        .encode_to_vec();
+    assert!(Order::from_buf_with_options(complete.as_slice(), &arena, strict).is_ok());
+}
+
+#[test]
+fn test_occurrences_of_a_message_are_merged() {
+    let arena = Arena::new();
+    let email_only = PartialCustomer {
+        name: None,
+        email: Some("ann@example.com"),
+    };
+    let mut bytes = PartialOrder {
+        id: Some("o-1"),
+        items: &[],
+        customer: Some(&NAME_ONLY),
+        addresses: ArenaMap::default(),
+    }
+    .encode_to_vec();
+    bytes.extend(
+        PartialOrder {
+            id: None,
+            items: &[],
+            customer: Some(&email_only),
+            addresses: ArenaMap::default(),
+        }
+        .encode_to_vec(),
+    );
+
+    let strict = DecodeOptions::new().check_required(true);
+    let order = Order::from_buf_with_options(bytes.as_slice(), &arena, strict).unwrap();
+    assert_eq!(order.customer().email, "ann@example.com");
+}
+
+#[test]
+fn test_lazy_messages_are_checked() {
+    let arena = Arena::new();
+    let envelope = Envelope {
+        order: Some(LazyMessage::new(partial().arena_encode(&arena))),
+    };
+    let missing = missing_required_fields::<Envelope>(&envelope.encode_to_vec()).unwrap();
+    assert_eq!(missing[0], "order.id");
+
+    // Views check their lazy bodies without encoding the whole message
+    assert!(!envelope.is_initialized());
+    assert_eq!(
+        envelope.missing_required_fields(),
+        [
+            "order.id",
+            "order.items[1].sku",
+            "order.customer.email",
+            "order.addresses[\"home\"].email"
+        ]
+    );
+}
+
+#[test]
+fn test_views_are_checked_before_encoding() {
+    let arena = Arena::new();
+    let bytes = partial().encode_to_vec();
+    let order = Order::from_buf(bytes.as_slice(), &arena).unwrap();
+    // A decoded view holds a value for every required field
+    assert!(order.is_initialized());
+    let mut buf = Vec::new();
+    order.encode_checked(&mut buf).unwrap();
+    assert_eq!(buf, order.encode_to_vec());
+
+    let envelope = Envelope {
+        order: Some(LazyMessage::new(partial().arena_encode(&arena))),
+    };
+    let mut buf = Vec::new();
+    let error = envelope.encode_checked(&mut buf).unwrap_err();
+    assert!(error.is_missing_required_fields());
+    assert!(buf.is_empty());
+}
+
+#[test]
+#[cfg(not(feature = "no-recursion-limit"))]
+fn test_deep_nesting_is_limited() {
+    // Field 1 nested in itself, far deeper than the recursion limit
+    const LEVELS: usize = 100_000;
+    let mut lens = vec![0usize; LEVELS + 1];
+    for level in (0..LEVELS).rev() {
+        let body = lens[level + 1];
+        lens[level] = 1 + encoded_len_varint(body as u64) + body;
+    }
+    let mut bytes = Vec::with_capacity(lens[0]);
+    for &len in &lens[1..] {
+        bytes.push(0x0a);
+        encode_varint(len as u64, &mut bytes);
+    }
+
+    let error = missing_required_fields::<Node>(&bytes).unwrap_err();
+    assert!(error.to_string().contains("recursion limit reached"));
+
+    let arena = Arena::new();
+    let strict = DecodeOptions::new().check_required(true);
+    assert!(Node::from_buf_with_options(bytes.as_slice(), &arena, strict).is_err());
+
+    // A lazy body nested too deeply is missing as a whole
+    let envelope = LazyEnvelope {
+        node: Some(LazyMessage::new(&bytes)),
+    };
+    assert_eq!(envelope.missing_required_fields(), ["node"]);
+}
@@ -0,0 +1,149 @@
+//! Test that the reverse encoder produces the same bytes as the forward encoder
+
+use defiant::{Arena, ArenaMap, Encode, Oneof, UnknownFields};
+use defiant_derive::View;
+
+#[derive(View)]
+struct Record<'arena> {
+    #[defiant(string, tag = 1)]
+    name: &'arena str,
+    #[defiant(sint64, tag = 2)]
+    delta: i64,
+    #[defiant(bytes, tag = 3)]
+    payload: &'arena [u8],
+    #[defiant(fixed32, repeated, tag = 4)]
+    samples: &'arena [u32],
+    #[defiant(string, repeated, tag = 5)]
+    tags: &'arena [&'arena str],
+    #[defiant(arena_map = "int32, string", tag = 6)]
+    labels: ArenaMap<'arena, i32, &'arena str>,
+    #[defiant(arena_map = "string, message", tag = 7)]
+    children: ArenaMap<'arena, &'arena str, &'arena Record<'arena>>,
+    #[defiant(message, repeated, tag = 8)]
+    history: &'arena [&'arena Record<'arena>],
+    #[defiant(oneof = "Value", tags = "9, 10, 11")]
+    value: Option<Value<'arena>>,
+    #[defiant(bool, tag = 12)]
+    active: bool,
+}
+
+#[derive(Clone, Oneof)]
+enum Value<'arena> {
+    #[defiant(string, tag = 9)]
+    Text(&'arena str),
+    #[defiant(double, tag = 10)]
+    Number(f64),
+    #[defiant(message, tag = 11)]
+    Record(&'arena Record<'arena>),
+}
+
+#[derive(View)]
+struct Partial<'arena> {
+    #[defiant(bool, tag = 12)]
+    active: bool,
+    #[defiant(unknown_fields)]
+    unknown_fields: UnknownFields<'arena>,
+}
+
+fn empty<'arena>() -> Record<'arena> {
+    Record {
+        name: "",
+        delta: 0,
+        payload: &[],
+        samples: &[],
+        tags: &[],
+        labels: ArenaMap::default(),
+        children: ArenaMap::default(),
+        history: &[],
+        value: None,
+        active: false,
+    }
+}
+
+fn record<'arena>(arena: &'arena Arena) -> &'arena Record<'arena> {
+    let leaf = arena.alloc(Record {
+        name: "leaf",
+        value: Some(Value::Number(0.5)),
+        ..empty()
+    });
+    let mut children = arena.new_vec();
+    children.push(("left", &*leaf));
+    children.push(("", &*arena.alloc(empty())));
+    let mut history = arena.new_vec();
+    history.push(&*leaf);
+    history.push(&*arena.alloc(Record {
+        value: Some(Value::Text("old")),
+        ..empty()
+    }));
+    arena.alloc(Record {
+        name: "root",
+        delta: -300,
+        payload: &[0, 1, 2, 3],
+        samples: &[1, 2, 3],
+        tags: &["a", "", "c"],
+        labels: ArenaMap::new(&[(0, "zero"), (1, ""), (2, "two")]),
+        children: ArenaMap::from_vec(children),
+        history: history.freeze(),
+        value: Some(Value::Record(leaf)),
+        active: true,
+    })
+}
+
+#[test]
+fn test_reverse_encode_matches_forward() {
+    let arena = Arena::new();
+    let root = record(&arena);
+
+    let forward = root.arena_encode(&arena);
+    let reverse = root.arena_encode_reverse(&arena);
+    assert_eq!(reverse, forward);
+
+    let decoded = Record::from_buf(reverse, &arena).unwrap();
+    assert_eq!(decoded.name, "root");
+    assert_eq!(decoded.delta, -300);
+    assert_eq!(decoded.tags, &["a", "", "c"]);
+    assert_eq!(decoded.children.get(&"left").unwrap().name, "leaf");
+    assert!(matches!(decoded.value, Some(Value::Record(record)) if record.name == "leaf"));
+}
+
+#[test]
+fn test_reverse_encode_empty() {
+    let arena = Arena::new();
+    assert!(empty().arena_encode_reverse(&arena).is_empty());
+}
+
+#[test]
+fn test_reverse_encode_unknown_fields() {
+    let arena = Arena::new();
+    let bytes = record(&arena).encode_to_vec();
+
+    let partial = Partial::from_buf(bytes.as_slice(), &arena).unwrap();
+    assert!(partial.active);
+    assert_eq!(
+        partial.arena_encode_reverse(&arena),
+        partial.encode_to_vec().as_slice()
+    );
+}
+
+#[test]
+fn test_reverse_encode_grows_buffer() {
+    let arena = Arena::new();
+    let data = vec![0xa5; 100_000];
+    let mut history = arena.new_vec();
+    for _ in 0..64 {
+        history.push(&*arena.alloc(Record {
+            payload: &data[..1000],
+            ..empty()
+        }));
+    }
+    let root = Record {
+        payload: &data,
+        history: history.freeze(),
+        ..empty()
+    };
+
+    assert_eq!(
+        root.arena_encode_reverse(&arena),
+        root.encode_to_vec().as_slice()
+    );
+}
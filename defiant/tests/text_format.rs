@@ -0,0 +1,234 @@
+//! Test printing and parsing text format
+
+use defiant::encoding::{encode_varint, encoded_len_varint};
+use defiant::{Arena, ArenaMap, Enumeration, Oneof, UnknownFields};
+use defiant_derive::View;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Enumeration)]
+#[repr(i32)]
+enum Color {
+    #[defiant(name = "COLOR_UNSPECIFIED")]
+    Unspecified = 0,
+    #[defiant(name = "COLOR_RED")]
+    Red = 1,
+    // Named after the variant
+    Blue = 2,
+}
+
+#[derive(View)]
+struct Point<'arena> {
+    #[defiant(string, tag = 1)]
+    label: &'arena str,
+    #[defiant(sint32, tag = 2)]
+    x: i32,
+    #[defiant(double, tag = 3)]
+    weight: f64,
+}
+
+#[derive(View)]
+struct Shape<'arena> {
+    #[defiant(string, tag = 1)]
+    name: &'arena str,
+    #[defiant(message, optional, tag = 2)]
+    origin: Option<&'arena Point<'arena>>,
+    #[defiant(message, repeated, tag = 3)]
+    points: &'arena [&'arena Point<'arena>],
+    #[defiant(int32, repeated, tag = 4)]
+    sizes: &'arena [i32],
+    #[defiant(enumeration = "Color", tag = 5)]
+    color: i32,
+    #[defiant(arena_map = "string, message", tag = 6)]
+    anchors: ArenaMap<'arena, &'arena str, &'arena Point<'arena>>,
+    #[defiant(arena_map = "int32, enumeration(Color)", tag = 7)]
+    palette: ArenaMap<'arena, i32, i32>,
+    #[defiant(bytes, tag = 8)]
+    checksum: &'arena [u8],
+    #[defiant(oneof = "Fill", tags = "9, 10")]
+    fill: Option<Fill<'arena>>,
+}
+
+#[derive(Clone, Oneof)]
+enum Fill<'arena> {
+    #[defiant(string, tag = 9)]
+    Pattern(&'arena str),
+    #[defiant(message, tag = 10)]
+    Gradient(&'arena Point<'arena>),
+}
+
+const SHAPE: &str = r#"name: "triangle"
+origin {
+  label: "o"
+  x: -1
+  weight: 0.5
+}
+points {
+  label: "a"
+}
+points {
+  x: 2
+}
+sizes: 1
+sizes: 2
+sizes: 3
+color: COLOR_RED
+anchors {
+  key: "top"
+  value {
+    x: 7
+  }
+}
+palette {
+  key: 1
+  value: BLUE
+}
+checksum: "\000\377ab\n"
+pattern: "dots"
+"#;
+
+#[test]
+fn parses_every_kind_of_field() {
+    let arena = Arena::new();
+    let shape = ShapeBuilder::parse_text(SHAPE, &arena).unwrap().freeze();
+
+    assert_eq!(shape.name, "triangle");
+    let origin = shape.origin.unwrap();
+    assert_eq!((origin.label, origin.x, origin.weight), ("o", -1, 0.5));
+    assert_eq!(shape.points.len(), 2);
+    assert_eq!(shape.points[0].label, "a");
+    assert_eq!(shape.points[1].x, 2);
+    assert_eq!(shape.sizes, &[1, 2, 3]);
+    assert_eq!(shape.color, Color::Red as i32);
+    assert_eq!(shape.anchors.get(&"top").unwrap().x, 7);
+    assert_eq!(shape.palette.get(&1), Some(&(Color::Blue as i32)));
+    assert_eq!(shape.checksum, b"\0\xffab\n");
+    assert!(matches!(shape.fill, Some(Fill::Pattern("dots"))));
+}
+
+#[test]
+fn prints_what_it_parses() {
+    let arena = Arena::new();
+    let shape = ShapeBuilder::parse_text(SHAPE, &arena).unwrap().freeze();
+    assert_eq!(shape.to_text_format(), SHAPE);
+}
+
+#[test]
+fn accepts_alternative_syntax() {
+    let arena = Arena::new();
+    let text = "
+        # A comment
+        name: 'tri' \"angle\";
+        sizes: [0x10, 010, 3], origin < x: 4 weight: inf >
+        points: [{ label: 'a' }, { label: 'b' }]
+        color: 2
+        gradient { weight: -1.5e3f }
+    ";
+    let shape = ShapeBuilder::parse_text(text, &arena).unwrap().freeze();
+
+    assert_eq!(shape.name, "triangle");
+    assert_eq!(shape.sizes, &[16, 8, 3]);
+    assert_eq!(shape.origin.unwrap().x, 4);
+    assert_eq!(shape.origin.unwrap().weight, f64::INFINITY);
+    assert_eq!(shape.points.len(), 2);
+    assert_eq!(shape.color, Color::Blue as i32);
+    assert!(matches!(shape.fill, Some(Fill::Gradient(point)) if point.weight == -1500.0));
+}
+
+#[test]
+fn prints_unknown_enum_values_by_number() {
+    let arena = Arena::new();
+    let shape = ShapeBuilder::parse_text("color: 9", &arena)
+        .unwrap()
+        .freeze();
+    assert_eq!(shape.to_text_format(), "color: 9\n");
+}
+
+#[test]
+fn reports_errors_with_position() {
+    let arena = Arena::new();
+    let error = |text| ShapeBuilder::parse_text(text, &arena).err().unwrap();
+
+    let unknown = error("name: \"a\"\n  shade: 1");
+    assert_eq!(unknown.to_string(), "2:3: unknown field: shade");
+    assert_eq!((unknown.line(), unknown.column()), (2, 3));
+
+    assert_eq!(
+        error("color: COLOR_GREEN").to_string(),
+        "1:8: unknown enum value: COLOR_GREEN"
+    );
+    assert_eq!(
+        error("sizes: 2147483648").to_string(),
+        "1:8: integer out of range"
+    );
+    assert_eq!(
+        error("origin { x: 1").to_string(),
+        "1:14: unexpected end of input"
+    );
+    assert_eq!(
+        error(r#"checksum: "\x""#).to_string(),
+        "1:11: invalid string: incomplete hex value"
+    );
+    assert_eq!(
+        error("name: [\"a\"]").to_string(),
+        "1:1: field name is not repeated"
+    );
+}
+
+#[derive(View)]
+struct Envelope<'arena> {
+    #[defiant(group, optional, tag = 1)]
+    metadata: Option<&'arena Metadata<'arena>>,
+}
+
+#[derive(View)]
+struct Metadata<'arena> {
+    #[defiant(string, tag = 2)]
+    author: &'arena str,
+}
+
+#[test]
+fn names_groups_after_their_type() {
+    let arena = Arena::new();
+    let text = "Metadata {\n  author: \"me\"\n}\n";
+    let envelope = EnvelopeBuilder::parse_text(text, &arena).unwrap().freeze();
+    assert_eq!(envelope.metadata.unwrap().author, "me");
+    assert_eq!(envelope.to_text_format(), text);
+
+    // The field name is accepted too
+    let envelope = EnvelopeBuilder::parse_text("metadata { author: \"you\" }", &arena)
+        .unwrap()
+        .freeze();
+    assert_eq!(envelope.metadata.unwrap().author, "you");
+}
+
+#[derive(View)]
+struct Opaque<'arena> {
+    #[defiant(unknown_fields)]
+    unknown_fields: UnknownFields<'arena>,
+}
+
+#[test]
+#[cfg(not(feature = "no-recursion-limit"))]
+fn prints_deeply_nested_unknown_fields_as_bytes() {
+    // Field 1 nested in itself, far deeper than the recursion limit
+    const LEVELS: usize = 100_000;
+    let mut lens = vec![0usize; LEVELS + 1];
+    for level in (0..LEVELS).rev() {
+        let body = lens[level + 1];
+        lens[level] = 1 + encoded_len_varint(body as u64) + body;
+    }
+    let mut bytes = Vec::with_capacity(lens[0]);
+    for &len in &lens[1..] {
+        bytes.push(0x0a);
+        encode_varint(len as u64, &mut bytes);
+    }
+
+    let arena = Arena::new();
+    let opaque = Opaque::from_buf(&bytes[..], &arena).unwrap();
+    let text = opaque.to_text_format();
+    let lines: Vec<&str> = text.lines().collect();
+    // The default recursion limit of 100 allows the top level and 99 nested messages
+    assert_eq!(lines.len(), 199);
+    assert_eq!(lines[98], format!("{}1 {{", "  ".repeat(98)));
+    assert!(lines[99].starts_with(&format!("{}1: \"\\n", "  ".repeat(99))));
+    assert_eq!(lines[100], format!("{}}}", "  ".repeat(98)));
+}
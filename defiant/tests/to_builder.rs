@@ -0,0 +1,113 @@
+//! Test editing decoded views through their builders
+
+use defiant::{Arena, ArenaMap, Encode, Oneof, UnknownFields};
+use defiant_derive::View;
+
+#[derive(View)]
+struct Order<'arena> {
+    #[defiant(string, tag = 1)]
+    id: &'arena str,
+    #[defiant(uint32, repeated, tag = 2)]
+    quantities: &'arena [u32],
+    #[defiant(arena_map = "string, string", tag = 3)]
+    labels: ArenaMap<'arena, &'arena str, &'arena str>,
+    #[defiant(message, optional, tag = 4)]
+    customer: Option<&'arena Customer<'arena>>,
+    #[defiant(oneof = "Payment", tags = "5, 6")]
+    payment: Option<Payment<'arena>>,
+    #[defiant(message, optional, tag = 7)]
+    total: Option<Money>,
+    #[defiant(unknown_fields)]
+    unknown_fields: UnknownFields<'arena>,
+}
+
+#[derive(View)]
+struct Customer<'arena> {
+    #[defiant(string, tag = 1)]
+    name: &'arena str,
+}
+
+#[derive(Clone, Oneof)]
+enum Payment<'arena> {
+    #[defiant(string, tag = 5)]
+    Card(&'arena str),
+    #[defiant(bool, tag = 6)]
+    Cash(bool),
+}
+
+#[derive(Clone, Copy, View)]
+struct Money {
+    #[defiant(int64, tag = 1)]
+    cents: i64,
+}
+
+fn order_bytes() -> Vec<u8> {
+    let customer = Customer { name: "ada" };
+    let mut bytes = Order {
+        id: "o-1",
+        quantities: &[1, 2],
+        labels: ArenaMap::new(&[("gift", "yes")]),
+        customer: Some(&customer),
+        payment: Some(Payment::Card("4242")),
+        total: Some(Money { cents: 995 }),
+        unknown_fields: UnknownFields::default(),
+    }
+    .encode_to_vec();
+    // An unknown varint field 20
+    bytes.extend_from_slice(&[0xa0, 0x01, 0x07]);
+    bytes
+}
+
+#[test]
+fn test_unmodified_builder_encodes_the_same_bytes() {
+    let arena = Arena::new();
+    let bytes = order_bytes();
+    let order = Order::from_buf(bytes.as_slice(), &arena).unwrap();
+    assert_eq!(order.to_builder(&arena).freeze().encode_to_vec(), bytes);
+}
+
+#[test]
+fn test_modify_decoded_message() {
+    let arena = Arena::new();
+    let bytes = order_bytes();
+    let order = Order::from_buf(bytes.as_slice(), &arena).unwrap();
+
+    let mut builder = order.to_builder(&arena);
+    builder.set_id("o-2");
+    builder.push_quantities(3);
+    let edited = builder.freeze();
+
+    assert_eq!(edited.id, "o-2");
+    assert_eq!(edited.quantities, &[1, 2, 3]);
+    assert_eq!(edited.labels.get(&"gift"), Some(&"yes"));
+    assert!(std::ptr::eq(
+        edited.customer.unwrap(),
+        order.customer.unwrap()
+    ));
+    assert!(matches!(edited.payment, Some(Payment::Card("4242"))));
+    assert_eq!(edited.total.unwrap().cents, 995);
+    assert_eq!(edited.unknown_fields.as_bytes(), &[0xa0, 0x01, 0x07]);
+    // The view itself is untouched
+    assert_eq!(order.id, "o-1");
+    assert_eq!(order.quantities, &[1, 2]);
+
+    let reencoded = edited.encode_to_vec();
+    let decoded = Order::from_buf(reencoded.as_slice(), &arena).unwrap();
+    assert_eq!(decoded.id, "o-2");
+    assert_eq!(decoded.quantities, &[1, 2, 3]);
+    assert_eq!(decoded.customer.unwrap().name, "ada");
+}
+
+#[test]
+fn test_copy_to_builder_is_to_builder() {
+    let arena = Arena::new();
+    let bytes = order_bytes();
+    let order = Order::from_buf(bytes.as_slice(), &arena).unwrap();
+
+    let copied = order.copy_to_builder(&arena).freeze();
+    assert!(std::ptr::eq(
+        copied.customer.unwrap(),
+        order.customer.unwrap()
+    ));
+    assert_eq!(copied.encode_to_vec(), bytes);
+}
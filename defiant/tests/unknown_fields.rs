@@ -0,0 +1,118 @@
+//! Test that unknown fields survive a decode/encode roundtrip
+
+use defiant::{Arena, Encode, UnknownFields};
+use defiant_derive::View;
+
+/// The "new" schema, which knows about every field.
+#[derive(View)]
+struct Envelope<'arena> {
+    #[defiant(string, tag = "1")]
+    name: &'arena str,
+
+    #[defiant(int32, tag = "2")]
+    count: i32,
+
+    #[defiant(bytes, tag = "3")]
+    payload: &'arena [u8],
+
+    #[defiant(fixed64, tag = "4")]
+    checksum: u64,
+
+    #[defiant(int32, repeated, tag = "5")]
+    values: &'arena [i32],
+}
+
+/// An "old" schema that only knows about the name, and preserves everything else.
+#[derive(View)]
+struct OldEnvelope<'arena> {
+    #[defiant(string, tag = "1")]
+    name: &'arena str,
+
+    #[defiant(unknown_fields)]
+    unknown_fields: UnknownFields<'arena>,
+}
+
+/// An "old" schema that opts out of unknown field preservation.
+#[derive(View)]
+struct LossyEnvelope<'arena> {
+    #[defiant(string, tag = "1")]
+    name: &'arena str,
+}
+
+fn encoded_envelope(arena: &Arena) -> Vec<u8> {
+    let mut builder = EnvelopeBuilder::new_in(arena);
+    builder.set_name("proxy");
+    builder.set_count(42);
+    builder.set_payload(b"\x00\x01\x02");
+    builder.set_checksum(0xdead_beef);
+    builder.push_values(1);
+    builder.push_values(-2);
+    builder.freeze().encode_to_vec()
+}
+
+#[test]
+fn test_unknown_fields_roundtrip() {
+    let arena = Arena::new();
+    let bytes = encoded_envelope(&arena);
+
+    let old = OldEnvelope::from_buf(bytes.as_slice(), &arena).unwrap();
+    assert_eq!(old.name, "proxy");
+    assert!(!old.unknown_fields.is_empty());
+
+    // Re-encoding with the old schema produces the original bytes
+    assert_eq!(old.encoded_len(), bytes.len());
+    assert_eq!(old.encode_to_vec(), bytes);
+
+    // And the new schema can still read everything
+    let new = Envelope::from_buf(old.encode_to_vec().as_slice(), &arena).unwrap();
+    assert_eq!(new.name, "proxy");
+    assert_eq!(new.count, 42);
+    assert_eq!(new.payload, b"\x00\x01\x02");
+    assert_eq!(new.checksum, 0xdead_beef);
+    assert_eq!(new.values, &[1, -2]);
+}
+
+#[test]
+fn test_unknown_fields_opt_out() {
+    let arena = Arena::new();
+    let bytes = encoded_envelope(&arena);
+
+    let lossy = LossyEnvelope::from_buf(bytes.as_slice(), &arena).unwrap();
+    assert_eq!(lossy.name, "proxy");
+    assert_eq!(lossy.encoded_len(), 7);
+}
+
+#[test]
+fn test_unknown_fields_groups() {
+    // Field 7 is a group containing a varint field 1, followed by a known field.
+    let bytes = [0x3b, 0x08, 0x96, 0x01, 0x3c, 0x0a, 0x01, b'x'];
+
+    let arena = Arena::new();
+    let old = OldEnvelope::from_buf(&bytes[..], &arena).unwrap();
+    assert_eq!(old.name, "x");
+    assert_eq!(old.unknown_fields.as_bytes(), &bytes[..5]);
+
+    // Known fields are encoded first, followed by the unknown fields
+    let mut expected = bytes[5..].to_vec();
+    expected.extend_from_slice(&bytes[..5]);
+    assert_eq!(old.encode_to_vec(), expected);
+}
+
+#[test]
+fn test_unknown_fields_mismatched_group() {
+    let bytes = [0x3b, 0x08, 0x96, 0x01, 0x44];
+
+    let arena = Arena::new();
+    assert!(OldEnvelope::from_buf(&bytes[..], &arena).is_err());
+}
+
+#[test]
+fn test_unknown_fields_copy_to_builder() {
+    let arena = Arena::new();
+    let bytes = encoded_envelope(&arena);
+
+    let old = OldEnvelope::from_buf(bytes.as_slice(), &arena).unwrap();
+    let mut builder = old.copy_to_builder(&arena);
+    builder.set_name("proxy");
+    assert_eq!(builder.freeze().encode_to_vec(), bytes);
+}
@@ -58,6 +58,7 @@ fn main() -> Result<()> {
         .protoc_executable(&protoc_executable)
         .compile_well_known_types()
         .btree_map(["."])
+        .preserve_unknown_fields([".protobuf_test_messages"])
         .compile_protos(
             &[
                 proto_dir.join("google/protobuf/wrappers.proto"),
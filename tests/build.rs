@@ -137,6 +137,19 @@ fn main() {
         .compile_protos(&[src.join("proto3_presence.proto")], includes)
         .unwrap();
 
+    defiant_build::Config::new(&arena)
+        .owned_types(true)
+        .compile_protos(&[src.join("owned_types.proto")], includes)
+        .unwrap();
+
+    defiant_build::Config::new(&arena)
+        .compile_protos(&[src.join("extensions.proto")], includes)
+        .unwrap();
+
+    defiant_build::Config::new(&arena)
+        .compile_protos(&[src.join("editions.proto")], includes)
+        .unwrap();
+
     defiant_build::Config::new(&arena)
         .disable_comments(["."])
         .compile_protos(&[src.join("disable_comments.proto")], includes)
@@ -0,0 +1,29 @@
+use defiant::{DecodeOptions, Encode};
+
+include!(concat!(env!("OUT_DIR"), "/editions.rs"));
+
+#[test]
+fn test_edition_2023_roundtrip() {
+    let arena = defiant::Arena::new();
+    // id: 7, note: "n", key: "k", expanded ids: [1, 2], packed codes: [3, 4],
+    // item as a group: {sku: "s", qty: 5}
+    let bytes = [
+        0x08, 0x07, 0x12, 0x01, b'n', 0x1a, 0x01, b'k', 0x20, 0x01, 0x20, 0x02, 0x2a, 0x02, 0x03,
+        0x04, 0x33, 0x0a, 0x01, b's', 0x10, 0x05, 0x34,
+    ];
+    let order = Order::from_buf(&bytes[..], &arena).unwrap();
+    assert_eq!(order.id, Some(7));
+    assert_eq!((order.note, order.key), ("n", "k"));
+    assert_eq!((order.ids, order.codes), (&[1, 2][..], &[3, 4][..]));
+    let item = order.item.unwrap();
+    assert_eq!((item.sku, item.qty), (Some("s"), 5));
+    assert_eq!(order.encode_to_vec(), bytes);
+
+    // `LEGACY_REQUIRED` fields are checked like proto2 required fields
+    let strict = DecodeOptions::new().check_required(true);
+    let error = Order::from_buf_with_options(&bytes[..5], &arena, strict).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "failed to decode Protobuf message: missing required fields: key"
+    );
+}
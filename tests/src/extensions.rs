@@ -0,0 +1,56 @@
+use defiant::{Encode, ExtensionRegistry};
+
+include!(concat!(env!("OUT_DIR"), "/extensions.rs"));
+
+#[test]
+fn test_generated_extensions() {
+    let arena = defiant::Arena::new();
+    let mut builder = Envelope::builder(&arena);
+    builder.set_route("orders");
+    builder.set_extension(&PRIORITY, Priority::High as i32);
+    builder.set_extension(&DEADLINE, -30);
+    builder.set_extension(&TAGS, &["a", "b"]);
+    builder.set_extension(
+        &TRACE,
+        Trace {
+            id: Some("t-1"),
+            hops: Some(3),
+        },
+    );
+    builder.set_extension(&AUDIT, Audit { by: Some("ann") });
+    builder.set_extension(&holder::HELD, true);
+    let bytes = builder.freeze().encode_to_vec();
+
+    let mut registry = ExtensionRegistry::new();
+    registry
+        .register(&PRIORITY)
+        .register(&DEADLINE)
+        .register(&TAGS)
+        .register(&TRACE)
+        .register(&AUDIT)
+        .register(&holder::HELD);
+    let envelope = Envelope::from_buf_with_extensions(bytes.as_slice(), &arena, &registry).unwrap();
+    assert_eq!(envelope.route, Some("orders"));
+    assert_eq!(
+        envelope.get_extension(&PRIORITY, &arena).unwrap(),
+        Some(Priority::High as i32)
+    );
+    assert_eq!(
+        envelope.get_extension(&DEADLINE, &arena).unwrap(),
+        Some(-30)
+    );
+    assert_eq!(
+        envelope.get_extension(&TAGS, &arena).unwrap(),
+        Some(&["a", "b"][..])
+    );
+    let trace = envelope.get_extension(&TRACE, &arena).unwrap().unwrap();
+    assert_eq!((trace.id, trace.hops), (Some("t-1"), Some(3)));
+    // The group is encoded between start and end group tags, not length-delimited
+    let audit = envelope.get_extension(&AUDIT, &arena).unwrap().unwrap();
+    assert_eq!(audit.by, Some("ann"));
+    assert!(bytes.windows(2).any(|tag| tag == [0xc3, 0x06]));
+    assert_eq!(
+        envelope.get_extension(&holder::HELD, &arena).unwrap(),
+        Some(true)
+    );
+}
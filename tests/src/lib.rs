@@ -108,6 +108,15 @@ pub mod oneof_attributes {
 #[cfg(test)]
 mod proto3_presence;
 
+#[cfg(test)]
+mod owned_types;
+
+#[cfg(test)]
+mod extensions;
+
+#[cfg(test)]
+mod editions;
+
 use core::fmt::Debug;
 
 #[cfg(not(feature = "std"))]
@@ -0,0 +1,31 @@
+use defiant::{Encode, MessageView};
+
+include!(concat!(env!("OUT_DIR"), "/owned_types.rs"));
+
+#[test]
+fn test_owned_types_cross_threads() {
+    let arena = defiant::Arena::new();
+    // label: "root", children: [{label: "a", index: 7}], origin: {x: 3}, name: "r"
+    let bytes = [
+        0x0a, 0x04, b'r', b'o', b'o', b't', 0x12, 0x05, 0x0a, 0x01, b'a', 0x28, 0x07, 0x1a, 0x02,
+        0x08, 0x03, 0x22, 0x01, b'r',
+    ];
+    let tree = Tree::from_buf(&bytes[..], &arena).unwrap();
+
+    let owned: TreeOwned = tree.to_owned();
+    assert_eq!(owned.label, "root");
+    assert_eq!(owned.children.len(), 1);
+    assert_eq!(owned.children[0].key, Some(tree::KeyOwned::Index(7)));
+    assert_eq!(owned.origin.map(|origin| origin.x), Some(3));
+
+    let encoded = std::thread::spawn(move || {
+        let arena = defiant::Arena::new();
+        let tree = owned.view_in(&arena);
+        assert_eq!(tree.label, "root");
+        assert_eq!(tree.children[0].label, "a");
+        tree.encode_to_vec()
+    })
+    .join()
+    .unwrap();
+    assert_eq!(encoded, tree.encode_to_vec());
+}